@@ -0,0 +1,190 @@
+use crate::watcher::PAUSED;
+use log::info;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use tauri::Manager;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActionArg {
+    pub name: &'static str,
+    pub kind: &'static str,
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActionInfo {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub category: &'static str,
+    pub args: &'static [ActionArg],
+}
+
+const fn arg(name: &'static str, kind: &'static str) -> ActionArg {
+    ActionArg {
+        name,
+        kind,
+        required: true,
+    }
+}
+
+/// Everything the command palette can run. Keep ids stable: scripts refer to them.
+pub const ACTIONS: &[ActionInfo] = &[
+    ActionInfo {
+        id: "watcher.pause",
+        title: "Pause Watching",
+        description: "Stop compressing new files in watched folders",
+        category: "Watcher",
+        args: &[],
+    },
+    ActionInfo {
+        id: "watcher.resume",
+        title: "Resume Watching",
+        description: "Compress new files in watched folders again",
+        category: "Watcher",
+        args: &[],
+    },
+    ActionInfo {
+        id: "compress.files",
+        title: "Compress Files",
+        description: "Compress the given image files",
+        category: "Compression",
+        args: &[arg("paths", "string[]")],
+    },
+    ActionInfo {
+        id: "settings.quality",
+        title: "Set Quality",
+        description: "Change the default compression quality (1-100)",
+        category: "Settings",
+        args: &[arg("value", "number")],
+    },
+    ActionInfo {
+        id: "folder.open",
+        title: "Open Folder",
+        description: "Open a folder in the system file manager",
+        category: "Folders",
+        args: &[arg("path", "string")],
+    },
+    ActionInfo {
+        id: "config.open",
+        title: "Open Config Folder",
+        description: "Show Hat's configuration directory",
+        category: "Settings",
+        args: &[],
+    },
+    ActionInfo {
+        id: "history.verify",
+        title: "Verify Outputs",
+        description: "Re-hash compressed files to detect corruption",
+        category: "History",
+        args: &[],
+    },
+    ActionInfo {
+        id: "history.clear",
+        title: "Clear History",
+        description: "Remove all compression history entries",
+        category: "History",
+        args: &[],
+    },
+    ActionInfo {
+        id: "window.show",
+        title: "Show Window",
+        description: "Bring the Hat window to the front",
+        category: "App",
+        args: &[],
+    },
+    ActionInfo {
+        id: "app.quit",
+        title: "Quit Hat",
+        description: "Stop watching and exit",
+        category: "App",
+        args: &[],
+    },
+];
+
+fn string_arg(args: &Value, name: &str) -> Result<String, String> {
+    args.get(name)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| format!("missing string argument '{}'", name))
+}
+
+/// Run the action `id`, returning its result as JSON.
+pub fn invoke(app: &tauri::AppHandle, id: &str, args: &Value) -> Result<Value, String> {
+    info!("[actions] Invoking {}", id);
+    match id {
+        "watcher.pause" | "watcher.resume" => {
+            let paused = id == "watcher.pause";
+            PAUSED.store(paused, Ordering::Relaxed);
+            Ok(json!({ "paused": paused }))
+        }
+        "compress.files" => {
+            let paths: Vec<String> = args
+                .get("paths")
+                .cloned()
+                .map(serde_json::from_value)
+                .transpose()
+                .map_err(|e| e.to_string())?
+                .ok_or("missing string[] argument 'paths'")?;
+            let vips = app.state::<crate::watcher::VipsState>().get();
+            let handle = app.clone();
+            std::thread::spawn(move || {
+                for path in paths {
+                    let _ = crate::processor::process_any(
+                        &handle,
+                        vips.as_ref(),
+                        std::path::Path::new(&path),
+                        crate::processor::InputMode::Manual,
+                    );
+                }
+            });
+            Ok(Value::Null)
+        }
+        "settings.quality" => {
+            let value = args
+                .get("value")
+                .and_then(Value::as_u64)
+                .ok_or("missing number argument 'value'")?;
+            let clamped = value.clamp(1, 100) as u8;
+            let config = app.state::<Mutex<crate::config::ConfigManager>>();
+            config
+                .lock()
+                .map_err(|e| e.to_string())?
+                .set_quality(clamped);
+            Ok(json!(clamped))
+        }
+        "folder.open" => {
+            use tauri_plugin_opener::OpenerExt;
+            app.opener()
+                .open_path(string_arg(args, "path")?, None::<String>)
+                .map_err(|e| e.to_string())?;
+            Ok(Value::Null)
+        }
+        "config.open" => crate::commands::open_config_dir(app.clone()).map(|_| Value::Null),
+        "history.verify" => {
+            let log = app.state::<Mutex<crate::log::CompressionLog>>();
+            let records = log.lock().map_err(|e| e.to_string())?.records.clone();
+            serde_json::to_value(crate::checksum::verify(&records)).map_err(|e| e.to_string())
+        }
+        "history.clear" => {
+            let log = app.state::<Mutex<crate::log::CompressionLog>>();
+            log.lock().map_err(|e| e.to_string())?.clear();
+            Ok(Value::Null)
+        }
+        "window.show" => {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.unminimize();
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+            Ok(Value::Null)
+        }
+        "app.quit" => {
+            crate::tasks::request_quit(app);
+            Ok(Value::Null)
+        }
+        _ => Err(format!("Unknown action: {}", id)),
+    }
+}
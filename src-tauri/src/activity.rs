@@ -0,0 +1,119 @@
+use crate::locale::format_bytes;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
+
+/// Entries pushed within one interval are merged into a single event.
+const FLUSH_INTERVAL: Duration = Duration::from_millis(750);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivityKind {
+    Compressed,
+    Analyzed,
+    Failed,
+    Held,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityEntry {
+    pub kind: ActivityKind,
+    pub message: String,
+    pub count: usize,
+    pub bytes_saved: u64,
+    pub timestamp: u64,
+}
+
+struct Pending {
+    kind: ActivityKind,
+    file_name: String,
+    bytes_saved: u64,
+}
+
+/// Buffers activity so bursts (e.g. a folder drop) become one feed entry.
+#[derive(Default)]
+pub struct ActivityFeed {
+    pending: Mutex<Vec<Pending>>,
+}
+
+pub fn push(app: &tauri::AppHandle, kind: ActivityKind, path: &Path, bytes_saved: u64) {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string());
+    if let Ok(mut pending) = app.state::<ActivityFeed>().pending.lock() {
+        pending.push(Pending {
+            kind,
+            file_name,
+            bytes_saved,
+        });
+    }
+}
+
+/// Spawn the thread that drains the buffer and emits `activity` events.
+pub fn start(app: &tauri::AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(FLUSH_INTERVAL);
+        let drained = match app.state::<ActivityFeed>().pending.lock() {
+            Ok(mut pending) => std::mem::take(&mut *pending),
+            Err(_) => continue,
+        };
+        for entry in aggregate(drained) {
+            let _ = app.emit("activity", &entry);
+            crate::tray::set_status(&app, &entry.message, entry.timestamp);
+        }
+    });
+}
+
+fn aggregate(items: Vec<Pending>) -> Vec<ActivityEntry> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    let kinds = [
+        ActivityKind::Compressed,
+        ActivityKind::Analyzed,
+        ActivityKind::Failed,
+        ActivityKind::Held,
+    ];
+    kinds
+        .into_iter()
+        .filter_map(|kind| {
+            let group: Vec<&Pending> = items.iter().filter(|i| i.kind == kind).collect();
+            let first = group.first()?;
+            let bytes_saved = group.iter().map(|i| i.bytes_saved).sum();
+            let subject = if group.len() == 1 {
+                first.file_name.clone()
+            } else {
+                format!("{} images", group.len())
+            };
+            let message = match kind {
+                ActivityKind::Compressed => {
+                    format!(
+                        "Compressed {}, saved {}",
+                        subject,
+                        format_bytes(bytes_saved)
+                    )
+                }
+                ActivityKind::Analyzed => format!(
+                    "Analyzed {}, could save {}",
+                    subject,
+                    format_bytes(bytes_saved)
+                ),
+                ActivityKind::Failed => format!("Failed to compress {}", subject),
+                ActivityKind::Held => format!("Held {} for approval", subject),
+            };
+            Some(ActivityEntry {
+                kind,
+                message,
+                count: group.len(),
+                bytes_saved,
+                timestamp,
+            })
+        })
+        .collect()
+}
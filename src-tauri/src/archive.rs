@@ -0,0 +1,93 @@
+use crate::compression::CompressionRecord;
+use log::{info, warn};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use tauri::Manager;
+
+static DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Set from the config whenever `archive_dir` changes.
+pub fn set_dir(dir: Option<&str>) {
+    let dir = dir
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .map(crate::output_dir::expand);
+    if let Ok(mut current) = DIR.write() {
+        *current = dir;
+    }
+}
+
+fn dir() -> Option<PathBuf> {
+    DIR.read().ok()?.clone()
+}
+
+/// Whether `path` was moved into the archive, so the watcher leaves it be.
+pub fn contains(path: &Path) -> bool {
+    dir().is_some_and(|dir| path.parent().is_some_and(|parent| parent.starts_with(&dir)))
+}
+
+/// With an archive folder set, move `record`'s original into it once the
+/// output is written, below the same subfolders it had under its watched
+/// folder. Failures leave the original where it was.
+pub fn apply(app: &tauri::AppHandle, record: &mut CompressionRecord) {
+    let Some(dir) = dir() else {
+        return;
+    };
+    // Replaced originals are already in the backup folder
+    if record.analysis_only
+        || record.group_id.is_some()
+        || record.reused_from.is_some()
+        || record.backup_path.is_some()
+        || record.final_path == record.initial_path
+    {
+        return;
+    }
+    // Extra outputs are written after this one and still need the original.
+    // The settings refuse the combination, but a profile can still bring it in
+    let extra_outputs = app
+        .state::<Mutex<crate::config::ConfigManager>>()
+        .lock()
+        .map_or(true, |c| {
+            !c.config.active_format_options().extra_outputs.is_empty()
+        });
+    if extra_outputs {
+        warn!(
+            "[archive] Keeping {} in place: extra output formats still need it",
+            record.initial_path
+        );
+        return;
+    }
+    if let Err(e) = archive(&dir, record) {
+        warn!("[archive] Keeping {} in place: {}", record.initial_path, e);
+    }
+}
+
+fn archive(dir: &Path, record: &mut CompressionRecord) -> Result<(), String> {
+    let original = PathBuf::from(&record.initial_path);
+    let folder = dir.join(crate::output_dir::source_subfolder(&original));
+    std::fs::create_dir_all(&folder).map_err(|e| e.to_string())?;
+    let stem = original
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or("Invalid file name")?;
+    let ext = original.extension().and_then(|e| e.to_str());
+    let names = crate::config::FolderNames::read(&folder);
+    let target = (1..)
+        .map(|n| match (n, ext) {
+            (1, Some(ext)) => folder.join(format!("{}.{}", stem, ext)),
+            (1, None) => folder.join(stem),
+            (n, Some(ext)) => folder.join(format!("{}_{}.{}", stem, n, ext)),
+            (n, None) => folder.join(format!("{}_{}", stem, n)),
+        })
+        .find(|p| !p.exists() && !names.collides(p))
+        .ok_or("No free archive name")?;
+
+    crate::replace::move_file(&original, &target).map_err(|e| e.to_string())?;
+    info!(
+        "[archive] Moved {} to {}",
+        original.display(),
+        target.display()
+    );
+    record.original_archived_path = Some(target.display().to_string());
+    Ok(())
+}
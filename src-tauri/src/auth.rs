@@ -0,0 +1,37 @@
+use log::{info, warn};
+use std::sync::Mutex;
+use tauri::Manager;
+
+/// Authenticate the user before `action` when the config asks for it. The
+/// error can be handed straight back from a command.
+pub async fn confirm(app: &tauri::AppHandle, action: &str) -> Result<(), String> {
+    let required = app
+        .state::<Mutex<crate::config::ConfigManager>>()
+        .lock()
+        .map(|c| c.config.require_authentication)
+        .map_err(|e| e.to_string())?;
+    if !required {
+        return Ok(());
+    }
+    prompt(action).await
+}
+
+/// Authenticate the user for `action` unconditionally. Runs off the async
+/// runtime since the OS prompt blocks until answered.
+pub async fn prompt(action: &str) -> Result<(), String> {
+    let reason = format!("Hat needs to confirm it's you to {}.", action);
+    let result =
+        tauri::async_runtime::spawn_blocking(move || crate::platform::authenticate(&reason))
+            .await
+            .map_err(|e| e.to_string())?;
+    match result {
+        Ok(()) => {
+            info!("[auth] Confirmed: {}", action);
+            Ok(())
+        }
+        Err(e) => {
+            warn!("[auth] Refused to {}: {}", action, e);
+            Err(format!("Authentication failed, not allowed to {}", action))
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::suggestions::FolderSuggestion;
+use log::info;
+use std::path::{Path, PathBuf};
+
+/// Chromium-based browsers keep one `Preferences` file per profile under
+/// their user data folder.
+fn chromium_user_data_dirs() -> Vec<(&'static str, PathBuf)> {
+    let mut found = Vec::new();
+    #[cfg(target_os = "windows")]
+    if let Some(local) = dirs::data_local_dir() {
+        found.push(("Chrome", local.join("Google/Chrome/User Data")));
+        found.push(("Edge", local.join("Microsoft/Edge/User Data")));
+        found.push(("Brave", local.join("BraveSoftware/Brave-Browser/User Data")));
+    }
+    #[cfg(target_os = "macos")]
+    if let Some(support) = dirs::data_dir() {
+        found.push(("Chrome", support.join("Google/Chrome")));
+        found.push(("Edge", support.join("Microsoft Edge")));
+        found.push(("Brave", support.join("BraveSoftware/Brave-Browser")));
+    }
+    #[cfg(target_os = "linux")]
+    if let Some(config) = dirs::config_dir() {
+        found.push(("Chrome", config.join("google-chrome")));
+        found.push(("Chromium", config.join("chromium")));
+        found.push(("Edge", config.join("microsoft-edge")));
+        found.push(("Brave", config.join("BraveSoftware/Brave-Browser")));
+    }
+    found
+}
+
+fn firefox_profiles_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    {
+        dirs::config_dir().map(|d| d.join("Mozilla/Firefox/Profiles"))
+    }
+    #[cfg(target_os = "macos")]
+    {
+        dirs::data_dir().map(|d| d.join("Firefox/Profiles"))
+    }
+    #[cfg(target_os = "linux")]
+    {
+        dirs::home_dir().map(|d| d.join(".mozilla/firefox"))
+    }
+}
+
+fn subdirs(dir: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.is_dir())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `download.default_directory` from a Chromium profile's `Preferences`.
+fn chromium_download_dir(profile: &Path) -> Option<String> {
+    let prefs = std::fs::read_to_string(profile.join("Preferences")).ok()?;
+    let prefs: serde_json::Value = serde_json::from_str(&prefs).ok()?;
+    prefs
+        .pointer("/download/default_directory")?
+        .as_str()
+        .map(str::to_string)
+}
+
+/// `browser.download.dir` from a Firefox profile's `prefs.js`, used only
+/// when `browser.download.folderList` selects a custom folder.
+fn firefox_download_dir(profile: &Path) -> Option<String> {
+    let prefs = std::fs::read_to_string(profile.join("prefs.js")).ok()?;
+    let pref = |name: &str| {
+        let prefix = format!("user_pref(\"{name}\", ");
+        prefs.lines().find_map(|line| {
+            line.strip_prefix(&prefix)?
+                .strip_suffix(");")
+                .map(str::to_string)
+        })
+    };
+    if pref("browser.download.folderList").as_deref() != Some("2") {
+        return None;
+    }
+    let dir = pref("browser.download.dir")?;
+    let dir = dir.strip_prefix('"')?.strip_suffix('"')?;
+    Some(dir.replace("\\\\", "\\").replace("\\\"", "\""))
+}
+
+/// Download folders configured in installed browsers, excluding folders
+/// already in `watched` and ones that no longer exist.
+pub fn suggest_watch_folders(watched: &[String]) -> Vec<FolderSuggestion> {
+    let mut found: Vec<FolderSuggestion> = Vec::new();
+
+    for (browser, user_data) in chromium_user_data_dirs() {
+        for profile in subdirs(&user_data) {
+            if !profile.join("Preferences").is_file() {
+                continue;
+            }
+            if let Some(path) = chromium_download_dir(&profile) {
+                let name = profile.file_name().unwrap_or_default().to_string_lossy();
+                found.push(FolderSuggestion {
+                    path,
+                    source: format!("{browser} ({name})"),
+                });
+            }
+        }
+    }
+
+    if let Some(profiles) = firefox_profiles_dir() {
+        for profile in subdirs(&profiles) {
+            if let Some(path) = firefox_download_dir(&profile) {
+                // Profile folders are named "<salt>.<profile name>"
+                let name = profile.file_name().unwrap_or_default().to_string_lossy();
+                let name = name.split_once('.').map_or(&*name, |(_, n)| n).to_string();
+                found.push(FolderSuggestion {
+                    path,
+                    source: format!("Firefox ({name})"),
+                });
+            }
+        }
+    }
+
+    let mut suggestions: Vec<FolderSuggestion> = Vec::new();
+    for suggestion in found {
+        let path = Path::new(&suggestion.path);
+        if !path.is_dir()
+            || watched.iter().any(|w| Path::new(w) == path)
+            || suggestions.iter().any(|s| Path::new(&s.path) == path)
+        {
+            continue;
+        }
+        suggestions.push(suggestion);
+    }
+    info!(
+        "[browsers] Found {} download folders to suggest",
+        suggestions.len()
+    );
+    suggestions
+}
@@ -0,0 +1,153 @@
+use crate::config::TempConfig;
+use log::{info, warn};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime};
+use tauri::Manager;
+
+const TRIM_INTERVAL: Duration = Duration::from_secs(15 * 60);
+/// Files touched more recently than this may still be in use and are kept.
+const IN_USE_AGE: Duration = Duration::from_secs(60);
+
+static ROOT: RwLock<Option<PathBuf>> = RwLock::new(None);
+static MAX_BYTES: AtomicU64 = AtomicU64::new(u64::MAX);
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Apply the temp settings; without a directory the app cache folder is used.
+pub fn configure(app: &tauri::AppHandle, config: &TempConfig) {
+    let root = config
+        .directory
+        .as_deref()
+        .map(PathBuf::from)
+        .or_else(|| app.path().app_cache_dir().ok().map(|d| d.join("temp")));
+    info!("[cache] Temp files go to {:?}", root);
+    if let Ok(mut current) = ROOT.write() {
+        *current = root;
+    }
+    MAX_BYTES.store(config.max_bytes, Ordering::Relaxed);
+}
+
+/// Folder for intermediate files, created on demand.
+pub fn root() -> PathBuf {
+    let root = ROOT
+        .read()
+        .ok()
+        .and_then(|r| r.clone())
+        .unwrap_or_else(|| std::env::temp_dir().join("hat"));
+    let _ = std::fs::create_dir_all(&root);
+    root
+}
+
+/// A fresh path under the temp root whose file name ends in `name`.
+pub fn temp_path(name: &str) -> PathBuf {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    root().join(format!("{}-{id}-{name}", std::process::id()))
+}
+
+/// Move a finished temp file to `dest`, copying when they are on different
+/// volumes.
+pub fn persist(temp: &Path, dest: &Path) -> std::io::Result<()> {
+    if std::fs::rename(temp, dest).is_ok() {
+        return Ok(());
+    }
+    std::fs::copy(temp, dest)?;
+    std::fs::remove_file(temp)
+}
+
+struct CachedFile {
+    path: PathBuf,
+    size: u64,
+    used: SystemTime,
+}
+
+fn cached_files(dir: &Path, out: &mut Vec<CachedFile>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if meta.is_dir() {
+            cached_files(&entry.path(), out);
+        } else {
+            let used = meta
+                .accessed()
+                .into_iter()
+                .chain(meta.modified())
+                .max()
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            out.push(CachedFile {
+                path: entry.path(),
+                size: meta.len(),
+                used,
+            });
+        }
+    }
+}
+
+/// Delete files oldest-used first until no more than `keep` bytes remain,
+/// returning the bytes freed.
+fn evict(keep: u64) -> u64 {
+    let root = root();
+    let mut files = Vec::new();
+    cached_files(&root, &mut files);
+    files.sort_by_key(|f| f.used);
+
+    let mut total: u64 = files.iter().map(|f| f.size).sum();
+    let mut freed = 0;
+    for file in files {
+        if total <= keep {
+            break;
+        }
+        if file.used.elapsed().unwrap_or_default() < IN_USE_AGE {
+            continue;
+        }
+        match std::fs::remove_file(&file.path) {
+            Ok(()) => {
+                total -= file.size;
+                freed += file.size;
+            }
+            Err(e) => warn!("[cache] Failed to delete {}: {e}", file.path.display()),
+        }
+    }
+    remove_empty_dirs(&root);
+    freed
+}
+
+fn remove_empty_dirs(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            remove_empty_dirs(&entry.path());
+            let _ = std::fs::remove_dir(entry.path());
+        }
+    }
+}
+
+/// Shrink the temp folder back under its size cap.
+pub fn trim() -> u64 {
+    let freed = evict(MAX_BYTES.load(Ordering::Relaxed));
+    if freed > 0 {
+        info!("[cache] Trimmed {} bytes of temp files", freed);
+    }
+    freed
+}
+
+/// Delete every temp file that isn't in use, returning the bytes freed.
+pub fn clear() -> u64 {
+    let freed = evict(0);
+    info!("[cache] Cleared {} bytes of temp files", freed);
+    freed
+}
+
+/// Trim the temp folder now and then periodically.
+pub fn start() {
+    std::thread::spawn(|| loop {
+        trim();
+        std::thread::sleep(TRIM_INTERVAL);
+    });
+}
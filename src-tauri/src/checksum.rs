@@ -0,0 +1,100 @@
+use crate::compression::CompressionRecord;
+use crate::crypto::sha256_file;
+use log::{info, warn};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Write a `sha256sum`-compatible `<file>.sha256` next to `path`.
+pub fn write_sidecar(path: &Path, hash: &str) -> std::io::Result<PathBuf> {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut sidecar = path.as_os_str().to_os_string();
+    sidecar.push(".sha256");
+    let sidecar = PathBuf::from(sidecar);
+    std::fs::write(&sidecar, format!("{}  {}\n", hash, name))?;
+    Ok(sidecar)
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VerifyStatus {
+    Mismatch,
+    Missing,
+    Unreadable,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyProblem {
+    pub record_id: u64,
+    pub path: String,
+    pub status: VerifyStatus,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyReport {
+    pub checked: usize,
+    pub ok: usize,
+    pub problems: Vec<VerifyProblem>,
+}
+
+/// Where `record`'s original is now: still in place, or moved into the
+/// archive or, when its output replaced it, the backup folder. `None` once
+/// it was deleted.
+pub fn original_path(record: &CompressionRecord) -> Option<&str> {
+    if record.original_deleted {
+        return None;
+    }
+    record
+        .original_archived_path
+        .as_deref()
+        .or(record.backup_path.as_deref())
+        .or(Some(record.initial_path.as_str()))
+}
+
+/// Re-hash every file that has a stored checksum and report any that changed.
+pub fn verify(records: &[CompressionRecord]) -> VerifyReport {
+    let mut report = VerifyReport {
+        checked: 0,
+        ok: 0,
+        problems: Vec::new(),
+    };
+
+    for record in records {
+        // Deleted originals are expected to be gone
+        let files = [
+            (original_path(record), &record.original_sha256),
+            (Some(record.final_path.as_str()), &record.output_sha256),
+        ];
+        for (path, expected) in files {
+            let (Some(path), Some(expected)) = (path, expected) else {
+                continue;
+            };
+            report.checked += 1;
+            let status = match sha256_file(Path::new(path)) {
+                Ok(actual) if &actual == expected => {
+                    report.ok += 1;
+                    continue;
+                }
+                Ok(_) => VerifyStatus::Mismatch,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => VerifyStatus::Missing,
+                Err(_) => VerifyStatus::Unreadable,
+            };
+            warn!("[checksum] {:?}: {}", status, path);
+            report.problems.push(VerifyProblem {
+                record_id: record.id,
+                path: path.to_string(),
+                status,
+            });
+        }
+    }
+
+    info!(
+        "[checksum] Verified {} files, {} ok, {} problems",
+        report.checked,
+        report.ok,
+        report.problems.len()
+    );
+    report
+}
@@ -1,5 +1,6 @@
 use crate::compression::{
-    compressed_output_path, CompressionFlags, CompressionRecord, ImageFormat,
+    compressed_output_path, CompressionFlags, CompressionRecord, EncodeEngine, EncodeSettings,
+    ImageFormat,
 };
 use crate::watcher::VipsState;
 use log::{error, info};
@@ -31,102 +32,1266 @@ pub fn get_quality(
     Ok(config_manager.config.quality)
 }
 
+#[tauri::command]
+pub fn get_format_quality(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<std::collections::HashMap<String, u8>, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.format_quality.clone())
+}
+
+/// Replace the per-format qualities. Names are stored in their canonical
+/// form, so `jpg` and `jpeg` share one entry.
+#[tauri::command]
+pub fn set_format_quality(
+    format_quality: std::collections::HashMap<String, u8>,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<std::collections::HashMap<String, u8>, String> {
+    let mut normalized = std::collections::HashMap::new();
+    for (name, quality) in format_quality {
+        let format = ImageFormat::from_extension(&name)
+            .ok_or_else(|| format!("Unsupported format: {name}"))?;
+        normalized.insert(format.to_string(), quality.clamp(1, 100));
+    }
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_format_quality(normalized.clone());
+    info!("[compression] Per-format quality changed: {:?}", normalized);
+    Ok(normalized)
+}
+
 #[tauri::command]
 pub fn get_show_background_notification(
     config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
-) -> Result<bool, String> {
+) -> Result<bool, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.show_background_notification)
+}
+
+#[tauri::command]
+pub fn set_show_background_notification(
+    value: bool,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_show_background_notification(value);
+    Ok(value)
+}
+
+#[tauri::command]
+pub fn get_show_system_notifications(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.show_system_notifications)
+}
+
+#[tauri::command]
+pub fn set_show_system_notifications(
+    value: bool,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_show_system_notifications(value);
+    Ok(value)
+}
+
+#[tauri::command]
+pub fn get_legacy_event_names(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.legacy_event_names)
+}
+
+#[tauri::command]
+pub fn set_legacy_event_names(
+    enabled: bool,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_legacy_event_names(enabled);
+    info!("[config] Legacy event names changed: {}", enabled);
+    Ok(enabled)
+}
+
+/// Every event the backend emits, with its payload type and version.
+#[tauri::command]
+pub fn get_event_schema() -> Vec<crate::events::EventInfo> {
+    crate::events::schema()
+}
+
+#[tauri::command]
+pub fn get_notification_sinks(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<Vec<crate::config::NotificationSinkConfig>, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.public_notification_sinks())
+}
+
+#[tauri::command]
+pub fn set_notification_sinks(
+    sinks: Vec<crate::config::NotificationSinkConfig>,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<Vec<crate::config::NotificationSinkConfig>, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    info!("[config] Notification sinks changed: {}", sinks.len());
+    config_manager.set_notification_sinks(sinks)?;
+    Ok(config_manager.public_notification_sinks())
+}
+
+/// Send a test message through `sink` before saving it.
+#[tauri::command]
+pub async fn test_notification_sink(
+    sink: crate::config::NotificationSinkConfig,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || crate::notifier::test(&app, &sink))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn get_format_options(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::FormatOptions, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.format_options.clone())
+}
+
+#[tauri::command]
+pub fn set_format_options(
+    options: crate::config::FormatOptions,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::FormatOptions, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_format_options(options.clone())?;
+    info!("[config] Format options updated: {:?}", options);
+    Ok(options)
+}
+
+#[tauri::command]
+pub fn get_folder_settings(
+    folder: String,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::FolderSettings, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager
+        .config
+        .folder_settings
+        .get(&folder)
+        .cloned()
+        .unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn set_folder_settings(
+    folder: String,
+    settings: crate::config::FolderSettings,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::FolderSettings, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_folder_settings(folder.clone(), settings.clone())?;
+    info!(
+        "[config] Folder settings for {} updated: {:?}",
+        folder, settings
+    );
+    Ok(settings)
+}
+
+/// Input format → rule for one folder; formats without a rule use the global options.
+#[tauri::command]
+pub fn get_folder_format_options(
+    folder: String,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<std::collections::HashMap<String, crate::config::FormatRule>, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager
+        .config
+        .folder_format_rules
+        .get(&folder)
+        .cloned()
+        .unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn set_folder_format_options(
+    folder: String,
+    rules: std::collections::HashMap<String, crate::config::FormatRule>,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<std::collections::HashMap<String, crate::config::FormatRule>, String> {
+    // Keys are stored under canonical names so "jpg" and "jpeg" share a rule
+    let rules = rules
+        .into_iter()
+        .map(|(key, rule)| match ImageFormat::from_extension(&key) {
+            Some(format) => Ok((format.to_string(), rule)),
+            None => Err(format!("Unknown input format: {}", key)),
+        })
+        .collect::<Result<std::collections::HashMap<_, _>, String>>()?;
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_folder_format_rules(folder.clone(), rules.clone());
+    info!("[config] Format rules for {} updated: {:?}", folder, rules);
+    Ok(rules)
+}
+
+#[tauri::command]
+pub fn get_texture_config(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::TextureConfig, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.texture.clone())
+}
+
+#[tauri::command]
+pub fn set_texture_config(
+    texture: crate::config::TextureConfig,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::TextureConfig, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_texture_config(texture.clone());
+    info!("[config] Texture settings updated: {:?}", texture);
+    Ok(texture)
+}
+
+#[tauri::command]
+pub fn get_video_config(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::VideoConfig, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.video.clone())
+}
+
+#[tauri::command]
+pub fn set_video_config(
+    video: crate::config::VideoConfig,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::VideoConfig, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_video_config(video.clone());
+    info!("[config] Video settings updated: {:?}", video);
+    Ok(video)
+}
+
+#[tauri::command]
+pub fn get_ocr_config(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::OcrConfig, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.ocr.clone())
+}
+
+#[tauri::command]
+pub fn set_ocr_config(
+    ocr: crate::config::OcrConfig,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::OcrConfig, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_ocr_config(ocr.clone());
+    info!("[config] OCR settings updated: {:?}", ocr);
+    Ok(ocr)
+}
+
+#[tauri::command]
+pub fn get_sensitive_guard(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::SensitiveGuardConfig, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.sensitive_guard.clone())
+}
+
+#[tauri::command]
+pub fn set_sensitive_guard(
+    guard: crate::config::SensitiveGuardConfig,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::SensitiveGuardConfig, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_sensitive_guard(guard.clone());
+    info!("[config] Sensitive-content guard updated: {:?}", guard);
+    Ok(guard)
+}
+
+#[tauri::command]
+pub fn get_duplicate_scan_locations(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<Vec<String>, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.duplicate_scan_locations.clone())
+}
+
+#[tauri::command]
+pub fn set_duplicate_scan_locations(
+    locations: Vec<String>,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<Vec<String>, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_duplicate_scan_locations(locations.clone());
+    Ok(locations)
+}
+
+#[tauri::command]
+pub fn get_producer_rules(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::ProducerRules, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.producer_rules.clone())
+}
+
+#[tauri::command]
+pub fn set_producer_rules(
+    rules: crate::config::ProducerRules,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::ProducerRules, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_producer_rules(rules.clone());
+    info!("[config] Producer rules updated: {:?}", rules);
+    Ok(rules)
+}
+
+#[tauri::command]
+pub fn get_checksum_mode(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::ChecksumMode, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.checksum_mode)
+}
+
+#[tauri::command]
+pub fn set_checksum_mode(
+    mode: crate::config::ChecksumMode,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::ChecksumMode, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_checksum_mode(mode);
+    info!("[config] Checksum mode changed: {:?}", mode);
+    Ok(mode)
+}
+
+#[tauri::command]
+pub fn get_high_depth(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::HighDepthPolicy, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.high_depth)
+}
+
+#[tauri::command]
+pub fn set_high_depth(
+    policy: crate::config::HighDepthPolicy,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::HighDepthPolicy, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_high_depth(policy);
+    info!("[config] High bit depth policy changed: {:?}", policy);
+    Ok(policy)
+}
+
+#[tauri::command]
+pub fn get_duplicate_downloads(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::DuplicateDownloadPolicy, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.duplicate_downloads)
+}
+
+#[tauri::command]
+pub fn set_duplicate_downloads(
+    policy: crate::config::DuplicateDownloadPolicy,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::DuplicateDownloadPolicy, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_duplicate_downloads(policy);
+    info!("[config] Duplicate download policy changed: {:?}", policy);
+    Ok(policy)
+}
+
+#[tauri::command]
+pub fn get_collision_policy(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::CollisionPolicy, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.collision_policy)
+}
+
+#[tauri::command]
+pub fn set_collision_policy(
+    policy: crate::config::CollisionPolicy,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::CollisionPolicy, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_collision_policy(policy);
+    info!("[config] Collision policy changed: {:?}", policy);
+    Ok(policy)
+}
+
+#[tauri::command]
+pub fn get_temp_config(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::TempConfig, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.temp.clone())
+}
+
+/// Save the temp settings and trim the (possibly new) temp folder to them.
+#[tauri::command]
+pub fn set_temp_config(
+    temp: crate::config::TempConfig,
+    app: tauri::AppHandle,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::TempConfig, String> {
+    if let Some(dir) = temp.directory.as_deref() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Can't use {dir}: {e}"))?;
+    }
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_temp(temp.clone());
+    info!("[config] Temp settings changed: {:?}", temp);
+    crate::cache::configure(&app, &temp);
+    crate::cache::trim();
+    Ok(temp)
+}
+
+/// Delete temp files, returning how many bytes were freed.
+#[tauri::command]
+pub fn clear_cache() -> u64 {
+    crate::cache::clear()
+}
+
+#[tauri::command]
+pub fn get_recent_files(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::RecentFilesConfig, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.recent_files.clone())
+}
+
+#[tauri::command]
+pub fn set_recent_files(
+    recent_files: crate::config::RecentFilesConfig,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::RecentFilesConfig, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_recent_files(recent_files.clone());
+    info!("[config] Recent files policy changed: {:?}", recent_files);
+    Ok(recent_files)
+}
+
+/// Delete a numbered re-download after checking again that it still matches
+/// the earlier download.
+#[tauri::command]
+pub async fn delete_duplicate_download(app: tauri::AppHandle, path: String) -> Result<(), String> {
+    let path = std::path::PathBuf::from(path);
+    let duplicate = crate::processor::find_duplicate_download(&app, &path)
+        .ok_or_else(|| format!("{} is not a duplicate download", path.display()))?;
+    crate::auth::confirm(&app, "delete a download").await?;
+    std::fs::remove_file(&path).map_err(|e| e.to_string())?;
+    info!(
+        "[duplicates] Deleted {}, a duplicate of {}",
+        duplicate.path, duplicate.original_path
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_reuse_redownloads(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.reuse_redownloads)
+}
+
+#[tauri::command]
+pub fn set_reuse_redownloads(
+    enabled: bool,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_reuse_redownloads(enabled);
+    info!("[config] Re-download reuse changed: {}", enabled);
+    Ok(enabled)
+}
+
+#[tauri::command]
+pub fn get_preserve_metadata(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.preserve_metadata)
+}
+
+#[tauri::command]
+pub fn set_preserve_metadata(
+    enabled: bool,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_preserve_metadata(enabled);
+    info!("[config] Metadata preservation changed: {}", enabled);
+    Ok(enabled)
+}
+
+#[tauri::command]
+pub fn get_strip_location(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.strip_location)
+}
+
+#[tauri::command]
+pub fn set_strip_location(
+    enabled: bool,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_strip_location(enabled);
+    info!("[config] Location stripping changed: {}", enabled);
+    Ok(enabled)
+}
+
+#[tauri::command]
+pub fn get_keep_icc_profile(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.keep_icc_profile)
+}
+
+#[tauri::command]
+pub fn set_keep_icc_profile(
+    enabled: bool,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_keep_icc_profile(enabled);
+    info!("[config] Keep ICC profile changed: {}", enabled);
+    Ok(enabled)
+}
+
+#[tauri::command]
+pub fn get_keep_cmyk(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.keep_cmyk)
+}
+
+#[tauri::command]
+pub fn set_keep_cmyk(
+    enabled: bool,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_keep_cmyk(enabled);
+    info!("[config] Keep CMYK changed: {}", enabled);
+    Ok(enabled)
+}
+
+#[tauri::command]
+pub fn get_trim_borders(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.trim_borders)
+}
+
+#[tauri::command]
+pub fn set_trim_borders(
+    enabled: bool,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_trim_borders(enabled);
+    info!("[config] Trim borders changed: {}", enabled);
+    Ok(enabled)
+}
+
+#[tauri::command]
+pub fn get_require_authentication(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.require_authentication)
+}
+
+/// Turning the guard off needs the same authentication it enforces.
+#[tauri::command]
+pub async fn set_require_authentication(
+    enabled: bool,
+    app: tauri::AppHandle,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    if !enabled {
+        crate::auth::confirm(&app, "turn off authentication").await?;
+    }
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_require_authentication(enabled);
+    info!("[config] Require authentication changed: {}", enabled);
+    Ok(enabled)
+}
+
+#[tauri::command]
+pub fn get_replace_in_place(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.replace_in_place)
+}
+
+/// Overwriting originals from now on is confirmed like other deletions.
+#[tauri::command]
+pub async fn set_replace_in_place(
+    enabled: bool,
+    app: tauri::AppHandle,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    if enabled {
+        // Refuse before asking, not after
+        config
+            .lock()
+            .map_err(|e| e.to_string())?
+            .config
+            .check_replace_in_place()?;
+        crate::auth::confirm(&app, "replace originals with their outputs").await?;
+    }
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_replace_in_place(enabled)?;
+    info!("[config] Replace in place changed: {}", enabled);
+    Ok(enabled)
+}
+
+/// Restore the original an output replaced and forget the record.
+#[tauri::command]
+pub async fn undo_replace(record_id: u64, app: tauri::AppHandle) -> Result<(), String> {
+    crate::replace::undo(&app, record_id)
+}
+
+#[tauri::command]
+pub fn get_watermark(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::WatermarkConfig, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.watermark.clone())
+}
+
+#[tauri::command]
+pub fn set_watermark(
+    watermark: crate::config::WatermarkConfig,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::WatermarkConfig, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_watermark(watermark.clone());
+    info!("[config] Watermark changed: {:?}", watermark);
+    Ok(watermark)
+}
+
+/// Maximum threads and encoder effort for `minutes`, e.g. before packing up
+/// a laptop. Normal limits come back on their own afterwards.
+#[tauri::command]
+pub fn run_turbo(minutes: u32, app: tauri::AppHandle) -> Result<crate::turbo::TurboStatus, String> {
+    if minutes == 0 || minutes > crate::turbo::MAX_MINUTES {
+        return Err(format!(
+            "Turbo runs for 1 to {} minutes",
+            crate::turbo::MAX_MINUTES
+        ));
+    }
+    Ok(crate::turbo::start(&app, minutes))
+}
+
+#[tauri::command]
+pub fn stop_turbo(app: tauri::AppHandle) -> crate::turbo::TurboStatus {
+    crate::turbo::stop(&app)
+}
+
+#[tauri::command]
+pub fn get_turbo_status() -> crate::turbo::TurboStatus {
+    crate::turbo::status()
+}
+
+#[tauri::command]
+pub fn get_watch_debug() -> bool {
+    crate::watcher::WATCH_DEBUG.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+/// Start or stop emitting `watch-debug` events. Not persisted.
+#[tauri::command]
+pub fn set_watch_debug(enabled: bool) -> bool {
+    crate::watcher::WATCH_DEBUG.store(enabled, std::sync::atomic::Ordering::Relaxed);
+    info!("[watcher] Watch debugging changed: {}", enabled);
+    enabled
+}
+
+#[tauri::command]
+pub fn get_skip_log(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.skip_log)
+}
+
+#[tauri::command]
+pub fn set_skip_log(
+    enabled: bool,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_skip_log(enabled);
+    info!("[config] Skip log changed: {}", enabled);
+    Ok(enabled)
+}
+
+#[tauri::command]
+pub fn get_warm_start(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.warm_start)
+}
+
+/// Takes effect from the next launch or engine reload.
+#[tauri::command]
+pub fn set_warm_start(
+    enabled: bool,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_warm_start(enabled);
+    info!("[config] Warm start changed: {}", enabled);
+    Ok(enabled)
+}
+
+/// Files left alone most recently and why, newest first. Empty unless the
+/// skip log is on.
+#[tauri::command]
+pub fn get_skipped(limit: Option<usize>) -> Vec<crate::skips::SkippedFile> {
+    crate::skips::recent(limit.unwrap_or(100))
+}
+
+#[tauri::command]
+pub fn get_deterministic(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.deterministic)
+}
+
+#[tauri::command]
+pub fn set_deterministic(
+    enabled: bool,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<bool, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_deterministic(enabled);
+    info!("[config] Deterministic output changed: {}", enabled);
+    Ok(enabled)
+}
+
+#[tauri::command]
+pub fn get_profiles(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<Vec<crate::config::CompressionProfile>, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.profiles.clone())
+}
+
+#[tauri::command]
+pub fn set_profiles(
+    profiles: Vec<crate::config::CompressionProfile>,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<Vec<crate::config::CompressionProfile>, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_profiles(profiles.clone())?;
+    info!("[config] {} compression profiles saved", profiles.len());
+    Ok(profiles)
+}
+
+/// Add the "Eco" preset of fast, low-effort encoder settings to the profiles.
+#[tauri::command]
+pub fn add_eco_profile(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<Vec<crate::config::CompressionProfile>, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.add_eco_profile();
+    info!("[config] Eco profile added");
+    Ok(config_manager.config.profiles.clone())
+}
+
+#[tauri::command]
+pub fn get_context_rules(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<Vec<crate::config::ContextRule>, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.context_rules.clone())
+}
+
+#[tauri::command]
+pub fn set_context_rules(
+    app: tauri::AppHandle,
+    rules: Vec<crate::config::ContextRule>,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<Vec<crate::config::ContextRule>, String> {
+    {
+        let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+        config_manager.set_context_rules(rules.clone());
+        info!("[config] Context rules updated: {:?}", rules);
+    }
+    // Apply right away instead of waiting for the next poll
+    if let Some(profile) = crate::context::apply(&app, &crate::context::current()) {
+        let _ = app.emit("profile-changed", &profile);
+    }
+    Ok(rules)
+}
+
+#[tauri::command]
+pub fn get_context_status(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::context::ContextStatus, String> {
+    let context = crate::context::current();
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(crate::context::ContextStatus {
+        context,
+        active_profile: config_manager.config.active_profile.clone(),
+    })
+}
+
+#[tauri::command]
+pub fn get_pdf_config(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::PdfConfig, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.pdf.clone())
+}
+
+#[tauri::command]
+pub fn set_pdf_config(
+    pdf: crate::config::PdfConfig,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::PdfConfig, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_pdf_config(pdf.clone());
+    info!("[config] PDF settings updated: {:?}", pdf);
+    Ok(pdf)
+}
+
+#[tauri::command]
+pub fn get_svg_config(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::SvgConfig, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.svg.clone())
+}
+
+#[tauri::command]
+pub fn set_svg_config(
+    svg: crate::config::SvgConfig,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::SvgConfig, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_svg_config(svg.clone());
+    info!("[config] SVG settings updated: {:?}", svg);
+    Ok(svg)
+}
+
+/// Resetting also turns authentication off, so it is confirmed first.
+#[tauri::command]
+pub async fn reset_config(
+    app: tauri::AppHandle,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+    watcher_state: tauri::State<'_, crate::watcher::WatcherHandle>,
+) -> Result<(), String> {
+    crate::auth::confirm(&app, "reset settings").await?;
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+
+    // 1. Unwatch all current folders
+    let mut watcher = watcher_state.watcher.lock().map_err(|e| e.to_string())?;
+    if let Some(ref mut w) = *watcher {
+        for folder in &config_manager.config.watched_folders {
+            let _ = w.unwatch(Path::new(folder));
+        }
+    }
+
+    // 2. Reset config to default
+    config_manager.reset()?;
+
+    // 3. Watch new default folders
+    if let Some(ref mut w) = *watcher {
+        for folder in &config_manager.config.watched_folders {
+            let path = Path::new(folder);
+            if path.exists() {
+                if let Err(e) = w.watch(path, notify::RecursiveMode::NonRecursive) {
+                    error!("Failed to watch directory {}: {}", folder, e);
+                } else {
+                    info!("Watching directory: {}", folder);
+                }
+            }
+        }
+    }
+
+    info!("[config] Config reset to default");
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_compression_history(
+    log: tauri::State<'_, Mutex<crate::log::CompressionLog>>,
+) -> Vec<CompressionRecord> {
+    log.lock().map(|l| l.records.clone()).unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn get_history_retention(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::HistoryRetention, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.history_retention.clone())
+}
+
+/// Save new retention limits and prune the history to them right away.
+#[tauri::command]
+pub fn set_history_retention(
+    retention: crate::config::HistoryRetention,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+    log: tauri::State<'_, Mutex<crate::log::CompressionLog>>,
+) -> Result<crate::config::HistoryRetention, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_history_retention(retention.clone());
+    info!("[config] History retention changed: {:?}", retention);
+    log.lock()
+        .map_err(|e| e.to_string())?
+        .set_retention(retention.clone());
+    Ok(retention)
+}
+
+/// All-time totals, including records that have been pruned.
+#[tauri::command]
+pub fn get_history_totals(
+    log: tauri::State<'_, Mutex<crate::log::CompressionLog>>,
+) -> Result<crate::log::HistoryTotals, String> {
+    Ok(log.lock().map_err(|e| e.to_string())?.totals())
+}
+
+#[tauri::command]
+pub fn get_history_rollup(
+    log: tauri::State<'_, Mutex<crate::log::CompressionLog>>,
+) -> Result<Vec<crate::log::DailyRollup>, String> {
+    Ok(log.lock().map_err(|e| e.to_string())?.rollup().to_vec())
+}
+
+/// Daily size samples of a watched folder, oldest first.
+#[tauri::command]
+pub fn get_folder_trend(
+    path: String,
+    log: tauri::State<'_, Mutex<crate::log::CompressionLog>>,
+) -> Result<Vec<crate::log::FolderSample>, String> {
+    Ok(log.lock().map_err(|e| e.to_string())?.folder_trend(&path))
+}
+
+#[tauri::command]
+pub fn get_history_as_of(
+    timestamp: u64,
+    log: tauri::State<'_, Mutex<crate::log::CompressionLog>>,
+) -> Result<Vec<CompressionRecord>, String> {
+    Ok(log.lock().map_err(|e| e.to_string())?.as_of(timestamp))
+}
+
+/// Formats seen in a folder over the last 90 days, so settings can focus on them.
+#[tauri::command]
+pub fn get_seen_formats(
+    folder: String,
+    log: tauri::State<'_, Mutex<crate::log::CompressionLog>>,
+) -> Result<Vec<crate::log::SeenFormat>, String> {
+    const WINDOW_SECS: u64 = 90 * 24 * 60 * 60;
+    let since = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        .saturating_sub(WINDOW_SECS);
+    Ok(log
+        .lock()
+        .map_err(|e| e.to_string())?
+        .seen_formats(Path::new(&folder), since))
+}
+
+/// Move outputs written under the `from` naming scheme to where `to` puts them.
+#[tauri::command]
+pub fn migrate_outputs(
+    from: crate::config::OutputNaming,
+    to: crate::config::OutputNaming,
+    dry_run: bool,
+    log: tauri::State<'_, Mutex<crate::log::CompressionLog>>,
+) -> Result<crate::migrate::MigrationReport, String> {
+    let mut log = log.lock().map_err(|e| e.to_string())?;
+    Ok(crate::migrate::migrate_outputs(
+        &mut log, &from, &to, dry_run,
+    ))
+}
+
+/// Outputs in watched folders with no history record or a vanished original.
+#[tauri::command]
+pub fn find_orphaned_outputs(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+    log: tauri::State<'_, Mutex<crate::log::CompressionLog>>,
+) -> Result<Vec<crate::orphans::OrphanedOutput>, String> {
+    let (folders, naming) = {
+        let config_manager = config.lock().map_err(|e| e.to_string())?;
+        (
+            config_manager.config.watched_folders.clone(),
+            config_manager.config.output_naming.clone(),
+        )
+    };
+    let log = log.lock().map_err(|e| e.to_string())?;
+    Ok(crate::orphans::find_orphaned_outputs(
+        &folders, &naming, &log,
+    ))
+}
+
+#[tauri::command]
+pub async fn delete_orphaned_outputs(
+    paths: Vec<String>,
+    app: tauri::AppHandle,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<Vec<String>, String> {
+    crate::auth::confirm(&app, &format!("delete {} files", paths.len())).await?;
+    let naming = config
+        .lock()
+        .map_err(|e| e.to_string())?
+        .config
+        .output_naming
+        .clone();
+    Ok(crate::orphans::delete_outputs(&paths, &naming))
+}
+
+#[tauri::command]
+pub fn adopt_orphaned_outputs(
+    paths: Vec<String>,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+    log: tauri::State<'_, Mutex<crate::log::CompressionLog>>,
+) -> Result<Vec<CompressionRecord>, String> {
+    let naming = config
+        .lock()
+        .map_err(|e| e.to_string())?
+        .config
+        .output_naming
+        .clone();
+    let mut log = log.lock().map_err(|e| e.to_string())?;
+    Ok(crate::orphans::adopt_outputs(&paths, &naming, &mut log))
+}
+
+#[tauri::command]
+pub fn get_locale_config(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::LocaleConfig, String> {
     let config_manager = config.lock().map_err(|e| e.to_string())?;
-    Ok(config_manager.config.show_background_notification)
+    Ok(config_manager.config.locale.clone())
 }
 
 #[tauri::command]
-pub fn set_show_background_notification(
-    value: bool,
+pub fn set_locale_config(
+    locale: crate::config::LocaleConfig,
     config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
-) -> Result<bool, String> {
+) -> Result<crate::config::LocaleConfig, String> {
     let mut config_manager = config.lock().map_err(|e| e.to_string())?;
-    config_manager.set_show_background_notification(value);
-    Ok(value)
+    config_manager.set_locale(locale.clone());
+    info!("[config] Locale settings updated: {:?}", locale);
+    Ok(locale)
 }
 
 #[tauri::command]
-pub fn get_show_system_notifications(
+pub fn get_smallest_wins(
     config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
-) -> Result<bool, String> {
+) -> Result<crate::config::SmallestWinsConfig, String> {
     let config_manager = config.lock().map_err(|e| e.to_string())?;
-    Ok(config_manager.config.show_system_notifications)
+    Ok(config_manager.config.smallest_wins.clone())
 }
 
 #[tauri::command]
-pub fn set_show_system_notifications(
-    value: bool,
+pub fn set_smallest_wins(
+    smallest_wins: crate::config::SmallestWinsConfig,
     config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
-) -> Result<bool, String> {
+) -> Result<crate::config::SmallestWinsConfig, String> {
+    if let Some(bad) = smallest_wins
+        .candidates
+        .iter()
+        .find(|c| !ImageFormat::from_extension(c).is_some_and(|f| f.is_writable()))
+    {
+        return Err(format!("Can't write {bad} files"));
+    }
     let mut config_manager = config.lock().map_err(|e| e.to_string())?;
-    config_manager.set_show_system_notifications(value);
-    Ok(value)
+    config_manager.set_smallest_wins(smallest_wins.clone());
+    info!(
+        "[config] Smallest-wins settings updated: {:?}",
+        smallest_wins
+    );
+    Ok(smallest_wins)
 }
 
 #[tauri::command]
-pub fn get_format_options(
+pub fn get_target_format(
     config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
-) -> Result<crate::config::FormatOptions, String> {
+) -> Result<Option<String>, String> {
     let config_manager = config.lock().map_err(|e| e.to_string())?;
-    Ok(config_manager.config.format_options.clone())
+    Ok(config_manager.config.target_format.clone())
 }
 
 #[tauri::command]
-pub fn set_format_options(
-    options: crate::config::FormatOptions,
+pub fn set_target_format(
+    format: Option<String>,
     config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
-) -> Result<crate::config::FormatOptions, String> {
+) -> Result<Option<String>, String> {
+    if let Some(name) = format.as_deref() {
+        if !ImageFormat::from_extension(name).is_some_and(|f| f.is_writable()) {
+            return Err(format!("Can't convert images to {name}"));
+        }
+    }
     let mut config_manager = config.lock().map_err(|e| e.to_string())?;
-    config_manager.set_format_options(options.clone());
-    info!("[config] Format options updated: {:?}", options);
-    Ok(options)
+    config_manager.set_target_format(format.clone());
+    info!("[config] Target format changed: {:?}", format);
+    Ok(format)
 }
 
 #[tauri::command]
-pub fn reset_config(
+pub fn get_output_naming(
     config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
-    watcher_state: tauri::State<'_, crate::watcher::WatcherHandle>,
-) -> Result<(), String> {
-    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+) -> Result<crate::config::OutputNaming, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.output_naming.clone())
+}
 
-    // 1. Unwatch all current folders
-    let mut watcher = watcher_state.watcher.lock().map_err(|e| e.to_string())?;
-    if let Some(ref mut w) = *watcher {
-        for folder in &config_manager.config.watched_folders {
-            let _ = w.unwatch(Path::new(folder));
-        }
+#[tauri::command]
+pub fn set_output_naming(
+    naming: crate::config::OutputNaming,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::config::OutputNaming, String> {
+    if naming.suffix.is_empty() && naming.directory.is_none() {
+        return Err("Outputs need a suffix or a separate folder".to_string());
     }
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_output_naming(naming.clone());
+    info!("[config] Output naming changed: {:?}", naming);
+    Ok(naming)
+}
 
-    // 2. Reset config to default
-    config_manager.reset()?;
+#[tauri::command]
+pub fn get_output_dir(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<Option<String>, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.output_dir.clone())
+}
 
-    // 3. Watch new default folders
-    if let Some(ref mut w) = *watcher {
-        for folder in &config_manager.config.watched_folders {
-            let path = Path::new(folder);
-            if path.exists() {
-                if let Err(e) = w.watch(path, notify::RecursiveMode::NonRecursive) {
-                    error!("Failed to watch directory {}: {}", folder, e);
-                } else {
-                    info!("Watching directory: {}", folder);
-                }
-            }
-        }
-    }
+/// None or an empty path goes back to writing outputs per `output_naming`.
+#[tauri::command]
+pub fn set_output_dir(
+    dir: Option<String>,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<Option<String>, String> {
+    let dir = dir.filter(|d| !d.trim().is_empty());
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_output_dir(dir.clone());
+    info!("[config] Output directory changed: {:?}", dir);
+    Ok(dir)
+}
 
-    info!("[config] Config reset to default");
-    Ok(())
+#[tauri::command]
+pub fn get_archive_dir(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<Option<String>, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.archive_dir.clone())
 }
 
+/// None or an empty path leaves originals where they are.
 #[tauri::command]
-pub fn get_compression_history(
+pub fn set_archive_dir(
+    dir: Option<String>,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<Option<String>, String> {
+    let dir = dir.filter(|d| !d.trim().is_empty());
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_archive_dir(dir.clone())?;
+    info!("[config] Archive directory changed: {:?}", dir);
+    Ok(dir)
+}
+
+#[tauri::command]
+pub async fn get_restore_plan(
+    timestamp: u64,
+    log: tauri::State<'_, Mutex<crate::log::CompressionLog>>,
+) -> Result<crate::log::RestorePlan, String> {
+    Ok(log
+        .lock()
+        .map_err(|e| e.to_string())?
+        .restore_plan(timestamp))
+}
+
+/// Count and size of the originals delete-originals would remove, for the
+/// confirmation before turning it on.
+#[tauri::command]
+pub async fn estimate_delete_originals(
+    log: tauri::State<'_, Mutex<crate::log::CompressionLog>>,
+) -> Result<crate::log::DeleteOriginalsEstimate, String> {
+    Ok(log
+        .lock()
+        .map_err(|e| e.to_string())?
+        .delete_originals_estimate())
+}
+
+#[tauri::command]
+pub fn search_images(
+    query: String,
     log: tauri::State<'_, Mutex<crate::log::CompressionLog>>,
 ) -> Vec<CompressionRecord> {
-    log.lock().map(|l| l.records.clone()).unwrap_or_default()
+    let query = query.to_lowercase();
+    log.lock()
+        .map(|l| {
+            l.records
+                .iter()
+                .filter(|r| {
+                    r.initial_path.to_lowercase().contains(&query)
+                        || r.ocr_text
+                            .as_ref()
+                            .is_some_and(|t| t.to_lowercase().contains(&query))
+                })
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[derive(serde::Serialize)]
+pub struct AnalysisSummary {
+    pub files: usize,
+    pub original_bytes: u64,
+    pub potential_savings: u64,
+}
+
+#[tauri::command]
+pub fn get_analysis_summary(
+    folder: Option<String>,
+    log: tauri::State<'_, Mutex<crate::log::CompressionLog>>,
+) -> Result<AnalysisSummary, String> {
+    let log = log.lock().map_err(|e| e.to_string())?;
+    let mut summary = AnalysisSummary {
+        files: 0,
+        original_bytes: 0,
+        potential_savings: 0,
+    };
+    for record in log.records.iter().filter(|r| r.analysis_only) {
+        let in_folder = folder
+            .as_ref()
+            .is_none_or(|f| Path::new(&record.initial_path).parent() == Some(Path::new(f)));
+        if in_folder {
+            summary.files += 1;
+            summary.original_bytes += record.initial_size;
+            summary.potential_savings += record.initial_size.saturating_sub(record.compressed_size);
+        }
+    }
+    Ok(summary)
+}
+
+#[tauri::command]
+pub async fn verify_outputs(
+    log: tauri::State<'_, Mutex<crate::log::CompressionLog>>,
+) -> Result<crate::checksum::VerifyReport, String> {
+    let records = log.lock().map_err(|e| e.to_string())?.records.clone();
+    Ok(crate::checksum::verify(&records))
 }
 
 #[tauri::command]
@@ -143,20 +1308,7 @@ pub fn recompress(
     app: tauri::AppHandle,
     vips_state: tauri::State<'_, VipsState>,
 ) -> Result<(), String> {
-    let vips = vips_state
-        .inner()
-        .vips
-        .as_ref()
-        .ok_or("libvips not available")?;
-    let input = Path::new(&path);
-
-    let format =
-        ImageFormat::from_path(input).ok_or_else(|| "Unsupported image format".to_string())?;
-    let output = compressed_output_path(input, None)
-        .ok_or_else(|| "Could not determine output path".to_string())?;
-    let initial_size = std::fs::metadata(input)
-        .map(|m| m.len())
-        .map_err(|e| e.to_string())?;
+    let vips = vips_state.get().ok_or("libvips not available")?;
 
     let timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -172,42 +1324,80 @@ pub fn recompress(
         },
     );
 
-    let flags = app
-        .state::<Mutex<crate::config::ConfigManager>>()
-        .lock()
-        .map(|c| {
-            let opts = &c.config.format_options;
-            CompressionFlags::from_format_options(opts, format)
-        })
-        .unwrap_or_default();
-
     let quality: u8 = previous_quality.saturating_add(10).min(100);
-    let compressed_size = match vips.compress(input, &output, quality, &flags, None) {
-        Ok(s) => s,
-        Err(e) => {
-            let err_msg = e.to_string();
+    match recompress_file(&app, &vips, Path::new(&path), quality, None) {
+        Ok(record) => {
+            let _ = app.emit("compression-complete", &record);
+            Ok(())
+        }
+        Err(err_msg) => {
             let _ = app.emit(
                 "compression-failed",
                 &crate::processor::CompressionFailed {
-                    initial_path: path.clone(),
+                    initial_path: path,
                     timestamp,
                     error: err_msg.clone(),
                 },
             );
-            return Err(err_msg);
+            Err(err_msg)
         }
-    };
+    }
+}
+
+/// Encode `input` again at `quality`, optionally as `target`, and log the result.
+fn recompress_file(
+    app: &tauri::AppHandle,
+    vips: &crate::compression::Vips,
+    input: &Path,
+    quality: u8,
+    target: Option<ImageFormat>,
+) -> Result<CompressionRecord, String> {
+    let format =
+        ImageFormat::from_path(input).ok_or_else(|| "Unsupported image format".to_string())?;
+    let dest_format = target.unwrap_or(format);
+    let extension = target.map(|f| f.extension());
+    let output = compressed_output_path(input, extension)
+        .ok_or_else(|| "Could not determine output path".to_string())?;
+    let initial_size = std::fs::metadata(input)
+        .map(|m| m.len())
+        .map_err(|e| e.to_string())?;
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    let flags = app
+        .state::<Mutex<crate::config::ConfigManager>>()
+        .lock()
+        .map(|c| CompressionFlags {
+            deterministic: c.config.deterministic,
+            ..CompressionFlags::from_format_options(c.config.active_format_options(), dest_format)
+        })
+        .unwrap_or_default();
+
+    let compressed_size = vips
+        .compress(input, &output, quality, &flags, target)
+        .map_err(|e| e.to_string())?;
 
     let record = CompressionRecord {
-        initial_path: path.clone(),
+        initial_path: input.display().to_string(),
         final_path: output.display().to_string(),
         initial_size,
         compressed_size,
         initial_format: format.to_string(),
-        final_format: format.to_string(),
+        final_format: dest_format.to_string(),
         quality,
         timestamp,
         original_deleted: false,
+        deterministic: flags.deterministic,
+        settings: Some(EncodeSettings {
+            engine: EncodeEngine::Libvips,
+            format: dest_format,
+            quality,
+            flags,
+        }),
+        ..Default::default()
     };
 
     info!(
@@ -219,13 +1409,189 @@ pub fn recompress(
         quality
     );
 
-    let _ = app.emit("compression-complete", &record);
     let log = app.state::<Mutex<crate::log::CompressionLog>>();
-    if let Ok(mut log) = log.lock() {
-        log.append(record);
+    let record = match log.lock() {
+        Ok(mut log) => log.append(record),
+        Err(_) => record,
+    };
+    Ok(record)
+}
+
+/// Compress `path` with exactly the engine, format, quality and flags that
+/// produced record `record_id`, to reproduce a result or a report.
+#[tauri::command]
+pub fn repeat_compression(
+    record_id: u64,
+    path: String,
+    app: tauri::AppHandle,
+    vips_state: tauri::State<'_, VipsState>,
+) -> Result<CompressionRecord, String> {
+    let settings = {
+        let log = app.state::<Mutex<crate::log::CompressionLog>>();
+        let log = log.lock().map_err(|e| e.to_string())?;
+        let record = log
+            .find(record_id)
+            .ok_or_else(|| format!("Unknown task id: {}", record_id))?;
+        record
+            .settings
+            .clone()
+            .ok_or_else(|| format!("Task {} has no recorded settings", record_id))?
+    };
+    let vips = vips_state.get();
+    crate::processor::repeat(&app, vips.as_deref(), Path::new(&path), &settings)
+}
+
+/// What to change when recompressing a selection of tasks.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct RecompressOptions {
+    /// Quality for every task; each task's previous quality + 10 when unset.
+    pub quality: Option<u8>,
+    /// Output format extension; each task keeps its format when unset.
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TaskFailure {
+    pub id: u64,
+    pub error: String,
+}
+
+/// Outcome of a bulk task operation, also emitted as `tasks-bulk-complete`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BulkSummary {
+    pub operation: String,
+    pub requested: usize,
+    pub succeeded: usize,
+    pub failed: Vec<TaskFailure>,
+    /// Records written by a retry or recompress.
+    pub records: Vec<CompressionRecord>,
+    pub energy: crate::energy::EnergyEstimate,
+}
+
+/// The records for `ids`, or an error naming any that don't exist so a
+/// selection is either handled whole or not at all.
+fn select_tasks(app: &tauri::AppHandle, ids: &[u64]) -> Result<Vec<CompressionRecord>, String> {
+    let log = app.state::<Mutex<crate::log::CompressionLog>>();
+    let log = log.lock().map_err(|e| e.to_string())?;
+    let missing: Vec<String> = ids
+        .iter()
+        .filter(|id| log.find(**id).is_none())
+        .map(|id| id.to_string())
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("Unknown task ids: {}", missing.join(", ")));
     }
+    Ok(ids.iter().filter_map(|id| log.find(*id).cloned()).collect())
+}
 
-    Ok(())
+/// Run `f` over `tasks` in parallel and emit one summary for the lot.
+fn run_bulk(
+    app: &tauri::AppHandle,
+    operation: &str,
+    tasks: Vec<CompressionRecord>,
+    f: impl Fn(&CompressionRecord) -> Result<CompressionRecord, String> + Sync,
+) -> BulkSummary {
+    use rayon::prelude::*;
+
+    let meter = crate::energy::Meter::start();
+    let results: Vec<(u64, Result<CompressionRecord, String>)> =
+        tasks.par_iter().map(|task| (task.id, f(task))).collect();
+    let energy = meter.finish(app);
+    let mut summary = BulkSummary {
+        operation: operation.to_string(),
+        requested: tasks.len(),
+        succeeded: 0,
+        failed: Vec::new(),
+        records: Vec::new(),
+        energy,
+    };
+    for (id, result) in results {
+        match result {
+            Ok(record) => {
+                summary.succeeded += 1;
+                summary.records.push(record);
+            }
+            Err(error) => summary.failed.push(TaskFailure { id, error }),
+        }
+    }
+    info!(
+        "[tasks] Bulk {}: {} of {} succeeded",
+        operation, summary.succeeded, summary.requested
+    );
+    let _ = app.emit("tasks-bulk-complete", &summary);
+    summary
+}
+
+#[tauri::command]
+pub fn delete_tasks(ids: Vec<u64>, app: tauri::AppHandle) -> Result<BulkSummary, String> {
+    select_tasks(&app, &ids)?;
+    let removed = app
+        .state::<Mutex<crate::log::CompressionLog>>()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&ids);
+    info!("[tasks] Bulk delete: removed {} records", removed);
+    let summary = BulkSummary {
+        operation: "delete".to_string(),
+        requested: ids.len(),
+        succeeded: removed,
+        failed: Vec::new(),
+        records: Vec::new(),
+        energy: crate::energy::EnergyEstimate::default(),
+    };
+    let _ = app.emit("tasks-bulk-complete", &summary);
+    Ok(summary)
+}
+
+/// Run each selected task's original through the pipeline again.
+#[tauri::command]
+pub async fn retry_tasks(
+    ids: Vec<u64>,
+    app: tauri::AppHandle,
+    vips_state: tauri::State<'_, VipsState>,
+) -> Result<BulkSummary, String> {
+    let tasks = select_tasks(&app, &ids)?;
+    let vips = vips_state.get();
+    let paths: Vec<_> = tasks
+        .iter()
+        .map(|t| std::path::PathBuf::from(&t.initial_path))
+        .collect();
+    crate::tasks::enqueue(&app, &paths, crate::processor::InputMode::Manual);
+    Ok(run_bulk(&app, "retry", tasks, |task| {
+        crate::processor::process_any(
+            &app,
+            vips.as_ref(),
+            Path::new(&task.initial_path),
+            crate::processor::InputMode::Manual,
+        )
+    }))
+}
+
+#[tauri::command]
+pub async fn recompress_tasks(
+    ids: Vec<u64>,
+    options: RecompressOptions,
+    app: tauri::AppHandle,
+    vips_state: tauri::State<'_, VipsState>,
+) -> Result<BulkSummary, String> {
+    let vips = vips_state.get().ok_or("libvips not available")?;
+    let target = options
+        .format
+        .as_deref()
+        .map(|name| {
+            ImageFormat::from_extension(name)
+                .ok_or_else(|| format!("Unsupported target format: {}", name))
+        })
+        .transpose()?;
+    let tasks = select_tasks(&app, &ids)?;
+    Ok(run_bulk(&app, "recompress", tasks, |task| {
+        let quality = options
+            .quality
+            .unwrap_or_else(|| task.quality.saturating_add(10))
+            .clamp(1, 100);
+        recompress_file(&app, &vips, Path::new(&task.initial_path), quality, target)
+    }))
 }
 
 #[tauri::command]
@@ -235,11 +1601,7 @@ pub fn convert_image(
     app: tauri::AppHandle,
     vips_state: tauri::State<'_, VipsState>,
 ) -> Result<(), String> {
-    let vips = vips_state
-        .inner()
-        .vips
-        .as_ref()
-        .ok_or("libvips not available")?;
+    let vips = vips_state.get().ok_or("libvips not available")?;
     let input = Path::new(&path);
 
     let source_format =
@@ -270,7 +1632,7 @@ pub fn convert_image(
     let (quality, flags) = config
         .lock()
         .map(|c| {
-            let opts = &c.config.format_options;
+            let opts = c.config.active_format_options();
             let q = match dest_format {
                 ImageFormat::Png => opts.png.quality,
                 ImageFormat::Jpeg => opts.jpeg.quality,
@@ -278,8 +1640,15 @@ pub fn convert_image(
                 ImageFormat::Avif => opts.avif.quality,
                 ImageFormat::Heif => opts.heif.quality,
                 ImageFormat::Tiff => opts.tiff.quality,
+                ImageFormat::Gif => opts.gif.quality,
+                ImageFormat::Jxl => opts.jxl.quality,
+                ImageFormat::Raw => opts.raw.quality,
+                ImageFormat::Psd => opts.psd.quality,
+            };
+            let flags = CompressionFlags {
+                deterministic: c.config.deterministic,
+                ..CompressionFlags::from_format_options(opts, dest_format)
             };
-            let flags = CompressionFlags::from_format_options(opts, dest_format);
             (q, flags)
         })
         .unwrap_or((crate::DEFAULT_QUALITY, CompressionFlags::default()));
@@ -300,30 +1669,322 @@ pub fn convert_image(
         }
     };
 
-    let record = CompressionRecord {
-        initial_path: path.clone(),
-        final_path: output.display().to_string(),
-        initial_size,
-        compressed_size,
-        initial_format: source_format.to_string(),
-        final_format: dest_format.to_string(),
-        quality,
-        timestamp,
-        original_deleted: false,
+    let record = CompressionRecord {
+        initial_path: path.clone(),
+        final_path: output.display().to_string(),
+        initial_size,
+        compressed_size,
+        initial_format: source_format.to_string(),
+        final_format: dest_format.to_string(),
+        quality,
+        timestamp,
+        original_deleted: false,
+        deterministic: flags.deterministic,
+        settings: Some(EncodeSettings {
+            engine: EncodeEngine::Libvips,
+            format: dest_format,
+            quality,
+            flags,
+        }),
+        ..Default::default()
+    };
+
+    info!(
+        "[conversion] Converted {} → {} ({} → {} bytes)",
+        record.initial_path, record.final_path, record.initial_size, record.compressed_size,
+    );
+
+    let log = app.state::<Mutex<crate::log::CompressionLog>>();
+    let record = match log.lock() {
+        Ok(mut log) => log.append(record),
+        Err(_) => record,
+    };
+    let _ = app.emit("compression-complete", &record);
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn generate_contact_sheet(
+    record_ids: Vec<u64>,
+    path: String,
+    vips_state: tauri::State<'_, VipsState>,
+    log: tauri::State<'_, Mutex<crate::log::CompressionLog>>,
+) -> Result<u64, String> {
+    let vips = vips_state.get().ok_or("libvips not available")?;
+
+    let records: Vec<CompressionRecord> = {
+        let log = log.lock().map_err(|e| e.to_string())?;
+        record_ids
+            .iter()
+            .filter_map(|id| log.find(*id).cloned())
+            .collect()
+    };
+    if records.is_empty() {
+        return Err("No matching history records".to_string());
+    }
+
+    crate::contact_sheet::generate(&vips, &records, Path::new(&path)).map_err(|e| e.to_string())
+}
+
+/// Write a multi-resolution `.ico` made from `path`. Without `out` it goes
+/// next to the input.
+#[tauri::command]
+pub fn generate_favicon(
+    path: String,
+    out: Option<String>,
+    vips_state: tauri::State<'_, VipsState>,
+) -> Result<String, String> {
+    let vips = vips_state.get().ok_or("libvips not available")?;
+
+    let input = Path::new(&path);
+    let output = out
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| input.with_extension("ico"));
+    if output == input {
+        return Err("Output would overwrite the input".to_string());
+    }
+    crate::favicon::generate(&vips, input, &output).map_err(|e| e.to_string())?;
+    Ok(output.display().to_string())
+}
+
+#[tauri::command]
+pub async fn images_to_pdf(
+    paths: Vec<String>,
+    out: String,
+    vips_state: tauri::State<'_, VipsState>,
+) -> Result<u64, String> {
+    let vips = vips_state.get().ok_or("libvips not available")?;
+    let paths: Vec<std::path::PathBuf> = paths.iter().map(std::path::PathBuf::from).collect();
+    crate::pdf::images_to_pdf(&vips, &paths, Path::new(&out)).map_err(|e| e.to_string())
+}
+
+/// A page for `create_pdf_from_images`: a history record or a file.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+pub enum PdfPageSource {
+    Record { id: u64 },
+    Path { path: String },
+}
+
+/// Compress each source with the configured JPEG settings and bundle them
+/// into one PDF, one page each in the given order. History records use
+/// their output when it is still readable, otherwise the original.
+#[tauri::command]
+pub async fn create_pdf_from_images(
+    sources: Vec<PdfPageSource>,
+    out: String,
+    vips_state: tauri::State<'_, VipsState>,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+    log: tauri::State<'_, Mutex<crate::log::CompressionLog>>,
+) -> Result<u64, String> {
+    let vips = vips_state.get().ok_or("libvips not available")?;
+    if sources.is_empty() {
+        return Err("No images to bundle".to_string());
+    }
+
+    let paths: Vec<std::path::PathBuf> = {
+        let log = log.lock().map_err(|e| e.to_string())?;
+        sources
+            .iter()
+            .map(|source| match source {
+                PdfPageSource::Path { path } => Ok(std::path::PathBuf::from(path)),
+                PdfPageSource::Record { id } => {
+                    let record = log
+                        .find(*id)
+                        .ok_or_else(|| format!("History record {id} not found"))?;
+                    let output = Path::new(&record.final_path);
+                    if !record.encrypted && !record.analysis_only && output.exists() {
+                        Ok(output.to_path_buf())
+                    } else {
+                        Ok(std::path::PathBuf::from(&record.initial_path))
+                    }
+                }
+            })
+            .collect::<Result<_, String>>()?
+    };
+
+    let (quality, flags) = {
+        let config_manager = config.lock().map_err(|e| e.to_string())?;
+        let opts = config_manager.config.active_format_options();
+        (
+            opts.jpeg.quality,
+            CompressionFlags {
+                deterministic: config_manager.config.deterministic,
+                ..CompressionFlags::from_format_options(opts, ImageFormat::Jpeg)
+            },
+        )
     };
 
-    info!(
-        "[conversion] Converted {} → {} ({} → {} bytes)",
-        record.initial_path, record.final_path, record.initial_size, record.compressed_size,
-    );
+    crate::pdf::bundle_images(&vips, &paths, quality, &flags, Path::new(&out))
+        .map_err(|e| e.to_string())
+}
 
-    let _ = app.emit("compression-complete", &record);
-    let log = app.state::<Mutex<crate::log::CompressionLog>>();
-    if let Ok(mut log) = log.lock() {
-        log.append(record);
+#[derive(serde::Serialize)]
+pub struct PdfExtraction {
+    /// One record per extracted image that compressed.
+    pub records: Vec<CompressionRecord>,
+    /// The PDF rebuilt with the smaller images, when asked for.
+    pub rebuilt: Option<String>,
+}
+
+/// Pull the embedded images out of a PDF into a `<name>_images` folder next
+/// to it and compress each one. With `rebuild`, also write a copy of the PDF
+/// that uses the compressed images wherever they are smaller.
+#[tauri::command]
+pub async fn extract_pdf_images(
+    path: String,
+    rebuild: bool,
+    app: tauri::AppHandle,
+    vips_state: tauri::State<'_, VipsState>,
+) -> Result<PdfExtraction, String> {
+    let vips = vips_state.get().ok_or("libvips not available")?;
+    let input = Path::new(&path);
+    let stem = input
+        .file_stem()
+        .ok_or_else(|| "Invalid PDF path".to_string())?
+        .to_string_lossy();
+    let out_dir = input.with_file_name(format!("{stem}_images"));
+
+    let extracted =
+        crate::pdf::extract_images(&vips, input, &out_dir).map_err(|e| e.to_string())?;
+    let mut records = Vec::new();
+    let mut replacements = Vec::new();
+    for image in &extracted {
+        match crate::processor::process_any(
+            &app,
+            Some(&vips),
+            &image.path,
+            crate::processor::InputMode::Manual,
+        ) {
+            Ok(record) => {
+                if !record.encrypted && !record.analysis_only {
+                    replacements.push((image.object, std::path::PathBuf::from(&record.final_path)));
+                }
+                records.push(record);
+            }
+            Err(e) => error!("[pdf] Failed to compress {}: {}", image.path.display(), e),
+        }
     }
 
-    Ok(())
+    let rebuilt = if rebuild {
+        let output = compressed_output_path(input, Some("pdf"))
+            .ok_or_else(|| "Invalid output path".to_string())?;
+        crate::pdf::replace_images(&vips, input, &output, &replacements)
+            .map_err(|e| e.to_string())?;
+        Some(output.display().to_string())
+    } else {
+        None
+    };
+
+    Ok(PdfExtraction { records, rebuilt })
+}
+
+#[tauri::command]
+pub async fn pdf_to_images(
+    path: String,
+    out_dir: String,
+    dpi: u32,
+    vips_state: tauri::State<'_, VipsState>,
+) -> Result<Vec<String>, String> {
+    let vips = vips_state.get().ok_or("libvips not available")?;
+    let outputs = crate::pdf::pdf_to_images(
+        &vips,
+        Path::new(&path),
+        Path::new(&out_dir),
+        dpi.clamp(36, 600),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(outputs.iter().map(|p| p.display().to_string()).collect())
+}
+
+#[tauri::command]
+pub fn get_engine_status(app: tauri::AppHandle) -> crate::engine::EngineStatus {
+    crate::engine::status(&app)
+}
+
+/// Run the engine self-check again, even if nothing was updated.
+#[tauri::command]
+pub fn run_self_check(app: tauri::AppHandle) {
+    crate::engine::start_self_check(&app, true);
+}
+
+/// Try loading libvips again without restarting.
+#[tauri::command]
+pub fn reload_engine(app: tauri::AppHandle) -> Result<(), String> {
+    crate::engine::reload(&app)
+}
+
+#[tauri::command]
+pub fn get_held_files(
+    held: tauri::State<'_, crate::guard::HeldFiles>,
+) -> Result<Vec<crate::guard::HeldFile>, String> {
+    let files = held.files.lock().map_err(|e| e.to_string())?;
+    Ok(files.clone())
+}
+
+#[tauri::command]
+pub async fn approve_held_file(
+    path: String,
+    app: tauri::AppHandle,
+    vips_state: tauri::State<'_, VipsState>,
+) -> Result<CompressionRecord, String> {
+    let vips = vips_state.get().ok_or("libvips not available")?;
+    if !crate::guard::release(&app, &path) {
+        return Err("File is not waiting for approval".to_string());
+    }
+    info!("[guard] Approved {}", path);
+    crate::processor::process_file_with_mode(
+        &app,
+        &vips,
+        Path::new(&path),
+        crate::processor::InputMode::Manual,
+    )
+}
+
+#[tauri::command]
+pub fn dismiss_held_file(path: String, app: tauri::AppHandle) -> bool {
+    crate::guard::release(&app, &path)
+}
+
+#[tauri::command]
+pub fn generate_encryption_key() -> String {
+    crate::crypto::generate_key()
+}
+
+#[tauri::command]
+pub fn decrypt_file(path: String, key: String, output: String) -> Result<u64, String> {
+    crate::crypto::decrypt_file(Path::new(&path), &key, Path::new(&output))
+}
+
+/// Report which originals are the last remaining copy of their content.
+/// With no paths, checks every original in history that still exists.
+#[tauri::command]
+pub async fn find_duplicate_originals(
+    paths: Vec<String>,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+    log: tauri::State<'_, Mutex<crate::log::CompressionLog>>,
+) -> Result<Vec<crate::duplicates::OriginalCopyReport>, String> {
+    let locations = config
+        .lock()
+        .map_err(|e| e.to_string())?
+        .config
+        .duplicate_scan_locations();
+    let originals: Vec<std::path::PathBuf> = if paths.is_empty() {
+        let log = log.lock().map_err(|e| e.to_string())?;
+        let mut originals: Vec<std::path::PathBuf> = log
+            .records
+            .iter()
+            .map(|r| std::path::PathBuf::from(&r.initial_path))
+            .filter(|p| p.exists())
+            .collect();
+        originals.sort();
+        originals.dedup();
+        originals
+    } else {
+        paths.iter().map(std::path::PathBuf::from).collect()
+    };
+    Ok(crate::duplicates::find_copies(&originals, &locations))
 }
 
 #[tauri::command]
@@ -331,44 +1992,206 @@ pub fn check_file_exists(path: String) -> bool {
     Path::new(&path).exists()
 }
 
+/// Per-file progress of a `compress_files` batch.
+#[derive(Clone, serde::Serialize)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum BatchEvent {
+    Started {
+        path: String,
+    },
+    Finished {
+        path: String,
+        record: CompressionRecord,
+    },
+    Failed {
+        path: String,
+        error: String,
+    },
+    /// Sent once every file is done.
+    Done {
+        energy: crate::energy::EnergyEstimate,
+    },
+}
+
 #[tauri::command]
 pub async fn compress_files(
     paths: Vec<String>,
+    on_event: tauri::ipc::Channel<BatchEvent>,
     app: tauri::AppHandle,
     vips_state: tauri::State<'_, VipsState>,
 ) -> Result<(), String> {
-    let vips = vips_state
-        .inner()
-        .vips
-        .as_ref()
-        .ok_or("libvips not available")?;
+    let vips = vips_state.get();
+    let queued: Vec<_> = paths.iter().map(std::path::PathBuf::from).collect();
+    crate::tasks::enqueue(&app, &queued, crate::processor::InputMode::Manual);
 
     use rayon::prelude::*;
 
+    let meter = crate::energy::Meter::start();
     paths.par_iter().for_each(|path_str| {
         let path = Path::new(path_str);
-        if let Err(e) = crate::processor::process_file_with_mode(
+        let _ = on_event.send(BatchEvent::Started {
+            path: path_str.clone(),
+        });
+        match crate::processor::process_any(
             &app,
-            vips,
+            vips.as_ref(),
             path,
             crate::processor::InputMode::Manual,
         ) {
-            error!(
-                "[manual-compression] Failed to compress {}: {}",
-                path_str, e
-            );
+            Ok(record) => {
+                let _ = on_event.send(BatchEvent::Finished {
+                    path: path_str.clone(),
+                    record,
+                });
+            }
+            Err(e) => {
+                error!(
+                    "[manual-compression] Failed to compress {}: {}",
+                    path_str, e
+                );
+                let _ = on_event.send(BatchEvent::Failed {
+                    path: path_str.clone(),
+                    error: e,
+                });
+            }
         }
     });
+    let _ = on_event.send(BatchEvent::Done {
+        energy: meter.finish(&app),
+    });
 
     Ok(())
 }
 
+/// Catch up on images that were already in a watched folder before it was added.
+/// Emits `scan-started` with an id for `cancel_scan`; after a cancel the
+/// summary covers the files processed so far.
+#[tauri::command]
+pub async fn compress_folder(
+    path: String,
+    app: tauri::AppHandle,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+    vips_state: tauri::State<'_, VipsState>,
+) -> Result<crate::scanner::ScanSummary, String> {
+    let vips = vips_state.get();
+    let folder = Path::new(&path);
+    let settings = config
+        .lock()
+        .map_err(|e| e.to_string())?
+        .config
+        .folder_settings
+        .get(&path)
+        .cloned()
+        .unwrap_or_default();
+    let scan = crate::scanner::start_scan(&app);
+    let _ = app.emit(
+        "scan-started",
+        &crate::scanner::ScanStarted {
+            scan_id: scan.id,
+            folder: path.clone(),
+        },
+    );
+    let files = crate::scanner::scan_folder(folder, &settings, &scan);
+    crate::tasks::enqueue(&app, &files, crate::processor::InputMode::Manual);
+
+    use rayon::prelude::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let processed = AtomicUsize::new(0);
+    let meter = crate::energy::Meter::start();
+    files.par_iter().for_each(|file| {
+        if scan.is_cancelled() {
+            crate::tasks::dequeue(&app, file);
+            return;
+        }
+        if let Err(e) = crate::processor::process_any(
+            &app,
+            vips.as_ref(),
+            file,
+            crate::processor::InputMode::Manual,
+        ) {
+            error!("[scanner] Failed to compress {}: {}", file.display(), e);
+        }
+        processed.fetch_add(1, Ordering::Relaxed);
+    });
+
+    let summary = crate::scanner::ScanSummary {
+        scan_id: scan.id,
+        folder: path,
+        total: files.len(),
+        processed: processed.into_inner(),
+        cancelled: scan.is_cancelled(),
+        energy: meter.finish(&app),
+    };
+    let _ = app.emit("scan-finished", &summary);
+    Ok(summary)
+}
+
+#[tauri::command]
+pub fn cancel_scan(scan_id: u64, app: tauri::AppHandle) -> bool {
+    crate::scanner::cancel_scan(&app, scan_id)
+}
+
+/// None until a library has been scanned.
+#[tauri::command]
+pub fn get_wizard_status(app: tauri::AppHandle) -> Option<crate::wizard::WizardStatus> {
+    crate::wizard::status(&app)
+}
+
+/// Start the library wizard over with every image under `root`.
+#[tauri::command]
+pub async fn wizard_scan(
+    root: String,
+    app: tauri::AppHandle,
+) -> Result<crate::wizard::WizardStatus, String> {
+    crate::wizard::scan(&app, Path::new(&root))
+}
+
+#[tauri::command]
+pub async fn wizard_estimate(app: tauri::AppHandle) -> Result<crate::wizard::WizardStatus, String> {
+    crate::wizard::estimate(&app)
+}
+
+#[tauri::command]
+pub async fn wizard_configure(
+    quality: u8,
+    deletion: crate::wizard::DeletionPolicy,
+    app: tauri::AppHandle,
+) -> Result<crate::wizard::WizardStatus, String> {
+    crate::wizard::configure(&app, quality, deletion).await
+}
+
+/// Run or resume the wizard; progress arrives as `wizard-progress` events.
+#[tauri::command]
+pub fn wizard_start(app: tauri::AppHandle) -> Result<crate::wizard::WizardStatus, String> {
+    crate::wizard::start(&app)
+}
+
+#[tauri::command]
+pub fn wizard_pause(app: tauri::AppHandle) -> Result<(), String> {
+    crate::wizard::pause(&app)
+}
+
+#[tauri::command]
+pub fn wizard_reset(app: tauri::AppHandle) -> Result<(), String> {
+    crate::wizard::reset(&app)
+}
+
 #[tauri::command]
 pub fn get_watched_folders(
+    app: tauri::AppHandle,
     config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
-) -> Result<Vec<String>, String> {
-    let config_manager = config.lock().map_err(|e| e.to_string())?;
-    Ok(config_manager.config.watched_folders.clone())
+) -> Result<Vec<crate::watcher::WatchedFolderInfo>, String> {
+    let folders = config
+        .lock()
+        .map_err(|e| e.to_string())?
+        .config
+        .watched_folders
+        .clone();
+    Ok(folders
+        .iter()
+        .map(|folder| crate::watcher::folder_info(&app, folder))
+        .collect())
 }
 
 #[tauri::command]
@@ -408,8 +2231,16 @@ pub fn remove_watched_folder(
 
     let mut watcher = watcher_state.watcher.lock().map_err(|e| e.to_string())?;
 
+    // A folder pair still needs its source watched
+    let paired = config_manager
+        .config
+        .folder_pairs
+        .iter()
+        .any(|p| p.source == path);
     if let Some(ref mut w) = *watcher {
-        let _ = w.unwatch(Path::new(&path));
+        if !paired {
+            let _ = w.unwatch(Path::new(&path));
+        }
     }
 
     config_manager.remove_folder(&path);
@@ -417,6 +2248,127 @@ pub fn remove_watched_folder(
     Ok(config_manager.config.watched_folders.clone())
 }
 
+#[tauri::command]
+pub fn get_folder_pairs(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<Vec<crate::config::FolderPair>, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(config_manager.config.folder_pairs.clone())
+}
+
+/// Compress new files in `pair.source` into `pair.destination`, creating the
+/// destination if needed.
+#[tauri::command]
+pub fn add_folder_pair(
+    pair: crate::config::FolderPair,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+    watcher_state: tauri::State<'_, crate::watcher::WatcherHandle>,
+) -> Result<Vec<crate::config::FolderPair>, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+
+    let source = Path::new(&pair.source);
+    if !source.is_dir() {
+        return Err("Source does not exist or is not a directory".to_string());
+    }
+    if Path::new(&pair.destination) == source {
+        return Err("Source and destination must differ".to_string());
+    }
+    std::fs::create_dir_all(&pair.destination)
+        .map_err(|e| format!("Failed to create destination: {}", e))?;
+
+    let mut watcher = watcher_state.watcher.lock().map_err(|e| e.to_string())?;
+    if let Some(ref mut w) = *watcher {
+        w.watch(source, notify::RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Failed to watch directory: {}", e))?;
+    } else {
+        return Err("File watcher is not initialized".to_string());
+    }
+
+    info!(
+        "[config] Folder pair added: {} → {}",
+        pair.source, pair.destination
+    );
+    config_manager.add_folder_pair(pair);
+    Ok(config_manager.config.folder_pairs.clone())
+}
+
+#[tauri::command]
+pub fn remove_folder_pair(
+    source: String,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+    watcher_state: tauri::State<'_, crate::watcher::WatcherHandle>,
+) -> Result<Vec<crate::config::FolderPair>, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+
+    let mut watcher = watcher_state.watcher.lock().map_err(|e| e.to_string())?;
+    if let Some(ref mut w) = *watcher {
+        if !config_manager.config.watched_folders.contains(&source) {
+            let _ = w.unwatch(Path::new(&source));
+        }
+    }
+
+    config_manager.remove_folder_pair(&source);
+    info!("[config] Folder pair removed: {}", source);
+    Ok(config_manager.config.folder_pairs.clone())
+}
+
+/// Download folders set in Chrome, Edge, Brave or Firefox profiles that
+/// aren't watched yet.
+#[tauri::command]
+pub fn suggest_watch_folders(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<Vec<crate::suggestions::FolderSuggestion>, String> {
+    let watched = config
+        .lock()
+        .map_err(|e| e.to_string())?
+        .config
+        .watched_folders
+        .clone();
+    Ok(crate::browsers::suggest_watch_folders(&watched))
+}
+
+#[derive(serde::Serialize)]
+pub struct RemovableVolume {
+    #[serde(flatten)]
+    pub volume: crate::platform::VolumeInfo,
+    pub enabled: bool,
+}
+
+/// Mounted removable volumes and whether the watcher may compress on them.
+#[tauri::command]
+pub fn list_removable_volumes(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<Vec<RemovableVolume>, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(crate::platform::mounted_volumes()
+        .iter()
+        .filter_map(|root| crate::platform::volume_of(root))
+        .filter(|v| v.removable)
+        .map(|volume| RemovableVolume {
+            enabled: config_manager
+                .config
+                .volume_enabled(&volume.label, &volume.serial),
+            volume,
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub fn set_volume_enabled(
+    label: String,
+    serial: String,
+    enabled: bool,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<Vec<crate::config::VolumePolicy>, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    info!(
+        "[config] Auto-compression on volume {} ({}): {}",
+        label, serial, enabled
+    );
+    config_manager.set_volume_enabled(label, serial, enabled);
+    Ok(config_manager.config.volume_policies.clone())
+}
+
 #[tauri::command]
 pub async fn search_directories(query: String) -> Vec<String> {
     let mut results = Vec::new();
@@ -544,6 +2496,121 @@ pub async fn search_directories(query: String) -> Vec<String> {
     results
 }
 
+#[tauri::command]
+pub fn list_scripts(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+    scripts: tauri::State<'_, crate::scripting::Scripts>,
+) -> Result<Vec<crate::scripting::ScriptInfo>, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(scripts.list(&config_manager.config.enabled_scripts))
+}
+
+#[tauri::command]
+pub fn set_script_enabled(
+    name: String,
+    enabled: bool,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+    scripts: tauri::State<'_, crate::scripting::Scripts>,
+) -> Result<Vec<crate::scripting::ScriptInfo>, String> {
+    let mut config_manager = config.lock().map_err(|e| e.to_string())?;
+    config_manager.set_script_enabled(&name, enabled);
+    info!(
+        "[scripts] {} {}",
+        name,
+        if enabled { "enabled" } else { "disabled" }
+    );
+    scripts.reload(&config_manager.config.enabled_scripts);
+    Ok(scripts.list(&config_manager.config.enabled_scripts))
+}
+
+#[tauri::command]
+pub fn reload_scripts(
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+    scripts: tauri::State<'_, crate::scripting::Scripts>,
+) -> Result<Vec<crate::scripting::ScriptInfo>, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    scripts.reload(&config_manager.config.enabled_scripts);
+    Ok(scripts.list(&config_manager.config.enabled_scripts))
+}
+
+#[tauri::command]
+pub fn open_scripts_dir(
+    app: tauri::AppHandle,
+    scripts: tauri::State<'_, crate::scripting::Scripts>,
+) -> Result<(), String> {
+    use tauri_plugin_opener::OpenerExt;
+    std::fs::create_dir_all(&scripts.dir).map_err(|e| e.to_string())?;
+    let _ = app
+        .opener()
+        .open_path(scripts.dir.display().to_string(), None::<String>);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn simulate_rules(
+    path: String,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+    scripts: tauri::State<'_, crate::scripting::Scripts>,
+) -> Result<crate::simulate::RuleSimulation, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    Ok(crate::simulate::simulate(
+        &config_manager.config,
+        &scripts.with_hook("on_file_detected"),
+        Path::new(&path),
+    ))
+}
+
+/// The format, quality, flags and output path `path` would get right now.
+#[tauri::command]
+pub fn resolve_settings(
+    path: String,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+) -> Result<crate::simulate::ResolvedSettings, String> {
+    let config_manager = config.lock().map_err(|e| e.to_string())?;
+    crate::simulate::resolve(&config_manager.config, Path::new(&path))
+}
+
+/// Compress `samples` across a grid of formats, qualities and efforts and
+/// write a CSV of size, SSIM and encode time, for tuning defaults on real
+/// images. The samples themselves are left alone.
+#[tauri::command]
+pub async fn run_experiment(
+    samples: Vec<String>,
+    grid: crate::experiment::ExperimentGrid,
+    app: tauri::AppHandle,
+    config: tauri::State<'_, Mutex<crate::config::ConfigManager>>,
+    vips_state: tauri::State<'_, VipsState>,
+) -> Result<crate::experiment::ExperimentReport, String> {
+    let vips = vips_state.get().ok_or("libvips not available")?;
+    let opts = config
+        .lock()
+        .map_err(|e| e.to_string())?
+        .config
+        .format_options
+        .clone();
+    let out_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("experiments");
+    let samples: Vec<_> = samples.iter().map(std::path::PathBuf::from).collect();
+    crate::experiment::run(&vips, &samples, &grid, &opts, &out_dir)
+}
+
+#[tauri::command]
+pub fn list_actions() -> Vec<crate::actions::ActionInfo> {
+    crate::actions::ACTIONS.to_vec()
+}
+
+#[tauri::command]
+pub fn invoke_action(
+    id: String,
+    args: Option<serde_json::Value>,
+    app: tauri::AppHandle,
+) -> Result<serde_json::Value, String> {
+    crate::actions::invoke(&app, &id, &args.unwrap_or_default())
+}
+
 #[tauri::command]
 pub fn open_config_dir(app: tauri::AppHandle) -> Result<(), String> {
     use tauri_plugin_opener::OpenerExt;
@@ -556,5 +2623,20 @@ pub fn open_config_dir(app: tauri::AppHandle) -> Result<(), String> {
 
 #[tauri::command]
 pub fn quit_app(app: tauri::AppHandle) {
-    app.exit(0);
+    crate::tasks::request_quit(&app);
+}
+
+/// Files handed to the processor that haven't finished yet.
+/// Stream the task queue: a snapshot first, then only what changed.
+#[tauri::command]
+pub fn subscribe_tasks(
+    app: tauri::AppHandle,
+    on_event: tauri::ipc::Channel<crate::tasks::TaskSync>,
+) {
+    crate::tasks::subscribe(&app, on_event);
+}
+
+#[tauri::command]
+pub fn get_pending_tasks(app: tauri::AppHandle) -> Vec<crate::tasks::PendingTask> {
+    crate::tasks::pending(&app)
 }
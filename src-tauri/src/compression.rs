@@ -1,3 +1,4 @@
+use crate::config::{CollisionPolicy, OutputNaming};
 use libloading::Library;
 use log::{info, warn};
 use serde::Serialize;
@@ -20,6 +21,12 @@ pub enum ImageFormat {
     Avif,
     Heif,
     Tiff,
+    Gif,
+    Jxl,
+    /// Camera RAW; read-only, always written out as another format.
+    Raw,
+    /// Photoshop document; read-only, flattened and written out as another format.
+    Psd,
 }
 
 impl ImageFormat {
@@ -31,6 +38,12 @@ impl ImageFormat {
             "avif" => Some(Self::Avif),
             "heif" | "heic" => Some(Self::Heif),
             "tif" | "tiff" => Some(Self::Tiff),
+            "gif" => Some(Self::Gif),
+            "jxl" => Some(Self::Jxl),
+            "cr2" | "nef" | "arw" | "dng" | "raf" | "orf" | "rw2" | "pef" | "srw" => {
+                Some(Self::Raw)
+            }
+            "psd" => Some(Self::Psd),
             _ => None,
         }
     }
@@ -49,8 +62,22 @@ impl ImageFormat {
             Self::Avif => "avif",
             Self::Heif => "heic",
             Self::Tiff => "tiff",
+            Self::Gif => "gif",
+            Self::Jxl => "jxl",
+            Self::Raw => "dng",
+            Self::Psd => "psd",
         }
     }
+
+    /// Formats Hat can encode; the others are only ever converted.
+    pub fn is_writable(&self) -> bool {
+        !matches!(self, Self::Raw | Self::Psd)
+    }
+
+    /// Formats whose encoders keep every frame of an animation.
+    pub fn supports_animation(&self) -> bool {
+        matches!(self, Self::Gif | Self::WebP)
+    }
 }
 
 impl std::fmt::Display for ImageFormat {
@@ -62,6 +89,10 @@ impl std::fmt::Display for ImageFormat {
             Self::Avif => write!(f, "avif"),
             Self::Heif => write!(f, "heif"),
             Self::Tiff => write!(f, "tiff"),
+            Self::Gif => write!(f, "gif"),
+            Self::Jxl => write!(f, "jxl"),
+            Self::Raw => write!(f, "raw"),
+            Self::Psd => write!(f, "psd"),
         }
     }
 }
@@ -70,8 +101,11 @@ impl std::fmt::Display for ImageFormat {
 // CompressionRecord
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, Clone, Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, serde::Deserialize)]
 pub struct CompressionRecord {
+    /// Assigned by `CompressionLog::append`; 0 until the record is logged.
+    #[serde(default)]
+    pub id: u64,
     pub initial_path: String,
     pub final_path: String,
     pub initial_size: u64,
@@ -82,6 +116,138 @@ pub struct CompressionRecord {
     pub timestamp: u64,
     #[serde(default)]
     pub original_deleted: bool,
+    /// Text recognised in the original before lossy compression.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ocr_text: Option<String>,
+    /// SHA-256 of the original, kept for encrypted outputs and checksum tracking.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_sha256: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_sha256: Option<String>,
+    #[serde(default)]
+    pub encrypted: bool,
+    /// Observe-only run: `compressed_size` is an estimate and no output was written.
+    #[serde(default)]
+    pub analysis_only: bool,
+    /// Encoded in determinism mode, so re-running it yields identical bytes.
+    #[serde(default)]
+    pub deterministic: bool,
+    /// The earlier record whose output was reused because this file was an
+    /// identical re-download of its original.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reused_from: Option<u64>,
+    /// Shared by the records of every output written from one input in a
+    /// single run; the id of the first of them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<u64>,
+    /// Every format encoded when several competed for the output, with its
+    /// size; `final_format` is the one kept.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<CandidateSize>,
+    /// What the output was encoded with, for `repeat_compression`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<EncodeSettings>,
+    /// Set when margins were cropped away before encoding.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trimmed: Option<TrimmedSize>,
+    /// Copy of the original taken before the output replaced it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup_path: Option<String>,
+    /// Where the original went when it was moved to the archive folder.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_archived_path: Option<String>,
+}
+
+/// Dimensions before and after trimming margins.
+#[derive(Debug, Clone, Copy, Serialize, serde::Deserialize)]
+pub struct TrimmedSize {
+    pub original_width: u32,
+    pub original_height: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Serialize, serde::Deserialize)]
+pub struct CandidateSize {
+    pub format: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EncodeEngine {
+    Libvips,
+    /// The pure-Rust encoders used without libvips.
+    Fallback,
+}
+
+/// Everything an output was encoded with, enough to encode another input
+/// exactly the same way.
+#[derive(Debug, Clone, Serialize, serde::Deserialize)]
+pub struct EncodeSettings {
+    pub engine: EncodeEngine,
+    pub format: ImageFormat,
+    pub quality: u8,
+    pub flags: CompressionFlags,
+}
+
+/// One format `Vips::compress_smallest` tries, with its own settings.
+pub struct EncodeCandidate {
+    pub format: ImageFormat,
+    pub quality: u8,
+    pub flags: CompressionFlags,
+}
+
+/// The file kept by `Vips::compress_smallest`, in the temp folder until
+/// `place` moves it to its output path.
+pub struct SmallestOutput {
+    pub format: ImageFormat,
+    pub quality: u8,
+    pub path: std::path::PathBuf,
+    pub size: u64,
+    /// Sizes of every candidate that encoded, the kept one included.
+    pub candidates: Vec<CandidateSize>,
+}
+
+impl SmallestOutput {
+    /// Move the kept file to `input`'s output path for its format, which is
+    /// `resolved` when that's the format already resolved up front. Only the
+    /// kept file goes through the collision policy; the temp file is removed
+    /// when it can't be placed.
+    pub fn place(
+        &mut self,
+        input: &Path,
+        resolved: &Path,
+        resolved_format: ImageFormat,
+    ) -> Result<()> {
+        let ext = self.format.extension();
+        let dest = if self.format == resolved_format {
+            Some(resolved.to_path_buf())
+        } else {
+            compressed_output_path(input, Some(ext))
+        };
+        let placed = match dest {
+            Some(dest) => crate::cache::persist(&self.path, &dest)
+                .map(|_| dest)
+                .map_err(CompressionError::from),
+            None => Err(CompressionError::InvalidPath(
+                match existing_output(input, Some(ext)) {
+                    Some(existing) => format!("{} already exists", existing.display()),
+                    None => format!("no {} output path for {}", self.format, input.display()),
+                },
+            )),
+        };
+        match placed {
+            Ok(dest) => {
+                self.path = dest;
+                Ok(())
+            }
+            Err(e) => {
+                let _ = fs::remove_file(&self.path);
+                Err(e)
+            }
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -100,6 +266,14 @@ pub enum CompressionError {
     Io(#[from] std::io::Error),
     #[error("libloading error: {0}")]
     LibLoading(#[from] libloading::Error),
+    #[error("external encoder error: {0}")]
+    External(String),
+    #[error("image codec error: {0}")]
+    Image(#[from] image::ImageError),
+    #[error("pdf error: {0}")]
+    Pdf(#[from] lopdf::Error),
+    #[error("svg error: {0}")]
+    Svg(String),
 }
 
 pub type Result<T> = std::result::Result<T, CompressionError>;
@@ -129,18 +303,77 @@ type VipsWriteToMemoryFn = unsafe extern "C" fn(*mut c_void, *mut usize) -> *mut
 type VipsGetWidthFn = unsafe extern "C" fn(*mut c_void) -> c_int;
 type VipsGetHeightFn = unsafe extern "C" fn(*mut c_void) -> c_int;
 type VipsGetBandsFn = unsafe extern "C" fn(*mut c_void) -> c_int;
+type VipsGetFormatFn = unsafe extern "C" fn(*mut c_void) -> c_int;
 type GFreeFn = unsafe extern "C" fn(*mut c_void);
 // VipsBandFormat enum value for VIPS_FORMAT_UCHAR
 const VIPS_FORMAT_UCHAR: c_int = 0;
+// VipsBandFormat enum value for VIPS_FORMAT_CHAR
+const VIPS_FORMAT_CHAR: c_int = 1;
 // Non-variadic: creates a VipsImage from a copy of a memory buffer
 type VipsNewFromMemoryCopyFn =
     unsafe extern "C" fn(*const c_void, usize, c_int, c_int, c_int, c_int) -> *mut c_void;
+// Variadic like the loaders above; optional args are passed as key/value
+// pairs followed by a NULL terminator.
+type VipsResizeFn = unsafe extern "C" fn(*mut c_void, *mut *mut c_void, f64, ...) -> c_int;
+type VipsAutorotFn = unsafe extern "C" fn(*mut c_void, *mut *mut c_void, ...) -> c_int;
+type VipsIccTransformFn =
+    unsafe extern "C" fn(*mut c_void, *mut *mut c_void, *const c_char, ...) -> c_int;
+// Non-variadic: the GType of a metadata field, 0 when the image lacks it
+type VipsGetTypeofFn = unsafe extern "C" fn(*mut c_void, *const c_char) -> usize;
+type VipsGetInterpretationFn = unsafe extern "C" fn(*mut c_void) -> c_int;
+// VipsInterpretation enum value for VIPS_INTERPRETATION_CMYK
+const VIPS_INTERPRETATION_CMYK: c_int = 15;
+type VipsThumbnailFn = unsafe extern "C" fn(*const c_char, *mut *mut c_void, c_int, ...) -> c_int;
+type VipsTextFn = unsafe extern "C" fn(*mut *mut c_void, *const c_char, ...) -> c_int;
+type VipsComposite2Fn =
+    unsafe extern "C" fn(*mut c_void, *mut c_void, *mut *mut c_void, c_int, ...) -> c_int;
+// VipsBlendMode enum value for VIPS_BLEND_MODE_OVER
+const VIPS_BLEND_MODE_OVER: c_int = 2;
+type VipsLinearFn = unsafe extern "C" fn(
+    *mut c_void,
+    *mut *mut c_void,
+    *const f64,
+    *const f64,
+    c_int,
+    ...
+) -> c_int;
+type VipsExtractBandFn = unsafe extern "C" fn(*mut c_void, *mut *mut c_void, c_int, ...) -> c_int;
+type VipsBandjoinConst1Fn = unsafe extern "C" fn(*mut c_void, *mut *mut c_void, f64, ...) -> c_int;
+type VipsFindTrimFn =
+    unsafe extern "C" fn(*mut c_void, *mut c_int, *mut c_int, *mut c_int, *mut c_int, ...) -> c_int;
+type VipsExtractAreaFn =
+    unsafe extern "C" fn(*mut c_void, *mut *mut c_void, c_int, c_int, c_int, c_int, ...) -> c_int;
+// Fills a g_malloc'd array with one value per band
+type VipsGetpointFn =
+    unsafe extern "C" fn(*mut c_void, *mut *mut f64, *mut c_int, c_int, c_int, ...) -> c_int;
+// Non-variadic: a refcounted VipsArrayDouble, released with vips_area_unref
+type VipsArrayDoubleNewFn = unsafe extern "C" fn(*const f64, c_int) -> *mut c_void;
+type VipsAreaUnrefFn = unsafe extern "C" fn(*mut c_void);
+type VipsJoinFn =
+    unsafe extern "C" fn(*mut c_void, *mut c_void, *mut *mut c_void, c_int, ...) -> c_int;
+type VipsArrayJoinFn =
+    unsafe extern "C" fn(*mut *mut c_void, *mut *mut c_void, c_int, ...) -> c_int;
+type VipsGetNPagesFn = unsafe extern "C" fn(*mut c_void) -> c_int;
+// Height of one frame when the frames of an animation are stacked vertically
+type VipsGetPageHeightFn = unsafe extern "C" fn(*mut c_void) -> c_int;
+type VipsWriteToBufferFn =
+    unsafe extern "C" fn(*mut c_void, *const c_char, *mut *mut c_void, *mut usize, ...) -> c_int;
+// Loads lazily from the caller's memory, which must outlive the image
+type VipsNewFromBufferFn =
+    unsafe extern "C" fn(*const c_void, usize, *const c_char, ...) -> *mut c_void;
+// VipsDirection enum value for VIPS_DIRECTION_VERTICAL
+const VIPS_DIRECTION_VERTICAL: c_int = 1;
+// Returns the GType of a registered class, or 0 when the operation is missing
+type VipsTypeFindFn = unsafe extern "C" fn(*const c_char, *const c_char) -> usize;
+// Worker threads per pipeline; 0 restores the libvips default
+type VipsConcurrencySetFn = unsafe extern "C" fn(c_int);
 
 // ---------------------------------------------------------------------------
 // Format-specific compression flags
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct CompressionFlags {
     // PNG
     pub png_palette: bool,
@@ -148,6 +381,11 @@ pub struct CompressionFlags {
     pub png_bitdepth: u8,
     pub png_filter: Option<String>,
     pub png_colors: u16,
+    pub png_quantize_after: bool,
+    pub png_oxipng_level: u8,
+    pub png_zopfli: bool,
+    /// 0.0–1.0, for palette output only.
+    pub png_dither: f32,
     // Quantization (non-PNG formats)
     pub jpeg_quantize: bool,
     pub jpeg_colors: u16,
@@ -165,6 +403,8 @@ pub struct CompressionFlags {
     pub jpeg_subsample_mode: Option<String>,
     pub jpeg_trellis_quant: bool,
     pub jpeg_overshoot_deringing: bool,
+    // Re-pack JPEG sources losslessly instead of re-encoding them
+    pub jpeg_lossless: bool,
     // WebP
     pub webp_effort: u8,
     pub webp_lossless: bool,
@@ -186,6 +426,31 @@ pub struct CompressionFlags {
     pub tiff_tile: bool,
     pub tiff_pyramid: bool,
     pub tiff_bitdepth: u8,
+    // GIF
+    pub gif_effort: u8,
+    pub gif_colors: u16,
+    pub gif_dither: f32,
+    // JPEG XL
+    pub jxl_effort: u8,
+    pub jxl_lossless: bool,
+    pub jxl_lossless_jpeg: bool,
+    pub jxl_encoder: Option<String>,
+    // Reproducible output: single-threaded encoders, nothing time-dependent
+    pub deterministic: bool,
+    // Keep EXIF, XMP, IPTC and ICC metadata instead of stripping it
+    pub keep_metadata: bool,
+    // Drop GPS tags from the kept EXIF after encoding
+    pub strip_gps: bool,
+    // Embed the source's ICC profile untouched instead of converting to sRGB
+    pub keep_icc: bool,
+    // Write CMYK JPEGs back as CMYK instead of converting to sRGB
+    pub keep_cmyk: bool,
+    // Overlay composited before encoding, for folders that enable it
+    pub watermark: Option<crate::config::WatermarkConfig>,
+    // Crop uniform or transparent margins before encoding
+    pub trim: bool,
+    // Turbo window: every core for libvips, whatever the usual limit
+    pub turbo: bool,
 }
 
 impl CompressionFlags {
@@ -198,6 +463,10 @@ impl CompressionFlags {
                 png_bitdepth: opts.png.bitdepth,
                 png_filter: opts.png.filter.clone(),
                 png_colors: opts.png.colors,
+                png_quantize_after: opts.png.quantize_after,
+                png_oxipng_level: opts.png.oxipng_level,
+                png_zopfli: opts.png.zopfli,
+                png_dither: opts.png.dither.min(100) as f32 / 100.0,
                 ..Default::default()
             },
             ImageFormat::Jpeg => CompressionFlags {
@@ -208,6 +477,7 @@ impl CompressionFlags {
                 jpeg_overshoot_deringing: opts.jpeg.overshoot_deringing,
                 jpeg_quantize: opts.jpeg.quantize,
                 jpeg_colors: opts.jpeg.colors,
+                jpeg_lossless: opts.jpeg.lossless,
                 ..Default::default()
             },
             ImageFormat::WebP => CompressionFlags {
@@ -247,6 +517,20 @@ impl CompressionFlags {
                 tiff_colors: opts.tiff.colors,
                 ..Default::default()
             },
+            ImageFormat::Gif => CompressionFlags {
+                gif_effort: opts.gif.effort,
+                gif_colors: opts.gif.colors,
+                gif_dither: opts.gif.dither.min(100) as f32 / 100.0,
+                ..Default::default()
+            },
+            ImageFormat::Jxl => CompressionFlags {
+                jxl_effort: opts.jxl.effort,
+                jxl_lossless: opts.jxl.lossless,
+                jxl_lossless_jpeg: opts.jxl.lossless_jpeg,
+                jxl_encoder: opts.jxl.encoder_path.clone(),
+                ..Default::default()
+            },
+            ImageFormat::Raw | ImageFormat::Psd => CompressionFlags::default(),
         }
     }
 }
@@ -255,6 +539,25 @@ impl CompressionFlags {
 // Minimal libvips FFI wrapper
 // ---------------------------------------------------------------------------
 
+/// Whether a PSD stores transparency, read from its header: the channel
+/// count at byte 12 and the color mode at byte 24.
+pub fn psd_has_alpha(path: &Path) -> bool {
+    use std::io::Read;
+    let mut header = [0u8; 26];
+    if fs::File::open(path)
+        .and_then(|mut f| f.read_exact(&mut header))
+        .is_err()
+    {
+        return false;
+    }
+    let channels = u16::from_be_bytes([header[12], header[13]]);
+    match u16::from_be_bytes([header[24], header[25]]) {
+        1 => channels >= 2, // grayscale
+        3 => channels >= 4, // RGB
+        _ => false,
+    }
+}
+
 /// RAII guard for a raw VipsImage pointer.  Calls `g_object_unref` on drop.
 pub struct VipsImage<'a> {
     ptr: *mut c_void,
@@ -290,8 +593,33 @@ pub struct Vips {
     fn_get_width: VipsGetWidthFn,
     fn_get_height: VipsGetHeightFn,
     fn_get_bands: VipsGetBandsFn,
+    fn_get_format: VipsGetFormatFn,
     fn_g_free: GFreeFn,
     fn_new_from_memory_copy: VipsNewFromMemoryCopyFn,
+    fn_resize: VipsResizeFn,
+    fn_autorot: VipsAutorotFn,
+    fn_icc_transform: VipsIccTransformFn,
+    fn_get_typeof: VipsGetTypeofFn,
+    fn_get_interpretation: VipsGetInterpretationFn,
+    fn_thumbnail: VipsThumbnailFn,
+    fn_text: VipsTextFn,
+    fn_composite2: VipsComposite2Fn,
+    fn_linear: VipsLinearFn,
+    fn_extract_band: VipsExtractBandFn,
+    fn_bandjoin_const1: VipsBandjoinConst1Fn,
+    fn_find_trim: VipsFindTrimFn,
+    fn_extract_area: VipsExtractAreaFn,
+    fn_getpoint: VipsGetpointFn,
+    fn_array_double_new: VipsArrayDoubleNewFn,
+    fn_area_unref: VipsAreaUnrefFn,
+    fn_join: VipsJoinFn,
+    fn_array_join: VipsArrayJoinFn,
+    fn_get_n_pages: VipsGetNPagesFn,
+    fn_get_page_height: VipsGetPageHeightFn,
+    fn_write_to_buffer: VipsWriteToBufferFn,
+    fn_new_from_buffer: VipsNewFromBufferFn,
+    fn_type_find: VipsTypeFindFn,
+    fn_concurrency_set: VipsConcurrencySetFn,
 }
 
 impl Vips {
@@ -320,9 +648,38 @@ impl Vips {
         let fn_get_width = *lib.get::<VipsGetWidthFn>(b"vips_image_get_width\0")?;
         let fn_get_height = *lib.get::<VipsGetHeightFn>(b"vips_image_get_height\0")?;
         let fn_get_bands = *lib.get::<VipsGetBandsFn>(b"vips_image_get_bands\0")?;
+        let fn_get_format = *lib.get::<VipsGetFormatFn>(b"vips_image_get_format\0")?;
         let fn_g_free = *lib.get::<GFreeFn>(b"g_free\0")?;
         let fn_new_from_memory_copy =
             *lib.get::<VipsNewFromMemoryCopyFn>(b"vips_image_new_from_memory_copy\0")?;
+        let fn_resize = *lib.get::<VipsResizeFn>(b"vips_resize\0")?;
+        let fn_autorot = *lib.get::<VipsAutorotFn>(b"vips_autorot\0")?;
+        let fn_icc_transform = *lib.get::<VipsIccTransformFn>(b"vips_icc_transform\0")?;
+        let fn_get_typeof = *lib.get::<VipsGetTypeofFn>(b"vips_image_get_typeof\0")?;
+        let fn_get_interpretation =
+            *lib.get::<VipsGetInterpretationFn>(b"vips_image_get_interpretation\0")?;
+        let fn_thumbnail = *lib.get::<VipsThumbnailFn>(b"vips_thumbnail\0")?;
+        let fn_text = *lib.get::<VipsTextFn>(b"vips_text\0")?;
+        let fn_composite2 = *lib.get::<VipsComposite2Fn>(b"vips_composite2\0")?;
+        let fn_linear = *lib.get::<VipsLinearFn>(b"vips_linear\0")?;
+        let fn_extract_band = *lib.get::<VipsExtractBandFn>(b"vips_extract_band\0")?;
+        let fn_bandjoin_const1 = *lib.get::<VipsBandjoinConst1Fn>(b"vips_bandjoin_const1\0")?;
+        let fn_find_trim = *lib.get::<VipsFindTrimFn>(b"vips_find_trim\0")?;
+        let fn_extract_area = *lib.get::<VipsExtractAreaFn>(b"vips_extract_area\0")?;
+        let fn_getpoint = *lib.get::<VipsGetpointFn>(b"vips_getpoint\0")?;
+        let fn_array_double_new = *lib.get::<VipsArrayDoubleNewFn>(b"vips_array_double_new\0")?;
+        let fn_area_unref = *lib.get::<VipsAreaUnrefFn>(b"vips_area_unref\0")?;
+        let fn_join = *lib.get::<VipsJoinFn>(b"vips_join\0")?;
+        let fn_array_join = *lib.get::<VipsArrayJoinFn>(b"vips_arrayjoin\0")?;
+        let fn_get_n_pages = *lib.get::<VipsGetNPagesFn>(b"vips_image_get_n_pages\0")?;
+        let fn_get_page_height =
+            *lib.get::<VipsGetPageHeightFn>(b"vips_image_get_page_height\0")?;
+        let fn_write_to_buffer =
+            *lib.get::<VipsWriteToBufferFn>(b"vips_image_write_to_buffer\0")?;
+        let fn_new_from_buffer =
+            *lib.get::<VipsNewFromBufferFn>(b"vips_image_new_from_buffer\0")?;
+        let fn_type_find = *lib.get::<VipsTypeFindFn>(b"vips_type_find\0")?;
+        let fn_concurrency_set = *lib.get::<VipsConcurrencySetFn>(b"vips_concurrency_set\0")?;
 
         Ok(Self {
             _lib: lib,
@@ -335,8 +692,33 @@ impl Vips {
             fn_get_width,
             fn_get_height,
             fn_get_bands,
+            fn_get_format,
             fn_g_free,
             fn_new_from_memory_copy,
+            fn_resize,
+            fn_autorot,
+            fn_icc_transform,
+            fn_get_typeof,
+            fn_get_interpretation,
+            fn_thumbnail,
+            fn_text,
+            fn_composite2,
+            fn_linear,
+            fn_extract_band,
+            fn_bandjoin_const1,
+            fn_find_trim,
+            fn_extract_area,
+            fn_getpoint,
+            fn_array_double_new,
+            fn_area_unref,
+            fn_join,
+            fn_array_join,
+            fn_get_n_pages,
+            fn_get_page_height,
+            fn_write_to_buffer,
+            fn_new_from_buffer,
+            fn_type_find,
+            fn_concurrency_set,
         })
     }
 
@@ -354,9 +736,116 @@ impl Vips {
         }
     }
 
+    /// Whether this libvips build includes an operation, e.g. `heifload`.
+    pub fn has_operation(&self, nickname: &str) -> bool {
+        let Ok(nickname) = CString::new(nickname) else {
+            return false;
+        };
+        let base = CString::new("VipsOperation").unwrap();
+        unsafe { (self.fn_type_find)(base.as_ptr(), nickname.as_ptr()) != 0 }
+    }
+
+    /// Limit libvips to `threads` workers per pipeline; 0 restores the default.
+    pub fn set_concurrency(&self, threads: i32) {
+        unsafe { (self.fn_concurrency_set)(threads) }
+    }
+
+    /// Bring libvips' thread count in line with the deterministic setting
+    /// and turbo. The count is process-wide, so it follows those rather
+    /// than each encode's flags, and is only set when it changes.
+    fn sync_concurrency(&self) {
+        // Threaded encoders may split work differently between runs
+        let threads = if DETERMINISTIC.load(std::sync::atomic::Ordering::Relaxed) {
+            1
+        } else if crate::turbo::status().active {
+            std::thread::available_parallelism().map_or(0, |n| n.get() as i32)
+        } else {
+            0
+        };
+        let Ok(mut applied) = CONCURRENCY.lock() else {
+            return;
+        };
+        if *applied != Some(threads) {
+            self.set_concurrency(threads);
+            *applied = Some(threads);
+        }
+    }
+
     pub fn load_image(&self, path: &Path) -> Result<VipsImage<'_>> {
+        // libvips only reads camera RAW when built with libraw
+        if ImageFormat::from_path(path) == Some(ImageFormat::Raw)
+            && !self.has_operation("dcrawload")
+        {
+            return self.load_raw(path);
+        }
+        if ImageFormat::from_path(path) == Some(ImageFormat::Psd)
+            && !self.has_operation("magickload")
+        {
+            return self.load_psd(path);
+        }
         let cpath = path_to_cstring(path)?;
         let img = unsafe { (self.fn_new_from_file)(cpath.as_ptr(), std::ptr::null::<c_char>()) };
+        if img.is_null() {
+            let error = self.vips_error();
+            // Builds without libheif can still read iPhone photos through the OS codecs
+            if ImageFormat::from_path(path) == Some(ImageFormat::Heif)
+                && !self.has_operation("heifload")
+            {
+                if let Some(decoded) = crate::platform::decode_with_os(path) {
+                    info!(
+                        "[compression] Decoded {} with the platform codec",
+                        path.display()
+                    );
+                    return self.load_image_from_pixels(
+                        &decoded.rgba,
+                        decoded.width,
+                        decoded.height,
+                        4,
+                    );
+                }
+                #[cfg(target_os = "windows")]
+                return Err(CompressionError::UnsupportedFormat(format!(
+                    "{}: install HEIF Image Extensions from the Microsoft Store to read HEIC",
+                    path.display()
+                )));
+            }
+            return Err(CompressionError::Vips(format!(
+                "failed to load {}: {}",
+                path.display(),
+                error
+            )));
+        }
+        // Phones store portrait shots sideways with an EXIF orientation that
+        // stripped outputs lose, so turn the pixels upright first
+        self.autorot(&VipsImage::new(img, self))
+    }
+
+    /// Demosaic a camera RAW file to 8-bit sRGB.
+    fn load_raw(&self, path: &Path) -> Result<VipsImage<'_>> {
+        let decoded = imagepipe::simple_decode_8bit(path, 0, 0)
+            .map_err(|e| CompressionError::UnsupportedFormat(format!("{}: {e}", path.display())))?;
+        info!(
+            "[compression] Decoded RAW {} ({}x{})",
+            path.display(),
+            decoded.width,
+            decoded.height
+        );
+        self.load_image_from_rgb(&decoded.data, decoded.width as u32, decoded.height as u32)
+    }
+
+    /// Read the flattened composite Photoshop stores alongside the layers.
+    fn load_psd(&self, path: &Path) -> Result<VipsImage<'_>> {
+        let bytes = fs::read(path)?;
+        let psd = psd::Psd::from_bytes(&bytes)
+            .map_err(|e| CompressionError::UnsupportedFormat(format!("{}: {e}", path.display())))?;
+        self.load_image_from_pixels(&psd.rgba(), psd.width(), psd.height(), 4)
+    }
+
+    /// Load with loader options in vips filename syntax, e.g. `page=2,dpi=150`.
+    pub fn load_image_with_options(&self, path: &Path, options: &str) -> Result<VipsImage<'_>> {
+        let cpath = CString::new(format!("{}[{}]", output_str(path)?, options))
+            .map_err(|_| CompressionError::InvalidPath(path.display().to_string()))?;
+        let img = unsafe { (self.fn_new_from_file)(cpath.as_ptr(), std::ptr::null::<c_char>()) };
         if img.is_null() {
             return Err(CompressionError::Vips(format!(
                 "failed to load {}: {}",
@@ -381,6 +870,547 @@ impl Vips {
         Ok(())
     }
 
+    /// Returns (width, height) of a loaded image.
+    pub fn dimensions(&self, img: &VipsImage<'_>) -> (u32, u32) {
+        let width = unsafe { (self.fn_get_width)(img.as_ptr()) } as u32;
+        let height = unsafe { (self.fn_get_height)(img.as_ptr()) } as u32;
+        (width, height)
+    }
+
+    pub fn bands(&self, img: &VipsImage<'_>) -> u32 {
+        unsafe { (self.fn_get_bands)(img.as_ptr()) as u32 }
+    }
+
+    /// Whether `img` has more than 8 bits per sample.
+    pub fn is_high_depth(&self, img: &VipsImage<'_>) -> bool {
+        let format = unsafe { (self.fn_get_format)(img.as_ptr()) };
+        format != VIPS_FORMAT_UCHAR && format != VIPS_FORMAT_CHAR
+    }
+
+    /// Smallest PNG bit depth that holds `img` without loss: 16 for
+    /// high-depth sources, 1, 2 or 4 when it has few enough colors for a
+    /// palette that size, otherwise 8.
+    fn auto_png_bitdepth(&self, img: &VipsImage<'_>) -> u8 {
+        if self.is_high_depth(img) {
+            return 16;
+        }
+        let Ok((_, _, rgba)) = self.extract_rgba(img) else {
+            return 8;
+        };
+        let mut colors = std::collections::HashSet::new();
+        for pixel in rgba.chunks_exact(4) {
+            colors.insert([pixel[0], pixel[1], pixel[2], pixel[3]]);
+            if colors.len() > 16 {
+                return 8;
+            }
+        }
+        match colors.len() {
+            0..=2 => 1,
+            3..=4 => 2,
+            _ => 4,
+        }
+    }
+
+    /// Number of pages in a multi-page image (PDF, animated WebP/GIF, TIFF).
+    pub fn n_pages(&self, img: &VipsImage<'_>) -> u32 {
+        unsafe { (self.fn_get_n_pages)(img.as_ptr()) }.max(1) as u32
+    }
+
+    /// Whether `img` holds more than one animation frame.
+    pub fn is_animated(&self, img: &VipsImage<'_>) -> bool {
+        let page_height = unsafe { (self.fn_get_page_height)(img.as_ptr()) };
+        let height = unsafe { (self.fn_get_height)(img.as_ptr()) };
+        page_height > 0 && page_height < height
+    }
+
+    /// Load every frame when both `format` and `target` can animate, so the
+    /// encoder sees the whole animation with its delays and loop count;
+    /// otherwise only the first frame.
+    pub fn load_frames(
+        &self,
+        path: &Path,
+        format: ImageFormat,
+        target: ImageFormat,
+    ) -> Result<VipsImage<'_>> {
+        if format.supports_animation() && target.supports_animation() {
+            let img = self.load_image_with_options(path, "n=-1")?;
+            if self.is_animated(&img) {
+                info!(
+                    "[compression] {} is animated ({} frames)",
+                    path.display(),
+                    self.n_pages(&img)
+                );
+            }
+            Ok(img)
+        } else {
+            self.load_image(path)
+        }
+    }
+
+    /// Encode an image into memory; `suffix` selects the saver, e.g. `.jpg[Q=85]`.
+    pub fn save_to_buffer(&self, img: &VipsImage<'_>, suffix: &str) -> Result<Vec<u8>> {
+        let csuffix =
+            CString::new(suffix).map_err(|_| CompressionError::InvalidPath(suffix.to_string()))?;
+        let mut buf: *mut c_void = std::ptr::null_mut();
+        let mut size: usize = 0;
+        let ret = unsafe {
+            (self.fn_write_to_buffer)(
+                img.as_ptr(),
+                csuffix.as_ptr(),
+                &mut buf,
+                &mut size,
+                std::ptr::null::<c_char>(),
+            )
+        };
+        if ret != 0 || buf.is_null() {
+            return Err(CompressionError::Vips(format!(
+                "write_to_buffer failed: {}",
+                self.vips_error()
+            )));
+        }
+        let bytes = unsafe { std::slice::from_raw_parts(buf as *const u8, size) }.to_vec();
+        unsafe { (self.fn_g_free)(buf) };
+        Ok(bytes)
+    }
+
+    /// Rotate `img` upright per its EXIF orientation and drop the tag.
+    pub fn autorot(&self, img: &VipsImage<'_>) -> Result<VipsImage<'_>> {
+        let mut out: *mut c_void = std::ptr::null_mut();
+        let ret = unsafe { (self.fn_autorot)(img.as_ptr(), &mut out, std::ptr::null::<c_char>()) };
+        if ret != 0 || out.is_null() {
+            return Err(CompressionError::Vips(format!(
+                "vips_autorot failed: {}",
+                self.vips_error()
+            )));
+        }
+        Ok(VipsImage::new(out, self))
+    }
+
+    /// Whether `img` carries an embedded ICC profile.
+    pub fn has_icc_profile(&self, img: &VipsImage<'_>) -> bool {
+        let name = CString::new("icc-profile-data").unwrap();
+        unsafe { (self.fn_get_typeof)(img.as_ptr(), name.as_ptr()) != 0 }
+    }
+
+    pub fn is_cmyk(&self, img: &VipsImage<'_>) -> bool {
+        unsafe { (self.fn_get_interpretation)(img.as_ptr()) == VIPS_INTERPRETATION_CMYK }
+    }
+
+    /// Convert `img` from its embedded profile to sRGB, keeping 16-bit
+    /// sources at 16 bits. CMYK without a profile goes through libvips'
+    /// built-in CMYK profile.
+    pub fn icc_to_srgb(&self, img: &VipsImage<'_>) -> Result<VipsImage<'_>> {
+        let profile = CString::new("srgb").unwrap();
+        let embedded_key = CString::new("embedded").unwrap();
+        let input_key = CString::new("input_profile").unwrap();
+        let input_profile = CString::new(if self.is_cmyk(img) { "cmyk" } else { "srgb" }).unwrap();
+        let depth_key = CString::new("depth").unwrap();
+        let depth: c_int = if self.is_high_depth(img) { 16 } else { 8 };
+        let mut out: *mut c_void = std::ptr::null_mut();
+        let ret = unsafe {
+            (self.fn_icc_transform)(
+                img.as_ptr(),
+                &mut out,
+                profile.as_ptr(),
+                embedded_key.as_ptr(),
+                1 as c_int,
+                input_key.as_ptr(),
+                input_profile.as_ptr(),
+                depth_key.as_ptr(),
+                depth,
+                std::ptr::null::<c_char>(),
+            )
+        };
+        if ret != 0 || out.is_null() {
+            return Err(CompressionError::Vips(format!(
+                "vips_icc_transform failed: {}",
+                self.vips_error()
+            )));
+        }
+        Ok(VipsImage::new(out, self))
+    }
+
+    /// Resize an image with independent horizontal and vertical scale factors.
+    pub fn resize(&self, img: &VipsImage<'_>, hscale: f64, vscale: f64) -> Result<VipsImage<'_>> {
+        let vscale_key = CString::new("vscale").unwrap();
+        let mut out: *mut c_void = std::ptr::null_mut();
+        let ret = unsafe {
+            (self.fn_resize)(
+                img.as_ptr(),
+                &mut out,
+                hscale,
+                vscale_key.as_ptr(),
+                vscale,
+                std::ptr::null::<c_char>(),
+            )
+        };
+        if ret != 0 || out.is_null() {
+            return Err(CompressionError::Vips(format!(
+                "vips_resize failed: {}",
+                self.vips_error()
+            )));
+        }
+        Ok(VipsImage::new(out, self))
+    }
+
+    /// Load a thumbnail that fits within `width` x `height`.
+    pub fn thumbnail(&self, path: &Path, width: u32, height: u32) -> Result<VipsImage<'_>> {
+        let cpath = path_to_cstring(path)?;
+        let height_key = CString::new("height").unwrap();
+        let mut out: *mut c_void = std::ptr::null_mut();
+        let ret = unsafe {
+            (self.fn_thumbnail)(
+                cpath.as_ptr(),
+                &mut out,
+                width as c_int,
+                height_key.as_ptr(),
+                height as c_int,
+                std::ptr::null::<c_char>(),
+            )
+        };
+        if ret != 0 || out.is_null() {
+            return Err(CompressionError::Vips(format!(
+                "failed to thumbnail {}: {}",
+                path.display(),
+                self.vips_error()
+            )));
+        }
+        Ok(VipsImage::new(out, self))
+    }
+
+    /// Render Pango markup into a one-band image no wider than `width`.
+    pub fn text(&self, markup: &str, width: u32) -> Result<VipsImage<'_>> {
+        let ctext = CString::new(markup)
+            .map_err(|_| CompressionError::Vips("text contains a NUL byte".into()))?;
+        let width_key = CString::new("width").unwrap();
+        let mut out: *mut c_void = std::ptr::null_mut();
+        let ret = unsafe {
+            (self.fn_text)(
+                &mut out,
+                ctext.as_ptr(),
+                width_key.as_ptr(),
+                width as c_int,
+                std::ptr::null::<c_char>(),
+            )
+        };
+        if ret != 0 || out.is_null() {
+            return Err(CompressionError::Vips(format!(
+                "vips_text failed: {}",
+                self.vips_error()
+            )));
+        }
+        Ok(VipsImage::new(out, self))
+    }
+
+    /// Render Pango `markup` in its own colors on a transparent background.
+    pub fn text_rgba(&self, markup: &str, dpi: u32) -> Result<VipsImage<'_>> {
+        let ctext = CString::new(markup)
+            .map_err(|_| CompressionError::Vips("text contains a NUL byte".into()))?;
+        let rgba_key = CString::new("rgba").unwrap();
+        let dpi_key = CString::new("dpi").unwrap();
+        let mut out: *mut c_void = std::ptr::null_mut();
+        let ret = unsafe {
+            (self.fn_text)(
+                &mut out,
+                ctext.as_ptr(),
+                rgba_key.as_ptr(),
+                1 as c_int,
+                dpi_key.as_ptr(),
+                dpi as c_int,
+                std::ptr::null::<c_char>(),
+            )
+        };
+        if ret != 0 || out.is_null() {
+            return Err(CompressionError::Vips(format!(
+                "vips_text failed: {}",
+                self.vips_error()
+            )));
+        }
+        Ok(VipsImage::new(out, self))
+    }
+
+    /// Draw `overlay` over `base` with its top-left corner at `x`, `y`. The
+    /// result always has an alpha band.
+    pub fn composite_over(
+        &self,
+        base: &VipsImage<'_>,
+        overlay: &VipsImage<'_>,
+        x: i32,
+        y: i32,
+    ) -> Result<VipsImage<'_>> {
+        let x_key = CString::new("x").unwrap();
+        let y_key = CString::new("y").unwrap();
+        let mut out: *mut c_void = std::ptr::null_mut();
+        let ret = unsafe {
+            (self.fn_composite2)(
+                base.as_ptr(),
+                overlay.as_ptr(),
+                &mut out,
+                VIPS_BLEND_MODE_OVER,
+                x_key.as_ptr(),
+                x as c_int,
+                y_key.as_ptr(),
+                y as c_int,
+                std::ptr::null::<c_char>(),
+            )
+        };
+        if ret != 0 || out.is_null() {
+            return Err(CompressionError::Vips(format!(
+                "vips_composite2 failed: {}",
+                self.vips_error()
+            )));
+        }
+        Ok(VipsImage::new(out, self))
+    }
+
+    /// Scale the last (alpha) band of an 8-bit image by `factor`.
+    pub fn multiply_alpha(&self, img: &VipsImage<'_>, factor: f64) -> Result<VipsImage<'_>> {
+        let bands = self.bands(img) as usize;
+        let mut a = vec![1.0; bands];
+        a[bands - 1] = factor;
+        let b = vec![0.0; bands];
+        let uchar_key = CString::new("uchar").unwrap();
+        let mut out: *mut c_void = std::ptr::null_mut();
+        let ret = unsafe {
+            (self.fn_linear)(
+                img.as_ptr(),
+                &mut out,
+                a.as_ptr(),
+                b.as_ptr(),
+                bands as c_int,
+                uchar_key.as_ptr(),
+                1 as c_int,
+                std::ptr::null::<c_char>(),
+            )
+        };
+        if ret != 0 || out.is_null() {
+            return Err(CompressionError::Vips(format!(
+                "vips_linear failed: {}",
+                self.vips_error()
+            )));
+        }
+        Ok(VipsImage::new(out, self))
+    }
+
+    /// Bands `first..first + n` of `img`.
+    pub fn extract_bands(&self, img: &VipsImage<'_>, first: u32, n: u32) -> Result<VipsImage<'_>> {
+        let n_key = CString::new("n").unwrap();
+        let mut out: *mut c_void = std::ptr::null_mut();
+        let ret = unsafe {
+            (self.fn_extract_band)(
+                img.as_ptr(),
+                &mut out,
+                first as c_int,
+                n_key.as_ptr(),
+                n as c_int,
+                std::ptr::null::<c_char>(),
+            )
+        };
+        if ret != 0 || out.is_null() {
+            return Err(CompressionError::Vips(format!(
+                "vips_extract_band failed: {}",
+                self.vips_error()
+            )));
+        }
+        Ok(VipsImage::new(out, self))
+    }
+
+    /// Append an opaque alpha band to an 8-bit image.
+    pub fn add_alpha(&self, img: &VipsImage<'_>) -> Result<VipsImage<'_>> {
+        let mut out: *mut c_void = std::ptr::null_mut();
+        let ret = unsafe {
+            (self.fn_bandjoin_const1)(img.as_ptr(), &mut out, 255.0, std::ptr::null::<c_char>())
+        };
+        if ret != 0 || out.is_null() {
+            return Err(CompressionError::Vips(format!(
+                "vips_bandjoin_const1 failed: {}",
+                self.vips_error()
+            )));
+        }
+        Ok(VipsImage::new(out, self))
+    }
+
+    /// The pixel at `x`, `y`, one value per band.
+    pub fn getpoint(&self, img: &VipsImage<'_>, x: u32, y: u32) -> Result<Vec<f64>> {
+        let mut vector: *mut f64 = std::ptr::null_mut();
+        let mut n: c_int = 0;
+        let ret = unsafe {
+            (self.fn_getpoint)(
+                img.as_ptr(),
+                &mut vector,
+                &mut n,
+                x as c_int,
+                y as c_int,
+                std::ptr::null::<c_char>(),
+            )
+        };
+        if ret != 0 || vector.is_null() {
+            return Err(CompressionError::Vips(format!(
+                "vips_getpoint failed: {}",
+                self.vips_error()
+            )));
+        }
+        let values = unsafe { std::slice::from_raw_parts(vector, n.max(0) as usize).to_vec() };
+        unsafe { (self.fn_g_free)(vector as *mut c_void) };
+        Ok(values)
+    }
+
+    /// The `width` x `height` area of `img` starting at `left`, `top`.
+    pub fn crop(
+        &self,
+        img: &VipsImage<'_>,
+        left: u32,
+        top: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<VipsImage<'_>> {
+        let mut out: *mut c_void = std::ptr::null_mut();
+        let ret = unsafe {
+            (self.fn_extract_area)(
+                img.as_ptr(),
+                &mut out,
+                left as c_int,
+                top as c_int,
+                width as c_int,
+                height as c_int,
+                std::ptr::null::<c_char>(),
+            )
+        };
+        if ret != 0 || out.is_null() {
+            return Err(CompressionError::Vips(format!(
+                "vips_extract_area failed: {}",
+                self.vips_error()
+            )));
+        }
+        Ok(VipsImage::new(out, self))
+    }
+
+    /// Crop away margins that are fully transparent, or for opaque images
+    /// the color of the top-left pixel. `None` when there are no margins or
+    /// nothing but margin.
+    pub fn trim_borders(&self, img: &VipsImage<'_>) -> Result<Option<VipsImage<'_>>> {
+        let bands = self.bands(img);
+        let alpha;
+        let (subject, background, threshold) = if matches!(bands, 2 | 4) {
+            alpha = self.extract_bands(img, bands - 1, 1)?;
+            (&alpha, vec![0.0], 0.0)
+        } else {
+            (img, self.getpoint(img, 0, 0)?, 10.0)
+        };
+
+        let background_key = CString::new("background").unwrap();
+        let threshold_key = CString::new("threshold").unwrap();
+        let (mut left, mut top, mut width, mut height): (c_int, c_int, c_int, c_int) = (0, 0, 0, 0);
+        let ret = unsafe {
+            let array = (self.fn_array_double_new)(background.as_ptr(), background.len() as c_int);
+            let ret = (self.fn_find_trim)(
+                subject.as_ptr(),
+                &mut left,
+                &mut top,
+                &mut width,
+                &mut height,
+                background_key.as_ptr(),
+                array,
+                threshold_key.as_ptr(),
+                threshold,
+                std::ptr::null::<c_char>(),
+            );
+            (self.fn_area_unref)(array);
+            ret
+        };
+        if ret != 0 {
+            return Err(CompressionError::Vips(format!(
+                "vips_find_trim failed: {}",
+                self.vips_error()
+            )));
+        }
+
+        let (full_width, full_height) = self.dimensions(img);
+        if width <= 0 || height <= 0 || (width as u32, height as u32) == (full_width, full_height) {
+            return Ok(None);
+        }
+        self.crop(img, left as u32, top as u32, width as u32, height as u32)
+            .map(Some)
+    }
+
+    /// Stack `bottom` under `top`, padding the narrower image.
+    pub fn join_vertical(
+        &self,
+        top: &VipsImage<'_>,
+        bottom: &VipsImage<'_>,
+    ) -> Result<VipsImage<'_>> {
+        let expand_key = CString::new("expand").unwrap();
+        let expand: c_int = 1;
+        let mut out: *mut c_void = std::ptr::null_mut();
+        let ret = unsafe {
+            (self.fn_join)(
+                top.as_ptr(),
+                bottom.as_ptr(),
+                &mut out,
+                VIPS_DIRECTION_VERTICAL,
+                expand_key.as_ptr(),
+                expand,
+                std::ptr::null::<c_char>(),
+            )
+        };
+        if ret != 0 || out.is_null() {
+            return Err(CompressionError::Vips(format!(
+                "vips_join failed: {}",
+                self.vips_error()
+            )));
+        }
+        Ok(VipsImage::new(out, self))
+    }
+
+    /// Lay images out in a grid `across` tiles wide, separated by `shim` pixels.
+    pub fn array_join(
+        &self,
+        images: &[VipsImage<'_>],
+        across: u32,
+        shim: u32,
+    ) -> Result<VipsImage<'_>> {
+        let mut ptrs: Vec<*mut c_void> = images.iter().map(|i| i.as_ptr()).collect();
+        let across_key = CString::new("across").unwrap();
+        let shim_key = CString::new("shim").unwrap();
+        let mut out: *mut c_void = std::ptr::null_mut();
+        let ret = unsafe {
+            (self.fn_array_join)(
+                ptrs.as_mut_ptr(),
+                &mut out,
+                ptrs.len() as c_int,
+                across_key.as_ptr(),
+                across as c_int,
+                shim_key.as_ptr(),
+                shim as c_int,
+                std::ptr::null::<c_char>(),
+            )
+        };
+        if ret != 0 || out.is_null() {
+            return Err(CompressionError::Vips(format!(
+                "vips_arrayjoin failed: {}",
+                self.vips_error()
+            )));
+        }
+        Ok(VipsImage::new(out, self))
+    }
+
+    /// Save an image, letting libvips pick the saver from the file extension.
+    pub fn save(&self, img: &VipsImage<'_>, output: &Path) -> Result<u64> {
+        self.save_image(img.as_ptr(), &output_str(output)?)?;
+        Ok(fs::metadata(output)?.len())
+    }
+
+    /// Save a lossless PNG at maximum compression effort.
+    pub fn save_png_lossless(&self, img: &VipsImage<'_>, output: &Path) -> Result<u64> {
+        let suffix = format!(
+            "{}[compression=9,effort=10,strip,bitdepth=8]",
+            output_str(output)?
+        );
+        info!("[compression] Lossless PNG save params: {}", suffix);
+        self.save_image(img.as_ptr(), &suffix)?;
+        Ok(fs::metadata(output)?.len())
+    }
+
     /// Extract raw pixel data from a VipsImage as RGBA u8 bytes.
     /// Returns (width, height, rgba_bytes).
     pub fn extract_rgba(&self, img: &VipsImage<'_>) -> Result<(u32, u32, Vec<u8>)> {
@@ -543,13 +1573,44 @@ impl Vips {
 
     /// Create a VipsImage from raw RGB data in memory.
     fn load_image_from_rgb(&self, rgb: &[u8], width: u32, height: u32) -> Result<VipsImage<'_>> {
+        self.load_image_from_pixels(rgb, width, height, 3)
+    }
+
+    /// Decode an encoded image (JPEG, PNG, ...) held in memory.
+    pub fn load_image_from_buffer<'a>(&'a self, data: &'a [u8]) -> Result<VipsImage<'a>> {
+        let options = CString::new("").unwrap();
+        let img = unsafe {
+            (self.fn_new_from_buffer)(
+                data.as_ptr() as *const c_void,
+                data.len(),
+                options.as_ptr(),
+                std::ptr::null::<c_char>(),
+            )
+        };
+        if img.is_null() {
+            return Err(CompressionError::Vips(format!(
+                "vips_image_new_from_buffer failed: {}",
+                self.vips_error()
+            )));
+        }
+        Ok(VipsImage::new(img, self))
+    }
+
+    /// Create a VipsImage from interleaved 8-bit pixels with `bands` channels.
+    pub fn load_image_from_pixels(
+        &self,
+        pixels: &[u8],
+        width: u32,
+        height: u32,
+        bands: u32,
+    ) -> Result<VipsImage<'_>> {
         let img = unsafe {
             (self.fn_new_from_memory_copy)(
-                rgb.as_ptr() as *const c_void,
-                rgb.len(),
+                pixels.as_ptr() as *const c_void,
+                pixels.len(),
                 width as c_int,
                 height as c_int,
-                3, // RGB bands
+                bands as c_int,
                 VIPS_FORMAT_UCHAR,
             )
         };
@@ -571,8 +1632,6 @@ impl Vips {
         quality: u8,
         flags: &CompressionFlags,
     ) -> Result<u64> {
-        let q = quality.clamp(1, 100);
-
         let (width, height, rgba) = self.extract_rgba(img)?;
 
         info!(
@@ -582,102 +1641,12 @@ impl Vips {
             rgba.len()
         );
 
-        let mut liq = imagequant::new();
-        liq.set_speed(4)
-            .map_err(|e| CompressionError::Vips(format!("imagequant: {}", e)))?;
-        liq.set_quality(0, q)
-            .map_err(|e| CompressionError::Vips(format!("imagequant set_quality: {}", e)))?;
-        let max_colors = if flags.png_colors >= 2 {
-            flags.png_colors.min(256) as u32
-        } else {
-            256
-        };
-        liq.set_max_colors(max_colors)
-            .map_err(|e| CompressionError::Vips(format!("imagequant set_max_colors: {}", e)))?;
-
-        let pixels: &[imagequant::RGBA] = unsafe {
-            std::slice::from_raw_parts(
-                rgba.as_ptr() as *const imagequant::RGBA,
-                (width as usize) * (height as usize),
-            )
-        };
-
-        let mut liq_img = liq
-            .new_image_borrowed(pixels, width as usize, height as usize, 0.0)
-            .map_err(|e| CompressionError::Vips(format!("imagequant new_image: {}", e)))?;
-
-        let mut quantized = liq
-            .quantize(&mut liq_img)
-            .map_err(|e| CompressionError::Vips(format!("imagequant quantize: {}", e)))?;
-
-        quantized
-            .set_dithering_level(1.0)
-            .map_err(|e| CompressionError::Vips(format!("imagequant dithering: {}", e)))?;
-
-        let (palette, indexed_pixels) = quantized
-            .remapped(&mut liq_img)
-            .map_err(|e| CompressionError::Vips(format!("imagequant remap: {}", e)))?;
-
-        let remap_quality = quantized.quantization_quality().unwrap_or(0);
-        info!(
-            "[compression] imagequant: {} palette colors, quality={}",
-            palette.len(),
-            remap_quality
-        );
-
-        // Encode indexed PNG
-        let file = fs::File::create(output)?;
-        let w = BufWriter::new(file);
-        let mut encoder = png::Encoder::new(w, width, height);
-        encoder.set_color(png::ColorType::Indexed);
-        encoder.set_depth(png::BitDepth::Eight);
-
-        // Build PLTE and tRNS chunks
-        let mut plte = Vec::with_capacity(palette.len() * 3);
-        let mut trns = Vec::with_capacity(palette.len());
-        let mut has_alpha = false;
-        for c in &palette {
-            plte.extend_from_slice(&[c.r, c.g, c.b]);
-            trns.push(c.a);
-            if c.a < 255 {
-                has_alpha = true;
-            }
-        }
-        encoder.set_palette(plte);
-        if has_alpha {
-            encoder.set_trns(trns);
-        }
-
-        if flags.png_interlace {
-            encoder.set_animated(1, 0).ok();
-        }
-
-        let compression = (((100u8.saturating_sub(q)) as f32 / 100.0) * 9.0)
-            .round()
-            .clamp(0.0, 9.0) as u8;
-        let png_compression = match compression {
-            0..=1 => png::Compression::Fast,
-            2..=5 => png::Compression::Default,
-            _ => png::Compression::Best,
-        };
-        encoder.set_compression(png_compression);
-
-        let mut writer = encoder
-            .write_header()
-            .map_err(|e| CompressionError::Vips(format!("PNG write_header: {}", e)))?;
-        writer
-            .write_image_data(&indexed_pixels)
-            .map_err(|e| CompressionError::Vips(format!("PNG write_image_data: {}", e)))?;
-        writer
-            .finish()
-            .map_err(|e| CompressionError::Vips(format!("PNG finish: {}", e)))?;
-
-        let size = fs::metadata(output)?.len();
+        let size = write_quantized_png(&rgba, width, height, quality, flags, output)?;
         info!(
             "[compression] PNG (imagequant) {} → {} bytes (q={})",
             input.display(),
             size,
-            q
+            quality.clamp(1, 100)
         );
         Ok(size)
     }
@@ -705,10 +1674,66 @@ impl Vips {
         info!("[compression] quality={} → libvips Q={}", quality, q);
 
         let effective_format = target_format.unwrap_or(format);
-        let img = self.load_image(input)?;
+        let img = self.load_frames(input, format, effective_format)?;
         self.compress_loaded(&img, input, output, q, flags, effective_format)
     }
 
+    /// Encode `img` as each of `candidates` into temp files and keep the
+    /// smallest, deleting the others. Candidates that fail to encode are
+    /// skipped. The kept file still needs `SmallestOutput::place`.
+    pub fn compress_smallest(
+        &self,
+        img: &VipsImage<'_>,
+        input: &Path,
+        candidates: &[EncodeCandidate],
+    ) -> Result<SmallestOutput> {
+        let mut best: Option<SmallestOutput> = None;
+        let mut sizes = Vec::new();
+        for candidate in candidates {
+            let format = candidate.format;
+            // Losers never touch the output folder
+            let path = crate::cache::temp_path(&format!("smallest.{}", format.extension()));
+            let quality = candidate.quality;
+            let size =
+                match self.compress_loaded(img, input, &path, quality, &candidate.flags, format) {
+                    Ok(size) => size,
+                    Err(e) => {
+                        warn!("[compression] Skipping {} candidate: {}", format, e);
+                        let _ = fs::remove_file(&path);
+                        continue;
+                    }
+                };
+            info!(
+                "[compression] {} candidate for {}: {} bytes",
+                format,
+                input.display(),
+                size
+            );
+            sizes.push(CandidateSize {
+                format: format.to_string(),
+                size,
+            });
+            if best.as_ref().is_some_and(|b| b.size <= size) {
+                let _ = fs::remove_file(&path);
+            } else if let Some(previous) = best.replace(SmallestOutput {
+                format,
+                quality,
+                path,
+                size,
+                candidates: Vec::new(),
+            }) {
+                let _ = fs::remove_file(&previous.path);
+            }
+        }
+        let mut best = best.ok_or_else(|| {
+            CompressionError::UnsupportedFormat(
+                "none of the candidate formats could be written".into(),
+            )
+        })?;
+        best.candidates = sizes;
+        Ok(best)
+    }
+
     /// Compress using a pre-loaded VipsImage (avoids repeated disk reads on retries).
     pub fn compress_loaded(
         &self,
@@ -719,14 +1744,86 @@ impl Vips {
         flags: &CompressionFlags,
         effective_format: ImageFormat,
     ) -> Result<u64> {
-        match effective_format {
+        self.sync_concurrency();
+        // Only worth it while the pixels stay exactly as they are
+        if flags.jpeg_lossless
+            && effective_format == ImageFormat::Jpeg
+            && ImageFormat::from_path(input) == Some(ImageFormat::Jpeg)
+            && flags.watermark.is_none()
+            && !flags.trim
+            && crate::jpegtran::is_upright(input)
+        {
+            match crate::jpegtran::optimize(input, output, flags.jpeg_interlace) {
+                // Re-packing drops every marker, and the colors mean nothing
+                // without the profile
+                Ok(_) if !flags.keep_metadata && !flags.keep_icc => {
+                    return crate::metadata::copy_icc(input, output)
+                        .map_err(CompressionError::External);
+                }
+                Ok(size) => return Ok(size),
+                Err(e) => warn!(
+                    "[compression] Lossless re-pack of {} failed, re-encoding: {}",
+                    input.display(),
+                    e
+                ),
+            }
+        }
+        // Stripping a wide-gamut profile leaves viewers assuming sRGB, which
+        // washes the colors out; convert the pixels instead. CMYK always
+        // needs converting unless kept, or it comes out with inverted colors
+        let cmyk = self.is_cmyk(img);
+        let keep_cmyk = cmyk && flags.keep_cmyk && effective_format == ImageFormat::Jpeg;
+        let to_srgb = if cmyk {
+            !keep_cmyk
+        } else {
+            !flags.keep_metadata && !flags.keep_icc && self.has_icc_profile(img)
+        };
+        let converted;
+        let img = if to_srgb {
+            match self.icc_to_srgb(img) {
+                Ok(srgb) => {
+                    converted = srgb;
+                    &converted
+                }
+                Err(e) => {
+                    warn!(
+                        "[compression] Keeping {}'s colors unconverted: {}",
+                        input.display(),
+                        e
+                    );
+                    img
+                }
+            }
+        } else {
+            img
+        };
+        // Stamped after color conversion so the overlay's sRGB colors hold
+        let watermarked;
+        let img = match &flags.watermark {
+            Some(watermark) if !keep_cmyk && !self.is_animated(img) => {
+                watermarked = crate::watermark::apply(self, img, watermark)?;
+                &watermarked
+            }
+            _ => img,
+        };
+        let size = match effective_format {
             ImageFormat::Png => self.compress_png(img, input, output, quality, flags),
             ImageFormat::Jpeg => self.compress_jpeg(img, input, output, quality, flags),
             ImageFormat::WebP => self.compress_webp(img, input, output, quality, flags),
             ImageFormat::Avif => self.compress_avif(img, input, output, quality, flags),
             ImageFormat::Heif => self.compress_heif(img, input, output, quality, flags),
             ImageFormat::Tiff => self.compress_tiff(img, input, output, quality, flags),
+            ImageFormat::Gif => self.compress_gif(img, input, output, quality, flags),
+            ImageFormat::Jxl => self.compress_jxl(img, input, output, quality, flags),
+            ImageFormat::Raw | ImageFormat::Psd => Err(CompressionError::UnsupportedFormat(
+                format!("{effective_format} can't be written, pick a target format"),
+            )),
+        }?;
+        // CMYK means little without the profile it was separated for
+        if keep_cmyk && !flags.keep_metadata {
+            return crate::metadata::copy_icc(input, output).map_err(CompressionError::External);
         }
+        Ok(size)
     }
 
     // -- format implementations ---------------------------------------------
@@ -765,7 +1862,13 @@ impl Vips {
         let bitdepth = if flags.png_bitdepth > 0 {
             flags.png_bitdepth
         } else {
-            16
+            let bitdepth = self.auto_png_bitdepth(img);
+            info!(
+                "[compression] {} fits in {}-bit PNG",
+                input.display(),
+                bitdepth
+            );
+            bitdepth
         };
 
         let mut parts = vec![
@@ -773,20 +1876,33 @@ impl Vips {
             format!("Q={}", q),
             "effort=10".to_string(),
             format!("filter={}", filter),
-            "strip".to_string(),
+            format!("strip={}", !flags.keep_metadata),
             format!("bitdepth={}", bitdepth),
         ];
 
         if flags.png_interlace {
             parts.push("interlace=true".to_string());
         }
+        // Depths below 8 are saved as a palette
+        if bitdepth < 8 {
+            parts.push(format!("dither={:.2}", flags.png_dither));
+        }
 
         let suffix = format!("{}[{}]", out, parts.join(","));
 
         info!("[compression] PNG save params: {}", suffix);
         self.save_image(img.as_ptr(), &suffix)?;
 
-        let size = fs::metadata(output)?.len();
+        let mut size = fs::metadata(output)?.len();
+        if flags.png_quantize_after {
+            match self
+                .extract_rgba(img)
+                .and_then(|(w, h, rgba)| quantize_png_if_smaller(&rgba, w, h, q, flags, output))
+            {
+                Ok(smaller) => size = smaller,
+                Err(e) => warn!("[compression] imagequant pass failed: {}", e),
+            }
+        }
         info!(
             "[compression] PNG {} → {} bytes (q={})",
             input.display(),
@@ -807,7 +1923,7 @@ impl Vips {
         let q = quality.clamp(1, 100);
         let mut parts = vec![
             format!("Q={}", q),
-            "strip=true".to_string(),
+            format!("strip={}", !flags.keep_metadata),
             format!("optimize-coding={}", flags.jpeg_optimize_coding),
         ];
         if flags.jpeg_interlace {
@@ -876,7 +1992,7 @@ impl Vips {
         let mut parts = vec![
             format!("Q={}", q),
             format!("effort={}", effort),
-            "strip=true".to_string(),
+            format!("strip={}", !flags.keep_metadata),
         ];
         if flags.webp_lossless {
             parts.push("lossless=true".to_string());
@@ -896,7 +2012,8 @@ impl Vips {
         info!("[compression] WebP save params: {}", suffix);
 
         let _quantized;
-        let save_ptr = if flags.webp_quantize {
+        // Quantizing goes through a single RGB buffer, which would merge the frames
+        let save_ptr = if flags.webp_quantize && !self.is_animated(img) {
             match self.extract_rgba(img).and_then(|(w, h, rgba)| {
                 let rgb =
                     self.quantize_rgba_to_rgb(&rgba, w, h, quality, flags.webp_colors, 0.0)?;
@@ -944,7 +2061,7 @@ impl Vips {
         let mut parts = vec![
             format!("Q={}", q),
             format!("effort={}", effort),
-            "strip=true".to_string(),
+            format!("strip={}", !flags.keep_metadata),
         ];
         if flags.avif_lossless {
             parts.push("lossless=true".to_string());
@@ -1009,7 +2126,7 @@ impl Vips {
         let mut parts = vec![
             format!("Q={}", q),
             format!("effort={}", effort),
-            "strip=true".to_string(),
+            format!("strip={}", !flags.keep_metadata),
         ];
         if flags.heif_lossless {
             parts.push("lossless=true".to_string());
@@ -1054,6 +2171,121 @@ impl Vips {
         Ok(size)
     }
 
+    pub fn compress_gif(
+        &self,
+        img: &VipsImage<'_>,
+        input: &Path,
+        output: &Path,
+        quality: u8,
+        flags: &CompressionFlags,
+    ) -> Result<u64> {
+        let q = quality.clamp(1, 100);
+        let effort = if flags.gif_effort > 0 {
+            flags.gif_effort
+        } else {
+            7
+        };
+        let colors = if flags.gif_colors > 0 {
+            flags.gif_colors.clamp(2, 256)
+        } else {
+            256
+        };
+        let bitdepth = (colors as f64).log2().ceil() as u8;
+        // Lower quality lets cgif reuse pixels from the previous frame more aggressively
+        let maxerror = (100 - q) as f64 * 0.32;
+        let parts = [
+            format!("effort={}", effort),
+            format!("bitdepth={}", bitdepth),
+            format!("dither={:.2}", flags.gif_dither),
+            format!("interframe-maxerror={:.1}", maxerror),
+            format!("strip={}", !flags.keep_metadata),
+        ];
+
+        let suffix = format!("{}[{}]", output_str(output)?, parts.join(","));
+        info!("[compression] GIF save params: {}", suffix);
+        if let Err(e) = self.save_image(img.as_ptr(), &suffix) {
+            // Without a working cgif saver, GIFs are re-encoded in pure Rust
+            if ImageFormat::from_path(input) != Some(ImageFormat::Gif) {
+                return Err(e);
+            }
+            warn!(
+                "[compression] libvips couldn't save {}, using the GIF fallback: {}",
+                input.display(),
+                e
+            );
+            return crate::fallback::optimize_gif(input, output, flags);
+        }
+
+        let size = fs::metadata(output)?.len();
+        info!(
+            "[compression] GIF {} → {} bytes (q={})",
+            input.display(),
+            size,
+            q
+        );
+        Ok(size)
+    }
+
+    pub fn compress_jxl(
+        &self,
+        img: &VipsImage<'_>,
+        input: &Path,
+        output: &Path,
+        quality: u8,
+        flags: &CompressionFlags,
+    ) -> Result<u64> {
+        // libvips only sees decoded pixels, so keeping the JPEG bitstream needs
+        // cjxl. Only worth it while the pixels stay exactly as they are
+        if flags.jxl_lossless_jpeg
+            && ImageFormat::from_path(input) == Some(ImageFormat::Jpeg)
+            && flags.watermark.is_none()
+            && !flags.trim
+            && crate::jpegtran::is_upright(input)
+        {
+            let encoder = flags.jxl_encoder.as_deref().unwrap_or("cjxl");
+            match transcode_jpeg_to_jxl(encoder, input, output, flags) {
+                Ok(size) if size < fs::metadata(input)?.len() => return Ok(size),
+                Ok(size) => info!(
+                    "[compression] Lossless JXL of {} is larger ({} bytes), encoding from pixels",
+                    input.display(),
+                    size
+                ),
+                Err(e) => warn!(
+                    "[compression] Lossless JPEG transcode failed, encoding from pixels: {}",
+                    e
+                ),
+            }
+        }
+
+        let q = quality.clamp(1, 100);
+        let effort = if flags.jxl_effort > 0 {
+            flags.jxl_effort
+        } else {
+            7
+        };
+        let mut parts = vec![
+            format!("Q={}", q),
+            format!("effort={}", effort),
+            format!("strip={}", !flags.keep_metadata),
+        ];
+        if flags.jxl_lossless {
+            parts.push("lossless=true".to_string());
+        }
+
+        let suffix = format!("{}[{}]", output_str(output)?, parts.join(","));
+        info!("[compression] JXL save params: {}", suffix);
+        self.save_image(img.as_ptr(), &suffix)?;
+
+        let size = fs::metadata(output)?.len();
+        info!(
+            "[compression] JXL {} → {} bytes (q={})",
+            input.display(),
+            size,
+            q
+        );
+        Ok(size)
+    }
+
     pub fn compress_tiff(
         &self,
         img: &VipsImage<'_>,
@@ -1069,7 +2301,7 @@ impl Vips {
             format!("compression={}", compression),
             format!("Q={}", q),
             format!("predictor={}", predictor),
-            "strip=true".to_string(),
+            format!("strip={}", !flags.keep_metadata),
         ];
         if flags.tiff_tile {
             parts.push("tile=true".to_string());
@@ -1151,15 +2383,294 @@ fn output_str(path: &Path) -> Result<String> {
         .ok_or_else(|| CompressionError::InvalidPath(path.display().to_string()))
 }
 
-pub fn compressed_output_path(
+/// Repack a JPEG's DCT coefficients as JPEG XL. Pixels are untouched and the
+/// original JPEG can be reconstructed bit-for-bit with `djxl`.
+fn transcode_jpeg_to_jxl(
+    encoder: &str,
+    input: &Path,
+    output: &Path,
+    flags: &CompressionFlags,
+) -> Result<u64> {
+    let effort = if flags.jxl_effort > 0 {
+        flags.jxl_effort
+    } else {
+        7
+    };
+    let mut cmd = std::process::Command::new(encoder);
+    cmd.arg(input)
+        .arg(output)
+        .arg("--lossless_jpeg=1")
+        .arg(format!("--effort={}", effort));
+    if flags.deterministic {
+        cmd.arg("--num_threads=1");
+    }
+    let result = cmd
+        .output()
+        .map_err(|e| CompressionError::External(format!("failed to run {}: {}", encoder, e)))?;
+
+    if !result.status.success() {
+        return Err(CompressionError::External(format!(
+            "{} exited with {}: {}",
+            encoder,
+            result.status,
+            String::from_utf8_lossy(&result.stderr).trim()
+        )));
+    }
+    let size = fs::metadata(output)?.len();
+    info!(
+        "[compression] JPEG → JXL (lossless) {} → {} bytes",
+        input.display(),
+        size
+    );
+    Ok(size)
+}
+
+static OUTPUT_NAMING: std::sync::RwLock<Option<OutputNaming>> = std::sync::RwLock::new(None);
+
+/// Naming scheme used by `compressed_output_path`; set from the config.
+pub fn set_output_naming(naming: OutputNaming) {
+    if let Ok(mut current) = OUTPUT_NAMING.write() {
+        *current = Some(naming);
+    }
+}
+
+pub fn output_naming() -> OutputNaming {
+    OUTPUT_NAMING
+        .read()
+        .ok()
+        .and_then(|n| n.clone())
+        .unwrap_or_default()
+}
+
+static DETERMINISTIC: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+/// Thread count last handed to libvips; held while setting it.
+static CONCURRENCY: std::sync::Mutex<Option<i32>> = std::sync::Mutex::new(None);
+
+/// Whether encodes run single-threaded to be reproducible; set from the config.
+pub fn set_deterministic(enabled: bool) {
+    DETERMINISTIC.store(enabled, std::sync::atomic::Ordering::Relaxed);
+}
+
+static COLLISION_POLICY: std::sync::RwLock<CollisionPolicy> =
+    std::sync::RwLock::new(CollisionPolicy::Overwrite);
+
+/// Policy for output paths that already exist; set from the config.
+pub fn set_collision_policy(policy: CollisionPolicy) {
+    if let Ok(mut current) = COLLISION_POLICY.write() {
+        *current = policy;
+    }
+}
+
+pub fn collision_policy() -> CollisionPolicy {
+    COLLISION_POLICY.read().map(|p| *p).unwrap_or_default()
+}
+
+/// Quantize RGBA pixels to at most `png_colors` colors with libimagequant and
+/// write them to `output` as an indexed PNG.
+pub fn write_quantized_png(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    quality: u8,
+    flags: &CompressionFlags,
+    output: &Path,
+) -> Result<u64> {
+    let q = quality.clamp(1, 100);
+
+    let mut liq = imagequant::new();
+    liq.set_speed(4)
+        .map_err(|e| CompressionError::External(format!("imagequant: {}", e)))?;
+    liq.set_quality(0, q)
+        .map_err(|e| CompressionError::External(format!("imagequant set_quality: {}", e)))?;
+    let max_colors = if flags.png_colors >= 2 {
+        flags.png_colors.min(256) as u32
+    } else {
+        256
+    };
+    liq.set_max_colors(max_colors)
+        .map_err(|e| CompressionError::External(format!("imagequant set_max_colors: {}", e)))?;
+
+    let pixels: &[imagequant::RGBA] = unsafe {
+        std::slice::from_raw_parts(
+            rgba.as_ptr() as *const imagequant::RGBA,
+            (width as usize) * (height as usize),
+        )
+    };
+
+    let mut liq_img = liq
+        .new_image_borrowed(pixels, width as usize, height as usize, 0.0)
+        .map_err(|e| CompressionError::External(format!("imagequant new_image: {}", e)))?;
+
+    let mut quantized = liq
+        .quantize(&mut liq_img)
+        .map_err(|e| CompressionError::External(format!("imagequant quantize: {}", e)))?;
+
+    quantized
+        .set_dithering_level(flags.png_dither)
+        .map_err(|e| CompressionError::External(format!("imagequant dithering: {}", e)))?;
+
+    let (palette, indexed_pixels) = quantized
+        .remapped(&mut liq_img)
+        .map_err(|e| CompressionError::External(format!("imagequant remap: {}", e)))?;
+
+    let remap_quality = quantized.quantization_quality().unwrap_or(0);
+    info!(
+        "[compression] imagequant: {} palette colors, quality={}",
+        palette.len(),
+        remap_quality
+    );
+
+    // Encode indexed PNG
+    let file = fs::File::create(output)?;
+    let w = BufWriter::new(file);
+    let mut encoder = png::Encoder::new(w, width, height);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    // Build PLTE and tRNS chunks
+    let mut plte = Vec::with_capacity(palette.len() * 3);
+    let mut trns = Vec::with_capacity(palette.len());
+    let mut has_alpha = false;
+    for c in &palette {
+        plte.extend_from_slice(&[c.r, c.g, c.b]);
+        trns.push(c.a);
+        if c.a < 255 {
+            has_alpha = true;
+        }
+    }
+    encoder.set_palette(plte);
+    if has_alpha {
+        encoder.set_trns(trns);
+    }
+
+    if flags.png_interlace {
+        encoder.set_animated(1, 0).ok();
+    }
+
+    let compression = (((100u8.saturating_sub(q)) as f32 / 100.0) * 9.0)
+        .round()
+        .clamp(0.0, 9.0) as u8;
+    let png_compression = match compression {
+        0..=1 => png::Compression::Fast,
+        2..=5 => png::Compression::Default,
+        _ => png::Compression::Best,
+    };
+    encoder.set_compression(png_compression);
+
+    let mut writer = encoder
+        .write_header()
+        .map_err(|e| CompressionError::External(format!("PNG write_header: {}", e)))?;
+    writer
+        .write_image_data(&indexed_pixels)
+        .map_err(|e| CompressionError::External(format!("PNG write_image_data: {}", e)))?;
+    writer
+        .finish()
+        .map_err(|e| CompressionError::External(format!("PNG finish: {}", e)))?;
+
+    Ok(fs::metadata(output)?.len())
+}
+
+/// Quantize `rgba` next to the truecolor PNG at `output` and keep whichever
+/// is smaller. Returns the size of the file left at `output`.
+pub fn quantize_png_if_smaller(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    quality: u8,
+    flags: &CompressionFlags,
+    output: &Path,
+) -> Result<u64> {
+    let current = fs::metadata(output)?.len();
+    let candidate = crate::cache::temp_path("quantized.png");
+    let quantized = match write_quantized_png(rgba, width, height, quality, flags, &candidate) {
+        Ok(size) => size,
+        Err(e) => {
+            let _ = fs::remove_file(&candidate);
+            return Err(e);
+        }
+    };
+    if quantized < current {
+        crate::cache::persist(&candidate, output)?;
+        info!(
+            "[compression] imagequant pass: {} → {} bytes",
+            current, quantized
+        );
+        Ok(quantized)
+    } else {
+        let _ = fs::remove_file(&candidate);
+        Ok(current)
+    }
+}
+
+/// Where the output for `input` goes, without touching the filesystem.
+pub fn planned_output_path(input: &Path, target_ext: Option<&str>) -> Option<std::path::PathBuf> {
+    output_path_for(input, target_ext, false)
+}
+
+/// Where the output goes once the collision policy had its say: a taken
+/// path is numbered, kept, or refused with `None`.
+fn output_path_for(
+    input: &Path,
+    target_ext: Option<&str>,
+    claim: bool,
+) -> Option<std::path::PathBuf> {
+    let output = unresolved_output_path(input, target_ext, claim)?;
+    if !output.exists() {
+        return Some(output);
+    }
+    match collision_policy() {
+        CollisionPolicy::Overwrite => Some(output),
+        CollisionPolicy::Version => {
+            let dir = output.parent()?;
+            let stem = output.file_stem()?.to_str()?;
+            let ext = output.extension()?.to_str()?;
+            let naming = output_naming();
+            let names = crate::config::FolderNames::read(dir);
+            (2..)
+                .map(|n| dir.join(format!("{}.{}", naming.numbered(stem, n), ext)))
+                .find(|path| !path.exists() && !names.collides(path))
+        }
+        CollisionPolicy::Skip | CollisionPolicy::Error => None,
+    }
+}
+
+/// The file in the way when the collision policy refused `input`'s output
+/// path, so callers can tell that apart from an unusable input.
+pub fn existing_output(input: &Path, target_ext: Option<&str>) -> Option<std::path::PathBuf> {
+    match collision_policy() {
+        CollisionPolicy::Skip | CollisionPolicy::Error => {
+            unresolved_output_path(input, target_ext, false).filter(|p| p.exists())
+        }
+        CollisionPolicy::Version | CollisionPolicy::Overwrite => None,
+    }
+}
+
+fn unresolved_output_path(
     input: &Path,
     target_ext: Option<&str>,
+    claim: bool,
 ) -> Option<std::path::PathBuf> {
-    let stem = input.file_stem()?.to_str()?;
     let ext = match target_ext {
         Some(e) => e,
         None => input.extension()?.to_str()?,
     };
-    let name = format!("{}_compressed.{}", stem, ext);
-    Some(input.with_file_name(name))
+    if let Some(output) = crate::pairs::output_path(input, ext) {
+        return Some(output);
+    }
+    if let Some(output) = crate::output_dir::output_path(input, ext, claim) {
+        return Some(output);
+    }
+    output_naming().path_for(input, ext)
+}
+
+/// Where the output for `input` goes, creating its folder if needed.
+pub fn compressed_output_path(
+    input: &Path,
+    target_ext: Option<&str>,
+) -> Option<std::path::PathBuf> {
+    let output = output_path_for(input, target_ext, true)?;
+    if let Some(parent) = output.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    Some(output)
 }
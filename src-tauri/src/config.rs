@@ -1,6 +1,29 @@
 use log::error;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// A key or password. Serialized as the plain string, but `Debug` prints
+/// it redacted so settings can be logged.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PngConfig {
@@ -11,12 +34,27 @@ pub struct PngConfig {
     pub convert_to: Option<String>,
     #[serde(default)]
     pub interlace: bool,
+    /// 1, 2, 4, 8 or 16; 0 picks the smallest depth that holds the image
+    /// without loss.
     #[serde(default)]
     pub bitdepth: u8,
     #[serde(default)]
     pub filter: Option<String>,
     #[serde(default = "default_png_colors")]
     pub colors: u16,
+    /// Dithering strength (0–100) when reducing to a palette.
+    #[serde(default = "default_dither")]
+    pub dither: u8,
+    /// Quantize the finished PNG to `colors` colors as well, keeping the
+    /// result only when it is smaller.
+    #[serde(default)]
+    pub quantize_after: bool,
+    /// oxipng preset (0–6) for PNGs written without libvips.
+    #[serde(default = "default_oxipng_level")]
+    pub oxipng_level: u8,
+    /// Deflate with Zopfli in the oxipng pass: smaller, but much slower.
+    #[serde(default)]
+    pub zopfli: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -38,6 +76,9 @@ pub struct JpegConfig {
     pub quantize: bool,
     #[serde(default = "default_quantize_colors")]
     pub colors: u16,
+    /// Re-pack JPEG sources without decoding them, ignoring quality
+    #[serde(default)]
+    pub lossless: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -118,10 +159,98 @@ pub struct TiffConfig {
     pub colors: u16,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GifConfig {
+    pub quality: u8,
+    #[serde(default)]
+    pub convert_to: Option<String>,
+    #[serde(default = "default_gif_effort")]
+    pub effort: u8,
+    #[serde(default = "default_quantize_colors")]
+    pub colors: u16,
+    /// Dithering strength (0–100): smooth gradients against grainy noise.
+    #[serde(default = "default_dither")]
+    pub dither: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JxlConfig {
+    pub quality: u8,
+    #[serde(default)]
+    pub convert_to: Option<String>,
+    #[serde(default = "default_jxl_effort")]
+    pub effort: u8,
+    #[serde(default)]
+    pub lossless: bool,
+    /// JPEG inputs are repacked without re-encoding when `cjxl` is available.
+    #[serde(default = "default_true")]
+    pub lossless_jpeg: bool,
+    /// Path to `cjxl`; looked up on PATH when unset.
+    #[serde(default)]
+    pub encoder_path: Option<String>,
+}
+
+/// Camera RAW files are developed and written out as `convert_to`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RawConfig {
+    #[serde(default = "default_raw_quality")]
+    pub quality: u8,
+    #[serde(default = "default_raw_convert_to")]
+    pub convert_to: Option<String>,
+}
+
+fn default_raw_quality() -> u8 {
+    90
+}
+
+fn default_raw_convert_to() -> Option<String> {
+    Some("jpeg".to_string())
+}
+
+impl Default for RawConfig {
+    fn default() -> Self {
+        Self {
+            quality: default_raw_quality(),
+            convert_to: default_raw_convert_to(),
+        }
+    }
+}
+
+/// Photoshop files are flattened and written out as `convert_to`; PNG when
+/// they have transparency and JPEG otherwise if unset.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PsdConfig {
+    #[serde(default = "default_psd_quality")]
+    pub quality: u8,
+    #[serde(default)]
+    pub convert_to: Option<String>,
+}
+
+fn default_psd_quality() -> u8 {
+    85
+}
+
+impl Default for PsdConfig {
+    fn default() -> Self {
+        Self {
+            quality: default_psd_quality(),
+            convert_to: None,
+        }
+    }
+}
+
 fn default_png_colors() -> u16 {
     256
 }
 
+fn default_oxipng_level() -> u8 {
+    6
+}
+
+fn default_dither() -> u8 {
+    100
+}
+
 fn default_quantize_colors() -> u16 {
     256
 }
@@ -134,6 +263,14 @@ fn default_effort_4() -> u8 {
     4
 }
 
+fn default_gif_effort() -> u8 {
+    7
+}
+
+fn default_jxl_effort() -> u8 {
+    7
+}
+
 fn default_alpha_q() -> u8 {
     100
 }
@@ -150,6 +287,18 @@ pub struct FormatOptions {
     pub heif: HeifConfig,
     #[serde(default = "default_tiff_config")]
     pub tiff: TiffConfig,
+    #[serde(default = "default_gif_config")]
+    pub gif: GifConfig,
+    #[serde(default = "default_jxl_config")]
+    pub jxl: JxlConfig,
+    #[serde(default)]
+    pub raw: RawConfig,
+    #[serde(default)]
+    pub psd: PsdConfig,
+    /// Formats written in addition to the usual output, e.g. `["jpeg"]` next
+    /// to WebP for apps that can't open it. Each gets its own record.
+    #[serde(default)]
+    pub extra_outputs: Vec<String>,
 }
 
 fn default_webp_config() -> WebpConfig {
@@ -205,6 +354,27 @@ fn default_tiff_config() -> TiffConfig {
     }
 }
 
+fn default_gif_config() -> GifConfig {
+    GifConfig {
+        quality: crate::DEFAULT_QUALITY,
+        convert_to: None,
+        effort: 7,
+        colors: 256,
+        dither: default_dither(),
+    }
+}
+
+fn default_jxl_config() -> JxlConfig {
+    JxlConfig {
+        quality: crate::DEFAULT_QUALITY,
+        convert_to: None,
+        effort: 7,
+        lossless: false,
+        lossless_jpeg: true,
+        encoder_path: None,
+    }
+}
+
 impl Default for FormatOptions {
     fn default() -> Self {
         let q = crate::DEFAULT_QUALITY;
@@ -217,6 +387,10 @@ impl Default for FormatOptions {
                 bitdepth: 0,
                 filter: None,
                 colors: 256,
+                dither: default_dither(),
+                quantize_after: false,
+                oxipng_level: default_oxipng_level(),
+                zopfli: false,
             },
             jpeg: JpegConfig {
                 quality: q,
@@ -228,106 +402,1603 @@ impl Default for FormatOptions {
                 overshoot_deringing: false,
                 quantize: false,
                 colors: 256,
+                lossless: false,
             },
             webp: default_webp_config(),
             avif: default_avif_config(),
             heif: default_heif_config(),
             tiff: default_tiff_config(),
+            gif: default_gif_config(),
+            jxl: default_jxl_config(),
+            raw: RawConfig::default(),
+            psd: PsdConfig::default(),
+            extra_outputs: Vec::new(),
         }
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TextureOutput {
+    #[default]
+    Png,
+    Ktx2,
+    /// Uncompressed BGRA8 with a full mip chain; needs no external tool.
+    Dds,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SizeUnits {
+    /// Powers of 1024, labelled KB/MB/GB as most file managers do.
+    #[default]
+    Binary,
+    /// Powers of 1000 (kB/MB/GB).
+    Decimal,
+}
+
+/// How sizes, percentages and dates are written in notifications, reports
+/// and the tray.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct LocaleConfig {
+    /// BCP 47 tag such as `de-DE`; the system language when unset.
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub units: SizeUnits,
+}
+
+/// A folder whose new files are compressed into `destination` instead of
+/// next to themselves: a one-way, optimized copy.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct FolderPair {
+    pub source: String,
+    pub destination: String,
+    /// Deleting a file in `source` deletes its output in `destination`.
+    #[serde(default)]
+    pub delete_with_source: bool,
+}
+
+/// Something worth telling the user about, for filtering notification sinks.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationEvent {
+    Compressed,
+    Failed,
+    /// Yesterday's totals, sent once a day.
+    DailySummary,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct AppConfig {
-    pub watched_folders: Vec<String>,
-    pub quality: u8,
-    pub show_background_notification: bool,
-    pub show_system_notifications: bool,
+pub struct EmailSettings {
+    pub smtp_host: String,
+    /// 465 connects over TLS, any other port upgrades with STARTTLS.
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
     #[serde(default)]
-    pub format_options: FormatOptions,
+    pub username: String,
+    /// Only ever sent by the frontend; it lives in the OS keychain, not the
+    /// config. An empty password removes it.
+    #[serde(default, skip_serializing)]
+    pub password: Option<String>,
+    /// Whether the keychain holds a password, filled in for the frontend.
+    #[serde(
+        default,
+        skip_deserializing,
+        skip_serializing_if = "std::ops::Not::not"
+    )]
+    pub password_set: bool,
+    pub from: String,
+    pub to: String,
 }
 
-impl Default for AppConfig {
-    fn default() -> Self {
-        let mut watched_folders = Vec::new();
-        if let Some(downloads) = dirs::download_dir() {
-            watched_folders.push(downloads.display().to_string());
-        }
-        Self {
-            watched_folders,
-            quality: crate::DEFAULT_QUALITY,
-            show_background_notification: true,
-            show_system_notifications: true,
-            format_options: FormatOptions::default(),
+impl EmailSettings {
+    pub fn keychain_account(&self) -> String {
+        format!("smtp:{}@{}", self.username, self.smtp_host)
+    }
+
+    /// The password to log in with: the one given, e.g. when testing a sink
+    /// before saving it, or else the stored one.
+    pub fn resolved_password(&self) -> String {
+        match &self.password {
+            Some(password) => password.clone(),
+            None => crate::keychain::get(&self.keychain_account()).unwrap_or_default(),
         }
     }
 }
 
-pub struct ConfigManager {
-    pub config: AppConfig,
-    path: PathBuf,
+fn default_smtp_port() -> u16 {
+    587
 }
 
-impl ConfigManager {
-    pub fn load(path: PathBuf) -> Self {
-        let config = std::fs::read_to_string(&path)
-            .ok()
-            .and_then(|s| serde_json::from_str(&s).ok())
-            .unwrap_or_default();
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SinkKind {
+    /// The OS notification center.
+    System,
+    /// Any URL; receives the event as JSON.
+    Webhook {
+        url: String,
+    },
+    /// A Slack incoming webhook.
+    Slack {
+        webhook_url: String,
+    },
+    /// A Discord channel webhook.
+    Discord {
+        webhook_url: String,
+    },
+    Email(EmailSettings),
+}
 
-        Self { config, path }
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NotificationSinkConfig {
+    #[serde(flatten)]
+    pub kind: SinkKind,
+    /// Events sent to this sink; every event when empty.
+    #[serde(default)]
+    pub events: Vec<NotificationEvent>,
+}
+
+fn default_notification_sinks() -> Vec<NotificationSinkConfig> {
+    vec![NotificationSinkConfig {
+        kind: SinkKind::System,
+        events: vec![NotificationEvent::Compressed],
+    }]
+}
+
+/// The email settings inside `sinks`.
+fn email_settings(
+    sinks: &mut [NotificationSinkConfig],
+) -> impl Iterator<Item = &mut EmailSettings> {
+    sinks.iter_mut().filter_map(|sink| match &mut sink.kind {
+        SinkKind::Email(settings) => Some(settings),
+        _ => None,
+    })
+}
+
+/// Where outputs are written relative to their originals.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct OutputNaming {
+    /// Appended to the file stem, e.g. `photo_compressed.jpg`.
+    #[serde(default = "default_output_suffix")]
+    pub suffix: String,
+    /// Output folder; relative paths are resolved against the original's folder.
+    #[serde(default)]
+    pub directory: Option<String>,
+    /// Sort outputs into `YYYY/MM/` subfolders by date taken.
+    #[serde(default)]
+    pub date_subfolders: bool,
+}
+
+fn default_output_suffix() -> String {
+    "_compressed".to_string()
+}
+
+impl Default for OutputNaming {
+    fn default() -> Self {
+        Self {
+            suffix: default_output_suffix(),
+            directory: None,
+            date_subfolders: false,
+        }
     }
+}
 
-    pub fn save(&self) -> Result<(), String> {
-        if let Some(parent) = self.path.parent() {
-            if let Err(e) = std::fs::create_dir_all(parent) {
-                error!("Failed to create config directory: {}", e);
-                return Err(format!("Failed to create config directory: {}", e));
-            }
+impl OutputNaming {
+    /// Output path for `input` with extension `ext`. `None` when the result
+    /// would overwrite the original. Names that only differ in case from the
+    /// original or from another file get a number, since APFS and NTFS
+    /// would treat them as the same file.
+    pub fn path_for(&self, input: &Path, ext: &str) -> Option<PathBuf> {
+        let stem = input.file_stem()?.to_str()?;
+        let parent = input.parent()?;
+        let dir = match self.directory.as_deref().map(Path::new) {
+            Some(dir) if dir.is_absolute() => dir.to_path_buf(),
+            Some(dir) => parent.join(dir),
+            None => parent.to_path_buf(),
+        };
+        let dir = if self.date_subfolders {
+            dir.join(crate::metadata::date_subfolder(input)?)
+        } else {
+            dir
+        };
+        let output = dir.join(format!("{}{}.{}", stem, self.suffix, ext));
+        if output == input {
+            return None;
         }
-        if let Ok(json) = serde_json::to_string_pretty(&self.config) {
-            if let Err(e) = std::fs::write(&self.path, json) {
-                error!("Failed to save config: {}", e);
-                return Err(format!("Failed to save config: {}", e));
-            }
+        let names = FolderNames::read(&dir);
+        let collides = |path: &PathBuf| same_ignoring_case(path, input) || names.collides(path);
+        if !collides(&output) {
+            return Some(output);
         }
-        Ok(())
+        (1..)
+            .map(|n| dir.join(format!("{}_{}{}.{}", stem, n, self.suffix, ext)))
+            .find(|path| !collides(path))
     }
 
-    pub fn add_folder(&mut self, folder: String) {
-        if !self.config.watched_folders.contains(&folder) {
-            self.config.watched_folders.push(folder);
-            let _ = self.save();
+    /// `file_stem` numbered `n`, before the suffix so `is_output` still
+    /// recognises it: `photo_compressed` becomes `photo_2_compressed`.
+    pub fn numbered(&self, file_stem: &str, n: u32) -> String {
+        match file_stem.strip_suffix(self.suffix.as_str()) {
+            Some(base) if !self.suffix.is_empty() => format!("{}_{}{}", base, n, self.suffix),
+            _ => format!("{}_{}", file_stem, n),
         }
     }
 
-    pub fn remove_folder(&mut self, folder: &str) {
-        self.config.watched_folders.retain(|f| f != folder);
-        let _ = self.save();
+    /// Whether `path` looks like an output of this scheme.
+    pub fn is_output(&self, path: &Path) -> bool {
+        let has_suffix = !self.suffix.is_empty()
+            && path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .is_some_and(|s| s.ends_with(&self.suffix));
+        let in_output_dir = self.directory.as_deref().map(Path::new).is_some_and(|dir| {
+            dir.is_absolute() && path.parent().is_some_and(|parent| parent.starts_with(dir))
+        });
+        has_suffix || in_output_dir
     }
+}
 
-    pub fn set_quality(&mut self, quality: u8) {
-        self.config.quality = quality;
-        let _ = self.save();
+fn same_ignoring_case(a: &Path, b: &Path) -> bool {
+    a.to_string_lossy().to_lowercase() == b.to_string_lossy().to_lowercase()
+}
+
+/// The file names in one folder, read once so several candidate names can be
+/// checked against them.
+pub struct FolderNames(Vec<std::ffi::OsString>);
+
+impl FolderNames {
+    pub fn read(dir: &Path) -> Self {
+        Self(
+            std::fs::read_dir(dir)
+                .map(|entries| entries.flatten().map(|e| e.file_name()).collect())
+                .unwrap_or_default(),
+        )
     }
 
-    pub fn set_show_background_notification(&mut self, show: bool) {
-        self.config.show_background_notification = show;
-        let _ = self.save();
+    /// Whether another file here has `path`'s name but for case, which a
+    /// case-insensitive filesystem would overwrite. `exists()` can't tell,
+    /// as it matches either spelling there.
+    pub fn collides(&self, path: &Path) -> bool {
+        let Some(name) = path.file_name() else {
+            return false;
+        };
+        let lower = name.to_string_lossy().to_lowercase();
+        self.0
+            .iter()
+            .any(|other| other != name && other.to_string_lossy().to_lowercase() == lower)
     }
+}
 
-    pub fn set_show_system_notifications(&mut self, show: bool) {
-        self.config.show_system_notifications = show;
-        let _ = self.save();
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VideoConfig {
+    /// Path to `ffmpeg`; the bundled copy or PATH is used when unset.
+    #[serde(default)]
+    pub ffmpeg_path: Option<String>,
+    /// Constant rate factor; higher is smaller and lower quality.
+    #[serde(default = "default_video_crf")]
+    pub crf: u8,
+    /// x264 preset, e.g. `medium` or `slow`. Ignored for WebM.
+    #[serde(default = "default_video_preset")]
+    pub preset: String,
+}
+
+fn default_video_crf() -> u8 {
+    28
+}
+
+fn default_video_preset() -> String {
+    "medium".to_string()
+}
+
+impl Default for VideoConfig {
+    fn default() -> Self {
+        Self {
+            ffmpeg_path: None,
+            crf: default_video_crf(),
+            preset: default_video_preset(),
+        }
     }
+}
 
-    pub fn set_format_options(&mut self, options: FormatOptions) {
-        self.config.format_options = options;
-        let _ = self.save();
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TextureConfig {
+    #[serde(default)]
+    pub output: TextureOutput,
+    /// Path to an external KTX2/Basis encoder (`toktx`). PNG is written when unset.
+    #[serde(default)]
+    pub encoder_path: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OcrConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_true")]
+    pub screenshots_only: bool,
+    /// Tesseract binary; looked up on PATH when unset.
+    #[serde(default)]
+    pub tesseract_path: Option<String>,
+    #[serde(default = "default_ocr_language")]
+    pub language: String,
+}
+
+fn default_ocr_language() -> String {
+    "eng".to_string()
+}
+
+impl Default for OcrConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            screenshots_only: true,
+            tesseract_path: None,
+            language: default_ocr_language(),
+        }
     }
+}
 
-    pub fn reset(&mut self) -> Result<(), String> {
-        self.config = AppConfig::default();
-        self.save()
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PdfConfig {
+    /// Compress PDFs that land in watched folders.
+    #[serde(default)]
+    pub enabled: bool,
+    /// JPEG quality for recompressed embedded images.
+    #[serde(default = "default_pdf_quality")]
+    pub quality: u8,
+    /// Embedded images are downsampled to fit this many pixels on the long side.
+    #[serde(default = "default_pdf_max_dimension")]
+    pub max_dimension: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SvgConfig {
+    /// Minify SVGs that land in watched folders. Off by default, like PDFs
+    /// and videos.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Decimal places kept in path coordinates.
+    #[serde(default = "default_svg_precision")]
+    pub precision: u8,
+}
+
+fn default_svg_precision() -> u8 {
+    3
+}
+
+impl Default for SvgConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            precision: default_svg_precision(),
+        }
+    }
+}
+
+/// Limits on the compression history. Records beyond any of them are pruned
+/// oldest first; their totals are kept in a daily rollup.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct HistoryRetention {
+    #[serde(default)]
+    pub max_records: Option<usize>,
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+    /// Approximate size of the history file.
+    #[serde(default)]
+    pub max_file_bytes: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RecentFilePolicy {
+    #[default]
+    Off,
+    /// Leave the file alone.
+    Skip,
+    /// Compress it once the window has passed.
+    Defer,
+}
+
+/// Keep automatic compression away from files the user just opened, going by
+/// the OS recent documents list.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecentFilesConfig {
+    #[serde(default)]
+    pub policy: RecentFilePolicy,
+    #[serde(default = "default_recent_window_minutes")]
+    pub window_minutes: u64,
+}
+
+fn default_recent_window_minutes() -> u64 {
+    5
+}
+
+impl Default for RecentFilesConfig {
+    fn default() -> Self {
+        Self {
+            policy: RecentFilePolicy::default(),
+            window_minutes: default_recent_window_minutes(),
+        }
+    }
+}
+
+/// Where intermediate files go and how large that folder may grow before
+/// the least recently used files are deleted.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TempConfig {
+    /// `None` uses the app's cache folder.
+    #[serde(default)]
+    pub directory: Option<String>,
+    #[serde(default = "default_temp_max_bytes")]
+    pub max_bytes: u64,
+}
+
+fn default_temp_max_bytes() -> u64 {
+    512 * 1024 * 1024
+}
+
+impl Default for TempConfig {
+    fn default() -> Self {
+        Self {
+            directory: None,
+            max_bytes: default_temp_max_bytes(),
+        }
+    }
+}
+
+/// Encode each image to several formats and keep whichever file is smallest.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SmallestWinsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Formats tried, by extension. Every candidate gets the quality
+    /// configured for the source format.
+    #[serde(default = "default_smallest_candidates")]
+    pub candidates: Vec<String>,
+    /// When converting, also encode the source format and keep it if the
+    /// converted file would be larger.
+    #[serde(default)]
+    pub conditional_conversion: bool,
+}
+
+fn default_smallest_candidates() -> Vec<String> {
+    vec!["jpeg".to_string(), "webp".to_string(), "avif".to_string()]
+}
+
+impl Default for SmallestWinsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            candidates: default_smallest_candidates(),
+            conditional_conversion: false,
+        }
+    }
+}
+
+fn default_pdf_quality() -> u8 {
+    75
+}
+
+fn default_pdf_max_dimension() -> u32 {
+    2000
+}
+
+impl Default for PdfConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            quality: default_pdf_quality(),
+            max_dimension: default_pdf_max_dimension(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumMode {
+    #[default]
+    Off,
+    /// Store hashes in the compression history only.
+    History,
+    /// Store hashes in history and write `.sha256` files next to both files.
+    Sidecar,
+}
+
+/// What to do with sources that have more than 8 bits per sample or HDR
+/// data, which 8-bit outputs flatten.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HighDepthPolicy {
+    /// Write 16-bit PNG/TIFF or 10-bit HEIF/AVIF; warn when the output
+    /// format can't hold it.
+    #[default]
+    Keep,
+    Skip,
+    /// Compress as usual, with a warning.
+    Warn,
+}
+
+/// What to do with a browser's `name (1).jpg` copy of a download Hat already
+/// processed, when the content is identical.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateDownloadPolicy {
+    /// Treat it like any other new file.
+    #[default]
+    Compress,
+    /// Skip it and offer to delete it.
+    Ask,
+    /// Delete it without compressing.
+    Delete,
+}
+
+/// What to do when an output's path is already taken by another file.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CollisionPolicy {
+    /// Write next to it as `name_2.ext`.
+    Version,
+    #[default]
+    Overwrite,
+    /// Leave the file alone and record a skip.
+    Skip,
+    /// Fail the compression.
+    Error,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SensitiveGuardConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Optional command run with the image path; non-empty stdout flags the file.
+    #[serde(default)]
+    pub classifier_command: Option<String>,
+}
+
+/// Filters keyed on the app that wrote a file. Names match case-insensitively
+/// as substrings, so "chrome" matches both "Google Chrome" and "chrome.exe".
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ProducerRules {
+    /// When non-empty, only files written by one of these apps are compressed.
+    #[serde(default)]
+    pub allowed_apps: Vec<String>,
+    #[serde(default)]
+    pub ignored_apps: Vec<String>,
+}
+
+impl ProducerRules {
+    pub fn is_empty(&self) -> bool {
+        self.allowed_apps.is_empty() && self.ignored_apps.is_empty()
+    }
+
+    /// Whether a file written by `app` should be compressed. Files whose writer
+    /// couldn't be identified are always allowed.
+    pub fn allows(&self, app: Option<&str>) -> bool {
+        let Some(app) = app.map(str::to_lowercase) else {
+            return true;
+        };
+        let matches = |names: &[String]| names.iter().any(|n| app.contains(&n.to_lowercase()));
+        if matches(&self.ignored_apps) {
+            return false;
+        }
+        self.allowed_apps.is_empty() || matches(&self.allowed_apps)
+    }
+}
+
+/// Named format options that replace the global ones while active.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CompressionProfile {
+    pub name: String,
+    pub format_options: FormatOptions,
+}
+
+impl CompressionProfile {
+    pub const ECO: &'static str = "Eco";
+
+    /// `base` with the fastest encoder settings, trading a little size for
+    /// much less CPU time.
+    pub fn eco(base: &FormatOptions) -> Self {
+        let mut format_options = base.clone();
+        format_options.png.oxipng_level = 1;
+        format_options.png.zopfli = false;
+        format_options.jpeg.trellis_quant = false;
+        format_options.webp.effort = 1;
+        format_options.avif.effort = 2;
+        format_options.heif.effort = 2;
+        format_options.gif.effort = 1;
+        format_options.jxl.effort = 3;
+        Self {
+            name: Self::ECO.to_string(),
+            format_options,
+        }
+    }
+}
+
+/// Activates `profile` when every condition that is set matches the current
+/// machine. Names match case-insensitively; `interface` as a substring, so
+/// "utun" covers any macOS VPN tunnel.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ContextRule {
+    pub profile: String,
+    #[serde(default)]
+    pub hostname: Option<String>,
+    #[serde(default)]
+    pub ssid: Option<String>,
+    #[serde(default)]
+    pub interface: Option<String>,
+}
+
+impl ContextRule {
+    pub fn matches(&self, context: &crate::context::NetworkContext) -> bool {
+        let equals =
+            |want: &str, have: Option<&str>| have.is_some_and(|h| h.eq_ignore_ascii_case(want));
+        if self.hostname.is_none() && self.ssid.is_none() && self.interface.is_none() {
+            return false;
+        }
+        self.hostname
+            .as_deref()
+            .is_none_or(|h| equals(h, context.hostname.as_deref()))
+            && self
+                .ssid
+                .as_deref()
+                .is_none_or(|s| equals(s, context.ssid.as_deref()))
+            && self.interface.as_deref().is_none_or(|i| {
+                let i = i.to_lowercase();
+                context
+                    .interfaces
+                    .iter()
+                    .any(|name| name.to_lowercase().contains(&i))
+            })
+    }
+}
+
+/// Which existing files in a folder are old enough to leave alone.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(tag = "mode", content = "cutoff", rename_all = "snake_case")]
+pub enum AgeFilter {
+    #[default]
+    Any,
+    /// Only files created after the folder was added to Hat.
+    SinceAdded,
+    /// Only files created after this Unix timestamp (seconds).
+    Since(u64),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct FolderSettings {
+    #[serde(default)]
+    pub game_asset_mode: bool,
+    #[serde(default)]
+    pub encrypt_output: bool,
+    /// Hex-encoded 32-byte ChaCha20-Poly1305 key used when `encrypt_output` is set.
+    /// Only ever sent by the frontend; it lives in the OS keychain, not the config.
+    #[serde(default, skip_serializing)]
+    pub encryption_key: Option<Secret>,
+    /// Measure potential savings for new files without writing any outputs.
+    #[serde(default)]
+    pub observe_only: bool,
+    #[serde(default)]
+    pub age_filter: AgeFilter,
+    /// Re-encode videos that arrive here. Off by default since it is slow.
+    #[serde(default)]
+    pub compress_videos: bool,
+    /// Develop camera RAW files that arrive here. Off by default since
+    /// photographers usually keep RAWs untouched.
+    #[serde(default)]
+    pub decode_raw: bool,
+    /// Output format for every image here, e.g. `webp`. Overrides the global
+    /// target and per-format conversions; folder format rules still win.
+    #[serde(default)]
+    pub target_format: Option<String>,
+    /// Keep camera, copyright and date metadata in outputs here. `None`
+    /// follows the global setting.
+    #[serde(default)]
+    pub preserve_metadata: Option<bool>,
+    /// Keep metadata but drop GPS location here. `None` follows the global
+    /// setting.
+    #[serde(default)]
+    pub strip_location: Option<bool>,
+    /// Write progressive JPEGs here, or baseline ones for tools that can't
+    /// read progressive files. `None` follows the JPEG options.
+    #[serde(default)]
+    pub progressive_jpeg: Option<bool>,
+    /// Stamp outputs here with the watermark configured in `AppConfig`.
+    #[serde(default)]
+    pub watermark: bool,
+    /// Crop uniform or transparent margins from every image here. `None`
+    /// follows the global setting, which only applies to screenshots.
+    #[serde(default)]
+    pub trim_borders: Option<bool>,
+    /// When the folder was added, in seconds since the epoch.
+    #[serde(default)]
+    pub added_at: Option<u64>,
+}
+
+impl FolderSettings {
+    /// Files created before this timestamp are not processed.
+    pub fn cutoff(&self) -> Option<u64> {
+        match self.age_filter {
+            AgeFilter::Any => None,
+            AgeFilter::SinceAdded => self.added_at,
+            AgeFilter::Since(cutoff) => Some(cutoff),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WatermarkPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+    Center,
+}
+
+/// An overlay composited onto outputs of folders that enable it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WatermarkConfig {
+    /// PNG or SVG to overlay; takes precedence over `text`.
+    #[serde(default)]
+    pub image: Option<String>,
+    #[serde(default)]
+    pub text: Option<String>,
+    /// Any color Pango understands, e.g. `#ffffff` or `white`.
+    #[serde(default = "default_watermark_color")]
+    pub text_color: String,
+    #[serde(default)]
+    pub position: WatermarkPosition,
+    /// 0.0 (invisible) to 1.0 (opaque).
+    #[serde(default = "default_watermark_opacity")]
+    pub opacity: f32,
+    /// Width of the watermark as a fraction of the image's width.
+    #[serde(default = "default_watermark_scale")]
+    pub scale: f32,
+    /// Distance from the nearest edges, as a fraction of the image's width.
+    #[serde(default = "default_watermark_margin")]
+    pub margin: f32,
+}
+
+fn default_watermark_color() -> String {
+    "#ffffff".into()
+}
+
+fn default_watermark_opacity() -> f32 {
+    0.5
+}
+
+fn default_watermark_scale() -> f32 {
+    0.2
+}
+
+fn default_watermark_margin() -> f32 {
+    0.02
+}
+
+impl Default for WatermarkConfig {
+    fn default() -> Self {
+        Self {
+            image: None,
+            text: None,
+            text_color: default_watermark_color(),
+            position: WatermarkPosition::default(),
+            opacity: default_watermark_opacity(),
+            scale: default_watermark_scale(),
+            margin: default_watermark_margin(),
+        }
+    }
+}
+
+/// Folder-scoped settings for one input format; unset fields fall back to
+/// the global format options.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct FormatRule {
+    #[serde(default)]
+    pub convert_to: Option<String>,
+    #[serde(default)]
+    pub quality: Option<u8>,
+    /// Fields of the output format's options, e.g. `{"effort": 6}`.
+    #[serde(default)]
+    pub options: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Whether auto-compression may touch a removable volume. Matched on both
+/// label and serial so re-plugging the same drive applies the same choice.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct VolumePolicy {
+    pub label: String,
+    pub serial: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AppConfig {
+    pub watched_folders: Vec<String>,
+    #[serde(default)]
+    pub folder_pairs: Vec<FolderPair>,
+    pub quality: u8,
+    pub show_background_notification: bool,
+    pub show_system_notifications: bool,
+    /// Where notifications go. The system sink also honours
+    /// `show_system_notifications`.
+    #[serde(default = "default_notification_sinks")]
+    pub notification_sinks: Vec<NotificationSinkConfig>,
+    /// Also emit events under their old names, e.g. `new-download`.
+    #[serde(default = "default_true")]
+    pub legacy_event_names: bool,
+    #[serde(default)]
+    pub format_options: FormatOptions,
+    /// Quality per format name (`png`, `jpeg`, ...), taking precedence over
+    /// the quality in the format options.
+    #[serde(default)]
+    pub format_quality: HashMap<String, u8>,
+    /// Output format for every image without a more specific conversion.
+    /// `None` keeps each image's own format.
+    #[serde(default)]
+    pub target_format: Option<String>,
+    /// Used for images without an explicit conversion target.
+    #[serde(default)]
+    pub smallest_wins: SmallestWinsConfig,
+    #[serde(default)]
+    pub folder_settings: HashMap<String, FolderSettings>,
+    /// Folder → input format name (`jpeg`, `png`, ...) → rule.
+    #[serde(default)]
+    pub folder_format_rules: HashMap<String, HashMap<String, FormatRule>>,
+    #[serde(default)]
+    pub texture: TextureConfig,
+    #[serde(default)]
+    pub video: VideoConfig,
+    #[serde(default)]
+    pub output_naming: OutputNaming,
+    /// One folder for every output, e.g. `~/Pictures/Hat`, overriding
+    /// `output_naming`. Folder pairs still write to their destination.
+    #[serde(default)]
+    pub output_dir: Option<String>,
+    /// Originals are moved here after compression instead of staying next
+    /// to their outputs, keeping their subfolders.
+    #[serde(default)]
+    pub archive_dir: Option<String>,
+    #[serde(default)]
+    pub locale: LocaleConfig,
+    #[serde(default)]
+    pub ocr: OcrConfig,
+    #[serde(default)]
+    pub pdf: PdfConfig,
+    #[serde(default)]
+    pub svg: SvgConfig,
+    /// Removable volumes are skipped by the watcher unless enabled here.
+    #[serde(default)]
+    pub volume_policies: Vec<VolumePolicy>,
+    #[serde(default)]
+    pub sensitive_guard: SensitiveGuardConfig,
+    /// Where to look for other copies of originals; watched folders and
+    /// the Pictures folder when empty.
+    #[serde(default)]
+    pub duplicate_scan_locations: Vec<String>,
+    #[serde(default)]
+    pub checksum_mode: ChecksumMode,
+    #[serde(default)]
+    pub high_depth: HighDepthPolicy,
+    /// Link identical re-downloads to the earlier output instead of
+    /// compressing them again. Originals are hashed to recognise them, so
+    /// this is off unless asked for.
+    #[serde(default)]
+    pub reuse_redownloads: bool,
+    #[serde(default)]
+    pub duplicate_downloads: DuplicateDownloadPolicy,
+    #[serde(default)]
+    pub collision_policy: CollisionPolicy,
+    #[serde(default)]
+    pub recent_files: RecentFilesConfig,
+    #[serde(default)]
+    pub temp: TempConfig,
+    #[serde(default)]
+    pub history_retention: HistoryRetention,
+    /// Keep a list of files left alone and why, for `get_skipped`.
+    #[serde(default)]
+    pub skip_log: bool,
+    /// Initialise libvips and its encoders in the background at launch.
+    #[serde(default)]
+    pub warm_start: bool,
+    /// File names of scripts in the scripts directory that are allowed to run.
+    #[serde(default)]
+    pub enabled_scripts: Vec<String>,
+    #[serde(default)]
+    pub producer_rules: ProducerRules,
+    /// Re-running on the same input produces byte-identical output.
+    #[serde(default)]
+    pub deterministic: bool,
+    /// Copy EXIF, XMP, IPTC and ICC metadata to outputs instead of stripping it.
+    #[serde(default)]
+    pub preserve_metadata: bool,
+    /// Privacy mode: keep metadata like `preserve_metadata`, minus the GPS tags.
+    #[serde(default)]
+    pub strip_location: bool,
+    /// Embed the original ICC profile as-is instead of converting
+    /// wide-gamut images to sRGB when metadata is stripped.
+    #[serde(default)]
+    pub keep_icc_profile: bool,
+    /// Write CMYK JPEGs back as CMYK with their profile, for print,
+    /// instead of converting them to sRGB.
+    #[serde(default)]
+    pub keep_cmyk: bool,
+    /// Applied in folders with `watermark` enabled.
+    #[serde(default)]
+    pub watermark: WatermarkConfig,
+    /// Crop uniform or transparent margins from screenshots.
+    #[serde(default)]
+    pub trim_borders: bool,
+    /// Ask the OS to authenticate the user before deleting originals or
+    /// other files in bulk, and before turning this off again.
+    #[serde(default)]
+    pub require_authentication: bool,
+    /// Write outputs over their originals, keeping a backup to undo from.
+    #[serde(default)]
+    pub replace_in_place: bool,
+    #[serde(default)]
+    pub profiles: Vec<CompressionProfile>,
+    /// Checked in order; the first match picks the active profile.
+    #[serde(default)]
+    pub context_rules: Vec<ContextRule>,
+    /// Set by the context monitor; not persisted.
+    #[serde(skip)]
+    pub active_profile: Option<String>,
+}
+
+impl AppConfig {
+    pub fn quality_for(&self, format: crate::compression::ImageFormat) -> Option<u8> {
+        self.format_quality
+            .iter()
+            .find(|(name, _)| crate::compression::ImageFormat::from_extension(name) == Some(format))
+            .map(|(_, &quality)| quality)
+    }
+
+    pub fn volume_enabled(&self, label: &str, serial: &str) -> bool {
+        self.volume_policies
+            .iter()
+            .any(|p| p.label == label && p.serial == serial && p.enabled)
+    }
+
+    pub fn duplicate_scan_locations(&self) -> Vec<PathBuf> {
+        if !self.duplicate_scan_locations.is_empty() {
+            return self
+                .duplicate_scan_locations
+                .iter()
+                .map(PathBuf::from)
+                .collect();
+        }
+        let mut locations: Vec<PathBuf> = self.watched_folders.iter().map(PathBuf::from).collect();
+        if let Some(pictures) = dirs::picture_dir() {
+            if !locations.contains(&pictures) {
+                locations.push(pictures);
+            }
+        }
+        locations
+    }
+
+    /// Whether the global format options or any profile write extra outputs.
+    pub fn has_extra_outputs(&self) -> bool {
+        !self.format_options.extra_outputs.is_empty()
+            || self
+                .profiles
+                .iter()
+                .any(|p| !p.format_options.extra_outputs.is_empty())
+    }
+
+    /// The setting that moves each original away once its first output is
+    /// written, which extra outputs still need to read.
+    fn moves_originals(&self) -> Option<&'static str> {
+        if self.replace_in_place {
+            return Some("Replacing originals in place");
+        }
+        self.archive_dir.is_some().then_some("Archiving originals")
+    }
+
+    /// Refuse replace-in-place while extra outputs still read the originals.
+    pub fn check_replace_in_place(&self) -> Result<(), String> {
+        if self.has_extra_outputs() {
+            return Err(extra_outputs_conflict("Replacing originals in place"));
+        }
+        Ok(())
+    }
+
+    /// Format options of the active profile, or the global ones.
+    pub fn active_format_options(&self) -> &FormatOptions {
+        self.active_profile
+            .as_ref()
+            .and_then(|name| self.profiles.iter().find(|p| &p.name == name))
+            .map(|p| &p.format_options)
+            .unwrap_or(&self.format_options)
+    }
+
+    /// The rule for `format` files in the folder that directly contains `path`.
+    pub fn format_rule_for(&self, path: &Path, format: &str) -> Option<&FormatRule> {
+        let dir = path.parent()?;
+        self.folder_format_rules
+            .iter()
+            .find(|(folder, _)| Path::new(folder) == dir)
+            .and_then(|(_, rules)| rules.get(format))
+    }
+
+    /// Settings for the watched folder that directly contains `path`.
+    pub fn folder_settings_for(&self, path: &Path) -> FolderSettings {
+        path.parent()
+            .and_then(|dir| {
+                self.folder_settings
+                    .iter()
+                    .find(|(folder, _)| Path::new(folder) == dir)
+            })
+            .map(|(folder, settings)| {
+                let mut settings = settings.clone();
+                if settings.encrypt_output {
+                    settings.encryption_key =
+                        crate::keychain::get(&crate::keychain::folder_key_account(folder))
+                            .map(Secret::new);
+                }
+                settings
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        let mut watched_folders = Vec::new();
+        if let Some(downloads) = dirs::download_dir() {
+            watched_folders.push(downloads.display().to_string());
+        }
+        Self {
+            watched_folders,
+            folder_pairs: Vec::new(),
+            quality: crate::DEFAULT_QUALITY,
+            show_background_notification: true,
+            show_system_notifications: true,
+            notification_sinks: default_notification_sinks(),
+            legacy_event_names: true,
+            format_options: FormatOptions::default(),
+            format_quality: HashMap::new(),
+            target_format: None,
+            folder_settings: HashMap::new(),
+            folder_format_rules: HashMap::new(),
+            texture: TextureConfig::default(),
+            video: VideoConfig::default(),
+            output_naming: OutputNaming::default(),
+            output_dir: None,
+            archive_dir: None,
+            locale: LocaleConfig::default(),
+            smallest_wins: SmallestWinsConfig::default(),
+            ocr: OcrConfig::default(),
+            pdf: PdfConfig::default(),
+            svg: SvgConfig::default(),
+            volume_policies: Vec::new(),
+            sensitive_guard: SensitiveGuardConfig::default(),
+            duplicate_scan_locations: Vec::new(),
+            checksum_mode: ChecksumMode::default(),
+            high_depth: HighDepthPolicy::default(),
+            reuse_redownloads: false,
+            duplicate_downloads: DuplicateDownloadPolicy::default(),
+            collision_policy: CollisionPolicy::default(),
+            recent_files: RecentFilesConfig::default(),
+            temp: TempConfig::default(),
+            history_retention: HistoryRetention::default(),
+            skip_log: false,
+            warm_start: false,
+            enabled_scripts: Vec::new(),
+            producer_rules: ProducerRules::default(),
+            deterministic: false,
+            preserve_metadata: false,
+            strip_location: false,
+            keep_icc_profile: false,
+            keep_cmyk: false,
+            watermark: WatermarkConfig::default(),
+            trim_borders: false,
+            require_authentication: false,
+            replace_in_place: false,
+            profiles: Vec::new(),
+            context_rules: Vec::new(),
+            active_profile: None,
+        }
+    }
+}
+
+pub struct ConfigManager {
+    pub config: AppConfig,
+    path: PathBuf,
+}
+
+fn extra_outputs_conflict(setting: &str) -> String {
+    format!(
+        "{} can't be combined with extra output formats, which still read the original after the first output is written",
+        setting
+    )
+}
+
+impl ConfigManager {
+    pub fn load(path: PathBuf) -> Self {
+        let config: AppConfig = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        crate::compression::set_output_naming(config.output_naming.clone());
+        crate::compression::set_collision_policy(config.collision_policy);
+        crate::compression::set_deterministic(config.deterministic);
+        crate::output_dir::set_dir(config.output_dir.as_deref());
+        crate::output_dir::set_roots(&config.watched_folders);
+        crate::archive::set_dir(config.archive_dir.as_deref());
+        crate::locale::set_config(config.locale.clone());
+        crate::skips::set_enabled(config.skip_log);
+        crate::pairs::set_pairs(config.folder_pairs.clone());
+
+        Self { config, path }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            if let Err(e) = std::fs::create_dir_all(parent) {
+                error!("Failed to create config directory: {}", e);
+                return Err(format!("Failed to create config directory: {}", e));
+            }
+        }
+        if let Ok(json) = serde_json::to_string_pretty(&self.config) {
+            if let Err(e) = std::fs::write(&self.path, json) {
+                error!("Failed to save config: {}", e);
+                return Err(format!("Failed to save config: {}", e));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn add_folder(&mut self, folder: String) {
+        if !self.config.watched_folders.contains(&folder) {
+            let added_at = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            self.config
+                .folder_settings
+                .entry(folder.clone())
+                .or_default()
+                .added_at = Some(added_at);
+            self.config.watched_folders.push(folder);
+            crate::output_dir::set_roots(&self.config.watched_folders);
+            let _ = self.save();
+        }
+    }
+
+    /// Add `pair`, replacing any pair with the same source.
+    pub fn add_folder_pair(&mut self, pair: FolderPair) {
+        self.config.folder_pairs.retain(|p| p.source != pair.source);
+        self.config.folder_pairs.push(pair);
+        crate::pairs::set_pairs(self.config.folder_pairs.clone());
+        let _ = self.save();
+    }
+
+    pub fn remove_folder_pair(&mut self, source: &str) {
+        self.config.folder_pairs.retain(|p| p.source != source);
+        crate::pairs::set_pairs(self.config.folder_pairs.clone());
+        let _ = self.save();
+    }
+
+    pub fn remove_folder(&mut self, folder: &str) {
+        self.config.watched_folders.retain(|f| f != folder);
+        self.config.folder_settings.remove(folder);
+        crate::output_dir::set_roots(&self.config.watched_folders);
+        let _ = self.save();
+    }
+
+    /// A key in `settings` is moved to the OS keychain; an empty one removes it.
+    pub fn set_folder_settings(
+        &mut self,
+        folder: String,
+        mut settings: FolderSettings,
+    ) -> Result<(), String> {
+        if let Some(key) = settings.encryption_key.take() {
+            crate::keychain::set(&crate::keychain::folder_key_account(&folder), key.expose())?;
+        }
+        if let Some(existing) = self.config.folder_settings.get(&folder) {
+            settings.added_at = settings.added_at.or(existing.added_at);
+        }
+        self.config.folder_settings.insert(folder, settings);
+        let _ = self.save();
+        Ok(())
+    }
+
+    pub fn set_folder_format_rules(&mut self, folder: String, rules: HashMap<String, FormatRule>) {
+        if rules.is_empty() {
+            self.config.folder_format_rules.remove(&folder);
+        } else {
+            self.config.folder_format_rules.insert(folder, rules);
+        }
+        let _ = self.save();
+    }
+
+    pub fn set_texture_config(&mut self, texture: TextureConfig) {
+        self.config.texture = texture;
+        let _ = self.save();
+    }
+
+    pub fn set_locale(&mut self, locale: LocaleConfig) {
+        crate::locale::set_config(locale.clone());
+        self.config.locale = locale;
+        let _ = self.save();
+    }
+
+    pub fn set_history_retention(&mut self, retention: HistoryRetention) {
+        self.config.history_retention = retention;
+        let _ = self.save();
+    }
+
+    pub fn set_smallest_wins(&mut self, smallest_wins: SmallestWinsConfig) {
+        self.config.smallest_wins = smallest_wins;
+        let _ = self.save();
+    }
+
+    pub fn set_target_format(&mut self, format: Option<String>) {
+        self.config.target_format = format;
+        let _ = self.save();
+    }
+
+    pub fn set_output_naming(&mut self, naming: OutputNaming) {
+        crate::compression::set_output_naming(naming.clone());
+        self.config.output_naming = naming;
+        let _ = self.save();
+    }
+
+    pub fn set_output_dir(&mut self, dir: Option<String>) {
+        crate::output_dir::set_dir(dir.as_deref());
+        self.config.output_dir = dir;
+        let _ = self.save();
+    }
+
+    pub fn set_archive_dir(&mut self, dir: Option<String>) -> Result<(), String> {
+        if dir.is_some() && self.config.has_extra_outputs() {
+            return Err(extra_outputs_conflict("Archiving originals"));
+        }
+        crate::archive::set_dir(dir.as_deref());
+        self.config.archive_dir = dir;
+        let _ = self.save();
+        Ok(())
+    }
+
+    pub fn set_video_config(&mut self, video: VideoConfig) {
+        self.config.video = video;
+        let _ = self.save();
+    }
+
+    pub fn set_ocr_config(&mut self, ocr: OcrConfig) {
+        self.config.ocr = ocr;
+        let _ = self.save();
+    }
+
+    pub fn set_pdf_config(&mut self, pdf: PdfConfig) {
+        self.config.pdf = pdf;
+        let _ = self.save();
+    }
+
+    pub fn set_svg_config(&mut self, svg: SvgConfig) {
+        self.config.svg = svg;
+        let _ = self.save();
+    }
+
+    pub fn set_volume_enabled(&mut self, label: String, serial: String, enabled: bool) {
+        match self
+            .config
+            .volume_policies
+            .iter_mut()
+            .find(|p| p.label == label && p.serial == serial)
+        {
+            Some(policy) => policy.enabled = enabled,
+            None => self.config.volume_policies.push(VolumePolicy {
+                label,
+                serial,
+                enabled,
+            }),
+        }
+        let _ = self.save();
+    }
+
+    pub fn set_duplicate_scan_locations(&mut self, locations: Vec<String>) {
+        self.config.duplicate_scan_locations = locations;
+        let _ = self.save();
+    }
+
+    pub fn set_checksum_mode(&mut self, mode: ChecksumMode) {
+        self.config.checksum_mode = mode;
+        let _ = self.save();
+    }
+
+    pub fn set_high_depth(&mut self, policy: HighDepthPolicy) {
+        self.config.high_depth = policy;
+        let _ = self.save();
+    }
+
+    pub fn set_reuse_redownloads(&mut self, enabled: bool) {
+        self.config.reuse_redownloads = enabled;
+        let _ = self.save();
+    }
+
+    pub fn set_preserve_metadata(&mut self, enabled: bool) {
+        self.config.preserve_metadata = enabled;
+        let _ = self.save();
+    }
+
+    pub fn set_strip_location(&mut self, enabled: bool) {
+        self.config.strip_location = enabled;
+        let _ = self.save();
+    }
+
+    pub fn set_keep_icc_profile(&mut self, enabled: bool) {
+        self.config.keep_icc_profile = enabled;
+        let _ = self.save();
+    }
+
+    pub fn set_keep_cmyk(&mut self, enabled: bool) {
+        self.config.keep_cmyk = enabled;
+        let _ = self.save();
+    }
+
+    pub fn set_watermark(&mut self, watermark: WatermarkConfig) {
+        self.config.watermark = watermark;
+        let _ = self.save();
+    }
+
+    pub fn set_trim_borders(&mut self, enabled: bool) {
+        self.config.trim_borders = enabled;
+        let _ = self.save();
+    }
+
+    pub fn set_require_authentication(&mut self, enabled: bool) {
+        self.config.require_authentication = enabled;
+        let _ = self.save();
+    }
+
+    pub fn set_replace_in_place(&mut self, enabled: bool) -> Result<(), String> {
+        if enabled {
+            self.config.check_replace_in_place()?;
+        }
+        self.config.replace_in_place = enabled;
+        let _ = self.save();
+        Ok(())
+    }
+
+    pub fn set_skip_log(&mut self, enabled: bool) {
+        self.config.skip_log = enabled;
+        crate::skips::set_enabled(enabled);
+        let _ = self.save();
+    }
+
+    pub fn set_warm_start(&mut self, enabled: bool) {
+        self.config.warm_start = enabled;
+        let _ = self.save();
+    }
+
+    pub fn set_duplicate_downloads(&mut self, policy: DuplicateDownloadPolicy) {
+        self.config.duplicate_downloads = policy;
+        let _ = self.save();
+    }
+
+    pub fn set_collision_policy(&mut self, policy: CollisionPolicy) {
+        crate::compression::set_collision_policy(policy);
+        self.config.collision_policy = policy;
+        let _ = self.save();
+    }
+
+    pub fn set_temp(&mut self, temp: TempConfig) {
+        self.config.temp = temp;
+        let _ = self.save();
+    }
+
+    pub fn set_recent_files(&mut self, recent_files: RecentFilesConfig) {
+        self.config.recent_files = recent_files;
+        let _ = self.save();
+    }
+
+    pub fn set_script_enabled(&mut self, name: &str, enabled: bool) {
+        self.config.enabled_scripts.retain(|s| s != name);
+        if enabled {
+            self.config.enabled_scripts.push(name.to_string());
+        }
+        let _ = self.save();
+    }
+
+    pub fn set_producer_rules(&mut self, rules: ProducerRules) {
+        self.config.producer_rules = rules;
+        let _ = self.save();
+    }
+
+    pub fn set_deterministic(&mut self, enabled: bool) {
+        crate::compression::set_deterministic(enabled);
+        self.config.deterministic = enabled;
+        let _ = self.save();
+    }
+
+    pub fn set_profiles(&mut self, profiles: Vec<CompressionProfile>) -> Result<(), String> {
+        if profiles
+            .iter()
+            .any(|p| !p.format_options.extra_outputs.is_empty())
+        {
+            if let Some(setting) = self.config.moves_originals() {
+                return Err(extra_outputs_conflict(setting));
+            }
+        }
+        self.config.profiles = profiles;
+        let _ = self.save();
+        Ok(())
+    }
+
+    /// Add the eco preset built from the global format options, replacing an
+    /// earlier one.
+    pub fn add_eco_profile(&mut self) {
+        let eco = CompressionProfile::eco(&self.config.format_options);
+        self.config
+            .profiles
+            .retain(|p| p.name != CompressionProfile::ECO);
+        self.config.profiles.push(eco);
+        let _ = self.save();
+    }
+
+    pub fn set_context_rules(&mut self, rules: Vec<ContextRule>) {
+        self.config.context_rules = rules;
+        let _ = self.save();
+    }
+
+    pub fn set_sensitive_guard(&mut self, guard: SensitiveGuardConfig) {
+        self.config.sensitive_guard = guard;
+        let _ = self.save();
+    }
+
+    pub fn set_quality(&mut self, quality: u8) {
+        self.config.quality = quality;
+        let _ = self.save();
+    }
+
+    pub fn set_format_quality(&mut self, format_quality: HashMap<String, u8>) {
+        self.config.format_quality = format_quality;
+        let _ = self.save();
+    }
+
+    pub fn set_show_background_notification(&mut self, show: bool) {
+        self.config.show_background_notification = show;
+        let _ = self.save();
+    }
+
+    pub fn set_show_system_notifications(&mut self, show: bool) {
+        self.config.show_system_notifications = show;
+        let _ = self.save();
+    }
+
+    pub fn set_legacy_event_names(&mut self, enabled: bool) {
+        self.config.legacy_event_names = enabled;
+        let _ = self.save();
+    }
+
+    /// Passwords in `sinks` are moved to the OS keychain.
+    pub fn set_notification_sinks(
+        &mut self,
+        mut sinks: Vec<NotificationSinkConfig>,
+    ) -> Result<(), String> {
+        for settings in email_settings(&mut sinks) {
+            if let Some(password) = settings.password.take() {
+                crate::keychain::set(&settings.keychain_account(), &password)?;
+            }
+        }
+        self.config.notification_sinks = sinks;
+        let _ = self.save();
+        Ok(())
+    }
+
+    /// Notification sinks as shown to the frontend, with whether a password
+    /// is stored rather than the password.
+    pub fn public_notification_sinks(&self) -> Vec<NotificationSinkConfig> {
+        let mut sinks = self.config.notification_sinks.clone();
+        for settings in email_settings(&mut sinks) {
+            settings.password_set = crate::keychain::get(&settings.keychain_account()).is_some();
+        }
+        sinks
+    }
+
+    pub fn set_format_options(&mut self, options: FormatOptions) -> Result<(), String> {
+        if !options.extra_outputs.is_empty() {
+            if let Some(setting) = self.config.moves_originals() {
+                return Err(extra_outputs_conflict(setting));
+            }
+        }
+        self.config.format_options = options;
+        let _ = self.save();
+        Ok(())
+    }
+
+    pub fn reset(&mut self) -> Result<(), String> {
+        self.config = AppConfig::default();
+        crate::compression::set_output_naming(self.config.output_naming.clone());
+        crate::compression::set_collision_policy(self.config.collision_policy);
+        crate::compression::set_deterministic(self.config.deterministic);
+        crate::output_dir::set_dir(self.config.output_dir.as_deref());
+        crate::output_dir::set_roots(&self.config.watched_folders);
+        crate::archive::set_dir(self.config.archive_dir.as_deref());
+        crate::locale::set_config(self.config.locale.clone());
+        crate::pairs::set_pairs(self.config.folder_pairs.clone());
+        crate::skips::set_enabled(self.config.skip_log);
+        self.save()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty folder of its own under the system temp dir.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("hat-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn path_for_numbers_outputs_that_collide_ignoring_case() {
+        let dir = temp_dir("case-collision");
+        let input = dir.join("Photo.JPG");
+        std::fs::write(&input, b"").unwrap();
+        let naming = OutputNaming::default();
+
+        assert_eq!(
+            naming.path_for(&input, "jpg"),
+            Some(dir.join("Photo_compressed.jpg"))
+        );
+
+        std::fs::write(dir.join("photo_compressed.jpg"), b"").unwrap();
+        let output = naming.path_for(&input, "jpg").unwrap();
+        assert_eq!(output, dir.join("Photo_1_compressed.jpg"));
+        assert!(naming.is_output(&output));
+
+        std::fs::write(dir.join("PHOTO_1_COMPRESSED.jpg"), b"").unwrap();
+        let output = naming.path_for(&input, "jpg").unwrap();
+        assert_eq!(output, dir.join("Photo_2_compressed.jpg"));
+        assert!(naming.is_output(&output));
+        assert!(!naming.is_output(&input));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn path_for_numbers_outputs_that_only_differ_from_the_input_in_case() {
+        let dir = temp_dir("case-input");
+        let input = dir.join("Photo.JPG");
+        std::fs::write(&input, b"").unwrap();
+        let naming = OutputNaming {
+            suffix: String::new(),
+            ..OutputNaming::default()
+        };
+
+        assert_eq!(naming.path_for(&input, "JPG"), None);
+        assert_eq!(
+            naming.path_for(&input, "jpg"),
+            Some(dir.join("Photo_1.jpg"))
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn folder_names_ignore_the_exact_name() {
+        let dir = temp_dir("folder-names");
+        std::fs::write(dir.join("photo_compressed.jpg"), b"").unwrap();
+        let names = FolderNames::read(&dir);
+
+        assert!(!names.collides(&dir.join("photo_compressed.jpg")));
+        assert!(names.collides(&dir.join("Photo_Compressed.JPG")));
+        assert!(!names.collides(&dir.join("photo_1_compressed.jpg")));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn numbered_keeps_the_suffix_last() {
+        let naming = OutputNaming::default();
+        assert_eq!(naming.numbered("photo_compressed", 2), "photo_2_compressed");
+        assert_eq!(naming.numbered("photo", 2), "photo_2");
     }
 }
@@ -0,0 +1,76 @@
+use crate::compression::{CompressionError, CompressionRecord, Result, Vips, VipsImage};
+use crate::locale::{format_bytes, format_date, format_percent};
+use log::{info, warn};
+use std::path::Path;
+
+const TILE_SIZE: u32 = 256;
+const TILE_SPACING: u32 = 16;
+const MAX_COLUMNS: u32 = 6;
+
+/// Composite thumbnails of `records` into a labelled grid saved at `output`.
+pub fn generate(vips: &Vips, records: &[CompressionRecord], output: &Path) -> Result<u64> {
+    let mut tiles = Vec::with_capacity(records.len());
+    for record in records {
+        match tile(vips, record) {
+            Ok(t) => tiles.push(t),
+            Err(e) => warn!("[contact-sheet] Skipping {}: {}", record.initial_path, e),
+        }
+    }
+    if tiles.is_empty() {
+        return Err(CompressionError::Vips(
+            "none of the selected images could be loaded".into(),
+        ));
+    }
+
+    let across = (tiles.len() as u32).min(MAX_COLUMNS);
+    let sheet = vips.array_join(&tiles, across, TILE_SPACING)?;
+    let size = vips.save(&sheet, output)?;
+    info!(
+        "[contact-sheet] Wrote {} tiles to {} ({} bytes)",
+        tiles.len(),
+        output.display(),
+        size
+    );
+    Ok(size)
+}
+
+fn tile<'a>(vips: &'a Vips, record: &CompressionRecord) -> Result<VipsImage<'a>> {
+    // Prefer the compressed output so the sheet shows what the batch produced
+    let source = if Path::new(&record.final_path).exists() {
+        Path::new(&record.final_path)
+    } else {
+        Path::new(&record.initial_path)
+    };
+    let thumb = vips.thumbnail(source, TILE_SIZE, TILE_SIZE)?;
+    let label = vips.text(&label_markup(record), TILE_SIZE)?;
+    vips.join_vertical(&thumb, &label)
+}
+
+fn label_markup(record: &CompressionRecord) -> String {
+    let name = Path::new(&record.initial_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| record.initial_path.clone());
+    let saved = if record.initial_size > 0 {
+        (record.initial_size.saturating_sub(record.compressed_size) as f64
+            / record.initial_size as f64
+            * 100.0)
+            .round()
+    } else {
+        0.0
+    };
+    format!(
+        "{}\n{} → {} (saved {})\n{}",
+        escape_markup(&name),
+        format_bytes(record.initial_size),
+        format_bytes(record.compressed_size),
+        format_percent(saved),
+        format_date(record.timestamp)
+    )
+}
+
+fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
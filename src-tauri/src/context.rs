@@ -0,0 +1,65 @@
+use log::info;
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+/// Network changes are picked up within this interval.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct NetworkContext {
+    pub hostname: Option<String>,
+    pub ssid: Option<String>,
+    pub interfaces: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextStatus {
+    pub context: NetworkContext,
+    pub active_profile: Option<String>,
+}
+
+pub fn current() -> NetworkContext {
+    let mut interfaces = crate::platform::active_interfaces();
+    interfaces.sort();
+    NetworkContext {
+        hostname: crate::platform::hostname(),
+        ssid: crate::platform::wifi_ssid(),
+        interfaces,
+    }
+}
+
+/// Pick the active profile for `context` and store it in the config.
+/// Returns the new profile name when it changed.
+pub fn apply(app: &tauri::AppHandle, context: &NetworkContext) -> Option<Option<String>> {
+    let config = app.state::<Mutex<crate::config::ConfigManager>>();
+    let mut manager = config.lock().ok()?;
+    let profile = manager
+        .config
+        .context_rules
+        .iter()
+        .find(|rule| rule.matches(context))
+        .map(|rule| rule.profile.clone());
+    if manager.config.active_profile == profile {
+        return None;
+    }
+    info!(
+        "[context] Switching profile {:?} → {:?} ({:?})",
+        manager.config.active_profile, profile, context
+    );
+    manager.config.active_profile = profile.clone();
+    Some(profile)
+}
+
+/// Spawn the thread that re-evaluates context rules and emits
+/// `profile-changed` when the active profile switches.
+pub fn start(app: &tauri::AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        if let Some(profile) = apply(&app, &current()) {
+            let _ = app.emit("profile-changed", &profile);
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    });
+}
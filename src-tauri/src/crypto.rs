@@ -0,0 +1,65 @@
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Header identifying files written by `encrypt_file`.
+const MAGIC: &[u8; 8] = b"HATENC1\0";
+const NONCE_LEN: usize = 12;
+
+/// Hex-encoded SHA-256 of a file's contents.
+pub fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// A fresh random key, hex-encoded for storage in folder settings.
+pub fn generate_key() -> String {
+    hex::encode(ChaCha20Poly1305::generate_key(&mut OsRng))
+}
+
+fn cipher(key_hex: &str) -> Result<ChaCha20Poly1305, String> {
+    let key = hex::decode(key_hex.trim()).map_err(|e| format!("Invalid key: {}", e))?;
+    ChaCha20Poly1305::new_from_slice(&key).map_err(|_| "Key must be 32 bytes".to_string())
+}
+
+/// Encrypt `input` into `<input>.enc` and remove the plaintext.
+/// Returns the encrypted path and its size.
+pub fn encrypt_file(input: &Path, key_hex: &str) -> Result<(PathBuf, u64), String> {
+    let cipher = cipher(key_hex)?;
+    let plaintext = fs::read(input).map_err(|e| e.to_string())?;
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_ref())
+        .map_err(|_| "Encryption failed".to_string())?;
+
+    let mut data = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+    data.extend_from_slice(MAGIC);
+    data.extend_from_slice(&nonce);
+    data.extend_from_slice(&ciphertext);
+
+    let mut name = input.as_os_str().to_os_string();
+    name.push(".enc");
+    let output = PathBuf::from(name);
+    fs::write(&output, &data).map_err(|e| e.to_string())?;
+    fs::remove_file(input).map_err(|e| e.to_string())?;
+    Ok((output, data.len() as u64))
+}
+
+/// Decrypt a file produced by `encrypt_file` into `output`.
+pub fn decrypt_file(input: &Path, key_hex: &str, output: &Path) -> Result<u64, String> {
+    let cipher = cipher(key_hex)?;
+    let data = fs::read(input).map_err(|e| e.to_string())?;
+    if data.len() < MAGIC.len() + NONCE_LEN || !data.starts_with(MAGIC) {
+        return Err("Not a Hat encrypted file".to_string());
+    }
+    let (nonce, ciphertext) = data[MAGIC.len()..].split_at(NONCE_LEN);
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Decryption failed: wrong key or corrupted file".to_string())?;
+    fs::write(output, &plaintext).map_err(|e| e.to_string())?;
+    Ok(plaintext.len() as u64)
+}
@@ -0,0 +1,151 @@
+use crate::compression::CompressionRecord;
+use crate::crypto::sha256_file;
+use log::{info, warn};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// How deep to descend below each scan location.
+const MAX_SCAN_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OriginalCopyReport {
+    pub path: String,
+    pub sha256: Option<String>,
+    /// Other files with identical content.
+    pub copies: Vec<String>,
+    /// True when deleting `path` would remove the last copy of this content.
+    pub only_copy: bool,
+}
+
+/// Look for other copies of each original under `locations`, matching on size
+/// first and SHA-256 second so only plausible candidates are hashed.
+pub fn find_copies(originals: &[PathBuf], locations: &[PathBuf]) -> Vec<OriginalCopyReport> {
+    let mut wanted: HashMap<u64, Vec<&PathBuf>> = HashMap::new();
+    for original in originals {
+        if let Ok(meta) = std::fs::metadata(original) {
+            wanted.entry(meta.len()).or_default().push(original);
+        }
+    }
+
+    let mut candidates: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    for location in locations {
+        collect_candidates(location, 0, &wanted, &mut candidates);
+    }
+
+    let mut hash_cache: HashMap<PathBuf, Option<String>> = HashMap::new();
+    let mut hash_of = |p: &Path| -> Option<String> {
+        hash_cache
+            .entry(p.to_path_buf())
+            .or_insert_with(|| sha256_file(p).ok())
+            .clone()
+    };
+
+    let reports: Vec<OriginalCopyReport> = originals
+        .iter()
+        .map(|original| {
+            let sha256 = hash_of(original.as_path());
+            let size = std::fs::metadata(original).map(|m| m.len()).ok();
+            let copies: Vec<String> = match (&sha256, size) {
+                (Some(hash), Some(size)) => candidates
+                    .get(&size)
+                    .into_iter()
+                    .flatten()
+                    .filter(|c| !same_file(c, original))
+                    .filter(|c| hash_of(c.as_path()).as_ref() == Some(hash))
+                    .map(|c| c.display().to_string())
+                    .collect(),
+                _ => Vec::new(),
+            };
+            OriginalCopyReport {
+                path: original.display().to_string(),
+                only_copy: sha256.is_some() && copies.is_empty(),
+                sha256,
+                copies,
+            }
+        })
+        .collect();
+
+    info!(
+        "[duplicates] Checked {} originals, {} are the only copy",
+        reports.len(),
+        reports.iter().filter(|r| r.only_copy).count()
+    );
+    reports
+}
+
+/// A browser's numbered copy of a file it had already downloaded.
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateDownload {
+    pub path: String,
+    pub original_path: String,
+    /// The record of the earlier download.
+    pub record_id: u64,
+}
+
+/// `photo.jpg` for the `photo (1).jpg` browsers save when a name is taken.
+pub fn numbered_download_original(path: &Path) -> Option<PathBuf> {
+    let stem = path.file_stem()?.to_str()?;
+    let (base, counter) = stem.strip_suffix(')')?.rsplit_once(" (")?;
+    if base.is_empty() || counter.is_empty() || !counter.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let name = match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{base}.{ext}"),
+        None => base.to_string(),
+    };
+    Some(path.with_file_name(name))
+}
+
+/// Whether `path` has the same content as the original of `record`. The hash
+/// stored in the record is preferred; otherwise the original must still exist.
+pub fn same_content_as(path: &Path, record: &CompressionRecord) -> bool {
+    if std::fs::metadata(path).map(|m| m.len()).ok() != Some(record.initial_size) {
+        return false;
+    }
+    let expected = match &record.original_sha256 {
+        Some(hash) => Some(hash.clone()),
+        None => sha256_file(Path::new(&record.initial_path)).ok(),
+    };
+    expected.is_some_and(|expected| sha256_file(path).ok().as_ref() == Some(&expected))
+}
+
+fn collect_candidates(
+    dir: &Path,
+    depth: usize,
+    wanted: &HashMap<u64, Vec<&PathBuf>>,
+    out: &mut HashMap<u64, Vec<PathBuf>>,
+) {
+    if depth > MAX_SCAN_DEPTH {
+        return;
+    }
+    let entries = match std::fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(e) => {
+            warn!("[duplicates] Cannot read {}: {}", dir.display(), e);
+            return;
+        }
+    };
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            collect_candidates(&path, depth + 1, wanted, out);
+        } else if file_type.is_file() {
+            if let Ok(meta) = entry.metadata() {
+                if wanted.contains_key(&meta.len()) {
+                    out.entry(meta.len()).or_default().push(path);
+                }
+            }
+        }
+    }
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
@@ -0,0 +1,47 @@
+use cpu_time::ProcessTime;
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::Manager;
+
+/// Typical draw of one busy laptop core, in watts. Only good for an
+/// order-of-magnitude figure.
+const WATTS_PER_CORE: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct EnergyEstimate {
+    pub cpu_seconds: f64,
+    pub watt_hours: f64,
+}
+
+impl EnergyEstimate {
+    pub fn from_cpu_ms(cpu_ms: u64) -> Self {
+        let cpu_seconds = cpu_ms as f64 / 1000.0;
+        Self {
+            cpu_seconds,
+            watt_hours: cpu_seconds * WATTS_PER_CORE / 3600.0,
+        }
+    }
+}
+
+/// Measures the process CPU time spent on a batch or a watched file. Work
+/// running at the same time, such as another batch, is counted by both.
+pub struct Meter(Option<ProcessTime>);
+
+impl Meter {
+    pub fn start() -> Self {
+        Self(ProcessTime::try_now().ok())
+    }
+
+    /// Add the CPU time since `start` to the history totals and estimate its
+    /// energy.
+    pub fn finish(self, app: &tauri::AppHandle) -> EnergyEstimate {
+        let cpu_ms = self
+            .0
+            .and_then(|started| started.try_elapsed().ok())
+            .map_or(0, |cpu| cpu.as_millis() as u64);
+        if let Ok(mut log) = app.state::<Mutex<crate::log::CompressionLog>>().lock() {
+            log.add_cpu_time(cpu_ms);
+        }
+        EnergyEstimate::from_cpu_ms(cpu_ms)
+    }
+}
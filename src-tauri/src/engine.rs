@@ -0,0 +1,485 @@
+use crate::compression::{CompressionFlags, ImageFormat, Vips};
+use crate::platform::get_lib_path;
+use crate::watcher::VipsState;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
+
+const FIRST_RETRY: Duration = Duration::from_secs(30);
+const MAX_RETRY: Duration = Duration::from_secs(30 * 60);
+/// Reload attempts closer together than this are refused.
+const MIN_RELOAD_INTERVAL: Duration = Duration::from_secs(5);
+/// Consecutive libvips failures after which a format goes to the fallback.
+const DEMOTE_AFTER: u32 = 5;
+/// How long a demotion lasts before libvips gets another try.
+const DEMOTION_SECS: u64 = 30 * 60;
+
+const SELF_CHECK_FILE: &str = "self_check.json";
+/// Formats the self-check encodes, with the libvips saver each one needs.
+const SELF_CHECK_FORMATS: &[(ImageFormat, &str)] = &[
+    (ImageFormat::Png, "pngsave"),
+    (ImageFormat::Jpeg, "jpegsave"),
+    (ImageFormat::WebP, "webpsave"),
+    (ImageFormat::Avif, "heifsave"),
+    (ImageFormat::Heif, "heifsave"),
+    (ImageFormat::Tiff, "tiffsave"),
+    (ImageFormat::Gif, "gifsave"),
+    (ImageFormat::Jxl, "jxlsave"),
+];
+const SELF_CHECK_SIZE: u32 = 16;
+
+/// When libvips was last loaded. Held while loading so attempts never overlap.
+static LAST_ATTEMPT: Mutex<Option<Instant>> = Mutex::new(None);
+static HEALTH: Mutex<Vec<FormatHealth>> = Mutex::new(Vec::new());
+static SELF_CHECK: Mutex<Option<SelfCheckReport>> = Mutex::new(None);
+static SELF_CHECK_RUNNING: AtomicBool = AtomicBool::new(false);
+static WARMED_UP: AtomicBool = AtomicBool::new(false);
+
+/// How libvips has fared with one format since launch.
+#[derive(Debug, Clone, Serialize)]
+pub struct FormatHealth {
+    pub format: ImageFormat,
+    pub successes: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    /// Set while the format is handled by the fallback encoder, in seconds
+    /// since the epoch.
+    pub demoted_at: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfCheckFailure {
+    pub format: ImageFormat,
+    pub error: String,
+}
+
+/// Result of encoding a tiny test image in every format libvips can write.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfCheckReport {
+    /// App version and libvips build the check ran against.
+    pub fingerprint: String,
+    pub checked_at: u64,
+    pub passed: Vec<ImageFormat>,
+    pub failures: Vec<SelfCheckFailure>,
+}
+
+/// Sent as `engine-warmup-complete` once every output format in use has
+/// encoded a pixel, so the first real compression doesn't pay for it.
+#[derive(Debug, Clone, Serialize)]
+pub struct WarmupReport {
+    pub formats: Vec<ImageFormat>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EngineStatus {
+    pub libvips_loaded: bool,
+    /// libvips is loaded and passed the self-check for this build.
+    pub ready: bool,
+    pub warmed_up: bool,
+    pub self_check_running: bool,
+    pub self_check: Option<SelfCheckReport>,
+    pub formats: Vec<FormatHealth>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn update(format: ImageFormat, f: impl FnOnce(&mut FormatHealth)) {
+    let Ok(mut health) = HEALTH.lock() else {
+        return;
+    };
+    let index = match health.iter().position(|h| h.format == format) {
+        Some(index) => index,
+        None => {
+            health.push(FormatHealth {
+                format,
+                successes: 0,
+                failures: 0,
+                consecutive_failures: 0,
+                demoted_at: None,
+                last_error: None,
+            });
+            health.len() - 1
+        }
+    };
+    f(&mut health[index]);
+}
+
+pub fn record_success(format: ImageFormat) {
+    update(format, |h| {
+        h.successes += 1;
+        h.consecutive_failures = 0;
+        h.demoted_at = None;
+    });
+}
+
+/// Count a libvips failure, demoting the format once they keep coming.
+pub fn record_failure(format: ImageFormat, error: &str) {
+    update(format, |h| {
+        h.failures += 1;
+        h.consecutive_failures += 1;
+        h.last_error = Some(error.to_string());
+        if h.consecutive_failures >= DEMOTE_AFTER && h.demoted_at.is_none() {
+            warn!(
+                "[compression] libvips failed {} times in a row for {}, using the fallback encoder",
+                h.consecutive_failures, format
+            );
+            h.demoted_at = Some(now());
+        }
+    });
+}
+
+/// Whether `format` should skip libvips for now. Demotions expire so a
+/// fixed libvips is picked up again; one more failure demotes it again.
+pub fn is_demoted(format: ImageFormat) -> bool {
+    let Ok(mut health) = HEALTH.lock() else {
+        return false;
+    };
+    let Some(h) = health.iter_mut().find(|h| h.format == format) else {
+        return false;
+    };
+    match h.demoted_at {
+        Some(at) if now().saturating_sub(at) < DEMOTION_SECS => true,
+        Some(_) => {
+            info!("[compression] Giving libvips another try for {}", format);
+            h.demoted_at = None;
+            h.consecutive_failures = DEMOTE_AFTER - 1;
+            false
+        }
+        None => false,
+    }
+}
+
+/// Hand `format` to the fallback encoder right away.
+fn demote(format: ImageFormat, error: &str) {
+    update(format, |h| {
+        h.last_error = Some(error.to_string());
+        h.consecutive_failures = h.consecutive_failures.max(DEMOTE_AFTER);
+        h.demoted_at = Some(now());
+    });
+}
+
+pub fn status(app: &tauri::AppHandle) -> EngineStatus {
+    let libvips_loaded = app.state::<VipsState>().get().is_some();
+    let self_check = SELF_CHECK.lock().ok().and_then(|r| r.clone());
+    let running = SELF_CHECK_RUNNING.load(Ordering::Relaxed);
+    EngineStatus {
+        libvips_loaded,
+        ready: libvips_loaded
+            && !running
+            && self_check.as_ref().is_some_and(|r| r.failures.is_empty()),
+        warmed_up: WARMED_UP.load(Ordering::Relaxed),
+        self_check_running: running,
+        self_check,
+        formats: HEALTH.lock().map(|h| h.clone()).unwrap_or_default(),
+    }
+}
+
+/// Changes whenever the app or the libvips library file is updated.
+fn fingerprint(app: &tauri::AppHandle) -> String {
+    let lib = std::fs::metadata(get_lib_path(app))
+        .map(|m| {
+            let modified = m
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs());
+            format!("{}-{}", m.len(), modified)
+        })
+        .unwrap_or_default();
+    format!("{}/{}", app.package_info().version, lib)
+}
+
+fn self_check_path(app: &tauri::AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_config_dir()
+        .ok()
+        .map(|dir| dir.join(SELF_CHECK_FILE))
+}
+
+/// Run the self-check in the background when the app or libvips changed
+/// since the last one, or always when `force` is set. Otherwise the stored
+/// result is reused.
+pub fn start_self_check(app: &tauri::AppHandle, force: bool) {
+    let fingerprint = fingerprint(app);
+    if !force {
+        let stored: Option<SelfCheckReport> = self_check_path(app)
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|json| serde_json::from_str(&json).ok());
+        if let Some(report) = stored.filter(|r| r.fingerprint == fingerprint) {
+            for failure in &report.failures {
+                demote(failure.format, &failure.error);
+            }
+            if let Ok(mut current) = SELF_CHECK.lock() {
+                *current = Some(report);
+            }
+            return;
+        }
+    }
+    if SELF_CHECK_RUNNING.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let report = app
+            .state::<VipsState>()
+            .get()
+            .map(|vips| run_self_check(&app, &vips, fingerprint));
+        SELF_CHECK_RUNNING.store(false, Ordering::Relaxed);
+        let Some(report) = report else {
+            return;
+        };
+        if let Some(path) = self_check_path(&app) {
+            match serde_json::to_string_pretty(&report) {
+                Ok(json) => {
+                    if let Err(e) = std::fs::write(path, json) {
+                        warn!("[compression] Failed to save the self-check: {e}");
+                    }
+                }
+                Err(e) => warn!("[compression] Failed to save the self-check: {e}"),
+            }
+        }
+        if let Ok(mut current) = SELF_CHECK.lock() {
+            *current = Some(report.clone());
+        }
+        let _ = app.emit("engine-ready", &report);
+    });
+}
+
+/// Encode a small gradient in every format this libvips can write, with the
+/// configured options, and decode each result again.
+fn run_self_check(app: &tauri::AppHandle, vips: &Vips, fingerprint: String) -> SelfCheckReport {
+    let options = app
+        .state::<Mutex<crate::config::ConfigManager>>()
+        .lock()
+        .map(|c| c.config.active_format_options().clone())
+        .unwrap_or_default();
+    let dir = crate::cache::temp_path("self-check");
+    let _ = std::fs::create_dir_all(&dir);
+    let input = dir.join("probe.png");
+
+    let size = SELF_CHECK_SIZE;
+    let pixels: Vec<u8> = (0..size * size)
+        .flat_map(|i| {
+            let (x, y) = ((i % size) * 255 / size, (i / size) * 255 / size);
+            [x as u8, y as u8, 128]
+        })
+        .collect();
+
+    let mut report = SelfCheckReport {
+        fingerprint,
+        checked_at: now(),
+        passed: Vec::new(),
+        failures: Vec::new(),
+    };
+    for &(format, saver) in SELF_CHECK_FORMATS {
+        if !vips.has_operation(saver) {
+            continue;
+        }
+        let output = dir.join(format!("probe-out.{}", format.extension()));
+        let flags = CompressionFlags::from_format_options(&options, format);
+        let result = vips
+            .load_image_from_pixels(&pixels, size, size, 3)
+            .and_then(|img| {
+                vips.compress_loaded(
+                    &img,
+                    &input,
+                    &output,
+                    crate::DEFAULT_QUALITY,
+                    &flags,
+                    format,
+                )
+            })
+            .and_then(|_| vips.load_image(&output))
+            .map(|decoded| vips.dimensions(&decoded));
+        match result {
+            Ok(dimensions) if dimensions == (size, size) => report.passed.push(format),
+            Ok((width, height)) => report.failures.push(SelfCheckFailure {
+                format,
+                error: format!("decoded back as {width}x{height}"),
+            }),
+            Err(e) => report.failures.push(SelfCheckFailure {
+                format,
+                error: e.to_string(),
+            }),
+        }
+        let _ = std::fs::remove_file(&output);
+    }
+    let _ = std::fs::remove_dir_all(&dir);
+
+    for failure in &report.failures {
+        warn!(
+            "[compression] Self-check failed for {}: {}",
+            failure.format, failure.error
+        );
+        demote(failure.format, &failure.error);
+    }
+    info!(
+        "[compression] Self-check passed {} of {} formats",
+        report.passed.len(),
+        report.passed.len() + report.failures.len()
+    );
+    report
+}
+
+/// With `warm_start` on, encode a 1×1 image in every format the current
+/// settings write, in the background, so libvips and its encoders are
+/// initialised before the first file arrives.
+pub fn start_warmup(app: &tauri::AppHandle) {
+    let formats = {
+        let config = app.state::<Mutex<crate::config::ConfigManager>>();
+        let Ok(config) = config.lock() else {
+            return;
+        };
+        if !config.config.warm_start {
+            return;
+        }
+        warmup_formats(&config.config)
+    };
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let Some(vips) = app.state::<VipsState>().get() else {
+            return;
+        };
+        let started = Instant::now();
+        let options = app
+            .state::<Mutex<crate::config::ConfigManager>>()
+            .lock()
+            .map(|c| c.config.active_format_options().clone())
+            .unwrap_or_default();
+        let dir = crate::cache::temp_path("warmup");
+        let _ = std::fs::create_dir_all(&dir);
+        let input = dir.join("pixel.png");
+
+        let mut warmed = Vec::new();
+        for format in formats {
+            let saver = SELF_CHECK_FORMATS
+                .iter()
+                .find(|(f, _)| *f == format)
+                .map(|(_, saver)| *saver);
+            if !saver.is_some_and(|saver| vips.has_operation(saver)) {
+                continue;
+            }
+            let output = dir.join(format!("pixel-out.{}", format.extension()));
+            let flags = CompressionFlags::from_format_options(&options, format);
+            let result = vips
+                .load_image_from_pixels(&[128, 128, 128], 1, 1, 3)
+                .and_then(|img| {
+                    vips.compress_loaded(
+                        &img,
+                        &input,
+                        &output,
+                        crate::DEFAULT_QUALITY,
+                        &flags,
+                        format,
+                    )
+                });
+            match result {
+                Ok(_) => warmed.push(format),
+                Err(e) => warn!("[compression] Warm-up failed for {}: {e}", format),
+            }
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let report = WarmupReport {
+            formats: warmed,
+            duration_ms: started.elapsed().as_millis() as u64,
+        };
+        info!(
+            "[compression] Warmed up {} formats in {}ms",
+            report.formats.len(),
+            report.duration_ms
+        );
+        WARMED_UP.store(true, Ordering::Relaxed);
+        let _ = app.emit("engine-warmup-complete", &report);
+    });
+}
+
+/// Every format a file could be written as with the current settings: each
+/// input format's target and the extra outputs.
+fn warmup_formats(config: &crate::config::AppConfig) -> Vec<ImageFormat> {
+    let mut formats: Vec<ImageFormat> = Vec::new();
+    let targets = SELF_CHECK_FORMATS.iter().map(|&(format, _)| {
+        crate::processor::resolve_format_settings(config, std::path::Path::new(""), format)
+            .2
+            .unwrap_or(format)
+    });
+    let extra = config
+        .active_format_options()
+        .extra_outputs
+        .iter()
+        .filter_map(|ext| ImageFormat::from_extension(ext));
+    for format in targets.chain(extra) {
+        if !formats.contains(&format) {
+            formats.push(format);
+        }
+    }
+    formats
+}
+
+/// Load libvips from the bundled library path.
+pub fn load(app: &tauri::AppHandle) -> Result<Arc<Vips>, String> {
+    let mut last = LAST_ATTEMPT.lock().map_err(|e| e.to_string())?;
+    *last = Some(Instant::now());
+    let lib_path = get_lib_path(app);
+    let vips = unsafe { Vips::new(&lib_path) }.map_err(|e| e.to_string())?;
+    info!("[compression] libvips loaded from {}", lib_path.display());
+    Ok(Arc::new(vips))
+}
+
+/// Try loading libvips again after it failed at startup. On success every
+/// later compression uses it and `engine-available` is emitted.
+pub fn reload(app: &tauri::AppHandle) -> Result<(), String> {
+    let state = app.state::<VipsState>();
+    if state.get().is_some() {
+        return Ok(());
+    }
+    let recent = LAST_ATTEMPT
+        .lock()
+        .map_err(|e| e.to_string())?
+        .is_some_and(|t| t.elapsed() < MIN_RELOAD_INTERVAL);
+    if recent {
+        return Err("libvips was tried moments ago, wait a few seconds".to_string());
+    }
+
+    let vips = load(app)?;
+    state.set(vips);
+    let _ = app.emit("engine-available", ());
+    start_self_check(app, false);
+    start_warmup(app);
+    Ok(())
+}
+
+/// Retry loading libvips in the background, backing off up to
+/// `MAX_RETRY` between attempts, until it loads.
+pub fn start_retry(app: &tauri::AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let mut delay = FIRST_RETRY;
+        loop {
+            std::thread::sleep(delay);
+            if app.state::<VipsState>().get().is_some() {
+                return;
+            }
+            match reload(&app) {
+                Ok(()) => return,
+                Err(e) => warn!(
+                    "[compression] libvips still unavailable, retrying in {}s: {e}",
+                    (delay * 2).min(MAX_RETRY).as_secs()
+                ),
+            }
+            delay = (delay * 2).min(MAX_RETRY);
+        }
+    });
+}
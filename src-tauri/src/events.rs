@@ -0,0 +1,116 @@
+use log::warn;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+/// An event the backend emits, for `get_event_schema`.
+#[derive(Debug, Clone, Serialize)]
+pub struct EventInfo {
+    pub name: &'static str,
+    /// Emitted as well while `legacy_event_names` is on.
+    pub legacy_name: Option<&'static str>,
+    /// Bumped whenever the payload changes incompatibly.
+    pub version: u32,
+    /// The frontend type the payload deserializes into.
+    pub payload: &'static str,
+}
+
+const fn event(name: &'static str, version: u32, payload: &'static str) -> EventInfo {
+    EventInfo {
+        name,
+        legacy_name: None,
+        version,
+        payload,
+    }
+}
+
+const EVENTS: &[EventInfo] = &[
+    EventInfo {
+        name: "file-detected",
+        legacy_name: Some("new-download"),
+        version: 1,
+        payload: "NewFile",
+    },
+    event("compression-started", 1, "CompressionStarted"),
+    event("compression-complete", 1, "CompressionRecord"),
+    event("compression-failed", 1, "CompressionFailed"),
+    event("compression-skipped", 1, "CompressionSkipped"),
+    event("compression-retry", 1, "CompressionRetry"),
+    event("compression-warning", 1, "CompressionWarning"),
+    event("compression-held", 1, "HeldFile"),
+    event("duplicate-download", 1, "DuplicateDownload"),
+    event("activity", 1, "ActivityEntry"),
+    event("scan-started", 1, "ScanStarted"),
+    event("scan-finished", 1, "ScanSummary"),
+    event("tasks-bulk-complete", 1, "BulkSummary"),
+    event("engine-ready", 1, "SelfCheckReport"),
+    event("engine-available", 1, "null"),
+    event("engine-warmup-complete", 1, "WarmupReport"),
+    event("profile-changed", 1, "string | null"),
+    event("folder-suggestion", 1, "FolderSuggestion"),
+    event("turbo-changed", 1, "TurboStatus"),
+    event("video-progress", 1, "VideoProgress"),
+    event("watch-debug", 1, "WatchDebug"),
+    event("session-summary", 1, "SessionSummary"),
+    event("wizard-progress", 1, "WizardStatus"),
+    event("deprecation-warning", 1, "DeprecationWarning"),
+];
+
+/// Legacy names that already triggered a `deprecation-warning` this run.
+static WARNED: Mutex<Option<HashSet<&'static str>>> = Mutex::new(None);
+
+/// Sent once per run for each legacy event name still being emitted.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeprecationWarning {
+    pub event: &'static str,
+    pub replacement: &'static str,
+}
+
+pub fn schema() -> Vec<EventInfo> {
+    EVENTS.to_vec()
+}
+
+/// Emit `name`, and its legacy alias too while the config keeps legacy
+/// names on.
+pub fn emit<S: Serialize + Clone>(
+    app: &tauri::AppHandle,
+    name: &str,
+    payload: &S,
+) -> tauri::Result<()> {
+    app.emit(name, payload)?;
+    let Some((legacy, replacement)) = EVENTS
+        .iter()
+        .find(|e| e.name == name)
+        .and_then(|e| Some((e.legacy_name?, e.name)))
+    else {
+        return Ok(());
+    };
+    let legacy_names = app
+        .state::<Mutex<crate::config::ConfigManager>>()
+        .lock()
+        .map(|c| c.config.legacy_event_names)
+        .unwrap_or(true);
+    if !legacy_names {
+        return Ok(());
+    }
+    app.emit(legacy, payload)?;
+
+    let first = WARNED
+        .lock()
+        .is_ok_and(|mut warned| warned.get_or_insert_with(HashSet::new).insert(legacy));
+    if first {
+        warn!(
+            "[events] \"{}\" is deprecated, listen for \"{}\" instead",
+            legacy, replacement
+        );
+        app.emit(
+            "deprecation-warning",
+            &DeprecationWarning {
+                event: legacy,
+                replacement,
+            },
+        )?;
+    }
+    Ok(())
+}
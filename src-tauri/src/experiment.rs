@@ -0,0 +1,246 @@
+use crate::compression::{CompressionFlags, ImageFormat, Vips};
+use crate::config::FormatOptions;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// SSIM is averaged over square blocks of this many pixels a side.
+const SSIM_BLOCK: usize = 8;
+const SSIM_C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+const SSIM_C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+/// Parameters to try on every sample. An empty `efforts` runs each format at
+/// its configured effort only.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExperimentGrid {
+    pub formats: Vec<String>,
+    pub qualities: Vec<u8>,
+    #[serde(default)]
+    pub efforts: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExperimentRow {
+    pub file: String,
+    pub format: String,
+    pub quality: u8,
+    pub effort: Option<u8>,
+    pub original_size: u64,
+    pub size: u64,
+    /// 1.0 is identical to the source.
+    pub ssim: f64,
+    pub encode_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExperimentReport {
+    pub csv_path: String,
+    pub rows: Vec<ExperimentRow>,
+    /// Samples or grid points that could not be run.
+    pub errors: Vec<String>,
+}
+
+/// Compress each of `samples` at every point of `grid` and write the sizes,
+/// SSIM and encode times to a CSV in `out_dir`. Encodes go to a scratch
+/// file that is removed again; the samples are never touched.
+pub fn run(
+    vips: &Vips,
+    samples: &[PathBuf],
+    grid: &ExperimentGrid,
+    opts: &FormatOptions,
+    out_dir: &Path,
+) -> Result<ExperimentReport, String> {
+    let formats = grid
+        .formats
+        .iter()
+        .map(|name| {
+            ImageFormat::from_extension(name)
+                .filter(ImageFormat::is_writable)
+                .ok_or_else(|| format!("Unsupported format: {}", name))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if formats.is_empty() || grid.qualities.is_empty() {
+        return Err("The grid needs at least one format and one quality".into());
+    }
+    let efforts: Vec<Option<u8>> = if grid.efforts.is_empty() {
+        vec![None]
+    } else {
+        grid.efforts.iter().copied().map(Some).collect()
+    };
+    std::fs::create_dir_all(out_dir).map_err(|e| e.to_string())?;
+
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    // A fresh scratch name per encode, so libvips' cache never hands back an
+    // earlier load of the same path
+    let mut encodes = 0;
+    for sample in samples {
+        let loaded = vips.load_image(sample).and_then(|img| {
+            let (width, height, rgba) = vips.extract_rgba(&img)?;
+            Ok((img, width, height, rgba))
+        });
+        let (img, width, height, reference) = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                errors.push(format!("{}: {}", sample.display(), e));
+                continue;
+            }
+        };
+        let original_size = std::fs::metadata(sample).map_or(0, |m| m.len());
+        let reference = luma(&reference);
+
+        for &format in &formats {
+            for &quality in &grid.qualities {
+                for &effort in &efforts {
+                    encodes += 1;
+                    let scratch =
+                        out_dir.join(format!("scratch-{}.{}", encodes, format.extension()));
+                    let mut flags = CompressionFlags::from_format_options(opts, format);
+                    if let Some(effort) = effort {
+                        set_effort(&mut flags, format, effort);
+                    }
+                    let quality = quality.clamp(1, 100);
+                    let started = Instant::now();
+                    let encoded =
+                        vips.compress_loaded(&img, sample, &scratch, quality, &flags, format);
+                    let encode_ms = started.elapsed().as_millis() as u64;
+                    let result = encoded.map_err(|e| e.to_string()).and_then(|size| {
+                        let out = vips.load_image(&scratch).map_err(|e| e.to_string())?;
+                        let (w, h, rgba) = vips.extract_rgba(&out).map_err(|e| e.to_string())?;
+                        if (w, h) != (width, height) {
+                            return Err(format!(
+                                "output is {}x{}, source {}x{}",
+                                w, h, width, height
+                            ));
+                        }
+                        Ok((size, ssim(&reference, &luma(&rgba), width as usize)))
+                    });
+                    let _ = std::fs::remove_file(&scratch);
+                    match result {
+                        Ok((size, ssim)) => rows.push(ExperimentRow {
+                            file: sample.display().to_string(),
+                            format: format.to_string(),
+                            quality,
+                            effort,
+                            original_size,
+                            size,
+                            ssim,
+                            encode_ms,
+                        }),
+                        Err(e) => {
+                            warn!(
+                                "[experiment] {} as {} at q{}: {}",
+                                sample.display(),
+                                format,
+                                quality,
+                                e
+                            );
+                            errors.push(format!(
+                                "{} as {} at quality {}: {}",
+                                sample.display(),
+                                format,
+                                quality,
+                                e
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let csv_path = out_dir.join(format!("experiment-{}.csv", timestamp));
+    std::fs::write(&csv_path, to_csv(&rows)).map_err(|e| e.to_string())?;
+    info!(
+        "[experiment] {} results for {} samples written to {}",
+        rows.len(),
+        samples.len(),
+        csv_path.display()
+    );
+    Ok(ExperimentReport {
+        csv_path: csv_path.display().to_string(),
+        rows,
+        errors,
+    })
+}
+
+fn set_effort(flags: &mut CompressionFlags, format: ImageFormat, effort: u8) {
+    match format {
+        ImageFormat::Png => flags.png_oxipng_level = effort,
+        ImageFormat::WebP => flags.webp_effort = effort,
+        ImageFormat::Avif => flags.avif_effort = effort,
+        ImageFormat::Heif => flags.heif_effort = effort,
+        ImageFormat::Gif => flags.gif_effort = effort,
+        ImageFormat::Jxl => flags.jxl_effort = effort,
+        _ => {}
+    }
+}
+
+fn to_csv(rows: &[ExperimentRow]) -> String {
+    let mut csv =
+        String::from("file,format,quality,effort,original_size,size,ratio,ssim,encode_ms\n");
+    for row in rows {
+        csv.push_str(&format!(
+            "\"{}\",{},{},{},{},{},{:.4},{:.5},{}\n",
+            row.file.replace('"', "\"\""),
+            row.format,
+            row.quality,
+            row.effort.map(|e| e.to_string()).unwrap_or_default(),
+            row.original_size,
+            row.size,
+            row.size as f64 / row.original_size.max(1) as f64,
+            row.ssim,
+            row.encode_ms
+        ));
+    }
+    csv
+}
+
+/// Rec. 601 luma of RGBA pixels, ignoring alpha.
+fn luma(rgba: &[u8]) -> Vec<f64> {
+    rgba.chunks_exact(4)
+        .map(|p| 0.299 * p[0] as f64 + 0.587 * p[1] as f64 + 0.114 * p[2] as f64)
+        .collect()
+}
+
+/// Mean SSIM over `SSIM_BLOCK`-sized blocks of two same-sized luma planes.
+fn ssim(a: &[f64], b: &[f64], width: usize) -> f64 {
+    let height = a.len() / width.max(1);
+    let mut total = 0.0;
+    let mut blocks = 0;
+    for by in (0..height).step_by(SSIM_BLOCK) {
+        for bx in (0..width).step_by(SSIM_BLOCK) {
+            let (mut sum_a, mut sum_b, mut sum_aa, mut sum_bb, mut sum_ab) =
+                (0.0, 0.0, 0.0, 0.0, 0.0);
+            let mut n = 0.0;
+            for y in by..(by + SSIM_BLOCK).min(height) {
+                for x in bx..(bx + SSIM_BLOCK).min(width) {
+                    let (pa, pb) = (a[y * width + x], b[y * width + x]);
+                    sum_a += pa;
+                    sum_b += pb;
+                    sum_aa += pa * pa;
+                    sum_bb += pb * pb;
+                    sum_ab += pa * pb;
+                    n += 1.0;
+                }
+            }
+            let (mean_a, mean_b) = (sum_a / n, sum_b / n);
+            let var_a = sum_aa / n - mean_a * mean_a;
+            let var_b = sum_bb / n - mean_b * mean_b;
+            let covar = sum_ab / n - mean_a * mean_b;
+            total += ((2.0 * mean_a * mean_b + SSIM_C1) * (2.0 * covar + SSIM_C2))
+                / ((mean_a * mean_a + mean_b * mean_b + SSIM_C1) * (var_a + var_b + SSIM_C2));
+            blocks += 1;
+        }
+    }
+    if blocks == 0 {
+        1.0
+    } else {
+        total / blocks as f64
+    }
+}
@@ -0,0 +1,487 @@
+//! Pure-Rust encoder used when libvips can't be loaded. Slower and with fewer
+//! knobs than the libvips pipeline, but decodes the common web formats.
+
+use crate::compression::{
+    compressed_output_path, CompressionError, CompressionFlags, ImageFormat, Result,
+};
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::{DynamicImage, ImageDecoder};
+use log::{info, warn};
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+pub struct FallbackOutput {
+    pub path: PathBuf,
+    pub size: u64,
+    pub format: ImageFormat,
+}
+
+/// Formats this module can decode. HEIC goes through the OS codecs, which
+/// Linux doesn't have, and AVIF needs the `avif-decode` feature.
+pub fn can_decode(format: ImageFormat) -> bool {
+    match format {
+        ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP | ImageFormat::Gif => true,
+        ImageFormat::Avif => cfg!(feature = "avif-decode"),
+        ImageFormat::Heif => cfg!(any(target_os = "windows", target_os = "macos")),
+        _ => false,
+    }
+}
+
+/// The format written for `source` when no supported target is configured.
+/// WebP can't be re-encoded here, so it becomes JPEG, or PNG when the image
+/// has transparency.
+fn output_format(source: ImageFormat, target: Option<ImageFormat>, has_alpha: bool) -> ImageFormat {
+    match target.unwrap_or(source) {
+        ImageFormat::Png => ImageFormat::Png,
+        ImageFormat::Jpeg => ImageFormat::Jpeg,
+        ImageFormat::Avif => ImageFormat::Avif,
+        ImageFormat::Gif if source == ImageFormat::Gif => ImageFormat::Gif,
+        _ if has_alpha => ImageFormat::Png,
+        _ => ImageFormat::Jpeg,
+    }
+}
+
+pub fn compress(
+    input: &Path,
+    quality: u8,
+    target: Option<ImageFormat>,
+    flags: &CompressionFlags,
+) -> Result<FallbackOutput> {
+    let source = ImageFormat::from_path(input)
+        .filter(|f| can_decode(*f))
+        .ok_or_else(|| CompressionError::UnsupportedFormat(input.display().to_string()))?;
+    if source == ImageFormat::Gif && output_format(source, target, false) == ImageFormat::Gif {
+        let output = compressed_output_path(input, Some("gif"))
+            .ok_or_else(|| CompressionError::InvalidPath(input.display().to_string()))?;
+        let size = optimize_gif(input, &output, flags)?;
+        return Ok(FallbackOutput {
+            path: output,
+            size,
+            format: ImageFormat::Gif,
+        });
+    }
+
+    let img = if source == ImageFormat::Heif {
+        decode_heif(input)?
+    } else {
+        decode_upright(input)?
+    };
+    let format = output_format(source, target, img.color().has_alpha());
+
+    let output = compressed_output_path(input, Some(format.extension()))
+        .ok_or_else(|| CompressionError::InvalidPath(input.display().to_string()))?;
+    let mut writer = BufWriter::new(File::create(&output)?);
+    match format {
+        ImageFormat::Avif => {
+            let avif = encode_avif(&img, quality, flags)?;
+            std::io::Write::write_all(&mut writer, &avif)?;
+        }
+        ImageFormat::Jpeg => {
+            let jpeg = encode_jpeg(&img, quality, flags)?;
+            std::io::Write::write_all(&mut writer, &jpeg)?;
+        }
+        _ => {
+            let encoder = PngEncoder::new_with_quality(
+                &mut writer,
+                CompressionType::Best,
+                FilterType::Adaptive,
+            );
+            img.write_with_encoder(encoder)?;
+        }
+    }
+    drop(writer);
+
+    if format == ImageFormat::Png && (flags.png_palette || flags.png_quantize_after) {
+        let rgba = img.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        if let Err(e) = crate::compression::quantize_png_if_smaller(
+            rgba.as_raw(),
+            width,
+            height,
+            quality,
+            flags,
+            &output,
+        ) {
+            warn!(
+                "[fallback] imagequant pass failed for {}: {}",
+                input.display(),
+                e
+            );
+        }
+    }
+
+    if format == ImageFormat::Png {
+        if let Err(e) = optimize_png(&output, flags) {
+            warn!("[fallback] oxipng failed for {}: {}", input.display(), e);
+        }
+    }
+
+    let size = std::fs::metadata(&output)?.len();
+    info!(
+        "[fallback] {} ({}) → {} ({}) {} bytes",
+        input.display(),
+        source,
+        output.display(),
+        format,
+        size
+    );
+    Ok(FallbackOutput {
+        path: output,
+        size,
+        format,
+    })
+}
+
+/// Decode with the image crate, turning the pixels upright per the EXIF
+/// orientation since outputs don't carry the tag.
+fn decode_upright(input: &Path) -> Result<DynamicImage> {
+    let mut decoder = image::ImageReader::open(input)?
+        .with_guessed_format()?
+        .into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut img = DynamicImage::from_decoder(decoder)?;
+    img.apply_orientation(orientation);
+    Ok(img)
+}
+
+/// Decode HEIC with the OS codec. Photos come back as RGBA, so the alpha
+/// channel is dropped when every pixel is opaque to keep them as JPEG.
+fn decode_heif(input: &Path) -> Result<DynamicImage> {
+    let decoded = crate::platform::decode_with_os(input).ok_or_else(|| {
+        CompressionError::UnsupportedFormat(format!(
+            "{}: no HEIF decoder available on this system",
+            input.display()
+        ))
+    })?;
+    let opaque = decoded.rgba.chunks_exact(4).all(|p| p[3] == 255);
+    let rgba = image::RgbaImage::from_raw(decoded.width, decoded.height, decoded.rgba)
+        .ok_or_else(|| CompressionError::External("OS decoder returned a short buffer".into()))?;
+    let img = DynamicImage::ImageRgba8(rgba);
+    Ok(if opaque {
+        DynamicImage::ImageRgb8(img.to_rgb8())
+    } else {
+        img
+    })
+}
+
+/// Losslessly shrink the PNG at `path` in place with oxipng.
+fn optimize_png(path: &Path, flags: &CompressionFlags) -> Result<()> {
+    let mut options = oxipng::Options::from_preset(flags.png_oxipng_level.min(6));
+    options.strip = oxipng::StripChunks::Safe;
+    if flags.png_zopfli {
+        options.deflate = oxipng::Deflaters::Zopfli {
+            iterations: std::num::NonZeroU8::new(15).unwrap(),
+        };
+    }
+    let data = std::fs::read(path)?;
+    let optimized = oxipng::optimize_from_memory(&data, &options)
+        .map_err(|e| CompressionError::External(format!("oxipng: {}", e)))?;
+    if optimized.len() < data.len() {
+        std::fs::write(path, &optimized)?;
+    }
+    Ok(())
+}
+
+/// Encode with MozJPEG, which comes much closer to libvips' sizes than the
+/// `image` crate's baseline encoder. libjpeg reports errors by panicking, so
+/// this is only used where the panic can be caught.
+#[cfg(panic = "unwind")]
+fn encode_jpeg(img: &DynamicImage, quality: u8, flags: &CompressionFlags) -> Result<Vec<u8>> {
+    use std::panic::{self, AssertUnwindSafe};
+
+    /// Largest side libjpeg accepts.
+    const JPEG_MAX_DIMENSION: u32 = 65_500;
+
+    let rgb = img.to_rgb8();
+    let (width, height) = rgb.dimensions();
+    if width == 0 || height == 0 || width.max(height) > JPEG_MAX_DIMENSION {
+        return Err(CompressionError::External(format!(
+            "mozjpeg: can't encode a {width}x{height} image"
+        )));
+    }
+
+    panic::catch_unwind(AssertUnwindSafe(|| -> std::io::Result<Vec<u8>> {
+        let mut compress = mozjpeg::Compress::new(mozjpeg::ColorSpace::JCS_RGB);
+        compress.set_size(width as usize, height as usize);
+        compress.set_quality(quality.clamp(1, 100) as f32);
+        compress.set_optimize_coding(flags.jpeg_optimize_coding);
+        if flags.jpeg_interlace {
+            compress.set_progressive_mode();
+        }
+        if flags.jpeg_subsample_mode.as_deref() == Some("off") {
+            compress.set_chroma_sampling_pixel_sizes((1, 1), (1, 1));
+        }
+        let mut started = compress.start_compress(Vec::new())?;
+        started.write_scanlines(rgb.as_raw())?;
+        started.finish()
+    }))
+    .map_err(|_| CompressionError::External("mozjpeg failed".to_string()))?
+    .map_err(CompressionError::from)
+}
+
+/// Release builds abort on panic, which would turn a libjpeg error into a
+/// crash of the whole app, so they use the `image` crate's encoder.
+#[cfg(not(panic = "unwind"))]
+fn encode_jpeg(img: &DynamicImage, quality: u8, _flags: &CompressionFlags) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut data, quality.clamp(1, 100))
+        .encode_image(&img.to_rgb8())?;
+    Ok(data)
+}
+
+/// Encode with ravif. `effort` uses the libvips scale (0 fastest – 9 slowest),
+/// which maps onto ravif's speed (10 fastest – 1 slowest).
+fn encode_avif(img: &DynamicImage, quality: u8, flags: &CompressionFlags) -> Result<Vec<u8>> {
+    let effort = if flags.avif_effort > 0 {
+        flags.avif_effort
+    } else {
+        4
+    };
+    let rgba = img.to_rgba8();
+    let (width, height) = rgba.dimensions();
+    let pixels: Vec<ravif::RGBA8> = rgba
+        .as_raw()
+        .chunks_exact(4)
+        .map(|p| ravif::RGBA8::new(p[0], p[1], p[2], p[3]))
+        .collect();
+
+    let encoded = ravif::Encoder::new()
+        .with_quality(quality.clamp(1, 100) as f32)
+        .with_speed(10u8.saturating_sub(effort).clamp(1, 10))
+        .with_num_threads(flags.deterministic.then_some(1))
+        .encode_rgba(ravif::Img::new(
+            &pixels[..],
+            width as usize,
+            height as usize,
+        ))
+        .map_err(|e| CompressionError::External(format!("ravif: {}", e)))?;
+    Ok(encoded.avif_file)
+}
+
+/// Re-encode an (animated) GIF: every frame is re-quantized to at most
+/// `gif_colors` colors with imagequant, and for opaque animations each frame only stores the
+/// rectangle that changed, with unchanged pixels made transparent.
+pub fn optimize_gif(input: &Path, output: &Path, flags: &CompressionFlags) -> Result<u64> {
+    let gif_err = |e: &dyn std::fmt::Display| CompressionError::External(format!("gif: {}", e));
+
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::RGBA);
+    let mut decoder = options
+        .read_info(std::io::BufReader::new(File::open(input)?))
+        .map_err(|e| gif_err(&e))?;
+    let (width, height) = (decoder.width(), decoder.height());
+    let repeat = decoder.repeat();
+
+    // Compose every frame onto a full canvas so frames can be compared directly
+    let (w, h) = (width as usize, height as usize);
+    let mut canvas = vec![0u8; w * h * 4];
+    let mut frames: Vec<(Vec<u8>, u16)> = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().map_err(|e| gif_err(&e))? {
+        let saved = (frame.dispose == gif::DisposalMethod::Previous).then(|| canvas.clone());
+        let rect = Rect::of(frame, w, h);
+        for y in 0..rect.height {
+            for x in 0..rect.width {
+                let src = (y * frame.width as usize + x) * 4;
+                let dst = ((rect.top + y) * w + rect.left + x) * 4;
+                if frame.buffer[src + 3] != 0 {
+                    canvas[dst..dst + 4].copy_from_slice(&frame.buffer[src..src + 4]);
+                }
+            }
+        }
+        frames.push((canvas.clone(), frame.delay));
+        match frame.dispose {
+            gif::DisposalMethod::Background => rect.fill(&mut canvas, w, [0; 4]),
+            gif::DisposalMethod::Previous => canvas = saved.unwrap_or(canvas),
+            _ => {}
+        }
+    }
+
+    // Transparent pixels can't be "cleared" by a later diff frame, so animations
+    // with transparency keep full frames that replace each other
+    let opaque = frames
+        .iter()
+        .all(|(c, _)| c.chunks_exact(4).all(|p| p[3] == 255));
+    let colors = if flags.gif_colors > 0 {
+        flags.gif_colors.clamp(4, 256) as usize
+    } else {
+        256
+    };
+    let effort = if flags.gif_effort > 0 {
+        flags.gif_effort
+    } else {
+        7
+    };
+    let speed = (11 - effort.min(10) as i32).clamp(1, 10);
+
+    let mut writer = BufWriter::new(File::create(output)?);
+    {
+        let mut encoder =
+            gif::Encoder::new(&mut writer, width, height, &[]).map_err(|e| gif_err(&e))?;
+        encoder.set_repeat(repeat).map_err(|e| gif_err(&e))?;
+
+        let full = Rect {
+            left: 0,
+            top: 0,
+            width: w,
+            height: h,
+        };
+        let mut previous: Option<&[u8]> = None;
+        let mut pending: Option<gif::Frame<'static>> = None;
+        for (composed, delay) in &frames {
+            let rect = match previous {
+                Some(prev) if opaque => match Rect::changed(prev, composed, w, h) {
+                    Some(rect) => rect,
+                    None => {
+                        // Identical frame: show the previous one for longer instead
+                        if let Some(frame) = pending.as_mut() {
+                            frame.delay = frame.delay.saturating_add(*delay);
+                        }
+                        continue;
+                    }
+                },
+                _ => full,
+            };
+            if let Some(frame) = pending.take() {
+                encoder.write_frame(&frame).map_err(|e| gif_err(&e))?;
+            }
+
+            let mut rgba = rect.crop(composed, w);
+            if let (Some(prev), true) = (previous, opaque) {
+                let before = rect.crop(prev, w);
+                for (px, old) in rgba.chunks_exact_mut(4).zip(before.chunks_exact(4)) {
+                    if px == old {
+                        px[3] = 0;
+                    }
+                }
+            }
+
+            let mut frame = quantize_frame(&rgba, &rect, colors, speed, flags.gif_dither)?;
+            frame.delay = *delay;
+            frame.dispose = if opaque {
+                gif::DisposalMethod::Keep
+            } else {
+                gif::DisposalMethod::Background
+            };
+            pending = Some(frame);
+            previous = Some(composed);
+        }
+        if let Some(frame) = pending {
+            encoder.write_frame(&frame).map_err(|e| gif_err(&e))?;
+        }
+    }
+    drop(writer);
+
+    let size = std::fs::metadata(output)?.len();
+    info!(
+        "[fallback] GIF {} → {} bytes ({} frames, {} colors)",
+        input.display(),
+        size,
+        frames.len(),
+        colors
+    );
+    Ok(size)
+}
+
+#[derive(Clone, Copy)]
+struct Rect {
+    left: usize,
+    top: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Rect {
+    /// Frame bounds clipped to the canvas.
+    fn of(frame: &gif::Frame<'_>, w: usize, h: usize) -> Self {
+        let left = (frame.left as usize).min(w);
+        let top = (frame.top as usize).min(h);
+        Rect {
+            left,
+            top,
+            width: (frame.width as usize).min(w - left),
+            height: (frame.height as usize).min(h - top),
+        }
+    }
+
+    /// Bounding box of the pixels that differ between two canvases.
+    fn changed(a: &[u8], b: &[u8], w: usize, h: usize) -> Option<Self> {
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (w, h, 0, 0);
+        for y in 0..h {
+            for x in 0..w {
+                let i = (y * w + x) * 4;
+                if a[i..i + 4] != b[i..i + 4] {
+                    min_x = min_x.min(x);
+                    min_y = min_y.min(y);
+                    max_x = max_x.max(x);
+                    max_y = max_y.max(y);
+                }
+            }
+        }
+        (min_x <= max_x).then_some(Rect {
+            left: min_x,
+            top: min_y,
+            width: max_x - min_x + 1,
+            height: max_y - min_y + 1,
+        })
+    }
+
+    fn crop(&self, canvas: &[u8], w: usize) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.width * self.height * 4);
+        for y in self.top..self.top + self.height {
+            let start = (y * w + self.left) * 4;
+            out.extend_from_slice(&canvas[start..start + self.width * 4]);
+        }
+        out
+    }
+
+    fn fill(&self, canvas: &mut [u8], w: usize, color: [u8; 4]) {
+        for y in self.top..self.top + self.height {
+            for x in self.left..self.left + self.width {
+                let i = (y * w + x) * 4;
+                canvas[i..i + 4].copy_from_slice(&color);
+            }
+        }
+    }
+}
+
+/// Build an indexed frame with its own palette of at most `colors` entries,
+/// quantized and dithered with libimagequant, the quantizer gifski uses.
+/// Fully transparent pixels share one palette entry.
+fn quantize_frame(
+    rgba: &[u8],
+    rect: &Rect,
+    colors: usize,
+    speed: i32,
+    dither: f32,
+) -> Result<gif::Frame<'static>> {
+    let liq_err = |e: imagequant::Error| CompressionError::External(format!("imagequant: {}", e));
+
+    let pixels: Vec<imagequant::RGBA> = rgba
+        .chunks_exact(4)
+        .map(|p| imagequant::RGBA::new(p[0], p[1], p[2], if p[3] == 0 { 0 } else { 255 }))
+        .collect();
+    let mut liq = imagequant::new();
+    liq.set_speed(speed).map_err(liq_err)?;
+    liq.set_max_colors(colors as u32).map_err(liq_err)?;
+    let mut image = liq
+        .new_image_borrowed(&pixels, rect.width, rect.height, 0.0)
+        .map_err(liq_err)?;
+    let mut quantized = liq.quantize(&mut image).map_err(liq_err)?;
+    quantized.set_dithering_level(dither).map_err(liq_err)?;
+    let (colors, indices) = quantized.remapped(&mut image).map_err(liq_err)?;
+
+    let transparent = colors.iter().position(|c| c.a == 0).map(|i| i as u8);
+    let palette: Vec<u8> = colors.iter().flat_map(|c| [c.r, c.g, c.b]).collect();
+    Ok(gif::Frame {
+        left: rect.left as u16,
+        top: rect.top as u16,
+        width: rect.width as u16,
+        height: rect.height as u16,
+        palette: Some(palette),
+        transparent,
+        buffer: Cow::Owned(indices),
+        ..Default::default()
+    })
+}
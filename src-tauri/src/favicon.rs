@@ -0,0 +1,64 @@
+use crate::compression::{CompressionError, Result, Vips};
+use image::codecs::ico::{IcoEncoder, IcoFrame};
+use image::{ExtendedColorType, RgbaImage};
+use log::info;
+use std::path::Path;
+
+/// Sizes browsers and Windows pick from, smallest first.
+pub const FAVICON_SIZES: &[u32] = &[16, 32, 48, 256];
+
+/// Render `input` at each favicon size and write them to `output` as one
+/// multi-resolution `.ico`. SVGs are rasterized at every size; raster inputs
+/// are not upscaled, so sizes above their longer side are left out.
+pub fn generate(vips: &Vips, input: &Path, output: &Path) -> Result<u64> {
+    let sizes: Vec<u32> = if crate::svg::is_svg(input) {
+        FAVICON_SIZES.to_vec()
+    } else {
+        let img = vips.load_image(input)?;
+        let (width, height) = vips.dimensions(&img);
+        let longest = width.max(height);
+        let sizes: Vec<u32> = FAVICON_SIZES
+            .iter()
+            .copied()
+            .filter(|&s| s <= longest)
+            .collect();
+        if sizes.is_empty() {
+            vec![FAVICON_SIZES[0]]
+        } else {
+            sizes
+        }
+    };
+
+    let mut frames = Vec::with_capacity(sizes.len());
+    for &size in &sizes {
+        let thumb = vips.thumbnail(input, size, size)?;
+        let (width, height, rgba) = vips.extract_rgba(&thumb)?;
+        let icon = RgbaImage::from_raw(width, height, rgba)
+            .ok_or_else(|| CompressionError::Vips("thumbnail buffer size mismatch".to_string()))?;
+        // Non-square sources are centered on a transparent square
+        let mut square = RgbaImage::new(size, size);
+        image::imageops::overlay(
+            &mut square,
+            &icon,
+            ((size - width) / 2) as i64,
+            ((size - height) / 2) as i64,
+        );
+        frames.push(IcoFrame::as_png(
+            square.as_raw(),
+            size,
+            size,
+            ExtendedColorType::Rgba8,
+        )?);
+    }
+
+    let file = std::fs::File::create(output)?;
+    IcoEncoder::new(std::io::BufWriter::new(file)).encode_images(&frames)?;
+    let written = std::fs::metadata(output)?.len();
+    info!(
+        "[favicon] Wrote {} ({:?} px, {} bytes)",
+        output.display(),
+        sizes,
+        written
+    );
+    Ok(written)
+}
@@ -0,0 +1,106 @@
+use crate::config::SensitiveGuardConfig;
+use log::{info, warn};
+use serde::Serialize;
+use std::path::Path;
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
+
+/// Phrases that show up in OCR text of passports, ID cards and licences.
+const ID_DOCUMENT_MARKERS: &[&str] = &[
+    "passport",
+    "date of birth",
+    "driver license",
+    "driver's license",
+    "driving licence",
+    "identity card",
+    "national id",
+    "social security",
+    "<<<",
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HeldFile {
+    pub path: String,
+    pub reason: String,
+    pub timestamp: u64,
+}
+
+/// Files waiting for the user to approve compression.
+#[derive(Default)]
+pub struct HeldFiles {
+    pub files: Mutex<Vec<HeldFile>>,
+}
+
+/// Returns a reason when the image looks like it contains an ID document or faces.
+pub fn classify(
+    config: &SensitiveGuardConfig,
+    path: &Path,
+    ocr_text: Option<&str>,
+) -> Option<String> {
+    if !config.enabled {
+        return None;
+    }
+
+    if let Some(text) = ocr_text {
+        let lower = text.to_lowercase();
+        if let Some(marker) = ID_DOCUMENT_MARKERS.iter().find(|m| lower.contains(*m)) {
+            return Some(format!("Looks like an ID document (found \"{}\")", marker));
+        }
+    }
+
+    // External classifier: any non-empty stdout is the reason the file was flagged
+    let classifier = config.classifier_command.as_deref()?;
+    match Command::new(classifier).arg(path).output() {
+        Ok(output) if output.status.success() => {
+            let reason = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            (!reason.is_empty()).then_some(reason)
+        }
+        Ok(output) => {
+            warn!(
+                "[guard] Classifier {} exited with {} for {}",
+                classifier,
+                output.status,
+                path.display()
+            );
+            None
+        }
+        Err(e) => {
+            warn!("[guard] Failed to run classifier {}: {}", classifier, e);
+            None
+        }
+    }
+}
+
+/// Park a file for manual approval and tell the frontend about it.
+pub fn hold(app: &tauri::AppHandle, path: &Path, reason: String) {
+    let held = HeldFile {
+        path: path.display().to_string(),
+        reason,
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+    };
+    info!("[guard] Holding {}: {}", held.path, held.reason);
+
+    let state = app.state::<HeldFiles>();
+    if let Ok(mut files) = state.files.lock() {
+        files.retain(|f| f.path != held.path);
+        files.push(held.clone());
+    }
+    let _ = app.emit("compression-held", &held);
+    crate::activity::push(app, crate::activity::ActivityKind::Held, path, 0);
+}
+
+/// Remove a file from the held list, returning whether it was there.
+pub fn release(app: &tauri::AppHandle, path: &str) -> bool {
+    let state = app.state::<HeldFiles>();
+    let Ok(mut files) = state.files.lock() else {
+        return false;
+    };
+    let before = files.len();
+    files.retain(|f| f.path != path);
+    files.len() != before
+}
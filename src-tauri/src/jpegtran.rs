@@ -0,0 +1,156 @@
+//! Lossless JPEG re-packing, like `jpegtran -optimize`: the DCT coefficients
+//! are copied as they are and only the entropy coding is redone, so the
+//! pixels come out bit-identical.
+//!
+//! libjpeg can only report errors by not returning from `error_exit`, and
+//! release builds abort on panic, so the work happens in a child process
+//! started from the app's own binary: a corrupt file can only take that down.
+
+use crate::compression::{CompressionError, Result};
+use log::info;
+use mozjpeg_sys::*;
+use std::os::raw::{c_ulong, c_void};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+extern "C" {
+    fn free(ptr: *mut c_void);
+}
+
+/// First argument of a re-packing child, followed by the input path, the
+/// output path and `progressive` or `baseline`.
+const HELPER_ARG: &str = "--repack-jpeg";
+
+/// libjpeg's default handler exits with its own message; panic instead so
+/// the child's stderr says what went wrong.
+unsafe extern "C-unwind" fn error_exit(cinfo: &mut jpeg_common_struct) -> ! {
+    let code = (*cinfo.err).msg_code;
+    panic!("libjpeg error {}", code);
+}
+
+/// Rewrite the JPEG at `input` to `output` with optimized Huffman tables,
+/// as progressive scans when `progressive`. Markers are not carried over;
+/// metadata is brought back afterwards like for any other encode. Returns
+/// the output's size.
+pub fn optimize(input: &Path, output: &Path, progressive: bool) -> Result<u64> {
+    let result = Command::new(std::env::current_exe()?)
+        .arg(HELPER_ARG)
+        .arg(input)
+        .arg(output)
+        .arg(if progressive {
+            "progressive"
+        } else {
+            "baseline"
+        })
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()?;
+    if !result.status.success() {
+        let _ = std::fs::remove_file(output);
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        return Err(CompressionError::External(
+            stderr
+                .lines()
+                .find(|line| line.contains("libjpeg"))
+                .unwrap_or("libjpeg failed")
+                .trim()
+                .to_string(),
+        ));
+    }
+    let size = std::fs::metadata(output)?.len();
+    info!(
+        "[jpegtran] {} → {} bytes, losslessly{}",
+        input.display(),
+        size,
+        if progressive { ", progressive" } else { "" }
+    );
+    Ok(size)
+}
+
+/// Do the re-packing when the process was started by `optimize`, and return
+/// its exit code; `None` for a normal launch.
+pub fn run_helper() -> Option<i32> {
+    let args: Vec<_> = std::env::args_os().skip(1).collect();
+    let [flag, input, output, mode] = args.as_slice() else {
+        return None;
+    };
+    if flag != HELPER_ARG {
+        return None;
+    }
+    let result = std::fs::read(input)
+        .map_err(CompressionError::from)
+        .and_then(|data| repack(&data, mode == "progressive"))
+        .and_then(|repacked| std::fs::write(output, repacked).map_err(CompressionError::from));
+    match result {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("{}", e);
+            Some(1)
+        }
+    }
+}
+
+/// Whether `input` needs no EXIF rotation. Re-packing keeps the stored
+/// orientation, which dropping the EXIF tag would then lose.
+pub fn is_upright(input: &Path) -> bool {
+    use image::{metadata::Orientation, ImageDecoder};
+    image::ImageReader::open(input)
+        .and_then(|reader| reader.with_guessed_format())
+        .ok()
+        .and_then(|reader| reader.into_decoder().ok())
+        .and_then(|mut decoder| decoder.orientation().ok())
+        .is_some_and(|orientation| orientation == Orientation::NoTransforms)
+}
+
+fn repack(data: &[u8], progressive: bool) -> Result<Vec<u8>> {
+    unsafe {
+        let mut src_err: jpeg_error_mgr = std::mem::zeroed();
+        let mut dst_err: jpeg_error_mgr = std::mem::zeroed();
+        let mut src: jpeg_decompress_struct = std::mem::zeroed();
+        let mut dst: jpeg_compress_struct = std::mem::zeroed();
+        src.common.err = jpeg_std_error(&mut src_err);
+        dst.common.err = jpeg_std_error(&mut dst_err);
+        src_err.error_exit = Some(error_exit);
+        dst_err.error_exit = Some(error_exit);
+        jpeg_create_decompress(&mut src);
+        jpeg_create_compress(&mut dst);
+
+        let mut buffer: *mut u8 = std::ptr::null_mut();
+        let mut size: c_ulong = 0;
+        jpeg_mem_src(&mut src, data.as_ptr(), data.len() as c_ulong);
+        jpeg_read_header(&mut src, 1);
+        let coefficients = jpeg_read_coefficients(&mut src);
+
+        jpeg_mem_dest(&mut dst, &mut buffer, &mut size);
+        jpeg_copy_critical_parameters(&src, &mut dst);
+        dst.optimize_coding = 1;
+        if progressive {
+            jpeg_simple_progression(&mut dst);
+        } else {
+            // mozjpeg's defaults already ask for progressive scans
+            jpeg_c_set_bool_param(&mut dst, J_BOOLEAN_PARAM::JBOOLEAN_OPTIMIZE_SCANS, 0);
+            dst.scan_info = std::ptr::null();
+            dst.num_scans = 0;
+        }
+        jpeg_write_coefficients(&mut dst, coefficients);
+        jpeg_finish_compress(&mut dst);
+        jpeg_finish_decompress(&mut src);
+        jpeg_destroy_compress(&mut dst);
+        jpeg_destroy_decompress(&mut src);
+
+        let repacked = if buffer.is_null() {
+            Vec::new()
+        } else {
+            let bytes = std::slice::from_raw_parts(buffer, size as usize).to_vec();
+            free(buffer.cast());
+            bytes
+        };
+        if repacked.is_empty() {
+            return Err(CompressionError::External(
+                "libjpeg wrote no data".to_string(),
+            ));
+        }
+        Ok(repacked)
+    }
+}
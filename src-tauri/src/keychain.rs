@@ -0,0 +1,56 @@
+use keyring::Entry;
+use log::warn;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Every secret is stored under this service name.
+const SERVICE: &str = "hat";
+
+/// Secrets already read, so each one costs a single keychain lookup.
+static CACHE: Mutex<BTreeMap<String, Option<String>>> = Mutex::new(BTreeMap::new());
+
+/// Store `secret` for `account`, replacing any earlier one; an empty secret
+/// removes it.
+pub fn set(account: &str, secret: &str) -> Result<(), String> {
+    let entry = Entry::new(SERVICE, account).map_err(|e| e.to_string())?;
+    if secret.is_empty() {
+        match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(e) => return Err(e.to_string()),
+        }
+    } else {
+        entry.set_password(secret).map_err(|e| e.to_string())?;
+    }
+    if let Ok(mut cache) = CACHE.lock() {
+        cache.insert(
+            account.to_string(),
+            (!secret.is_empty()).then(|| secret.to_string()),
+        );
+    }
+    Ok(())
+}
+
+/// The secret stored for `account`, if any.
+pub fn get(account: &str) -> Option<String> {
+    if let Some(cached) = CACHE.lock().ok().and_then(|c| c.get(account).cloned()) {
+        return cached;
+    }
+    let secret = match Entry::new(SERVICE, account).and_then(|entry| entry.get_password()) {
+        Ok(secret) => Some(secret),
+        Err(keyring::Error::NoEntry) => None,
+        Err(e) => {
+            // Not cached, so a locked keychain is retried next time
+            warn!("Failed to read {} from the keychain: {}", account, e);
+            return None;
+        }
+    };
+    if let Ok(mut cache) = CACHE.lock() {
+        cache.insert(account.to_string(), secret.clone());
+    }
+    secret
+}
+
+/// Account for the encryption key of the watched folder `folder`.
+pub fn folder_key_account(folder: &str) -> String {
+    format!("folder-key:{}", folder)
+}
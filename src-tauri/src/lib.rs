@@ -1,11 +1,55 @@
+mod actions;
+mod activity;
+mod archive;
+mod auth;
+mod browsers;
+mod cache;
+mod checksum;
 mod commands;
 mod compression;
 mod config;
+mod contact_sheet;
+mod context;
+mod crypto;
+mod duplicates;
+mod energy;
+mod engine;
+mod events;
+mod experiment;
+mod fallback;
+mod favicon;
+mod guard;
+mod jpegtran;
+mod keychain;
+mod locale;
 mod log;
+mod metadata;
+mod migrate;
+mod notifier;
+mod ocr;
+mod orphans;
+mod output_dir;
+mod pairs;
+mod pdf;
 mod platform;
 mod processor;
+mod replace;
+mod scanner;
+mod scripting;
+mod session;
+mod simulate;
+mod skips;
+mod suggestions;
+mod svg;
+mod tasks;
+mod texture;
 mod tray;
+mod trend;
+mod turbo;
+mod video;
 mod watcher;
+mod watermark;
+mod wizard;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     Mutex,
@@ -17,6 +61,12 @@ pub const DEFAULT_QUALITY: u8 = 80;
 pub static HAS_NOTIFIED_ON_CLOSE: AtomicBool = AtomicBool::new(false);
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
+/// Run a helper the app started as a child process, returning its exit
+/// code; `None` when this is a normal launch.
+pub fn run_helper() -> Option<i32> {
+    jpegtran::run_helper()
+}
+
 pub fn run() {
     let mut builder = tauri::Builder::default();
 
@@ -44,25 +94,177 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             commands::set_quality,
             commands::get_quality,
+            commands::get_format_quality,
+            commands::set_format_quality,
             commands::get_compression_history,
             commands::clear_compression_history,
+            commands::get_history_retention,
+            commands::set_history_retention,
+            commands::get_history_totals,
+            commands::get_history_rollup,
+            commands::get_folder_trend,
+            commands::get_history_as_of,
+            commands::get_restore_plan,
+            commands::estimate_delete_originals,
+            commands::get_seen_formats,
+            commands::migrate_outputs,
+            commands::find_orphaned_outputs,
+            commands::delete_orphaned_outputs,
+            commands::adopt_orphaned_outputs,
+            commands::get_locale_config,
+            commands::set_locale_config,
+            commands::get_smallest_wins,
+            commands::set_smallest_wins,
+            commands::get_target_format,
+            commands::set_target_format,
+            commands::get_output_naming,
+            commands::set_output_naming,
+            commands::get_output_dir,
+            commands::set_output_dir,
+            commands::get_archive_dir,
+            commands::set_archive_dir,
+            commands::search_images,
+            commands::get_analysis_summary,
+            commands::verify_outputs,
             commands::convert_image,
+            commands::generate_contact_sheet,
+            commands::generate_favicon,
+            commands::images_to_pdf,
+            commands::create_pdf_from_images,
+            commands::extract_pdf_images,
+            commands::pdf_to_images,
             commands::check_file_exists,
+            commands::get_engine_status,
+            commands::reload_engine,
+            commands::run_self_check,
+            commands::get_held_files,
+            commands::approve_held_file,
+            commands::dismiss_held_file,
+            commands::generate_encryption_key,
+            commands::decrypt_file,
+            commands::find_duplicate_originals,
             commands::recompress,
+            commands::delete_tasks,
+            commands::retry_tasks,
+            commands::recompress_tasks,
+            commands::repeat_compression,
             commands::compress_files,
+            commands::compress_folder,
+            commands::cancel_scan,
+            commands::get_wizard_status,
+            commands::wizard_scan,
+            commands::wizard_estimate,
+            commands::wizard_configure,
+            commands::wizard_start,
+            commands::wizard_pause,
+            commands::wizard_reset,
             commands::get_watched_folders,
             commands::add_watched_folder,
             commands::remove_watched_folder,
+            commands::get_folder_pairs,
+            commands::add_folder_pair,
+            commands::remove_folder_pair,
             commands::search_directories,
+            commands::suggest_watch_folders,
+            commands::list_removable_volumes,
+            commands::set_volume_enabled,
             commands::get_show_background_notification,
             commands::set_show_background_notification,
             commands::get_show_system_notifications,
             commands::set_show_system_notifications,
+            commands::get_legacy_event_names,
+            commands::set_legacy_event_names,
+            commands::get_event_schema,
+            commands::get_notification_sinks,
+            commands::set_notification_sinks,
+            commands::test_notification_sink,
             commands::get_format_options,
             commands::set_format_options,
+            commands::get_folder_settings,
+            commands::set_folder_settings,
+            commands::get_folder_format_options,
+            commands::set_folder_format_options,
+            commands::get_texture_config,
+            commands::set_texture_config,
+            commands::get_video_config,
+            commands::set_video_config,
+            commands::get_ocr_config,
+            commands::set_ocr_config,
+            commands::get_pdf_config,
+            commands::set_pdf_config,
+            commands::get_svg_config,
+            commands::set_svg_config,
+            commands::get_sensitive_guard,
+            commands::set_sensitive_guard,
+            commands::get_duplicate_scan_locations,
+            commands::set_duplicate_scan_locations,
+            commands::get_producer_rules,
+            commands::set_producer_rules,
+            commands::get_checksum_mode,
+            commands::set_checksum_mode,
+            commands::get_high_depth,
+            commands::set_high_depth,
+            commands::get_duplicate_downloads,
+            commands::set_duplicate_downloads,
+            commands::get_collision_policy,
+            commands::set_collision_policy,
+            commands::delete_duplicate_download,
+            commands::get_temp_config,
+            commands::set_temp_config,
+            commands::clear_cache,
+            commands::get_recent_files,
+            commands::set_recent_files,
+            commands::run_turbo,
+            commands::stop_turbo,
+            commands::get_turbo_status,
+            commands::get_watch_debug,
+            commands::set_watch_debug,
+            commands::get_skip_log,
+            commands::set_skip_log,
+            commands::get_warm_start,
+            commands::set_warm_start,
+            commands::get_skipped,
+            commands::get_reuse_redownloads,
+            commands::set_reuse_redownloads,
+            commands::get_preserve_metadata,
+            commands::set_preserve_metadata,
+            commands::get_strip_location,
+            commands::set_strip_location,
+            commands::get_keep_icc_profile,
+            commands::set_keep_icc_profile,
+            commands::get_keep_cmyk,
+            commands::set_keep_cmyk,
+            commands::get_watermark,
+            commands::set_watermark,
+            commands::get_trim_borders,
+            commands::set_trim_borders,
+            commands::get_require_authentication,
+            commands::set_require_authentication,
+            commands::get_replace_in_place,
+            commands::set_replace_in_place,
+            commands::undo_replace,
+            commands::get_deterministic,
+            commands::set_deterministic,
+            commands::get_profiles,
+            commands::set_profiles,
+            commands::add_eco_profile,
+            commands::get_context_rules,
+            commands::set_context_rules,
+            commands::get_context_status,
             commands::reset_config,
+            commands::list_scripts,
+            commands::set_script_enabled,
+            commands::reload_scripts,
+            commands::open_scripts_dir,
+            commands::simulate_rules,
+            commands::resolve_settings,
+            commands::run_experiment,
+            commands::list_actions,
+            commands::invoke_action,
             commands::open_config_dir,
             commands::quit_app,
+            commands::get_pending_tasks,
+            commands::subscribe_tasks,
         ])
         .setup(|app| {
             let window = app.get_webview_window("main").unwrap();
@@ -72,9 +274,13 @@ pub fn run() {
             let window_clone = window.clone();
             let app_handle = app.handle().clone();
             window.on_window_event(move |event| {
+                if let tauri::WindowEvent::Focused(true) = event {
+                    crate::session::shown(&app_handle);
+                }
                 if let tauri::WindowEvent::CloseRequested { api, .. } = event {
                     let _ = window_clone.hide();
                     api.prevent_close();
+                    crate::session::hidden();
 
                     let config = app_handle.state::<Mutex<crate::config::ConfigManager>>();
                     let show_notif = if let Ok(c) = config.lock() {
@@ -104,6 +310,8 @@ pub fn run() {
                 .expect("config dir")
                 .join("config.json");
             let config_manager = crate::config::ConfigManager::load(config_path);
+            let retention = config_manager.config.history_retention.clone();
+            crate::cache::configure(app.handle(), &config_manager.config.temp);
             app.manage(Mutex::new(config_manager));
 
             let log_path = app
@@ -111,10 +319,37 @@ pub fn run() {
                 .app_config_dir()
                 .expect("config dir")
                 .join("compression_log.json");
-            let compression_log = crate::log::CompressionLog::load(log_path);
+            let mut compression_log = crate::log::CompressionLog::load(log_path);
+            compression_log.set_retention(retention);
             app.manage(Mutex::new(compression_log));
+            app.manage(crate::guard::HeldFiles::default());
+            app.manage(crate::activity::ActivityFeed::default());
+            app.manage(crate::tasks::TaskQueue::default());
+            app.manage(crate::scanner::Scans::default());
+            app.manage(crate::wizard::Wizard::default());
+
+            let scripts = crate::scripting::Scripts::new(
+                app.path()
+                    .app_config_dir()
+                    .expect("config dir")
+                    .join("scripts"),
+            );
+            if let Ok(config) = app.state::<Mutex<crate::config::ConfigManager>>().lock() {
+                scripts.reload(&config.config.enabled_scripts);
+            }
+            app.manage(scripts);
+            crate::activity::start(app.handle());
+            crate::context::start(app.handle());
+            crate::suggestions::start(app.handle());
+            crate::cache::start();
+            crate::notifier::start(app.handle());
+            crate::trend::start(app.handle());
 
+            crate::pairs::load_mapping(app.handle());
+            crate::output_dir::load_claims(app.handle());
             watcher::init_watcher(app.handle());
+            crate::tasks::resume_deferred(app.handle());
+            crate::wizard::load(app.handle());
 
             Ok(())
         })
@@ -0,0 +1,107 @@
+use crate::config::{LocaleConfig, SizeUnits};
+use chrono::{Local, TimeZone};
+
+/// Languages that write `1,5` rather than `1.5`.
+const DECIMAL_COMMA: &[&str] = &[
+    "bg", "cs", "da", "de", "el", "es", "et", "fi", "fr", "hr", "hu", "id", "it", "lt", "lv", "nb",
+    "nl", "nn", "no", "pl", "pt", "ro", "ru", "sk", "sl", "sv", "tr", "uk", "vi",
+];
+
+/// Languages that put a space between a number and `%`.
+const SPACED_PERCENT: &[&str] = &[
+    "cs", "da", "de", "fi", "fr", "nb", "nn", "no", "ru", "sk", "sv", "uk",
+];
+
+static LOCALE: std::sync::RwLock<Option<LocaleConfig>> = std::sync::RwLock::new(None);
+
+/// Preferences used by every formatter below; set from the config.
+pub fn set_config(config: LocaleConfig) {
+    if let Ok(mut current) = LOCALE.write() {
+        *current = Some(config);
+    }
+}
+
+fn config() -> LocaleConfig {
+    LOCALE
+        .read()
+        .ok()
+        .and_then(|c| c.clone())
+        .unwrap_or_default()
+}
+
+/// Language and region of the configured locale, falling back to the
+/// system's, e.g. `("de", "DE")` for `de_DE.UTF-8`.
+fn language_region() -> (String, String) {
+    let tag = config()
+        .language
+        .or_else(crate::platform::system_language)
+        .unwrap_or_else(|| "en-US".to_string());
+    let tag = tag.split(['.', '@']).next().unwrap_or_default();
+    let mut parts = tag.split(['-', '_']);
+    let language = parts.next().unwrap_or_default().to_lowercase();
+    let region = parts
+        .find(|p| p.len() == 2)
+        .unwrap_or_default()
+        .to_uppercase();
+    (language, region)
+}
+
+/// `value` with `decimals` fraction digits and the locale's decimal mark.
+pub fn format_decimal(value: f64, decimals: usize) -> String {
+    let s = format!("{value:.decimals$}");
+    let (language, _) = language_region();
+    if DECIMAL_COMMA.contains(&language.as_str()) {
+        s.replace('.', ",")
+    } else {
+        s
+    }
+}
+
+/// Human-readable size in the configured units, e.g. `1.5 MB` or `1,5 MB`.
+pub fn format_bytes(bytes: u64) -> String {
+    let (base, units) = match config().units {
+        SizeUnits::Binary => (1024.0, ["KB", "MB", "GB"]),
+        SizeUnits::Decimal => (1000.0, ["kB", "MB", "GB"]),
+    };
+    let mut value = bytes as f64 / base;
+    let mut unit = units[0];
+    for next in &units[1..] {
+        if value < base {
+            break;
+        }
+        value /= base;
+        unit = next;
+    }
+    format!("{} {}", format_decimal(value, 1), unit)
+}
+
+/// A whole percentage, e.g. `42%` or `42 %`.
+pub fn format_percent(value: f64) -> String {
+    let (language, _) = language_region();
+    let separator = if SPACED_PERCENT.contains(&language.as_str()) {
+        "\u{a0}"
+    } else {
+        ""
+    };
+    format!("{}{}%", value.round(), separator)
+}
+
+/// Local date and time of a Unix timestamp in the locale's usual order.
+pub fn format_date(timestamp: u64) -> String {
+    let Some(time) = Local.timestamp_opt(timestamp as i64, 0).single() else {
+        return timestamp.to_string();
+    };
+    let (language, region) = language_region();
+    let pattern = match (language.as_str(), region.as_str()) {
+        ("en", "US" | "") => "%m/%d/%Y %-I:%M %p",
+        ("en", "CA") | ("sv" | "lt", _) => "%Y-%m-%d %H:%M",
+        ("ja" | "zh" | "ko", _) => "%Y/%m/%d %H:%M",
+        (
+            "bg" | "cs" | "da" | "de" | "fi" | "hr" | "hu" | "nb" | "nn" | "no" | "pl" | "ro"
+            | "ru" | "sk" | "sl" | "tr" | "uk",
+            _,
+        ) => "%d.%m.%Y %H:%M",
+        _ => "%d/%m/%Y %H:%M",
+    };
+    time.format(pattern).to_string()
+}
@@ -1,27 +1,479 @@
 use crate::compression::CompressionRecord;
-use log::error;
-use std::path::PathBuf;
+use crate::config::HistoryRetention;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// A file Hat touched before the planned point in time.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedFile {
+    pub record_id: u64,
+    pub path: String,
+    pub exists_now: bool,
+}
+
+/// What the filesystem looked like, according to the history, at `as_of`.
+#[derive(Debug, Clone, Serialize)]
+pub struct RestorePlan {
+    pub as_of: u64,
+    /// Outputs that existed at `as_of`.
+    pub outputs: Vec<PlannedFile>,
+    /// Originals that had already been deleted at `as_of`.
+    pub deleted_originals: Vec<PlannedFile>,
+    /// Outputs written after `as_of` that did not exist then.
+    pub created_after: Vec<PlannedFile>,
+}
+
+/// Originals in one folder that deleting originals would remove.
+#[derive(Debug, Clone, Serialize)]
+pub struct FolderImpact {
+    pub folder: String,
+    pub count: usize,
+    pub bytes: u64,
+}
+
+/// What turning on delete-originals would remove right now.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeleteOriginalsEstimate {
+    pub count: usize,
+    pub bytes: u64,
+    /// Largest first.
+    pub folders: Vec<FolderImpact>,
+}
+
+/// How often a folder received files of one input format.
+#[derive(Debug, Clone, Serialize)]
+pub struct SeenFormat {
+    pub format: String,
+    pub count: usize,
+    pub last_seen: u64,
+}
+
+/// Totals of the records of one input format from one day (UTC).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyRollup {
+    /// Days since the Unix epoch.
+    pub day: u64,
+    pub format: String,
+    pub count: u64,
+    pub initial_bytes: u64,
+    pub compressed_bytes: u64,
+}
+
+/// Size of a watched folder on one day (UTC), taken by the trend sampler.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderSample {
+    pub folder: String,
+    /// Days since the Unix epoch.
+    pub day: u64,
+    pub bytes: u64,
+    pub files: u64,
+    /// Saved by compressing the folder's files up to that day, as far as the
+    /// history still knows; without Hat the folder would be this much larger.
+    pub saved_bytes: u64,
+}
+
+/// Daily totals of every record appended, stored next to the log. Kept up
+/// to date on each append so statistics never have to scan the history, and
+/// survive pruning.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Rollup {
+    /// Highest id ever assigned, so ids aren't reused once records are gone.
+    #[serde(default)]
+    last_id: u64,
+    /// Highest record id already counted in `days`.
+    #[serde(default)]
+    counted_through: u64,
+    /// Records that only survive in `days`.
+    #[serde(default)]
+    pruned: u64,
+    #[serde(default)]
+    days: Vec<DailyRollup>,
+    /// Process CPU time measured while compressing, in milliseconds.
+    #[serde(default)]
+    cpu_ms: u64,
+    /// Oldest first; one sample per folder and day.
+    #[serde(default)]
+    folder_sizes: Vec<FolderSample>,
+}
+
+impl Rollup {
+    fn count(&mut self, record: &CompressionRecord) {
+        self.counted_through = self.counted_through.max(record.id);
+        if record.analysis_only {
+            return;
+        }
+        let day = record.timestamp / SECONDS_PER_DAY;
+        let index = match self
+            .days
+            .iter()
+            .rposition(|d| d.day == day && d.format == record.initial_format)
+        {
+            Some(index) => index,
+            None => {
+                self.days.push(DailyRollup {
+                    day,
+                    format: record.initial_format.clone(),
+                    count: 0,
+                    initial_bytes: 0,
+                    compressed_bytes: 0,
+                });
+                self.days
+                    .sort_by(|a, b| a.day.cmp(&b.day).then(a.format.cmp(&b.format)));
+                self.days
+                    .iter()
+                    .position(|d| d.day == day && d.format == record.initial_format)
+                    .unwrap_or_default()
+            }
+        };
+        let entry = &mut self.days[index];
+        entry.count += 1;
+        entry.initial_bytes += record.initial_size;
+        entry.compressed_bytes += record.compressed_size;
+    }
+}
+
+/// All-time statistics, pruned records included.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HistoryTotals {
+    pub count: u64,
+    pub initial_bytes: u64,
+    pub compressed_bytes: u64,
+    /// How many of `count` only survive in the rollup.
+    pub pruned: u64,
+    pub energy: crate::energy::EnergyEstimate,
+}
 
 pub struct CompressionLog {
     pub records: Vec<CompressionRecord>,
     path: PathBuf,
+    rollup: Rollup,
+    retention: HistoryRetention,
 }
 
 impl CompressionLog {
     pub fn load(path: PathBuf) -> Self {
-        let records = std::fs::read_to_string(&path)
+        let mut records: Vec<CompressionRecord> = std::fs::read_to_string(&path)
             .ok()
             .and_then(|s| serde_json::from_str(&s).ok())
             .unwrap_or_default();
-        Self { records, path }
+
+        // Records written before ids existed get sequential ids on first load
+        if records.iter().any(|r| r.id == 0) {
+            let mut next = records.iter().map(|r| r.id).max().unwrap_or(0) + 1;
+            for record in records.iter_mut().filter(|r| r.id == 0) {
+                record.id = next;
+                next += 1;
+            }
+        }
+
+        let mut rollup: Rollup = std::fs::read_to_string(rollup_path(&path))
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        // Rollups written before live records were counted held only pruned ones
+        if rollup.counted_through == 0 && rollup.pruned == 0 {
+            rollup.pruned = rollup.days.iter().map(|d| d.count).sum();
+        }
+        rollup.last_id = rollup
+            .last_id
+            .max(records.iter().map(|r| r.id).max().unwrap_or(0));
+        let counted = rollup.counted_through;
+        for record in records.iter().filter(|r| r.id > counted) {
+            rollup.count(record);
+        }
+
+        Self {
+            records,
+            path,
+            rollup,
+            retention: HistoryRetention::default(),
+        }
+    }
+
+    /// Assign the next id to `record`, persist it and return the stored copy.
+    pub fn append(&mut self, mut record: CompressionRecord) -> CompressionRecord {
+        record.id = self.rollup.last_id + 1;
+        self.rollup.last_id = record.id;
+        self.rollup.count(&record);
+        self.records.push(record.clone());
+        if self.prune() == 0 {
+            let _ = self.save();
+        }
+        record
     }
 
-    pub fn append(&mut self, record: CompressionRecord) {
-        self.records.push(record);
+    /// Put the records with `ids` into `group_id` and persist the change.
+    pub fn set_group(&mut self, ids: &[u64], group_id: u64) {
+        for record in self.records.iter_mut().filter(|r| ids.contains(&r.id)) {
+            record.group_id = Some(group_id);
+        }
         let _ = self.save();
     }
 
+    /// Drop the records with `ids` in one write, returning how many went.
+    pub fn remove(&mut self, ids: &[u64]) -> usize {
+        let before = self.records.len();
+        self.records.retain(|r| !ids.contains(&r.id));
+        let removed = before - self.records.len();
+        if removed > 0 {
+            let _ = self.save();
+        }
+        removed
+    }
+
+    pub fn add_cpu_time(&mut self, cpu_ms: u64) {
+        self.rollup.cpu_ms += cpu_ms;
+        let _ = self.save_rollup();
+    }
+
+    /// Apply new retention limits, pruning right away.
+    pub fn set_retention(&mut self, retention: HistoryRetention) {
+        self.retention = retention;
+        self.prune();
+    }
+
+    /// Drop the oldest records beyond the retention limits. Their totals stay
+    /// in the daily rollup. Returns how many were pruned.
+    pub fn prune(&mut self) -> usize {
+        let mut count = 0;
+        if let Some(max) = self.retention.max_records {
+            count = count.max(self.records.len().saturating_sub(max));
+        }
+        if let Some(days) = self.retention.max_age_days {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            let cutoff = now.saturating_sub(days as u64 * SECONDS_PER_DAY);
+            let expired = self
+                .records
+                .iter()
+                .rposition(|r| r.timestamp < cutoff)
+                .map_or(0, |i| i + 1);
+            count = count.max(expired);
+        }
+        if let Some(max_bytes) = self.retention.max_file_bytes {
+            // Approximate: each record's own pretty-printed size
+            let sizes: Vec<u64> = self
+                .records
+                .iter()
+                .map(|r| serde_json::to_string_pretty(r).map_or(0, |s| s.len() as u64 + 2))
+                .collect();
+            let mut total: u64 = sizes.iter().sum();
+            let mut oversized = 0;
+            while total > max_bytes && oversized < sizes.len() {
+                total -= sizes[oversized];
+                oversized += 1;
+            }
+            count = count.max(oversized);
+        }
+        if count == 0 {
+            return 0;
+        }
+
+        let pruned: Vec<CompressionRecord> = self.records.drain(..count).collect();
+        self.rollup.pruned += pruned.iter().filter(|r| !r.analysis_only).count() as u64;
+        info!("[log] Pruned {} records into the rollup", pruned.len());
+        let _ = self.save();
+        pruned.len()
+    }
+
+    pub fn rollup(&self) -> &[DailyRollup] {
+        &self.rollup.days
+    }
+
+    pub fn totals(&self) -> HistoryTotals {
+        let mut totals = HistoryTotals {
+            pruned: self.rollup.pruned,
+            energy: crate::energy::EnergyEstimate::from_cpu_ms(self.rollup.cpu_ms),
+            ..Default::default()
+        };
+        for day in &self.rollup.days {
+            totals.count += day.count;
+            totals.initial_bytes += day.initial_bytes;
+            totals.compressed_bytes += day.compressed_bytes;
+        }
+        totals
+    }
+
+    pub fn find(&self, id: u64) -> Option<&CompressionRecord> {
+        self.records.iter().find(|r| r.id == id)
+    }
+
+    /// Records written at or before `timestamp` (seconds since the epoch).
+    pub fn as_of(&self, timestamp: u64) -> Vec<CompressionRecord> {
+        self.records
+            .iter()
+            .filter(|r| r.timestamp <= timestamp)
+            .cloned()
+            .collect()
+    }
+
+    pub fn restore_plan(&self, timestamp: u64) -> RestorePlan {
+        let planned = |r: &CompressionRecord, path: &str| PlannedFile {
+            record_id: r.id,
+            path: path.to_string(),
+            exists_now: Path::new(path).exists(),
+        };
+
+        // Later records for the same output overwrite earlier ones
+        let mut outputs: HashMap<&str, &CompressionRecord> = HashMap::new();
+        let mut deleted_originals = Vec::new();
+        let mut created_after = Vec::new();
+        for record in self.records.iter().filter(|r| !r.final_path.is_empty()) {
+            if record.timestamp > timestamp {
+                if !outputs.contains_key(record.final_path.as_str()) {
+                    created_after.push(planned(record, &record.final_path));
+                }
+                continue;
+            }
+            outputs.insert(&record.final_path, record);
+            if record.original_deleted {
+                deleted_originals.push(planned(record, &record.initial_path));
+            }
+        }
+
+        let mut outputs: Vec<PlannedFile> = outputs
+            .into_iter()
+            .map(|(path, r)| planned(r, path))
+            .collect();
+        outputs.sort_by_key(|f| f.record_id);
+
+        RestorePlan {
+            as_of: timestamp,
+            outputs,
+            deleted_originals,
+            created_after,
+        }
+    }
+
+    /// Originals still on disk whose compressed output exists too, counted
+    /// at their current size, grouped by folder.
+    pub fn delete_originals_estimate(&self) -> DeleteOriginalsEstimate {
+        let mut originals: HashMap<&str, u64> = HashMap::new();
+        for record in self.records.iter().filter(|r| {
+            !r.analysis_only
+                && !r.original_deleted
+                && r.original_archived_path.is_none()
+                && !r.final_path.is_empty()
+                && r.final_path != r.initial_path
+        }) {
+            if originals.contains_key(record.initial_path.as_str())
+                || !Path::new(&record.final_path).is_file()
+            {
+                continue;
+            }
+            if let Ok(meta) = std::fs::metadata(&record.initial_path) {
+                if meta.is_file() {
+                    originals.insert(&record.initial_path, meta.len());
+                }
+            }
+        }
+
+        let mut folders: HashMap<String, FolderImpact> = HashMap::new();
+        for (path, bytes) in &originals {
+            let folder = Path::new(path)
+                .parent()
+                .map(|p| p.display().to_string())
+                .unwrap_or_default();
+            let impact = folders.entry(folder.clone()).or_insert(FolderImpact {
+                folder,
+                count: 0,
+                bytes: 0,
+            });
+            impact.count += 1;
+            impact.bytes += bytes;
+        }
+        let mut folders: Vec<FolderImpact> = folders.into_values().collect();
+        folders.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+
+        DeleteOriginalsEstimate {
+            count: originals.len(),
+            bytes: originals.values().sum(),
+            folders,
+        }
+    }
+
+    /// Store the day's size of `folder`, replacing an earlier sample from the
+    /// same day. The savings are filled in from the history.
+    pub fn add_folder_sample(&mut self, folder: &Path, bytes: u64, files: u64) {
+        let day = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+            / SECONDS_PER_DAY;
+        let (_, saved_bytes) = self.folder_savings(folder);
+        let folder = folder.display().to_string();
+        let sizes = &mut self.rollup.folder_sizes;
+        sizes.retain(|s| !(s.day == day && s.folder == folder));
+        sizes.push(FolderSample {
+            folder,
+            day,
+            bytes,
+            files,
+            saved_bytes,
+        });
+        let _ = self.save_rollup();
+    }
+
+    /// How many files from `folder` the history holds and the bytes
+    /// compressing them saved.
+    pub fn folder_savings(&self, folder: &Path) -> (usize, u64) {
+        self.records
+            .iter()
+            .filter(|r| !r.analysis_only && Path::new(&r.initial_path).parent() == Some(folder))
+            .fold((0, 0), |(count, saved), r| {
+                (
+                    count + 1,
+                    saved + r.initial_size.saturating_sub(r.compressed_size),
+                )
+            })
+    }
+
+    /// Every sample of `folder`, oldest first.
+    pub fn folder_trend(&self, folder: &str) -> Vec<FolderSample> {
+        self.rollup
+            .folder_sizes
+            .iter()
+            .filter(|s| s.folder == folder)
+            .cloned()
+            .collect()
+    }
+
+    /// Input formats of files from `folder` compressed since `since`, most
+    /// frequent first.
+    pub fn seen_formats(&self, folder: &Path, since: u64) -> Vec<SeenFormat> {
+        let mut seen: HashMap<&str, SeenFormat> = HashMap::new();
+        for record in self
+            .records
+            .iter()
+            .filter(|r| r.timestamp >= since && Path::new(&r.initial_path).parent() == Some(folder))
+        {
+            let entry = seen
+                .entry(record.initial_format.as_str())
+                .or_insert_with(|| SeenFormat {
+                    format: record.initial_format.clone(),
+                    count: 0,
+                    last_seen: 0,
+                });
+            entry.count += 1;
+            entry.last_seen = entry.last_seen.max(record.timestamp);
+        }
+        let mut seen: Vec<SeenFormat> = seen.into_values().collect();
+        seen.sort_by(|a, b| b.count.cmp(&a.count).then(b.last_seen.cmp(&a.last_seen)));
+        seen
+    }
+
     pub fn clear(&mut self) {
+        self.rollup.counted_through = self.rollup.last_id;
+        self.rollup.pruned = 0;
+        self.rollup.days.clear();
+        self.rollup.cpu_ms = 0;
         self.records.clear();
         let _ = self.save();
     }
@@ -39,6 +491,20 @@ impl CompressionLog {
                 return Err(format!("Failed to save log: {}", e));
             }
         }
+        self.save_rollup()
+    }
+
+    fn save_rollup(&self) -> Result<(), String> {
+        if let Ok(json) = serde_json::to_string_pretty(&self.rollup) {
+            if let Err(e) = std::fs::write(rollup_path(&self.path), json) {
+                error!("Failed to save log rollup: {}", e);
+                return Err(format!("Failed to save log rollup: {}", e));
+            }
+        }
         Ok(())
     }
 }
+
+fn rollup_path(log_path: &Path) -> PathBuf {
+    log_path.with_file_name("compression_rollup.json")
+}
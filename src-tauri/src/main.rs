@@ -2,5 +2,8 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    if let Some(code) = hat_lib::run_helper() {
+        std::process::exit(code);
+    }
     hat_lib::run()
 }
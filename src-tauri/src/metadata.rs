@@ -0,0 +1,352 @@
+use img_parts::jpeg::{markers, Jpeg, JpegSegment};
+use img_parts::{Bytes, DynImage, ImageEXIF, ImageICC};
+use log::info;
+use std::path::Path;
+
+/// Signature opening an XMP packet in a JPEG APP1 segment.
+const XMP_SIGNATURE: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+/// Signature opening the Photoshop IRB segment that carries IPTC.
+const IPTC_SIGNATURE: &[u8] = b"Photoshop 3.0\0";
+/// IFD0 tag pointing at the GPS IFD.
+const GPS_IFD_TAG: u16 = 0x8825;
+/// IFD0 tag holding the EXIF orientation.
+const ORIENTATION_TAG: u16 = 0x0112;
+/// IFD0 tag pointing at the EXIF sub-IFD.
+const EXIF_IFD_TAG: u16 = 0x8769;
+/// EXIF sub-IFD tag for when the photo was taken.
+const DATE_TIME_ORIGINAL_TAG: u16 = 0x9003;
+/// IFD0 tag for when the file was last changed, the fallback date.
+const DATE_TIME_TAG: u16 = 0x0132;
+/// How far into a file `is_hdr` looks; the markers sit in the headers.
+const HDR_SNIFF_LEN: u64 = 256 * 1024;
+/// Gain-map markers: Ultra HDR / Adobe XMP, Apple's auxiliary image and
+/// the ISO 21496-1 metadata URN.
+const GAIN_MAP_MARKERS: &[&[u8]] = &[
+    b"hdrgm:Version",
+    b"aux:hdrgainmap",
+    b"urn:iso:std:iso:ts:21496:-1",
+];
+/// PQ and HLG transfer characteristics (ITU-T H.273).
+const HDR_TRANSFERS: [u16; 2] = [16, 18];
+/// Bytes in one TIFF IFD entry.
+const ENTRY_LEN: usize = 12;
+
+fn load(path: &Path) -> Result<DynImage, String> {
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    DynImage::from_bytes(Bytes::from(bytes))
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("{} has no metadata container", path.display()))
+}
+
+/// Copy EXIF, the ICC profile and, between JPEGs, XMP and IPTC from `input`
+/// into `output` where the encoder left them out. libvips outputs usually
+/// have them already; the pure-Rust encoders never do. Only JPEG, PNG and
+/// WebP outputs can take them. Returns the output's size afterwards.
+pub fn preserve(input: &Path, output: &Path) -> Result<u64, String> {
+    let source = load(input)?;
+    let mut target = load(output)?;
+    let mut copied = Vec::new();
+
+    if target.exif().is_none() {
+        if let Some(exif) = source.exif() {
+            // The pixels were already turned upright while decoding
+            let mut tiff = exif.to_vec();
+            reset_orientation(&mut tiff);
+            target.set_exif(Some(Bytes::from(tiff)));
+            copied.push("EXIF");
+        }
+    }
+    if target.icc_profile().is_none() {
+        if let Some(icc) = source.icc_profile() {
+            target.set_icc_profile(Some(icc));
+            copied.push("ICC");
+        }
+    }
+    if let (DynImage::Jpeg(source), DynImage::Jpeg(target)) = (&source, &mut target) {
+        for (marker, signature, name) in [
+            (markers::APP1, XMP_SIGNATURE, "XMP"),
+            (markers::APP13, IPTC_SIGNATURE, "IPTC"),
+        ] {
+            if find_segment(target, marker, signature).is_none() {
+                if let Some(segment) = find_segment(source, marker, signature).cloned() {
+                    insert_after_app_segments(target, segment);
+                    copied.push(name);
+                }
+            }
+        }
+    }
+
+    if copied.is_empty() {
+        return std::fs::metadata(output)
+            .map(|m| m.len())
+            .map_err(|e| e.to_string());
+    }
+    let size = save(target, output)?;
+    info!(
+        "[metadata] Copied {} from {} to {}",
+        copied.join(", "),
+        input.display(),
+        output.display()
+    );
+    Ok(size)
+}
+
+/// Embed `input`'s ICC profile in `output` unchanged, when the encoder
+/// dropped it. Returns the output's size afterwards.
+pub fn copy_icc(input: &Path, output: &Path) -> Result<u64, String> {
+    let source = load(input)?;
+    let mut target = load(output)?;
+    match source.icc_profile() {
+        Some(icc) if target.icc_profile().is_none() => {
+            target.set_icc_profile(Some(icc));
+            let size = save(target, output)?;
+            info!(
+                "[metadata] Copied ICC profile from {} to {}",
+                input.display(),
+                output.display()
+            );
+            Ok(size)
+        }
+        _ => std::fs::metadata(output)
+            .map(|m| m.len())
+            .map_err(|e| e.to_string()),
+    }
+}
+
+/// Remove the GPS tags from `output`'s EXIF, keeping camera and timestamp
+/// tags. Returns the output's size afterwards.
+pub fn strip_location(output: &Path) -> Result<u64, String> {
+    let mut image = load(output)?;
+    let Some(exif) = image.exif() else {
+        return std::fs::metadata(output)
+            .map(|m| m.len())
+            .map_err(|e| e.to_string());
+    };
+    let mut tiff = exif.to_vec();
+    if !remove_gps_ifd(&mut tiff).ok_or_else(|| "EXIF is not valid TIFF".to_string())? {
+        return std::fs::metadata(output)
+            .map(|m| m.len())
+            .map_err(|e| e.to_string());
+    }
+    image.set_exif(Some(Bytes::from(tiff)));
+    let size = save(image, output)?;
+    info!("[metadata] Stripped location from {}", output.display());
+    Ok(size)
+}
+
+/// `YYYY/MM` of when `path` was taken according to its EXIF, or else of
+/// when it was last modified.
+pub fn date_subfolder(path: &Path) -> Option<std::path::PathBuf> {
+    use chrono::Datelike;
+    let (year, month) = load(path)
+        .ok()
+        .and_then(|image| image.exif())
+        .and_then(|exif| date_taken(&exif))
+        .or_else(|| {
+            let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+            let date = chrono::DateTime::<chrono::Local>::from(modified);
+            Some((date.year(), date.month()))
+        })?;
+    Some(Path::new(&format!("{:04}", year)).join(format!("{:02}", month)))
+}
+
+/// Year and month of DateTimeOriginal, falling back to DateTime.
+fn date_taken(tiff: &[u8]) -> Option<(i32, u32)> {
+    let little = match tiff.get(..2)? {
+        b"II" => true,
+        b"MM" => false,
+        _ => return None,
+    };
+    let ifd0 = read_u32(tiff, 4, little)? as usize;
+    let original = find_entry(tiff, ifd0, EXIF_IFD_TAG, little)
+        .and_then(|entry| read_u32(tiff, entry + 8, little))
+        .and_then(|exif| find_entry(tiff, exif as usize, DATE_TIME_ORIGINAL_TAG, little));
+    let entry = original.or_else(|| find_entry(tiff, ifd0, DATE_TIME_TAG, little))?;
+    // "YYYY:MM:DD HH:MM:SS", always stored out of line
+    let offset = read_u32(tiff, entry + 8, little)? as usize;
+    let text = std::str::from_utf8(tiff.get(offset..offset + 7)?).ok()?;
+    let year = text.get(..4)?.parse().ok()?;
+    let month = text.get(5..7)?.parse().ok()?;
+    (year > 0 && (1..=12).contains(&month)).then_some((year, month))
+}
+
+/// Offset of the entry for `tag` in the IFD at `ifd`.
+fn find_entry(tiff: &[u8], ifd: usize, tag: u16, little: bool) -> Option<usize> {
+    let count = read_u16(tiff, ifd, little)? as usize;
+    (0..count)
+        .map(|i| ifd + 2 + i * ENTRY_LEN)
+        .find(|e| read_u16(tiff, *e, little) == Some(tag))
+}
+
+/// Whether `path` carries HDR data an 8-bit SDR output would flatten: a
+/// gain map, or a PQ/HLG transfer in a HEIF/AVIF `nclx` box or PNG `cICP`.
+pub fn is_hdr(path: &Path) -> bool {
+    use std::io::Read;
+    let mut head = Vec::new();
+    if std::fs::File::open(path)
+        .and_then(|f| f.take(HDR_SNIFF_LEN).read_to_end(&mut head))
+        .is_err()
+    {
+        return false;
+    }
+    let find = |needle: &[u8]| head.windows(needle.len()).position(|w| w == needle);
+    if GAIN_MAP_MARKERS.iter().any(|m| find(m).is_some()) {
+        return true;
+    }
+    // colr box: "nclx", primaries (u16), transfer (u16)
+    if let Some(i) = find(b"colrnclx") {
+        if let Some(transfer) = read_u16(&head, i + 10, false) {
+            return HDR_TRANSFERS.contains(&transfer);
+        }
+    }
+    // cICP chunk: primaries (u8), transfer (u8)
+    if let Some(i) = find(b"cICP") {
+        if let Some(&transfer) = head.get(i + 5) {
+            return HDR_TRANSFERS.contains(&(transfer as u16));
+        }
+    }
+    false
+}
+
+fn save(image: DynImage, output: &Path) -> Result<u64, String> {
+    let file = std::fs::File::create(output).map_err(|e| e.to_string())?;
+    image
+        .encoder()
+        .write_to(std::io::BufWriter::new(file))
+        .map_err(|e| e.to_string())
+}
+
+/// Drop the GPS IFD pointer from IFD0 and blank the GPS IFD and its values,
+/// so no coordinates survive as orphaned bytes. `Some(false)` when there's
+/// no GPS IFD, `None` when `tiff` doesn't parse.
+fn remove_gps_ifd(tiff: &mut [u8]) -> Option<bool> {
+    let little = match tiff.get(..2)? {
+        b"II" => true,
+        b"MM" => false,
+        _ => return None,
+    };
+    let ifd0 = read_u32(tiff, 4, little)? as usize;
+    let count = read_u16(tiff, ifd0, little)? as usize;
+    let entries = ifd0 + 2;
+    let Some(index) =
+        (0..count).find(|i| read_u16(tiff, entries + i * ENTRY_LEN, little) == Some(GPS_IFD_TAG))
+    else {
+        return Some(false);
+    };
+    let pointer = entries + index * ENTRY_LEN;
+    let gps = read_u32(tiff, pointer + 8, little)? as usize;
+
+    // Values too big to sit inline live elsewhere; blank those first
+    if let Some(gps_count) = read_u16(tiff, gps, little).map(usize::from) {
+        for i in 0..gps_count {
+            let entry = gps + 2 + i * ENTRY_LEN;
+            let (Some(kind), Some(n)) = (
+                read_u16(tiff, entry + 2, little),
+                read_u32(tiff, entry + 4, little),
+            ) else {
+                break;
+            };
+            let len = type_size(kind).saturating_mul(n as usize);
+            if len > 4 {
+                if let Some(offset) = read_u32(tiff, entry + 8, little) {
+                    blank(tiff, offset as usize, len);
+                }
+            }
+        }
+        blank(tiff, gps, 2 + gps_count * ENTRY_LEN + 4);
+    }
+
+    // Close the gap in IFD0, moving the next-IFD offset up with the entries
+    let end = entries + count * ENTRY_LEN + 4;
+    if end > tiff.len() {
+        return None;
+    }
+    tiff.copy_within(pointer + ENTRY_LEN..end, pointer);
+    blank(tiff, end - ENTRY_LEN, ENTRY_LEN);
+    let count = count as u16 - 1;
+    let count = if little {
+        count.to_le_bytes()
+    } else {
+        count.to_be_bytes()
+    };
+    tiff[ifd0..ifd0 + 2].copy_from_slice(&count);
+    Some(true)
+}
+
+/// Mark `tiff` as upright, if it has an orientation tag at all.
+fn reset_orientation(tiff: &mut [u8]) {
+    let little = match tiff.get(..2) {
+        Some(b"II") => true,
+        Some(b"MM") => false,
+        _ => return,
+    };
+    let Some(ifd0) = read_u32(tiff, 4, little).map(|o| o as usize) else {
+        return;
+    };
+    let count = read_u16(tiff, ifd0, little).unwrap_or(0) as usize;
+    let entries = ifd0 + 2;
+    if let Some(entry) = (0..count)
+        .map(|i| entries + i * ENTRY_LEN)
+        .find(|e| read_u16(tiff, *e, little) == Some(ORIENTATION_TAG))
+    {
+        let upright = if little {
+            1u16.to_le_bytes()
+        } else {
+            1u16.to_be_bytes()
+        };
+        if let Some(value) = tiff.get_mut(entry + 8..entry + 10) {
+            value.copy_from_slice(&upright);
+        }
+    }
+}
+
+fn read_u16(tiff: &[u8], offset: usize, little: bool) -> Option<u16> {
+    let bytes: [u8; 2] = tiff.get(offset..offset + 2)?.try_into().ok()?;
+    Some(if little {
+        u16::from_le_bytes(bytes)
+    } else {
+        u16::from_be_bytes(bytes)
+    })
+}
+
+fn read_u32(tiff: &[u8], offset: usize, little: bool) -> Option<u32> {
+    let bytes: [u8; 4] = tiff.get(offset..offset + 4)?.try_into().ok()?;
+    Some(if little {
+        u32::from_le_bytes(bytes)
+    } else {
+        u32::from_be_bytes(bytes)
+    })
+}
+
+/// Bytes per value of a TIFF field type.
+fn type_size(kind: u16) -> usize {
+    match kind {
+        1 | 2 | 6 | 7 => 1,
+        3 | 8 => 2,
+        4 | 9 | 11 => 4,
+        5 | 10 | 12 => 8,
+        _ => 0,
+    }
+}
+
+fn blank(tiff: &mut [u8], offset: usize, len: usize) {
+    let end = offset.saturating_add(len).min(tiff.len());
+    if offset < end {
+        tiff[offset..end].fill(0);
+    }
+}
+
+fn find_segment<'a>(jpeg: &'a Jpeg, marker: u8, signature: &[u8]) -> Option<&'a JpegSegment> {
+    jpeg.segments()
+        .iter()
+        .find(|s| s.marker() == marker && s.contents().starts_with(signature))
+}
+
+fn insert_after_app_segments(jpeg: &mut Jpeg, segment: JpegSegment) {
+    let index = jpeg
+        .segments()
+        .iter()
+        .rposition(|s| (markers::APP0..=markers::APP15).contains(&s.marker()))
+        .map_or(0, |i| i + 1);
+    jpeg.segments_mut().insert(index, segment);
+}
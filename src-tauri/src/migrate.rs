@@ -0,0 +1,163 @@
+use crate::compression::CompressionRecord;
+use crate::config::OutputNaming;
+use crate::log::CompressionLog;
+use log::info;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MoveStatus {
+    Planned,
+    Moved,
+    /// The output no longer exists.
+    Missing,
+    /// Something already sits at the new path.
+    TargetExists,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedMove {
+    pub record_id: u64,
+    pub from: String,
+    pub to: String,
+    pub status: MoveStatus,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationReport {
+    pub dry_run: bool,
+    pub moves: Vec<PlannedMove>,
+}
+
+/// Where `record`'s output belongs under `to`, if it was written under `from`.
+fn target_for(
+    record: &CompressionRecord,
+    from: &OutputNaming,
+    to: &OutputNaming,
+) -> Option<PathBuf> {
+    if record.final_path.is_empty() || record.analysis_only {
+        return None;
+    }
+    let final_path = Path::new(&record.final_path);
+    // Encrypted outputs carry an extra `.enc` after the image extension
+    let output = if record.encrypted {
+        final_path.with_extension("")
+    } else {
+        final_path.to_path_buf()
+    };
+    let ext = output.extension()?.to_str()?;
+    let initial = Path::new(&record.initial_path);
+    if from.path_for(initial, ext)? != output {
+        return None;
+    }
+    let target = to.path_for(initial, ext)?;
+    Some(if record.encrypted {
+        let mut name = target.into_os_string();
+        name.push(".enc");
+        PathBuf::from(name)
+    } else {
+        target
+    })
+}
+
+/// Move outputs written under `from` to where `to` would put them and point
+/// their history records at the new paths. Nothing is touched on a dry run.
+pub fn migrate_outputs(
+    log: &mut CompressionLog,
+    from: &OutputNaming,
+    to: &OutputNaming,
+    dry_run: bool,
+) -> MigrationReport {
+    let mut moves = Vec::new();
+    let mut seen = HashSet::new();
+    // Newest first, so a re-compressed output is moved once for its latest record
+    for record in log.records.iter().rev() {
+        let Some(target) = target_for(record, from, to) else {
+            continue;
+        };
+        if target == Path::new(&record.final_path) || !seen.insert(record.final_path.clone()) {
+            continue;
+        }
+        let status = if !Path::new(&record.final_path).exists() {
+            MoveStatus::Missing
+        } else if target.exists() {
+            MoveStatus::TargetExists
+        } else {
+            MoveStatus::Planned
+        };
+        moves.push(PlannedMove {
+            record_id: record.id,
+            from: record.final_path.clone(),
+            to: target.display().to_string(),
+            status,
+            error: None,
+        });
+    }
+
+    if !dry_run {
+        for planned in moves.iter_mut().filter(|m| m.status == MoveStatus::Planned) {
+            match move_file(Path::new(&planned.from), Path::new(&planned.to)) {
+                Ok(()) => {
+                    planned.status = MoveStatus::Moved;
+                    for record in log
+                        .records
+                        .iter_mut()
+                        .filter(|r| r.final_path == planned.from)
+                    {
+                        record.final_path = planned.to.clone();
+                    }
+                    move_sidecar(log, planned);
+                }
+                Err(e) => {
+                    planned.status = MoveStatus::Failed;
+                    planned.error = Some(e.to_string());
+                }
+            }
+        }
+        let _ = log.save();
+        info!(
+            "[migrate] Moved {} of {} outputs",
+            moves
+                .iter()
+                .filter(|m| m.status == MoveStatus::Moved)
+                .count(),
+            moves.len()
+        );
+    }
+
+    MigrationReport { dry_run, moves }
+}
+
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Renames fail across volumes, e.g. when the new output folder is on another drive
+    if std::fs::rename(from, to).is_err() {
+        std::fs::copy(from, to)?;
+        std::fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+/// A checksum sidecar names its file, so it is rewritten next to the new path.
+fn move_sidecar(log: &CompressionLog, planned: &PlannedMove) {
+    let mut old = PathBuf::from(&planned.from).into_os_string();
+    old.push(".sha256");
+    if std::fs::remove_file(&old).is_err() {
+        return;
+    }
+    let hash = log
+        .records
+        .iter()
+        .rev()
+        .find(|r| r.final_path == planned.to)
+        .and_then(|r| r.output_sha256.as_deref());
+    if let Some(hash) = hash {
+        let _ = crate::checksum::write_sidecar(Path::new(&planned.to), hash);
+    }
+}
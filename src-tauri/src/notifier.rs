@@ -0,0 +1,285 @@
+use crate::config::{EmailSettings, NotificationEvent, NotificationSinkConfig, SinkKind};
+use log::{info, warn};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+/// How often the daily summary thread checks whether a day has ended.
+const SUMMARY_INTERVAL: Duration = Duration::from_secs(15 * 60);
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+const SUMMARY_STATE_FILE: &str = "daily_summary_sent";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    pub event: NotificationEvent,
+    pub title: String,
+    pub body: String,
+    /// The record, error or totals behind the notification, for webhooks.
+    pub data: serde_json::Value,
+}
+
+/// Somewhere a notification can be delivered.
+pub trait NotificationSink {
+    fn send(&self, app: &tauri::AppHandle, notification: &Notification) -> Result<(), String>;
+}
+
+struct SystemSink;
+
+impl NotificationSink for SystemSink {
+    fn send(&self, app: &tauri::AppHandle, notification: &Notification) -> Result<(), String> {
+        use tauri_plugin_notification::NotificationExt;
+        let enabled = app
+            .state::<Mutex<crate::config::ConfigManager>>()
+            .lock()
+            .map(|c| c.config.show_system_notifications)
+            .unwrap_or(true);
+        if !enabled {
+            return Ok(());
+        }
+        app.notification()
+            .builder()
+            .title(&notification.title)
+            .body(&notification.body)
+            .show()
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// POSTs the whole notification as JSON.
+struct WebhookSink {
+    url: String,
+}
+
+impl NotificationSink for WebhookSink {
+    fn send(&self, _app: &tauri::AppHandle, notification: &Notification) -> Result<(), String> {
+        post_json(&self.url, notification)
+    }
+}
+
+/// Slack and Discord incoming webhooks: a single message, under the field
+/// and with the bold markup each expects.
+struct ChatSink {
+    url: String,
+    field: &'static str,
+    bold: &'static str,
+}
+
+impl NotificationSink for ChatSink {
+    fn send(&self, _app: &tauri::AppHandle, notification: &Notification) -> Result<(), String> {
+        let text = format!(
+            "{bold}{}{bold}\n{}",
+            notification.title,
+            notification.body,
+            bold = self.bold
+        );
+        let mut message = serde_json::Map::new();
+        message.insert(self.field.into(), text.into());
+        post_json(&self.url, &message)
+    }
+}
+
+struct EmailSink {
+    settings: EmailSettings,
+}
+
+impl NotificationSink for EmailSink {
+    fn send(&self, _app: &tauri::AppHandle, notification: &Notification) -> Result<(), String> {
+        use lettre::transport::smtp::authentication::Credentials;
+        use lettre::{Message, SmtpTransport, Transport};
+
+        let s = &self.settings;
+        let message = Message::builder()
+            .from(s.from.parse().map_err(|e| format!("invalid sender: {e}"))?)
+            .to(s
+                .to
+                .parse()
+                .map_err(|e| format!("invalid recipient: {e}"))?)
+            .subject(&notification.title)
+            .body(notification.body.clone())
+            .map_err(|e| e.to_string())?;
+        let builder = if s.smtp_port == 465 {
+            SmtpTransport::relay(&s.smtp_host)
+        } else {
+            SmtpTransport::starttls_relay(&s.smtp_host)
+        }
+        .map_err(|e| e.to_string())?
+        .port(s.smtp_port)
+        .timeout(Some(HTTP_TIMEOUT));
+        let builder = if s.username.is_empty() {
+            builder
+        } else {
+            builder.credentials(Credentials::new(s.username.clone(), s.resolved_password()))
+        };
+        builder
+            .build()
+            .send(&message)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+fn post_json(url: &str, body: &impl Serialize) -> Result<(), String> {
+    ureq::post(url)
+        .timeout(HTTP_TIMEOUT)
+        .send_json(body)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn build(kind: &SinkKind) -> Box<dyn NotificationSink + Send> {
+    match kind {
+        SinkKind::System => Box::new(SystemSink),
+        SinkKind::Webhook { url } => Box::new(WebhookSink { url: url.clone() }),
+        SinkKind::Slack { webhook_url } => Box::new(ChatSink {
+            url: webhook_url.clone(),
+            field: "text",
+            bold: "*",
+        }),
+        SinkKind::Discord { webhook_url } => Box::new(ChatSink {
+            url: webhook_url.clone(),
+            field: "content",
+            bold: "**",
+        }),
+        SinkKind::Email(settings) => Box::new(EmailSink {
+            settings: settings.clone(),
+        }),
+    }
+}
+
+fn sink_name(kind: &SinkKind) -> &'static str {
+    match kind {
+        SinkKind::System => "system",
+        SinkKind::Webhook { .. } => "webhook",
+        SinkKind::Slack { .. } => "slack",
+        SinkKind::Discord { .. } => "discord",
+        SinkKind::Email(_) => "email",
+    }
+}
+
+fn sinks_for(app: &tauri::AppHandle, event: NotificationEvent) -> Vec<NotificationSinkConfig> {
+    app.state::<Mutex<crate::config::ConfigManager>>()
+        .lock()
+        .map(|c| {
+            c.config
+                .notification_sinks
+                .iter()
+                .filter(|s| s.events.is_empty() || s.events.contains(&event))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Deliver `notification` to every sink that wants its event, off the
+/// calling thread so slow servers never hold up compression.
+pub fn notify(app: &tauri::AppHandle, notification: Notification) {
+    let sinks = sinks_for(app, notification.event);
+    if sinks.is_empty() {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        for sink in &sinks {
+            if let Err(e) = build(&sink.kind).send(&app, &notification) {
+                warn!("[notifier] {} sink failed: {}", sink_name(&sink.kind), e);
+            }
+        }
+    });
+}
+
+/// Send a test message to one sink right away, whatever its event filter.
+pub fn test(app: &tauri::AppHandle, sink: &NotificationSinkConfig) -> Result<(), String> {
+    build(&sink.kind).send(
+        app,
+        &Notification {
+            event: NotificationEvent::Compressed,
+            title: "Hat test notification".into(),
+            body: "Notifications from Hat will arrive here.".into(),
+            data: serde_json::Value::Null,
+        },
+    )
+}
+
+fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        / 86_400
+}
+
+fn summary_state_path(app: &tauri::AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_config_dir()
+        .ok()
+        .map(|dir| dir.join(SUMMARY_STATE_FILE))
+}
+
+/// Spawn the thread that sends the previous day's totals to sinks
+/// subscribed to `daily_summary`, once per day (UTC). Days that ended while
+/// the app was closed are summarized at the next launch, only the latest.
+pub fn start(app: &tauri::AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        send_daily_summary(&app);
+        std::thread::sleep(SUMMARY_INTERVAL);
+    });
+}
+
+fn send_daily_summary(app: &tauri::AppHandle) {
+    let Some(state_path) = summary_state_path(app) else {
+        return;
+    };
+    let yesterday = today().saturating_sub(1);
+    let last_sent = std::fs::read_to_string(&state_path)
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok());
+    if last_sent.is_some_and(|day| day >= yesterday) {
+        return;
+    }
+    let _ = std::fs::write(&state_path, yesterday.to_string());
+    // The first launch only starts the count
+    if last_sent.is_none() || sinks_for(app, NotificationEvent::DailySummary).is_empty() {
+        return;
+    }
+
+    let (count, initial, compressed) = app
+        .state::<Mutex<crate::log::CompressionLog>>()
+        .lock()
+        .map(|log| {
+            log.rollup()
+                .iter()
+                .filter(|r| r.day == yesterday)
+                .fold((0, 0, 0), |(c, i, o), r| {
+                    (c + r.count, i + r.initial_bytes, o + r.compressed_bytes)
+                })
+        })
+        .unwrap_or_default();
+    if count == 0 {
+        return;
+    }
+    let saved = initial.saturating_sub(compressed);
+    info!("[notifier] Sending the summary of day {}", yesterday);
+    notify(
+        app,
+        Notification {
+            event: NotificationEvent::DailySummary,
+            title: "Hat daily summary".into(),
+            body: format!(
+                "{} images compressed yesterday, {} → {} (saved {})",
+                count,
+                crate::locale::format_bytes(initial),
+                crate::locale::format_bytes(compressed),
+                crate::locale::format_bytes(saved)
+            ),
+            data: serde_json::json!({
+                "day": yesterday,
+                "count": count,
+                "initial_bytes": initial,
+                "compressed_bytes": compressed,
+            }),
+        },
+    );
+}
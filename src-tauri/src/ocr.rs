@@ -0,0 +1,54 @@
+use crate::config::OcrConfig;
+use log::{info, warn};
+use std::path::Path;
+use std::process::Command;
+
+const SCREENSHOT_PREFIXES: &[&str] = &["screenshot", "screen shot", "screen_shot", "capture"];
+
+/// Heuristic for screenshot files based on the names common OS tools produce.
+pub fn is_screenshot(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .map(|n| n.to_lowercase())
+        .is_some_and(|n| SCREENSHOT_PREFIXES.iter().any(|p| n.starts_with(p)))
+}
+
+/// Run tesseract on `path` if OCR is enabled and applies to this file.
+pub fn extract_text(config: &OcrConfig, path: &Path) -> Option<String> {
+    if !config.enabled || (config.screenshots_only && !is_screenshot(path)) {
+        return None;
+    }
+
+    let tesseract = config.tesseract_path.as_deref().unwrap_or("tesseract");
+    let output = match Command::new(tesseract)
+        .arg(path)
+        .arg("stdout")
+        .args(["-l", &config.language])
+        .output()
+    {
+        Ok(o) => o,
+        Err(e) => {
+            warn!("[ocr] Failed to run {}: {}", tesseract, e);
+            return None;
+        }
+    };
+
+    if !output.status.success() {
+        warn!(
+            "[ocr] {} exited with {} for {}: {}",
+            tesseract,
+            output.status,
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return None;
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    info!(
+        "[ocr] Extracted {} characters from {}",
+        text.len(),
+        path.display()
+    );
+    (!text.is_empty()).then_some(text)
+}
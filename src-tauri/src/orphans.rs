@@ -0,0 +1,190 @@
+use crate::compression::CompressionRecord;
+use crate::config::OutputNaming;
+use crate::log::CompressionLog;
+use log::{info, warn};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrphanReason {
+    /// No history record points at the output.
+    Untracked,
+    /// The record's original was removed outside Hat.
+    OriginalMissing,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrphanedOutput {
+    pub path: String,
+    pub size: u64,
+    pub reason: OrphanReason,
+    /// The file the output was most likely made from, when it still exists.
+    pub original: Option<String>,
+}
+
+/// Outputs in `folders` that history has lost track of.
+pub fn find_orphaned_outputs(
+    folders: &[String],
+    naming: &OutputNaming,
+    log: &CompressionLog,
+) -> Vec<OrphanedOutput> {
+    let records: HashMap<&str, &CompressionRecord> = log
+        .records
+        .iter()
+        .filter(|r| !r.analysis_only)
+        .map(|r| (r.final_path.as_str(), r))
+        .collect();
+
+    let mut orphans = Vec::new();
+    for folder in folders {
+        for path in output_candidates(Path::new(folder), naming) {
+            let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            let key = path.display().to_string();
+            let orphan = match records.get(key.as_str()) {
+                None => OrphanedOutput {
+                    path: key,
+                    size,
+                    reason: OrphanReason::Untracked,
+                    original: original_for(&path, naming).map(|p| p.display().to_string()),
+                },
+                // Originals Hat deleted on purpose leave the output as the only copy
+                Some(record)
+                    if !record.original_deleted
+                        && record.original_archived_path.is_none()
+                        && !Path::new(&record.initial_path).exists() =>
+                {
+                    OrphanedOutput {
+                        path: key,
+                        size,
+                        reason: OrphanReason::OriginalMissing,
+                        original: None,
+                    }
+                }
+                Some(_) => continue,
+            };
+            orphans.push(orphan);
+        }
+    }
+    info!("[orphans] Found {} orphaned outputs", orphans.len());
+    orphans
+}
+
+/// Files in `folder`, and in its output folder, that look like outputs.
+fn output_candidates(folder: &Path, naming: &OutputNaming) -> Vec<PathBuf> {
+    let mut dirs = vec![folder.to_path_buf()];
+    if let Some(dir) = naming.directory.as_deref().map(Path::new) {
+        dirs.push(if dir.is_absolute() {
+            dir.to_path_buf()
+        } else {
+            folder.join(dir)
+        });
+    }
+    dirs.dedup();
+
+    let mut paths: Vec<PathBuf> = dirs
+        .iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten().map(|e| e.path()))
+        .filter(|p| p.is_file() && naming.is_output(p))
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// The non-output file whose name `output` was derived from.
+fn original_for(output: &Path, naming: &OutputNaming) -> Option<PathBuf> {
+    let stem = output.file_stem()?.to_str()?;
+    let stem = stem.strip_suffix(naming.suffix.as_str()).unwrap_or(stem);
+    let dir = output.parent()?;
+    // Relative output folders sit inside the original's folder
+    let search = match naming.directory.as_deref().map(Path::new) {
+        Some(rel) if !rel.is_absolute() && dir.ends_with(rel) => {
+            dir.ancestors().nth(rel.components().count())?
+        }
+        _ => dir,
+    };
+    std::fs::read_dir(search)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .find(|p| {
+            p.is_file()
+                && p != output
+                && !naming.is_output(p)
+                && p.file_stem().and_then(|s| s.to_str()) == Some(stem)
+        })
+}
+
+/// Delete orphaned outputs. Paths that don't look like outputs are refused so
+/// an original can never be removed through here.
+pub fn delete_outputs(paths: &[String], naming: &OutputNaming) -> Vec<String> {
+    let mut deleted = Vec::new();
+    for path in paths.iter().map(Path::new) {
+        if !naming.is_output(path) {
+            warn!("[orphans] Refusing to delete non-output {}", path.display());
+            continue;
+        }
+        match std::fs::remove_file(path) {
+            Ok(()) => {
+                let mut sidecar = path.as_os_str().to_owned();
+                sidecar.push(".sha256");
+                let _ = std::fs::remove_file(sidecar);
+                deleted.push(path.display().to_string());
+            }
+            Err(e) => warn!("[orphans] Failed to delete {}: {e}", path.display()),
+        }
+    }
+    info!(
+        "[orphans] Deleted {} of {} outputs",
+        deleted.len(),
+        paths.len()
+    );
+    deleted
+}
+
+/// Add history records for untracked outputs whose original still exists.
+pub fn adopt_outputs(
+    paths: &[String],
+    naming: &OutputNaming,
+    log: &mut CompressionLog,
+) -> Vec<CompressionRecord> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let mut adopted = Vec::new();
+    for path in paths.iter().map(Path::new) {
+        if log.records.iter().any(|r| Path::new(&r.final_path) == path) {
+            continue;
+        }
+        let Some(original) = original_for(path, naming) else {
+            warn!("[orphans] No original found for {}", path.display());
+            continue;
+        };
+        let format_of = |p: &Path| {
+            p.extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default()
+        };
+        let record = CompressionRecord {
+            initial_path: original.display().to_string(),
+            final_path: path.display().to_string(),
+            initial_size: std::fs::metadata(&original).map(|m| m.len()).unwrap_or(0),
+            compressed_size: std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+            initial_format: format_of(&original),
+            final_format: format_of(path),
+            timestamp,
+            ..Default::default()
+        };
+        adopted.push(log.append(record));
+    }
+    info!(
+        "[orphans] Adopted {} of {} outputs",
+        adopted.len(),
+        paths.len()
+    );
+    adopted
+}
@@ -0,0 +1,141 @@
+use log::warn;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use tauri::Manager;
+
+const CLAIMS_FILE: &str = "output_dir.json";
+
+static DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+/// The watched folders, whose subfolders are mirrored under `DIR`.
+static ROOTS: RwLock<Vec<PathBuf>> = RwLock::new(Vec::new());
+static CLAIMS: Mutex<Option<Claims>> = Mutex::new(None);
+
+/// Which source each file in the output folder was written for, by path
+/// relative to the folder, so two `IMG_0001.jpg` from different folders
+/// don't overwrite each other.
+struct Claims {
+    path: PathBuf,
+    sources: HashMap<String, String>,
+}
+
+impl Claims {
+    fn save(&self) {
+        match serde_json::to_string_pretty(&self.sources) {
+            Ok(json) => {
+                if let Err(e) = std::fs::write(&self.path, json) {
+                    warn!("[output-dir] Failed to save claims: {}", e);
+                }
+            }
+            Err(e) => warn!("[output-dir] Failed to serialize claims: {}", e),
+        }
+    }
+}
+
+/// Set from the config whenever `output_dir` changes. A leading `~` stands
+/// for the home folder.
+pub fn set_dir(dir: Option<&str>) {
+    let dir = dir.map(str::trim).filter(|d| !d.is_empty()).map(expand);
+    if let Ok(mut current) = DIR.write() {
+        *current = dir;
+    }
+}
+
+pub fn expand(dir: &str) -> PathBuf {
+    match dir.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => dirs::home_dir()
+            .map(|home| home.join(rest.trim_start_matches(['/', '\\'])))
+            .unwrap_or_else(|| PathBuf::from(dir)),
+        _ => PathBuf::from(dir),
+    }
+}
+
+/// Set from the config whenever the watched folders change.
+pub fn set_roots(folders: &[String]) {
+    if let Ok(mut roots) = ROOTS.write() {
+        *roots = folders.iter().map(PathBuf::from).collect();
+    }
+}
+
+/// Where `input` sits below the deepest watched folder containing it, so
+/// `Photos/2024/Trip/a.jpg` lands in `<output>/2024/Trip`. Files from
+/// outside every watched folder go straight into the output folder.
+pub fn source_subfolder(input: &Path) -> PathBuf {
+    let Some(parent) = input.parent() else {
+        return PathBuf::new();
+    };
+    ROOTS
+        .read()
+        .ok()
+        .and_then(|roots| {
+            roots
+                .iter()
+                .filter_map(|root| parent.strip_prefix(root).ok())
+                .min_by_key(|rel| rel.components().count())
+                .map(Path::to_path_buf)
+        })
+        .unwrap_or_default()
+}
+
+fn dir() -> Option<PathBuf> {
+    DIR.read().ok()?.clone()
+}
+
+pub fn load_claims(app: &tauri::AppHandle) {
+    let Ok(path) = app.path().app_config_dir().map(|dir| dir.join(CLAIMS_FILE)) else {
+        return;
+    };
+    let sources = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default();
+    if let Ok(mut claims) = CLAIMS.lock() {
+        *claims = Some(Claims { path, sources });
+    }
+}
+
+/// Whether `path` sits in the output folder or one of its subfolders.
+pub fn contains(path: &Path) -> bool {
+    dir().is_some_and(|dir| path.parent().is_some_and(|parent| parent.starts_with(&dir)))
+}
+
+/// Where `input`'s output goes when every output shares one folder: under
+/// the source's own name, numbered when another source got there first.
+/// `claim` reserves the name for `input`; plans leave it free.
+pub fn output_path(input: &Path, ext: &str, claim: bool) -> Option<PathBuf> {
+    let dir = dir()?;
+    let mut subfolder = source_subfolder(input);
+    if crate::compression::output_naming().date_subfolders {
+        subfolder.push(crate::metadata::date_subfolder(input)?);
+    }
+    let stem = input.file_stem()?.to_str()?;
+    let source = input.display().to_string();
+    let names = crate::config::FolderNames::read(&dir.join(&subfolder));
+    let mut claims = CLAIMS.lock().ok()?;
+    let taken = |name: &String| match claims.as_ref().and_then(|c| c.sources.get(name)) {
+        Some(owner) => *owner != source,
+        None => {
+            let path = dir.join(name);
+            path.exists() || names.collides(&path)
+        }
+    };
+    let name = (1..)
+        .map(|n| match n {
+            1 => format!("{}.{}", stem, ext),
+            n => format!("{}_{}.{}", stem, n, ext),
+        })
+        .map(|file| subfolder.join(file).display().to_string())
+        .find(|name| !taken(name))?;
+    let output = dir.join(&name);
+    if output.to_string_lossy().to_lowercase() == input.to_string_lossy().to_lowercase() {
+        return None;
+    }
+    if claim {
+        if let Some(claims) = claims.as_mut() {
+            if claims.sources.insert(name, source).is_none() {
+                claims.save();
+            }
+        }
+    }
+    Some(output)
+}
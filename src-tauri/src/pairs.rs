@@ -0,0 +1,129 @@
+use crate::config::FolderPair;
+use log::{info, warn};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use tauri::Manager;
+
+const MAPPING_FILE: &str = "folder_pairs.json";
+
+static PAIRS: RwLock<Vec<FolderPair>> = RwLock::new(Vec::new());
+static MAPPING: Mutex<Option<Mapping>> = Mutex::new(None);
+
+/// Which outputs in a destination came from which source file.
+struct Mapping {
+    path: PathBuf,
+    outputs: HashMap<String, Vec<String>>,
+}
+
+impl Mapping {
+    fn save(&self) {
+        match serde_json::to_string_pretty(&self.outputs) {
+            Ok(json) => {
+                if let Err(e) = std::fs::write(&self.path, json) {
+                    warn!("[pairs] Failed to save mapping: {}", e);
+                }
+            }
+            Err(e) => warn!("[pairs] Failed to serialize mapping: {}", e),
+        }
+    }
+}
+
+/// Set from the config whenever the pairs change.
+pub fn set_pairs(pairs: Vec<FolderPair>) {
+    if let Ok(mut current) = PAIRS.write() {
+        *current = pairs;
+    }
+}
+
+pub fn load_mapping(app: &tauri::AppHandle) {
+    let Ok(path) = app
+        .path()
+        .app_config_dir()
+        .map(|dir| dir.join(MAPPING_FILE))
+    else {
+        return;
+    };
+    let outputs = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default();
+    if let Ok(mut mapping) = MAPPING.lock() {
+        *mapping = Some(Mapping { path, outputs });
+    }
+}
+
+/// The pair whose source folder directly contains `path`.
+fn pair_for(path: &Path) -> Option<FolderPair> {
+    let parent = path.parent()?;
+    PAIRS
+        .read()
+        .ok()?
+        .iter()
+        .find(|p| Path::new(&p.source) == parent)
+        .cloned()
+}
+
+/// Where a paired source file's output goes: its destination folder, under
+/// the source's own name since the folder already sets it apart.
+pub fn output_path(input: &Path, ext: &str) -> Option<PathBuf> {
+    let pair = pair_for(input)?;
+    let stem = input.file_stem()?.to_str()?;
+    Some(Path::new(&pair.destination).join(format!("{}.{}", stem, ext)))
+}
+
+/// Whether `path` sits in a pair's destination folder.
+pub fn is_destination(path: &Path) -> bool {
+    let Some(parent) = path.parent() else {
+        return false;
+    };
+    PAIRS
+        .read()
+        .is_ok_and(|pairs| pairs.iter().any(|p| Path::new(&p.destination) == parent))
+}
+
+/// Remember that `output` was written for the paired source `input`.
+pub fn record(input: &Path, output: &Path) {
+    if pair_for(input).is_none() {
+        return;
+    }
+    if let Ok(mut mapping) = MAPPING.lock() {
+        if let Some(mapping) = mapping.as_mut() {
+            let outputs = mapping
+                .outputs
+                .entry(input.display().to_string())
+                .or_default();
+            let output = output.display().to_string();
+            if !outputs.contains(&output) {
+                outputs.push(output);
+            }
+            mapping.save();
+        }
+    }
+}
+
+/// A paired source file is gone: delete its outputs when the pair says so,
+/// and forget it either way.
+pub fn source_removed(path: &Path) {
+    let Some(pair) = pair_for(path) else {
+        return;
+    };
+    let Ok(mut mapping) = MAPPING.lock() else {
+        return;
+    };
+    let Some(mapping) = mapping.as_mut() else {
+        return;
+    };
+    let Some(outputs) = mapping.outputs.remove(&path.display().to_string()) else {
+        return;
+    };
+    if pair.delete_with_source {
+        for output in &outputs {
+            match std::fs::remove_file(output) {
+                Ok(()) => info!("[pairs] Deleted {} with its source", output),
+                Err(e) => warn!("[pairs] Could not delete {}: {}", output, e),
+            }
+        }
+    }
+    mapping.save();
+}
@@ -0,0 +1,502 @@
+use crate::compression::{CompressionError, CompressionFlags, ImageFormat, Result, Vips};
+use crate::config::PdfConfig;
+use log::{info, warn};
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Dictionary, Document, Object, ObjectId, Stream};
+use std::path::{Path, PathBuf};
+
+const PAGE_JPEG_QUALITY: u8 = 85;
+
+/// One page's JPEG and the pixel size the page is sized to.
+struct PageImage {
+    jpeg: Vec<u8>,
+    width: u32,
+    height: u32,
+    bands: u32,
+}
+
+impl PageImage {
+    fn from_jpeg(vips: &Vips, jpeg: Vec<u8>) -> Result<Self> {
+        let (width, height, bands) = {
+            let img = vips.load_image_from_buffer(&jpeg)?;
+            let (width, height) = vips.dimensions(&img);
+            (width, height, vips.bands(&img))
+        };
+        Ok(Self {
+            jpeg,
+            width,
+            height,
+            bands,
+        })
+    }
+}
+
+/// Build a PDF with one page per image, each page sized to its image.
+pub fn images_to_pdf(vips: &Vips, paths: &[PathBuf], output: &Path) -> Result<u64> {
+    let mut pages = Vec::with_capacity(paths.len());
+    for path in paths {
+        let img = vips.load_image(path)?;
+        let (width, height) = vips.dimensions(&img);
+        pages.push(PageImage {
+            jpeg: vips.save_to_buffer(&img, &format!(".jpg[Q={},strip]", PAGE_JPEG_QUALITY))?,
+            width,
+            height,
+            bands: vips.bands(&img),
+        });
+    }
+    write_pages(&pages, output)
+}
+
+/// Like `images_to_pdf`, but pages go through the JPEG encoder with the
+/// configured quality and options. JPEG inputs are embedded unchanged when
+/// re-encoding wouldn't make them smaller.
+pub fn bundle_images(
+    vips: &Vips,
+    paths: &[PathBuf],
+    quality: u8,
+    flags: &CompressionFlags,
+    output: &Path,
+) -> Result<u64> {
+    let scratch = crate::cache::temp_path("pdf-page.jpg");
+
+    let mut pages = Vec::with_capacity(paths.len());
+    for path in paths {
+        let img = vips.load_image(path)?;
+        let encoded = vips
+            .compress_loaded(&img, path, &scratch, quality, flags, ImageFormat::Jpeg)
+            .and_then(|_| std::fs::read(&scratch).map_err(CompressionError::from));
+        let _ = std::fs::remove_file(&scratch);
+        let mut page = PageImage::from_jpeg(vips, encoded?)?;
+
+        if ImageFormat::from_path(path) == Some(ImageFormat::Jpeg) {
+            // CMYK JPEGs would need a different color space, so those are re-encoded
+            match std::fs::read(path)
+                .map_err(CompressionError::from)
+                .and_then(|original| PageImage::from_jpeg(vips, original))
+            {
+                Ok(original)
+                    if matches!(original.bands, 1 | 3)
+                        && original.jpeg.len() <= page.jpeg.len() =>
+                {
+                    page = original;
+                }
+                Ok(_) => {}
+                Err(e) => warn!("[pdf] Re-encoding {}: {}", path.display(), e),
+            }
+        }
+        pages.push(page);
+    }
+    write_pages(&pages, output)
+}
+
+fn write_pages(pages: &[PageImage], output: &Path) -> Result<u64> {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let mut kids: Vec<Object> = Vec::with_capacity(pages.len());
+
+    for page in pages {
+        let (width, height) = (page.width as i64, page.height as i64);
+        let color_space = if page.bands <= 2 {
+            "DeviceGray"
+        } else {
+            "DeviceRGB"
+        };
+
+        let image_id = doc.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => width,
+                "Height" => height,
+                "ColorSpace" => color_space,
+                "BitsPerComponent" => 8,
+                "Filter" => "DCTDecode",
+            },
+            page.jpeg.clone(),
+        ));
+
+        // Scale the unit-square image to fill the page
+        let content = Content {
+            operations: vec![
+                Operation::new("q", vec![]),
+                Operation::new(
+                    "cm",
+                    vec![
+                        width.into(),
+                        0.into(),
+                        0.into(),
+                        height.into(),
+                        0.into(),
+                        0.into(),
+                    ],
+                ),
+                Operation::new("Do", vec![Object::Name(b"Im0".to_vec())]),
+                Operation::new("Q", vec![]),
+            ],
+        };
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode()?));
+
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+            "MediaBox" => vec![0.into(), 0.into(), width.into(), height.into()],
+            "Resources" => dictionary! {
+                "XObject" => dictionary! {
+                    "Im0" => image_id,
+                },
+            },
+        });
+        kids.push(page_id.into());
+    }
+
+    let count = kids.len() as i64;
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => kids,
+            "Count" => count,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.compress();
+    doc.save(output)?;
+
+    let size = std::fs::metadata(output)?.len();
+    info!(
+        "[pdf] Wrote {} pages to {} ({} bytes)",
+        count,
+        output.display(),
+        size
+    );
+    Ok(size)
+}
+
+/// Rasterize every page of a PDF into `out_dir` as PNG files.
+pub fn pdf_to_images(vips: &Vips, path: &Path, out_dir: &Path, dpi: u32) -> Result<Vec<PathBuf>> {
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| CompressionError::InvalidPath(path.display().to_string()))?;
+    let pages = vips.n_pages(&vips.load_image(path)?);
+    std::fs::create_dir_all(out_dir)?;
+
+    let mut outputs = Vec::with_capacity(pages as usize);
+    for page in 0..pages {
+        let img = vips.load_image_with_options(path, &format!("page={},dpi={}", page, dpi))?;
+        let output = out_dir.join(format!("{}_page{}.png", stem, page + 1));
+        vips.save(&img, &output)?;
+        outputs.push(output);
+    }
+
+    info!(
+        "[pdf] Rasterized {} pages of {} at {} dpi",
+        pages,
+        path.display(),
+        dpi
+    );
+    Ok(outputs)
+}
+
+pub fn is_pdf(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("pdf"))
+}
+
+/// Recompress the raster images embedded in a PDF as JPEG, downsampling any
+/// larger than `max_dimension`, and write the rebuilt document to `output`.
+/// Images are only replaced when the result is smaller.
+pub fn compress_pdf(vips: &Vips, input: &Path, output: &Path, settings: &PdfConfig) -> Result<u64> {
+    let mut doc = Document::load(input)?;
+
+    let candidates: Vec<(ObjectId, u32)> = doc
+        .objects
+        .iter()
+        .filter_map(|(id, object)| {
+            let stream = object.as_stream().ok()?;
+            image_bands(&doc, &stream.dict).map(|bands| (*id, bands))
+        })
+        .collect();
+
+    let mut replaced = 0;
+    for (id, bands) in &candidates {
+        let Ok(stream) = doc.get_object_mut(*id).and_then(Object::as_stream_mut) else {
+            continue;
+        };
+        match recompress_image(vips, stream, *bands, settings) {
+            Ok(Some((jpeg, width, height))) => {
+                stream.dict.set("Width", width as i64);
+                stream.dict.set("Height", height as i64);
+                stream.dict.set("BitsPerComponent", 8);
+                stream.dict.set("Filter", "DCTDecode");
+                stream.dict.remove(b"DecodeParms");
+                stream.set_content(jpeg);
+                replaced += 1;
+            }
+            Ok(None) => {}
+            Err(e) => warn!("[pdf] Keeping image {:?} in {}: {}", id, input.display(), e),
+        }
+    }
+
+    doc.compress();
+    doc.save(output)?;
+
+    let size = std::fs::metadata(output)?.len();
+    info!(
+        "[pdf] Recompressed {}/{} images in {} ({} bytes)",
+        replaced,
+        candidates.len(),
+        input.display(),
+        size
+    );
+    Ok(size)
+}
+
+/// An image pulled out of a PDF, and the object it came from.
+pub struct ExtractedImage {
+    pub object: ObjectId,
+    pub path: PathBuf,
+}
+
+fn image_candidates(doc: &Document) -> Vec<(ObjectId, u32)> {
+    doc.objects
+        .iter()
+        .filter_map(|(id, object)| {
+            let stream = object.as_stream().ok()?;
+            image_bands(doc, &stream.dict).map(|bands| (*id, bands))
+        })
+        .collect()
+}
+
+/// Write the gray and RGB images embedded in a PDF to `out_dir`. JPEG
+/// streams are copied as they are; Flate and LZW streams become PNGs.
+pub fn extract_images(vips: &Vips, input: &Path, out_dir: &Path) -> Result<Vec<ExtractedImage>> {
+    let doc = Document::load(input)?;
+    std::fs::create_dir_all(out_dir)?;
+    let stem = input
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "document".to_string());
+
+    let mut extracted = Vec::new();
+    for (id, bands) in image_candidates(&doc) {
+        let Ok(stream) = doc.get_object(id).and_then(Object::as_stream) else {
+            continue;
+        };
+        let filters = stream.filters().unwrap_or_default();
+        let n = extracted.len() + 1;
+        let path = if filters == [b"DCTDecode".as_slice()] {
+            let path = out_dir.join(format!("{stem}_image{n}.jpg"));
+            std::fs::write(&path, &stream.content)?;
+            path
+        } else if filters
+            .iter()
+            .all(|f| matches!(*f, b"FlateDecode" | b"LZWDecode"))
+        {
+            let width = stream.dict.get(b"Width").and_then(Object::as_i64)? as u32;
+            let height = stream.dict.get(b"Height").and_then(Object::as_i64)? as u32;
+            let pixels = stream.get_plain_content()?;
+            let expected = width as usize * height as usize * bands as usize;
+            if pixels.len() < expected {
+                warn!("[pdf] Image {:?} in {} is truncated", id, input.display());
+                continue;
+            }
+            let img = vips.load_image_from_pixels(&pixels[..expected], width, height, bands)?;
+            let path = out_dir.join(format!("{stem}_image{n}.png"));
+            vips.save_png_lossless(&img, &path)?;
+            path
+        } else {
+            continue;
+        };
+        extracted.push(ExtractedImage { object: id, path });
+    }
+
+    info!(
+        "[pdf] Extracted {} images from {} into {}",
+        extracted.len(),
+        input.display(),
+        out_dir.display()
+    );
+    Ok(extracted)
+}
+
+/// Rebuild a PDF with the images at `replacements` swapped in for the
+/// objects they were extracted from. Each is only used when it is smaller
+/// than the stream it replaces and has the same number of bands. Returns
+/// how many were swapped and the size of `output`.
+pub fn replace_images(
+    vips: &Vips,
+    input: &Path,
+    output: &Path,
+    replacements: &[(ObjectId, PathBuf)],
+) -> Result<(usize, u64)> {
+    let mut doc = Document::load(input)?;
+    let candidates: std::collections::HashMap<ObjectId, u32> =
+        image_candidates(&doc).into_iter().collect();
+
+    let mut replaced = 0;
+    for (id, path) in replacements {
+        let Some(&bands) = candidates.get(id) else {
+            continue;
+        };
+        let data = std::fs::read(path)?;
+        let is_jpeg = ImageFormat::from_path(path) == Some(ImageFormat::Jpeg);
+        let (content, width, height, filter) = {
+            let img = if is_jpeg {
+                vips.load_image_from_buffer(&data)?
+            } else {
+                vips.load_image(path)?
+            };
+            if vips.bands(&img) != bands && (is_jpeg || vips.bands(&img) < bands) {
+                continue;
+            }
+            let (width, height) = vips.dimensions(&img);
+            if is_jpeg {
+                (data.clone(), width, height, "DCTDecode")
+            } else {
+                // Other formats go back in as raw pixels under Flate
+                let (_, _, rgba) = vips.extract_rgba(&img)?;
+                let pixels: Vec<u8> = rgba
+                    .chunks_exact(4)
+                    .flat_map(|px| px[..bands as usize].iter().copied())
+                    .collect();
+                let mut stream = Stream::new(dictionary! {}, pixels);
+                stream.compress()?;
+                // Left unfiltered when deflating didn't help
+                if stream.dict.get(b"Filter").is_err() {
+                    continue;
+                }
+                (stream.content, width, height, "FlateDecode")
+            }
+        };
+
+        let Ok(stream) = doc.get_object_mut(*id).and_then(Object::as_stream_mut) else {
+            continue;
+        };
+        if content.len() >= stream.content.len() {
+            continue;
+        }
+        stream.dict.set("Width", width as i64);
+        stream.dict.set("Height", height as i64);
+        stream.dict.set("BitsPerComponent", 8);
+        stream.dict.set("Filter", filter);
+        stream.dict.remove(b"DecodeParms");
+        stream.set_content(content);
+        replaced += 1;
+    }
+
+    doc.compress();
+    doc.save(output)?;
+    let size = std::fs::metadata(output)?.len();
+    info!(
+        "[pdf] Swapped {}/{} images into {} ({} bytes)",
+        replaced,
+        replacements.len(),
+        output.display(),
+        size
+    );
+    Ok((replaced, size))
+}
+
+/// Band count of an 8-bit gray or RGB image XObject we know how to re-encode.
+/// Masks, CMYK, indexed and other color spaces are left alone.
+fn image_bands(doc: &Document, dict: &Dictionary) -> Option<u32> {
+    if dict.get(b"Subtype").and_then(Object::as_name).ok()? != b"Image" {
+        return None;
+    }
+    if dict
+        .get(b"ImageMask")
+        .and_then(Object::as_bool)
+        .unwrap_or(false)
+    {
+        return None;
+    }
+    if dict
+        .get(b"BitsPerComponent")
+        .and_then(Object::as_i64)
+        .ok()?
+        != 8
+    {
+        return None;
+    }
+    match dict.get_deref(b"ColorSpace", doc).ok()? {
+        Object::Name(name) => match name.as_slice() {
+            b"DeviceRGB" => Some(3),
+            b"DeviceGray" => Some(1),
+            _ => None,
+        },
+        Object::Array(items) if items.first()?.as_name().ok()? == b"ICCBased" => {
+            let (_, profile) = doc.dereference(items.get(1)?).ok()?;
+            match profile
+                .as_stream()
+                .ok()?
+                .dict
+                .get(b"N")
+                .ok()?
+                .as_i64()
+                .ok()?
+            {
+                1 => Some(1),
+                3 => Some(3),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Re-encode one image stream. Returns the JPEG and its dimensions when it
+/// is smaller than the current stream.
+fn recompress_image(
+    vips: &Vips,
+    stream: &Stream,
+    bands: u32,
+    settings: &PdfConfig,
+) -> Result<Option<(Vec<u8>, u32, u32)>> {
+    let filters = stream.filters().unwrap_or_default();
+    let pixels;
+    let img = if filters == [b"DCTDecode".as_slice()] {
+        vips.load_image_from_buffer(&stream.content)?
+    } else if filters
+        .iter()
+        .all(|f| matches!(*f, b"FlateDecode" | b"LZWDecode"))
+    {
+        let width = stream.dict.get(b"Width").and_then(Object::as_i64)? as u32;
+        let height = stream.dict.get(b"Height").and_then(Object::as_i64)? as u32;
+        pixels = stream.get_plain_content()?;
+        let expected = width as usize * height as usize * bands as usize;
+        if pixels.len() < expected {
+            return Ok(None);
+        }
+        vips.load_image_from_pixels(&pixels[..expected], width, height, bands)?
+    } else {
+        return Ok(None);
+    };
+
+    let (width, height) = vips.dimensions(&img);
+    let long_side = width.max(height);
+    let resized;
+    let img = if settings.max_dimension > 0 && long_side > settings.max_dimension {
+        let scale = settings.max_dimension as f64 / long_side as f64;
+        resized = vips.resize(&img, scale, scale)?;
+        &resized
+    } else {
+        &img
+    };
+
+    let jpeg = vips.save_to_buffer(
+        img,
+        &format!(".jpg[Q={},strip]", settings.quality.clamp(1, 100)),
+    )?;
+    if jpeg.len() >= stream.content.len() {
+        return Ok(None);
+    }
+    let (width, height) = vips.dimensions(img);
+    Ok(Some((jpeg, width, height)))
+}
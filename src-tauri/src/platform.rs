@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub fn get_target_double() -> &'static str {
     #[cfg(all(target_os = "windows", target_arch = "x86_64"))]
@@ -78,3 +78,726 @@ pub fn load_icon() -> tauri::image::Image<'static> {
             .expect("failed to load icon png")
     }
 }
+
+/// Name of a process that currently has `path` open, i.e. the app still writing it.
+/// Best effort: returns `None` once the writer has closed the file.
+pub fn producing_app(path: &Path) -> Option<String> {
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        use windows_sys::Win32::System::RestartManager::{
+            RmEndSession, RmGetList, RmRegisterResources, RmStartSession, CCH_RM_SESSION_KEY,
+            RM_PROCESS_INFO,
+        };
+
+        let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+        let files = [wide.as_ptr()];
+        let mut session = 0u32;
+        let mut key = [0u16; CCH_RM_SESSION_KEY as usize + 1];
+        unsafe {
+            if RmStartSession(&mut session, 0, key.as_mut_ptr()) != 0 {
+                return None;
+            }
+            let mut name = None;
+            let registered = RmRegisterResources(
+                session,
+                1,
+                files.as_ptr(),
+                0,
+                std::ptr::null(),
+                0,
+                std::ptr::null(),
+            );
+            if registered == 0 {
+                let mut needed = 0u32;
+                let mut count = 4u32;
+                let mut reasons = 0u32;
+                let mut infos: [RM_PROCESS_INFO; 4] = std::mem::zeroed();
+                let listed = RmGetList(
+                    session,
+                    &mut needed,
+                    &mut count,
+                    infos.as_mut_ptr(),
+                    &mut reasons,
+                );
+                if listed == 0 && count > 0 {
+                    let raw = &infos[0].strAppName;
+                    let len = raw.iter().position(|&c| c == 0).unwrap_or(raw.len());
+                    name = Some(String::from_utf16_lossy(&raw[..len]));
+                }
+            }
+            RmEndSession(session);
+            name
+        }
+    }
+    #[cfg(target_os = "macos")]
+    {
+        // `lsof -Fc` prints one `c<command>` line per process holding the file
+        let output = std::process::Command::new("lsof")
+            .arg("-Fc")
+            .arg("--")
+            .arg(path)
+            .output()
+            .ok()?;
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .find_map(|l| l.strip_prefix('c').map(str::to_string))
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let target = std::fs::canonicalize(path).ok()?;
+        std::fs::read_dir("/proc")
+            .ok()?
+            .flatten()
+            .find_map(|proc_entry| {
+                let pid_dir = proc_entry.path();
+                let holds_file = std::fs::read_dir(pid_dir.join("fd"))
+                    .ok()?
+                    .flatten()
+                    .any(|fd| std::fs::read_link(fd.path()).is_ok_and(|p| p == target));
+                if !holds_file {
+                    return None;
+                }
+                std::fs::read_to_string(pid_dir.join("comm"))
+                    .ok()
+                    .map(|c| c.trim().to_string())
+            })
+    }
+}
+
+fn command_stdout(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+pub fn hostname() -> Option<String> {
+    #[cfg(target_os = "windows")]
+    {
+        std::env::var("COMPUTERNAME").ok()
+    }
+    #[cfg(target_os = "macos")]
+    {
+        command_stdout("scutil", &["--get", "LocalHostName"]).map(|s| s.trim().to_string())
+    }
+    #[cfg(target_os = "linux")]
+    {
+        std::fs::read_to_string("/proc/sys/kernel/hostname")
+            .ok()
+            .map(|s| s.trim().to_string())
+    }
+}
+
+/// Names of network interfaces that are currently up, e.g. `wg0` or `utun3`
+/// while a VPN is connected.
+pub fn active_interfaces() -> Vec<String> {
+    #[cfg(target_os = "windows")]
+    {
+        // Admin State  State  Type  Interface Name — the name may contain spaces
+        command_stdout("netsh", &["interface", "show", "interface"])
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| {
+                let cols: Vec<&str> = line.split_whitespace().collect();
+                (cols.len() >= 4 && cols[1] == "Connected").then(|| cols[3..].join(" "))
+            })
+            .collect()
+    }
+    #[cfg(target_os = "macos")]
+    {
+        command_stdout("ifconfig", &["-l", "-u"])
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect()
+    }
+    #[cfg(target_os = "linux")]
+    {
+        // Tunnels report "unknown" rather than "up", so only skip interfaces that are down
+        std::fs::read_dir("/sys/class/net")
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter(|e| {
+                        std::fs::read_to_string(e.path().join("operstate"))
+                            .is_ok_and(|state| state.trim() != "down")
+                    })
+                    .map(|e| e.file_name().to_string_lossy().into_owned())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// SSID of the Wi-Fi network this machine is joined to.
+pub fn wifi_ssid() -> Option<String> {
+    #[cfg(target_os = "windows")]
+    {
+        command_stdout("netsh", &["wlan", "show", "interfaces"])?
+            .lines()
+            .find_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                (key.trim() == "SSID").then(|| value.trim().to_string())
+            })
+    }
+    #[cfg(target_os = "macos")]
+    {
+        command_stdout("networksetup", &["-getairportnetwork", "en0"])?
+            .split_once("Network: ")
+            .map(|(_, ssid)| ssid.trim().to_string())
+    }
+    #[cfg(target_os = "linux")]
+    {
+        command_stdout("iwgetid", &["-r"])
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    }
+}
+
+/// The user's language as a locale tag, e.g. `en-US` or `de_DE.UTF-8`.
+pub fn system_language() -> Option<String> {
+    #[cfg(target_os = "windows")]
+    {
+        use windows_sys::Win32::Globalization::GetUserDefaultLocaleName;
+
+        let mut name = [0u16; 85];
+        let len = unsafe { GetUserDefaultLocaleName(name.as_mut_ptr(), name.len() as i32) };
+        // The returned length includes the terminating null
+        (len > 1).then(|| String::from_utf16_lossy(&name[..len as usize - 1]))
+    }
+    #[cfg(target_os = "macos")]
+    {
+        command_stdout("defaults", &["read", "-g", "AppleLocale"])
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    }
+    #[cfg(target_os = "linux")]
+    {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty() && value != "C" && value != "POSIX")
+    }
+}
+
+/// When the OS last recorded `path` as opened by the user, from its recent
+/// documents list.
+pub fn last_opened(path: &Path) -> Option<std::time::SystemTime> {
+    #[cfg(target_os = "windows")]
+    {
+        // Recent items are shortcuts named after the file, without its folder
+        let mut name = path.file_name()?.to_os_string();
+        name.push(".lnk");
+        let recent = dirs::data_dir()?
+            .join("Microsoft/Windows/Recent")
+            .join(name);
+        std::fs::metadata(recent).ok()?.modified().ok()
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let path = path.to_str()?;
+        let date = command_stdout("mdls", &["-raw", "-name", "kMDItemLastUsedDate", path])?;
+        // "(null)" when the file was never opened
+        chrono::DateTime::parse_from_str(date.trim(), "%Y-%m-%d %H:%M:%S %z")
+            .ok()
+            .map(std::time::SystemTime::from)
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let xbel = std::fs::read_to_string(dirs::data_dir()?.join("recently-used.xbel")).ok()?;
+        let target = path.to_str()?;
+        xbel.lines()
+            .filter(|line| line.trim_start().starts_with("<bookmark "))
+            .filter(|line| {
+                xml_attribute(line, "href")
+                    .and_then(|href| href.strip_prefix("file://").map(percent_decode))
+                    .is_some_and(|p| p == target)
+            })
+            .flat_map(|line| {
+                [
+                    xml_attribute(line, "modified"),
+                    xml_attribute(line, "visited"),
+                ]
+            })
+            .flatten()
+            .filter_map(|date| chrono::DateTime::parse_from_rfc3339(&date).ok())
+            .map(std::time::SystemTime::from)
+            .max()
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn xml_attribute(tag: &str, name: &str) -> Option<String> {
+    let start = tag.find(&format!(" {name}=\""))? + name.len() + 3;
+    let len = tag[start..].find('"')?;
+    Some(
+        tag[start..start + len]
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&"),
+    )
+}
+
+#[cfg(target_os = "linux")]
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Folder new screenshots are saved to.
+pub fn screenshot_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "macos")]
+    {
+        command_stdout("defaults", &["read", "com.apple.screencapture", "location"])
+            .map(|s| PathBuf::from(s.trim()))
+            .filter(|p| p.is_absolute())
+            .or_else(dirs::desktop_dir)
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        dirs::picture_dir().map(|d| d.join("Screenshots"))
+    }
+}
+
+/// Have the OS confirm the user is present: Windows Hello (PIN, face or
+/// fingerprint), an administrator prompt on macOS, polkit on Linux.
+/// `reason` is shown in the prompt where the OS allows it.
+pub fn authenticate(reason: &str) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    {
+        use windows::core::HSTRING;
+        use windows::Security::Credentials::UI::{
+            UserConsentVerificationResult, UserConsentVerifier,
+        };
+
+        let result = UserConsentVerifier::RequestVerificationAsync(&HSTRING::from(reason))
+            .and_then(|op| op.get())
+            .map_err(|e| e.to_string())?;
+        if result == UserConsentVerificationResult::Verified {
+            Ok(())
+        } else {
+            Err(format!("verification ended with {:?}", result))
+        }
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let _ = reason;
+        let status = std::process::Command::new("security")
+            .args(["authorize", "-u", "system.privilege.admin"])
+            .status()
+            .map_err(|e| e.to_string())?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err("authorization was denied".into())
+        }
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let _ = reason;
+        let pid = std::process::id().to_string();
+        let status = std::process::Command::new("pkcheck")
+            .args([
+                "--action-id",
+                "org.freedesktop.policykit.exec",
+                "--process",
+                &pid,
+                "--allow-user-interaction",
+            ])
+            .status()
+            .map_err(|e| format!("polkit is not available: {}", e))?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err("authorization was denied".into())
+        }
+    }
+}
+
+/// Root folders of mounted external volumes such as SD cards and USB drives.
+pub fn mounted_volumes() -> Vec<PathBuf> {
+    #[cfg(target_os = "windows")]
+    {
+        // A: and B: are floppy drives that can stall when probed; C: is the system
+        ('D'..='Z')
+            .map(|letter| PathBuf::from(format!("{letter}:\\")))
+            .filter(|p| p.is_dir())
+            .collect()
+    }
+    #[cfg(target_os = "macos")]
+    {
+        // The boot volume shows up as a symlink to /
+        subdirs(Path::new("/Volumes"))
+            .into_iter()
+            .filter(|p| !p.is_symlink())
+            .collect()
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let user = std::env::var("USER").unwrap_or_default();
+        let mut found = subdirs(&Path::new("/media").join(&user));
+        found.extend(subdirs(&Path::new("/run/media").join(&user)));
+        found
+    }
+}
+
+/// A mounted volume, identified well enough to recognise the same drive
+/// when it is plugged in again.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct VolumeInfo {
+    pub root: String,
+    pub label: String,
+    /// Filesystem serial number or UUID.
+    pub serial: String,
+    /// SD cards, USB sticks and other external drives.
+    pub removable: bool,
+}
+
+/// The volume `path` lives on.
+pub fn volume_of(path: &Path) -> Option<VolumeInfo> {
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        use windows_sys::Win32::Storage::FileSystem::{
+            GetDriveTypeW, GetVolumeInformationW, GetVolumePathNameW,
+        };
+        use windows_sys::Win32::System::WindowsProgramming::DRIVE_REMOVABLE;
+
+        let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+        let mut root = [0u16; 261];
+        let mut label = [0u16; 261];
+        let mut serial = 0u32;
+        let removable = unsafe {
+            if GetVolumePathNameW(wide.as_ptr(), root.as_mut_ptr(), root.len() as u32) == 0 {
+                return None;
+            }
+            if GetVolumeInformationW(
+                root.as_ptr(),
+                label.as_mut_ptr(),
+                label.len() as u32,
+                &mut serial,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                0,
+            ) == 0
+            {
+                return None;
+            }
+            GetDriveTypeW(root.as_ptr()) == DRIVE_REMOVABLE
+        };
+        let to_string = |buf: &[u16]| {
+            let len = buf.iter().position(|&c| c == 0).unwrap_or(buf.len());
+            String::from_utf16_lossy(&buf[..len])
+        };
+        Some(VolumeInfo {
+            root: to_string(&root),
+            label: to_string(&label),
+            serial: format!("{:04X}-{:04X}", serial >> 16, serial & 0xFFFF),
+            removable,
+        })
+    }
+    #[cfg(target_os = "macos")]
+    {
+        // Everything but the boot volume is mounted under /Volumes
+        let path = path.canonicalize().ok()?;
+        let name = path.strip_prefix("/Volumes").ok()?.components().next()?;
+        let root = Path::new("/Volumes").join(name);
+        if root.is_symlink() {
+            return None;
+        }
+        let info = command_stdout("diskutil", &["info", root.to_str()?])?;
+        let field = |key: &str| {
+            info.lines()
+                .find_map(|line| line.trim().strip_prefix(key))
+                .map(|value| value.trim().to_string())
+        };
+        Some(VolumeInfo {
+            label: field("Volume Name:").unwrap_or_default(),
+            serial: field("Volume UUID:")
+                .or_else(|| field("Disk / Partition UUID:"))
+                .unwrap_or_default(),
+            removable: field("Removable Media:").as_deref() == Some("Removable")
+                || field("Device Location:").as_deref() == Some("External")
+                || field("Protocol:").as_deref() == Some("USB"),
+            root: root.display().to_string(),
+        })
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let path = path.canonicalize().ok()?;
+        let mounts = std::fs::read_to_string("/proc/self/mounts").ok()?;
+        let (device, root) = mounts
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let device = fields.next()?;
+                let mount = fields.next()?.replace("\\040", " ");
+                Some((device, PathBuf::from(mount)))
+            })
+            .filter(|(_, mount)| path.starts_with(mount))
+            .max_by_key(|(_, mount)| mount.as_os_str().len())?;
+        // tmpfs, network shares and the like have no block device
+        let device = Path::new(device).canonicalize().ok()?;
+        if !device.starts_with("/dev") {
+            return None;
+        }
+
+        let block = Path::new("/sys/class/block")
+            .join(device.file_name()?)
+            .canonicalize()
+            .ok()?;
+        let disk = if block.join("partition").exists() {
+            block.parent()?.to_path_buf()
+        } else {
+            block
+        };
+        let removable = std::fs::read_to_string(disk.join("removable"))
+            .is_ok_and(|s| s.trim() == "1")
+            || disk.to_string_lossy().contains("/usb")
+            || root.starts_with("/media")
+            || root.starts_with("/run/media");
+
+        // udev names a symlink after the label and the UUID of each filesystem
+        let link_name = |dir: &str| {
+            std::fs::read_dir(dir).ok()?.flatten().find_map(|entry| {
+                (entry.path().canonicalize().ok()? == device)
+                    .then(|| entry.file_name().to_string_lossy().replace("\\x20", " "))
+            })
+        };
+        Some(VolumeInfo {
+            label: link_name("/dev/disk/by-label")
+                .or_else(|| root.file_name().map(|n| n.to_string_lossy().into_owned()))
+                .unwrap_or_default(),
+            serial: link_name("/dev/disk/by-uuid").unwrap_or_default(),
+            removable,
+            root: root.display().to_string(),
+        })
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn subdirs(dir: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.is_dir())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 8-bit RGBA pixels decoded by an OS image codec.
+pub struct DecodedImage {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+/// Decode an image with the operating system's codecs (WIC on Windows,
+/// ImageIO on macOS). Used for formats the bundled libvips can't read.
+pub fn decode_with_os(path: &Path) -> Option<DecodedImage> {
+    #[cfg(target_os = "windows")]
+    {
+        use windows::core::HSTRING;
+        use windows::Win32::Foundation::GENERIC_READ;
+        use windows::Win32::Graphics::Imaging::{
+            CLSID_WICImagingFactory, GUID_WICPixelFormat32bppRGBA, IWICImagingFactory, IWICPalette,
+            WICBitmapDitherTypeNone, WICBitmapPaletteTypeCustom, WICDecodeMetadataCacheOnDemand,
+        };
+        use windows::Win32::System::Com::{
+            CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_MULTITHREADED,
+        };
+
+        unsafe {
+            // Fails harmlessly if this thread already joined an apartment
+            let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+            let factory: IWICImagingFactory =
+                CoCreateInstance(&CLSID_WICImagingFactory, None, CLSCTX_INPROC_SERVER).ok()?;
+            let decoder = factory
+                .CreateDecoderFromFilename(
+                    &HSTRING::from(path.as_os_str()),
+                    None,
+                    GENERIC_READ,
+                    WICDecodeMetadataCacheOnDemand,
+                )
+                .ok()?;
+            let frame = decoder.GetFrame(0).ok()?;
+            let converter = factory.CreateFormatConverter().ok()?;
+            converter
+                .Initialize(
+                    &frame,
+                    &GUID_WICPixelFormat32bppRGBA,
+                    WICBitmapDitherTypeNone,
+                    None::<&IWICPalette>,
+                    0.0,
+                    WICBitmapPaletteTypeCustom,
+                )
+                .ok()?;
+
+            let (mut width, mut height) = (0u32, 0u32);
+            converter.GetSize(&mut width, &mut height).ok()?;
+            let mut rgba = vec![0u8; width as usize * height as usize * 4];
+            converter
+                .CopyPixels(std::ptr::null(), width * 4, &mut rgba)
+                .ok()?;
+            Some(DecodedImage {
+                width,
+                height,
+                rgba,
+            })
+        }
+    }
+    #[cfg(target_os = "macos")]
+    {
+        imageio::decode(path)
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let _ = path;
+        None
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imageio {
+    use super::DecodedImage;
+    use std::ffi::c_void;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    type CFTypeRef = *const c_void;
+
+    #[repr(C)]
+    struct CGRect {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    }
+
+    const K_CG_IMAGE_ALPHA_PREMULTIPLIED_LAST: u32 = 1;
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFURLCreateFromFileSystemRepresentation(
+            allocator: CFTypeRef,
+            buffer: *const u8,
+            length: isize,
+            is_directory: u8,
+        ) -> CFTypeRef;
+        fn CFRelease(cf: CFTypeRef);
+    }
+
+    #[link(name = "ImageIO", kind = "framework")]
+    extern "C" {
+        fn CGImageSourceCreateWithURL(url: CFTypeRef, options: CFTypeRef) -> CFTypeRef;
+        fn CGImageSourceCreateImageAtIndex(
+            source: CFTypeRef,
+            index: usize,
+            options: CFTypeRef,
+        ) -> CFTypeRef;
+    }
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGImageGetWidth(image: CFTypeRef) -> usize;
+        fn CGImageGetHeight(image: CFTypeRef) -> usize;
+        fn CGColorSpaceCreateDeviceRGB() -> CFTypeRef;
+        fn CGBitmapContextCreate(
+            data: *mut c_void,
+            width: usize,
+            height: usize,
+            bits_per_component: usize,
+            bytes_per_row: usize,
+            space: CFTypeRef,
+            bitmap_info: u32,
+        ) -> CFTypeRef;
+        fn CGContextDrawImage(context: CFTypeRef, rect: CGRect, image: CFTypeRef);
+    }
+
+    pub fn decode(path: &Path) -> Option<DecodedImage> {
+        let bytes = path.as_os_str().as_bytes();
+        unsafe {
+            let url = CFURLCreateFromFileSystemRepresentation(
+                std::ptr::null(),
+                bytes.as_ptr(),
+                bytes.len() as isize,
+                0,
+            );
+            if url.is_null() {
+                return None;
+            }
+            let source = CGImageSourceCreateWithURL(url, std::ptr::null());
+            CFRelease(url);
+            if source.is_null() {
+                return None;
+            }
+            let image = CGImageSourceCreateImageAtIndex(source, 0, std::ptr::null());
+            CFRelease(source);
+            if image.is_null() {
+                return None;
+            }
+
+            let (width, height) = (CGImageGetWidth(image), CGImageGetHeight(image));
+            let mut rgba = vec![0u8; width * height * 4];
+            let space = CGColorSpaceCreateDeviceRGB();
+            let context = CGBitmapContextCreate(
+                rgba.as_mut_ptr() as *mut c_void,
+                width,
+                height,
+                8,
+                width * 4,
+                space,
+                K_CG_IMAGE_ALPHA_PREMULTIPLIED_LAST,
+            );
+            CFRelease(space);
+            if context.is_null() {
+                CFRelease(image);
+                return None;
+            }
+            let rect = CGRect {
+                x: 0.0,
+                y: 0.0,
+                width: width as f64,
+                height: height as f64,
+            };
+            CGContextDrawImage(context, rect, image);
+            CFRelease(context);
+            CFRelease(image);
+
+            Some(DecodedImage {
+                width: width as u32,
+                height: height as u32,
+                rgba,
+            })
+        }
+    }
+}
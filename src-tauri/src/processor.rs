@@ -1,8 +1,11 @@
 use crate::compression::{
-    compressed_output_path, CompressionFlags, CompressionRecord, ImageFormat, Vips,
+    compressed_output_path, CompressionFlags, CompressionRecord, EncodeCandidate, EncodeEngine,
+    EncodeSettings, ImageFormat, TrimmedSize, Vips, VipsImage,
 };
-use log::{error, info};
-use std::path::Path;
+use crate::config::{CollisionPolicy, HighDepthPolicy, NotificationEvent, Secret};
+use crate::skips::SkipReason;
+use log::{error, info, warn};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::{Emitter, Manager};
@@ -30,30 +33,236 @@ pub struct CompressionFailed {
     pub error: String,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, serde::Serialize)]
+pub struct CompressionSkipped {
+    pub initial_path: String,
+    pub timestamp: u64,
+    pub reason: String,
+}
+
+/// Compression went ahead, but the output lost something worth knowing.
+#[derive(Clone, serde::Serialize)]
+pub struct CompressionWarning {
+    pub initial_path: String,
+    pub timestamp: u64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum InputMode {
     Manual,
     Watched,
 }
 
-pub fn process_file(
+/// Process with libvips when it loaded, otherwise with the pure-Rust fallback.
+pub fn process_any(
     app: &tauri::AppHandle,
-    vips: &Arc<Vips>,
+    vips: Option<&Arc<Vips>>,
     path: &Path,
+    mode: InputMode,
 ) -> Result<CompressionRecord, String> {
-    process_file_with_mode(app, vips, path, InputMode::Watched)
+    let _task = crate::tasks::track(app, path, mode);
+    if mode == InputMode::Watched {
+        if let Some(volume) = crate::platform::volume_of(path).filter(|v| v.removable) {
+            let enabled = app
+                .state::<Mutex<crate::config::ConfigManager>>()
+                .lock()
+                .map(|c| c.config.volume_enabled(&volume.label, &volume.serial))
+                .unwrap_or(false);
+            if !enabled {
+                return Err(skip(
+                    path,
+                    SkipReason::RemovableVolume,
+                    format!(
+                        "Auto-compression is off for removable volume \"{}\", skipping {}",
+                        volume.label,
+                        path.display()
+                    ),
+                ));
+            }
+        }
+    }
+    if mode == InputMode::Watched {
+        handle_duplicate_download(app, path)?;
+        handle_recently_opened(app, vips, path)?;
+    }
+    if crate::video::is_video(path) {
+        return process_video(app, path, mode);
+    }
+    if crate::pdf::is_pdf(path) {
+        let vips = vips.ok_or_else(|| "PDF compression needs libvips".to_string())?;
+        return process_pdf(app, vips, path, mode);
+    }
+    if crate::svg::is_svg(path) {
+        return process_svg(app, path, mode);
+    }
+    if ImageFormat::from_path(path) == Some(ImageFormat::Raw) {
+        let vips = vips.ok_or_else(|| "RAW development needs libvips".to_string())?;
+        let opted_in = app
+            .state::<Mutex<crate::config::ConfigManager>>()
+            .lock()
+            .map(|c| c.config.folder_settings_for(path).decode_raw)
+            .unwrap_or(false);
+        if mode == InputMode::Watched && !opted_in {
+            return Err(skip(
+                path,
+                SkipReason::Disabled,
+                format!(
+                    "RAW development is off for this folder, skipping {}",
+                    path.display()
+                ),
+            ));
+        }
+        return process_file_with_mode(app, vips, path, mode);
+    }
+    match vips {
+        Some(vips) => process_file_with_mode(app, vips, path, mode),
+        None => process_file_fallback(app, path, mode),
+    }
 }
 
-pub fn process_file_with_mode(
+/// The earlier download `path` is a browser's numbered copy of, when Hat has a
+/// record of it and the content is identical.
+pub fn find_duplicate_download(
+    app: &tauri::AppHandle,
+    path: &Path,
+) -> Option<crate::duplicates::DuplicateDownload> {
+    let original = crate::duplicates::numbered_download_original(path)?;
+    let original = original.display().to_string();
+    let record = app
+        .state::<Mutex<crate::log::CompressionLog>>()
+        .lock()
+        .ok()?
+        .records
+        .iter()
+        .rev()
+        .find(|r| !r.analysis_only && r.initial_path == original)
+        .cloned()?;
+    crate::duplicates::same_content_as(path, &record).then(|| {
+        crate::duplicates::DuplicateDownload {
+            path: path.display().to_string(),
+            original_path: original,
+            record_id: record.id,
+        }
+    })
+}
+
+/// Apply the duplicate download policy. Errors when the file should not be
+/// compressed, either because it was deleted or because the user is asked.
+fn handle_duplicate_download(app: &tauri::AppHandle, path: &Path) -> Result<(), String> {
+    let policy = app
+        .state::<Mutex<crate::config::ConfigManager>>()
+        .lock()
+        .map(|c| c.config.duplicate_downloads)
+        .unwrap_or_default();
+    if policy == crate::config::DuplicateDownloadPolicy::Compress {
+        return Ok(());
+    }
+    let Some(duplicate) = find_duplicate_download(app, path) else {
+        return Ok(());
+    };
+
+    if policy == crate::config::DuplicateDownloadPolicy::Delete {
+        std::fs::remove_file(path).map_err(|e| e.to_string())?;
+        info!(
+            "[processor] Deleted {}, a duplicate of {}",
+            duplicate.path, duplicate.original_path
+        );
+        return Err(skip(
+            path,
+            SkipReason::DuplicateDownload,
+            format!(
+                "{} was a duplicate download and was deleted",
+                duplicate.path
+            ),
+        ));
+    }
+    info!(
+        "[processor] {} duplicates {}, asking before deleting",
+        duplicate.path, duplicate.original_path
+    );
+    let _ = app.emit("duplicate-download", &duplicate);
+    Err(skip(
+        path,
+        SkipReason::DuplicateDownload,
+        format!("{} is a duplicate download, skipping", duplicate.path),
+    ))
+}
+
+/// Hold back files the user opened within the configured window, either
+/// skipping them or trying again once the window has passed.
+fn handle_recently_opened(
+    app: &tauri::AppHandle,
+    vips: Option<&Arc<Vips>>,
+    path: &Path,
+) -> Result<(), String> {
+    let recent = app
+        .state::<Mutex<crate::config::ConfigManager>>()
+        .lock()
+        .map(|c| c.config.recent_files.clone())
+        .unwrap_or_default();
+    if recent.policy == crate::config::RecentFilePolicy::Off {
+        return Ok(());
+    }
+    let Some(opened) = crate::platform::last_opened(path) else {
+        return Ok(());
+    };
+    let window = std::time::Duration::from_secs(recent.window_minutes * 60);
+    let Some(remaining) = opened
+        .elapsed()
+        .ok()
+        .and_then(|elapsed| window.checked_sub(elapsed))
+    else {
+        return Ok(());
+    };
+
+    if recent.policy == crate::config::RecentFilePolicy::Defer {
+        info!(
+            "[processor] {} was opened recently, retrying in {}s",
+            path.display(),
+            remaining.as_secs()
+        );
+        let app = app.clone();
+        let vips = vips.cloned();
+        let path = path.to_path_buf();
+        std::thread::spawn(move || {
+            std::thread::sleep(remaining);
+            if let Err(e) = process_any(&app, vips.as_ref(), &path, InputMode::Watched) {
+                warn!("[processor] Deferred {} failed: {}", path.display(), e);
+            }
+        });
+    }
+    Err(skip(
+        path,
+        SkipReason::RecentlyOpened,
+        format!("{} was opened recently, skipping", path.display()),
+    ))
+}
+
+/// Re-encode a video with ffmpeg. Watched videos are only touched in folders
+/// that opted in; manual runs always go ahead.
+fn process_video(
     app: &tauri::AppHandle,
-    vips: &Arc<Vips>,
     path: &Path,
     mode: InputMode,
 ) -> Result<CompressionRecord, String> {
-    let format = ImageFormat::from_path(path).ok_or_else(|| "Unsupported format".to_string())?;
-
-    // Only wait for file stability on watched/download paths
+    let (settings, folder) = app
+        .state::<Mutex<crate::config::ConfigManager>>()
+        .lock()
+        .map(|c| (c.config.video.clone(), c.config.folder_settings_for(path)))
+        .unwrap_or_default();
     if mode == InputMode::Watched {
+        if !folder.compress_videos {
+            return Err(skip(
+                path,
+                SkipReason::Disabled,
+                format!(
+                    "Video compression is off for this folder, skipping {}",
+                    path.display()
+                ),
+            ));
+        }
         if let Err(e) = wait_for_file_stability(path) {
             error!(
                 "[processor] File stability check failed for {}: {}",
@@ -64,48 +273,108 @@ pub fn process_file_with_mode(
     }
 
     let initial_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
-    let (original_quality, flags, convert_to) = app
-        .state::<Mutex<crate::config::ConfigManager>>()
-        .lock()
-        .map(|c| {
-            let opts = &c.config.format_options;
-            let convert_to_str = match format {
-                ImageFormat::Png => opts.png.convert_to.clone(),
-                ImageFormat::Jpeg => opts.jpeg.convert_to.clone(),
-                ImageFormat::WebP => opts.webp.convert_to.clone(),
-                ImageFormat::Avif => opts.avif.convert_to.clone(),
-                ImageFormat::Heif => opts.heif.convert_to.clone(),
-                ImageFormat::Tiff => opts.tiff.convert_to.clone(),
-            };
-            let target = convert_to_str.and_then(|s| ImageFormat::from_extension(&s));
-            let effective = target.unwrap_or(format);
-            let quality = match effective {
-                ImageFormat::Png => opts.png.quality,
-                ImageFormat::Jpeg => opts.jpeg.quality,
-                ImageFormat::WebP => opts.webp.quality,
-                ImageFormat::Avif => opts.avif.quality,
-                ImageFormat::Heif => opts.heif.quality,
-                ImageFormat::Tiff => opts.tiff.quality,
-            };
-            let flags = CompressionFlags::from_format_options(opts, effective);
-            (quality, flags, target)
-        })
-        .unwrap_or((
-            crate::DEFAULT_QUALITY,
-            CompressionFlags::default(),
-            None::<ImageFormat>,
+    let format = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let (folder, ocr_text) = folder_policy(app, path, mode)?;
+    let output = if folder.observe_only {
+        analysis_probe(path, &format)?
+    } else {
+        compressed_output_path(path, None).ok_or_else(|| output_path_error(path, None))?
+    };
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let _ = app.emit(
+        "compression-started",
+        &CompressionStarted {
+            initial_path: path.display().to_string(),
+            timestamp,
+        },
+    );
+
+    let ffmpeg = crate::video::ffmpeg_path(app, &settings);
+    let size = crate::video::compress_video(app, &ffmpeg, path, &output, &settings, timestamp)
+        .map_err(|e| {
+            fail(
+                app,
+                path,
+                timestamp,
+                format!("Failed to compress {}: {e}", path.display()),
+            )
+        })?;
+    let mut record = CompressionRecord {
+        initial_path: path.display().to_string(),
+        final_path: output.display().to_string(),
+        initial_size,
+        compressed_size: size,
+        initial_format: format.clone(),
+        final_format: format,
+        quality: settings.crf,
+        timestamp,
+        ocr_text,
+        ..Default::default()
+    };
+    if folder.observe_only {
+        return Ok(complete_analysis(app, path, &output, record));
+    }
+    if size >= initial_size {
+        let _ = std::fs::remove_file(&output);
+        return Err(fail(
+            app,
+            path,
+            timestamp,
+            format!("{} would grow at CRF {}", path.display(), settings.crf),
         ));
+    }
+    encrypt_for_folder(app, path, timestamp, &folder, &mut record)?;
+    Ok(complete(app, path, record))
+}
 
-    let target_ext = convert_to.map(|f| f.extension());
-    let output = compressed_output_path(path, target_ext)
-        .ok_or_else(|| "Invalid output path".to_string())?;
+/// Recompress the images inside a PDF. Watched PDFs are only touched when
+/// PDF compression is enabled; manual runs always go ahead.
+fn process_pdf(
+    app: &tauri::AppHandle,
+    vips: &Arc<Vips>,
+    path: &Path,
+    mode: InputMode,
+) -> Result<CompressionRecord, String> {
+    let settings = app
+        .state::<Mutex<crate::config::ConfigManager>>()
+        .lock()
+        .map(|c| c.config.pdf.clone())
+        .unwrap_or_default();
+    if mode == InputMode::Watched {
+        if !settings.enabled {
+            return Err(skip(
+                path,
+                SkipReason::Disabled,
+                format!("PDF compression is off, skipping {}", path.display()),
+            ));
+        }
+        if let Err(e) = wait_for_file_stability(path) {
+            error!(
+                "[processor] File stability check failed for {}: {}",
+                path.display(),
+                e
+            );
+        }
+    }
 
+    let initial_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let (folder, ocr_text) = folder_policy(app, path, mode)?;
+    let output = if folder.observe_only {
+        analysis_probe(path, "pdf")?
+    } else {
+        compressed_output_path(path, Some("pdf"))
+            .ok_or_else(|| output_path_error(path, Some("pdf")))?
+    };
     let timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
         .as_secs();
-
-    // Notify frontend that we're starting
     let _ = app.emit(
         "compression-started",
         &CompressionStarted {
@@ -114,144 +383,1571 @@ pub fn process_file_with_mode(
         },
     );
 
-    let mut current_quality = original_quality;
-    let mut compressed_size = 0u64;
-    let mut success = false;
-    const MAX_RETRIES: u8 = 5;
-    const QUALITY_STEP: u8 = 10;
+    let size = crate::pdf::compress_pdf(vips, path, &output, &settings).map_err(|e| {
+        fail(
+            app,
+            path,
+            timestamp,
+            format!("Failed to compress {}: {e}", path.display()),
+        )
+    })?;
+    let mut record = CompressionRecord {
+        initial_path: path.display().to_string(),
+        final_path: output.display().to_string(),
+        initial_size,
+        compressed_size: size,
+        initial_format: "pdf".to_string(),
+        final_format: "pdf".to_string(),
+        quality: settings.quality,
+        timestamp,
+        ocr_text,
+        ..Default::default()
+    };
+    if folder.observe_only {
+        return Ok(complete_analysis(app, path, &output, record));
+    }
+    if size >= initial_size {
+        let _ = std::fs::remove_file(&output);
+        return Err(fail(
+            app,
+            path,
+            timestamp,
+            format!("{} has no images worth recompressing", path.display()),
+        ));
+    }
+    encrypt_for_folder(app, path, timestamp, &folder, &mut record)?;
+    Ok(complete(app, path, record))
+}
 
-    let effective_format = convert_to.unwrap_or(format);
-    let img = vips
-        .load_image(path)
-        .map_err(|e| format!("Failed to load {}: {e}", path.display()))?;
+/// Minify an SVG. Watched SVGs are skipped when SVG minification is off;
+/// manual runs always go ahead.
+fn process_svg(
+    app: &tauri::AppHandle,
+    path: &Path,
+    mode: InputMode,
+) -> Result<CompressionRecord, String> {
+    let settings = app
+        .state::<Mutex<crate::config::ConfigManager>>()
+        .lock()
+        .map(|c| c.config.svg.clone())
+        .unwrap_or_default();
+    if mode == InputMode::Watched {
+        if !settings.enabled {
+            return Err(skip(
+                path,
+                SkipReason::Disabled,
+                format!("SVG minification is off, skipping {}", path.display()),
+            ));
+        }
+        if let Err(e) = wait_for_file_stability(path) {
+            error!(
+                "[processor] File stability check failed for {}: {}",
+                path.display(),
+                e
+            );
+        }
+    }
 
-    for attempt in 0..=MAX_RETRIES {
-        match vips.compress_loaded(
-            &img,
-            path,
-            &output,
-            current_quality,
-            &flags,
-            effective_format,
-        ) {
-            Ok(size) => {
-                compressed_size = size;
-                if size <= initial_size || current_quality <= 1 {
-                    success = true;
-                    break;
-                }
+    let initial_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let (folder, ocr_text) = folder_policy(app, path, mode)?;
+    let output = if folder.observe_only {
+        analysis_probe(path, "svg")?
+    } else {
+        compressed_output_path(path, Some("svg"))
+            .ok_or_else(|| output_path_error(path, Some("svg")))?
+    };
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let _ = app.emit(
+        "compression-started",
+        &CompressionStarted {
+            initial_path: path.display().to_string(),
+            timestamp,
+        },
+    );
 
-                // Compressed file is larger — reduce quality and retry
-                let retry_quality = current_quality.saturating_sub(QUALITY_STEP).max(1);
-                info!(
-                    "[compression] Compressed size ({size}) > original ({initial_size}), retrying with quality {retry_quality} (attempt {})",
-                    attempt + 1
-                );
+    let size = crate::svg::compress_svg(path, &output, &settings).map_err(|e| {
+        fail(
+            app,
+            path,
+            timestamp,
+            format!("Failed to compress {}: {e}", path.display()),
+        )
+    })?;
+    let mut record = CompressionRecord {
+        initial_path: path.display().to_string(),
+        final_path: output.display().to_string(),
+        initial_size,
+        compressed_size: size,
+        initial_format: "svg".to_string(),
+        final_format: "svg".to_string(),
+        quality: settings.precision,
+        timestamp,
+        ocr_text,
+        ..Default::default()
+    };
+    if folder.observe_only {
+        return Ok(complete_analysis(app, path, &output, record));
+    }
+    if size >= initial_size {
+        let _ = std::fs::remove_file(&output);
+        return Err(fail(
+            app,
+            path,
+            timestamp,
+            format!("{} is already minified", path.display()),
+        ));
+    }
+    encrypt_for_folder(app, path, timestamp, &folder, &mut record)?;
+    Ok(complete(app, path, record))
+}
 
-                let _ = app.emit(
-                    "compression-retry",
-                    &CompressionRetry {
-                        path: path.display().to_string(),
-                        attempt: attempt + 1,
-                        original_quality,
-                        retry_quality,
-                        initial_size,
-                        compressed_size: size,
-                    },
-                );
+/// The settings of `path`'s folder and its OCR text, once the sensitive
+/// image guard let it through. Every engine goes through this before it
+/// writes anything.
+fn folder_policy(
+    app: &tauri::AppHandle,
+    path: &Path,
+    mode: InputMode,
+) -> Result<(crate::config::FolderSettings, Option<String>), String> {
+    // Keep searchable text before lossy compression destroys small glyphs
+    let (folder, ocr_config, guard_config) = app
+        .state::<Mutex<crate::config::ConfigManager>>()
+        .lock()
+        .map(|c| {
+            (
+                c.config.folder_settings_for(path),
+                c.config.ocr.clone(),
+                c.config.sensitive_guard.clone(),
+            )
+        })
+        .unwrap_or_default();
+    let ocr_text = crate::ocr::extract_text(&ocr_config, path);
 
-                current_quality = retry_quality;
-                if current_quality <= 1 {
-                    continue;
-                }
-            }
-            Err(e) => {
-                let err_msg = format!("Failed to compress {}: {e}", path.display());
-                let _ = app.emit(
-                    "compression-failed",
-                    &CompressionFailed {
-                        initial_path: path.display().to_string(),
-                        timestamp,
-                        error: err_msg.clone(),
-                    },
-                );
-                return Err(err_msg);
-            }
+    // Automatic runs never touch images that look sensitive; the user approves them
+    if mode == InputMode::Watched && guard_config.enabled {
+        // The guard reads every image, not just the screenshots OCR is configured for
+        let guard_text = ocr_text.clone().or_else(|| {
+            let config = crate::config::OcrConfig {
+                enabled: true,
+                screenshots_only: false,
+                ..ocr_config.clone()
+            };
+            crate::ocr::extract_text(&config, path)
+        });
+        if let Some(reason) = crate::guard::classify(&guard_config, path, guard_text.as_deref()) {
+            crate::guard::hold(app, path, reason);
+            return Err(format!("{} held for approval", path.display()));
         }
     }
+    Ok((folder, ocr_text))
+}
 
-    if success {
-        let record = CompressionRecord {
-            initial_path: path.display().to_string(),
-            final_path: output.display().to_string(),
-            initial_size,
-            compressed_size,
-            initial_format: format.to_string(),
-            final_format: convert_to.unwrap_or(format).to_string(),
-            quality: current_quality,
-            timestamp,
-            original_deleted: false,
-        };
+/// Single-pass compression without libvips.
+fn process_file_fallback(
+    app: &tauri::AppHandle,
+    path: &Path,
+    mode: InputMode,
+) -> Result<CompressionRecord, String> {
+    let format = ImageFormat::from_path(path)
+        .filter(|f| crate::fallback::can_decode(*f))
+        .ok_or_else(|| "Unsupported format without libvips".to_string())?;
 
-        // Log it
-        let log = app.state::<Mutex<crate::log::CompressionLog>>();
-        if let Ok(mut log) = log.lock() {
-            log.append(record.clone());
+    if mode == InputMode::Watched {
+        if let Err(e) = wait_for_file_stability(path) {
+            error!(
+                "[processor] File stability check failed for {}: {}",
+                path.display(),
+                e
+            );
         }
+    }
 
-        // Notify frontend
-        let _ = app.emit("compression-complete", &record);
+    let initial_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let (folder, ocr_text) = folder_policy(app, path, mode)?;
+    compress_fallback(app, path, format, initial_size, &folder, ocr_text)
+}
 
-        // System Notification
-        let config = app.state::<Mutex<crate::config::ConfigManager>>();
-        let show_system_notif = if let Ok(c) = config.lock() {
-            c.config.show_system_notifications
+/// Encode `path` with the fallback encoder, honouring its folder's
+/// settings. Textures and analysis need libvips, so files in folders set
+/// up for them are skipped rather than written.
+fn compress_fallback(
+    app: &tauri::AppHandle,
+    path: &Path,
+    format: ImageFormat,
+    initial_size: u64,
+    folder: &crate::config::FolderSettings,
+    ocr_text: Option<String>,
+) -> Result<CompressionRecord, String> {
+    if folder.game_asset_mode || folder.observe_only {
+        let what = if folder.game_asset_mode {
+            "Texture output"
         } else {
-            true
+            "Observe-only analysis"
         };
+        return Err(skip(
+            path,
+            SkipReason::Disabled,
+            format!("{} needs libvips, skipping {}", what, path.display()),
+        ));
+    }
 
-        if show_system_notif {
-            use tauri_plugin_notification::NotificationExt;
-            let file_name = path.file_name().and_then(|s| s.to_str()).unwrap_or("image");
+    let (quality, flags, convert_to) = app
+        .state::<Mutex<crate::config::ConfigManager>>()
+        .lock()
+        .map(|c| resolve_format_settings(&c.config, path, format))
+        .unwrap_or((crate::DEFAULT_QUALITY, CompressionFlags::default(), None));
 
-            let _ = app
-                .notification()
-                .builder()
-                .title("Image Compressed")
-                .body(format!(
-                    "{} compressed to {} (saved {}%)",
-                    file_name,
-                    format_bytes(record.compressed_size),
-                    ((record.initial_size - record.compressed_size) as f64
-                        / record.initial_size as f64
-                        * 100.0)
-                        .round()
-                ))
-                .show();
-        }
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let _ = app.emit(
+        "compression-started",
+        &CompressionStarted {
+            initial_path: path.display().to_string(),
+            timestamp,
+        },
+    );
 
-        Ok(record)
-    } else {
-        let err_msg = "Failed to compress file after retries".to_string();
-        let _ = app.emit(
-            "compression-failed",
-            &CompressionFailed {
-                initial_path: path.display().to_string(),
-                timestamp,
-                error: err_msg.clone(),
-            },
-        );
-        Err(err_msg)
+    let out = crate::fallback::compress(path, quality, convert_to, &flags).map_err(|e| {
+        fail(
+            app,
+            path,
+            timestamp,
+            format!("Failed to compress {}: {e}", path.display()),
+        )
+    })?;
+    let mut out = out;
+    if flags.keep_metadata || flags.keep_icc {
+        out.size = preserve_metadata(path, &out.path, out.size, &flags);
+    }
+    if out.size > initial_size {
+        let _ = std::fs::remove_file(&out.path);
+        return Err(fail(
+            app,
+            path,
+            timestamp,
+            format!(
+                "{} would grow when re-encoded without libvips",
+                path.display()
+            ),
+        ));
+    }
+
+    let mut record = CompressionRecord {
+        initial_path: path.display().to_string(),
+        final_path: out.path.display().to_string(),
+        initial_size,
+        compressed_size: out.size,
+        initial_format: format.to_string(),
+        final_format: out.format.to_string(),
+        quality,
+        timestamp,
+        ocr_text,
+        deterministic: flags.deterministic,
+        settings: Some(EncodeSettings {
+            engine: EncodeEngine::Fallback,
+            format: out.format,
+            quality,
+            flags,
+        }),
+        ..Default::default()
+    };
+    encrypt_for_folder(app, path, timestamp, folder, &mut record)?;
+    Ok(complete(app, path, record))
+}
+
+pub fn process_file_with_mode(
+    app: &tauri::AppHandle,
+    vips: &Arc<Vips>,
+    path: &Path,
+    mode: InputMode,
+) -> Result<CompressionRecord, String> {
+    let format = ImageFormat::from_path(path).ok_or_else(|| "Unsupported format".to_string())?;
+
+    // Only wait for file stability on watched/download paths
+    if mode == InputMode::Watched {
+        if let Err(e) = wait_for_file_stability(path) {
+            error!(
+                "[processor] File stability check failed for {}: {}",
+                path.display(),
+                e
+            );
+        }
+    }
+
+    let initial_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    if let Some(record) = reuse_previous_output(app, path, initial_size) {
+        return Ok(record);
+    }
+
+    let (original_quality, flags, convert_to) = app
+        .state::<Mutex<crate::config::ConfigManager>>()
+        .lock()
+        .map(|c| resolve_format_settings(&c.config, path, format))
+        .unwrap_or((
+            crate::DEFAULT_QUALITY,
+            CompressionFlags::default(),
+            None::<ImageFormat>,
+        ));
+
+    let (folder, ocr_text) = folder_policy(app, path, mode)?;
+
+    // Without libheif, HEIC is decoded through the OS but can't be written back
+    let convert_to = match convert_to.unwrap_or(format) {
+        ImageFormat::Heif if !vips.has_operation("heifsave") => {
+            info!(
+                "[processor] libvips has no HEIF encoder, writing {} as JPEG",
+                path.display()
+            );
+            Some(ImageFormat::Jpeg)
+        }
+        _ => convert_to,
+    };
+
+    let effective_format = convert_to.unwrap_or(format);
+    // Textures and analysis stay on libvips; the fallback can't do either
+    if crate::fallback::can_decode(format)
+        && !folder.game_asset_mode
+        && !folder.observe_only
+        && (crate::engine::is_demoted(format) || crate::engine::is_demoted(effective_format))
+    {
+        info!(
+            "[processor] libvips is demoted for {}, using the fallback encoder",
+            path.display()
+        );
+        return compress_fallback(app, path, format, initial_size, &folder, ocr_text);
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    // Notify frontend that we're starting
+    let _ = app.emit(
+        "compression-started",
+        &CompressionStarted {
+            initial_path: path.display().to_string(),
+            timestamp,
+        },
+    );
+
+    let (texture, smallest_wins, extra_outputs) = app
+        .state::<Mutex<crate::config::ConfigManager>>()
+        .lock()
+        .map(|c| {
+            (
+                c.config.texture.clone(),
+                c.config.smallest_wins.clone(),
+                c.config.active_format_options().extra_outputs.clone(),
+            )
+        })
+        .unwrap_or_default();
+    if folder.game_asset_mode {
+        return match crate::texture::compress_texture(vips, path, &texture) {
+            Ok(out) => Ok(complete(
+                app,
+                path,
+                CompressionRecord {
+                    initial_path: path.display().to_string(),
+                    final_path: out.path.display().to_string(),
+                    initial_size,
+                    compressed_size: out.size,
+                    initial_format: format.to_string(),
+                    final_format: out.format.to_string(),
+                    quality: 100,
+                    timestamp,
+                    original_deleted: false,
+                    ..Default::default()
+                },
+            )),
+            Err(e) => Err(fail(
+                app,
+                path,
+                timestamp,
+                format!("Failed to convert {} to a texture: {e}", path.display()),
+            )),
+        };
+    }
+
+    if folder.observe_only {
+        return analyze_only(
+            app,
+            vips,
+            path,
+            AnalysisInput {
+                format,
+                target: convert_to.unwrap_or(format),
+                quality: original_quality,
+                flags: &flags,
+                initial_size,
+                timestamp,
+            },
+        );
+    }
+
+    // Claimed only here, so analysis and textures never create its folder
+    let target_ext = convert_to.map(|f| f.extension());
+    let mut output = compressed_output_path(path, target_ext)
+        .ok_or_else(|| started_output_path_error(app, path, timestamp, target_ext))?;
+
+    let mut current_quality = original_quality;
+    let mut compressed_size = 0u64;
+    let mut success = false;
+    const MAX_RETRIES: u8 = 5;
+    const QUALITY_STEP: u8 = 10;
+
+    let img = vips
+        .load_frames(path, format, effective_format)
+        .map_err(|e| {
+            let message = format!("Failed to load {}: {e}", path.display());
+            crate::engine::record_failure(format, &message);
+            message
+        })?;
+
+    // Deep color and HDR sources are flattened by 8-bit SDR outputs
+    let high_depth = vips.is_high_depth(&img);
+    let hdr = crate::metadata::is_hdr(path);
+    let mut flags = flags;
+    if high_depth || hdr {
+        let policy = app
+            .state::<Mutex<crate::config::ConfigManager>>()
+            .lock()
+            .map(|c| c.config.high_depth)
+            .unwrap_or_default();
+        let what = if hdr {
+            "HDR data"
+        } else {
+            "more than 8 bits per sample"
+        };
+        match policy {
+            HighDepthPolicy::Skip => {
+                let message = format!("{} has {}, skipping", path.display(), what);
+                return Err(skip_started(
+                    app,
+                    path,
+                    timestamp,
+                    SkipReason::HighDepth,
+                    message,
+                ));
+            }
+            // Gain maps and PQ/HLG tagging don't survive libvips either way
+            HighDepthPolicy::Keep if !hdr && keep_high_depth(&mut flags, effective_format) => {
+                info!(
+                    "[processor] Keeping {} above 8 bits as {}",
+                    path.display(),
+                    effective_format
+                );
+            }
+            _ => warn_output(
+                app,
+                path,
+                timestamp,
+                format!(
+                    "{} has {}; the {} output is 8-bit SDR",
+                    path.display(),
+                    what,
+                    effective_format
+                ),
+            ),
+        }
+    }
+
+    let (img, trimmed) = trim_borders(vips, img, path, &flags);
+
+    // Smallest-wins picks the format itself when nothing else asked for one;
+    // conditional conversion lets the source format compete with the target
+    let mut final_format = effective_format;
+    let mut candidate_sizes = Vec::new();
+    let candidates: Vec<EncodeCandidate> =
+        if smallest_wins.enabled && convert_to.is_none() && !vips.is_animated(&img) {
+            // JPEG would drop transparency
+            let has_alpha = matches!(vips.bands(&img), 2 | 4);
+            smallest_wins
+                .candidates
+                .iter()
+                .filter_map(|c| ImageFormat::from_extension(c))
+                .filter(|f| f.is_writable() && !(has_alpha && *f == ImageFormat::Jpeg))
+                .map(|format| EncodeCandidate {
+                    format,
+                    quality: original_quality,
+                    flags: flags.clone(),
+                })
+                .collect()
+        } else if smallest_wins.conditional_conversion
+            && effective_format != format
+            && format.is_writable()
+            // Only the first frame was loaded when the target can't animate
+            && (effective_format.supports_animation() || !format.supports_animation())
+        {
+            let (quality, source_flags) = app
+                .state::<Mutex<crate::config::ConfigManager>>()
+                .lock()
+                .map(|c| output_settings(&c.config, path, format))
+                .unwrap_or((original_quality, flags.clone()));
+            vec![
+                EncodeCandidate {
+                    format: effective_format,
+                    quality: original_quality,
+                    flags: flags.clone(),
+                },
+                EncodeCandidate {
+                    format,
+                    quality,
+                    flags: source_flags,
+                },
+            ]
+        } else {
+            Vec::new()
+        };
+    if !candidates.is_empty() {
+        match vips.compress_smallest(&img, path, &candidates) {
+            Ok(mut best) if best.size <= initial_size => {
+                match best.place(path, &output, effective_format) {
+                    Ok(()) => {
+                        info!(
+                            "[processor] Smallest of {:?} for {} is {} ({} bytes)",
+                            best.candidates
+                                .iter()
+                                .map(|c| c.format.as_str())
+                                .collect::<Vec<_>>(),
+                            path.display(),
+                            best.format,
+                            best.size
+                        );
+                        output = best.path;
+                        final_format = best.format;
+                        current_quality = best.quality;
+                        compressed_size = best.size;
+                        candidate_sizes = best.candidates;
+                        success = true;
+                    }
+                    // Under Skip or Error, an earlier output in that format is
+                    // in the way; the resolved format is still free
+                    Err(e) => warn!(
+                        "[processor] Not keeping the smallest ({}) output of {}, writing {}: {}",
+                        best.format,
+                        path.display(),
+                        effective_format,
+                        e
+                    ),
+                }
+            }
+            // Even the smallest grew; the usual retries take over
+            Ok(best) => {
+                let _ = std::fs::remove_file(&best.path);
+            }
+            Err(e) => warn!(
+                "[processor] Smallest-wins failed for {}: {}",
+                path.display(),
+                e
+            ),
+        }
+    }
+
+    if !success {
+        for attempt in 0..=MAX_RETRIES {
+            match vips.compress_loaded(
+                &img,
+                path,
+                &output,
+                current_quality,
+                &flags,
+                effective_format,
+            ) {
+                Ok(size) => {
+                    crate::engine::record_success(effective_format);
+                    compressed_size = size;
+                    if size <= initial_size || current_quality <= 1 {
+                        success = true;
+                        break;
+                    }
+
+                    // Compressed file is larger — reduce quality and retry
+                    let retry_quality = current_quality.saturating_sub(QUALITY_STEP).max(1);
+                    info!(
+                        "[compression] Compressed size ({size}) > original ({initial_size}), retrying with quality {retry_quality} (attempt {})",
+                        attempt + 1
+                    );
+
+                    let _ = app.emit(
+                        "compression-retry",
+                        &CompressionRetry {
+                            path: path.display().to_string(),
+                            attempt: attempt + 1,
+                            original_quality,
+                            retry_quality,
+                            initial_size,
+                            compressed_size: size,
+                        },
+                    );
+
+                    current_quality = retry_quality;
+                    if current_quality <= 1 {
+                        continue;
+                    }
+                }
+                Err(e) => {
+                    let message = format!("Failed to compress {}: {e}", path.display());
+                    crate::engine::record_failure(effective_format, &message);
+                    return Err(fail(app, path, timestamp, message));
+                }
+            }
+        }
+    }
+
+    if success {
+        if flags.keep_metadata || flags.keep_icc {
+            compressed_size = preserve_metadata(path, &output, compressed_size, &flags);
+        }
+        // A conditional conversion may have kept the source format's own flags
+        let used_flags = candidates
+            .iter()
+            .find(|c| c.format == final_format)
+            .map_or(&flags, |c| &c.flags);
+        let mut record = CompressionRecord {
+            initial_path: path.display().to_string(),
+            final_path: output.display().to_string(),
+            initial_size,
+            compressed_size,
+            initial_format: format.to_string(),
+            final_format: final_format.to_string(),
+            quality: current_quality,
+            timestamp,
+            original_deleted: false,
+            ocr_text,
+            deterministic: flags.deterministic,
+            candidates: candidate_sizes,
+            trimmed,
+            settings: Some(EncodeSettings {
+                engine: EncodeEngine::Libvips,
+                format: final_format,
+                quality: current_quality,
+                flags: used_flags.clone(),
+            }),
+            ..Default::default()
+        };
+        encrypt_for_folder(app, path, timestamp, &folder, &mut record)?;
+        let record = complete(app, path, record);
+        let extra_formats: Vec<ImageFormat> = extra_outputs
+            .iter()
+            .filter_map(|f| ImageFormat::from_extension(f))
+            .filter(|f| f.is_writable() && *f != final_format)
+            .fold(Vec::new(), |mut formats, f| {
+                if !formats.contains(&f) {
+                    formats.push(f);
+                }
+                formats
+            });
+        if extra_formats.is_empty() {
+            return Ok(record);
+        }
+        let input = FanOutInput {
+            path,
+            format,
+            folder: &folder,
+            trimmed,
+        };
+        Ok(write_extra_outputs(
+            app,
+            vips,
+            &img,
+            &input,
+            record,
+            &extra_formats,
+        ))
+    } else {
+        Err(fail(
+            app,
+            path,
+            timestamp,
+            "Failed to compress file after retries".to_string(),
+        ))
+    }
+}
+
+struct FanOutInput<'a> {
+    path: &'a Path,
+    format: ImageFormat,
+    folder: &'a crate::config::FolderSettings,
+    trimmed: Option<TrimmedSize>,
+}
+
+/// Write `formats` from the image already loaded for `primary`, as sub-tasks
+/// of its task. Every output written is logged in one group with `primary`.
+fn write_extra_outputs(
+    app: &tauri::AppHandle,
+    vips: &Arc<Vips>,
+    img: &VipsImage<'_>,
+    input: &FanOutInput<'_>,
+    mut primary: CompressionRecord,
+    formats: &[ImageFormat],
+) -> CompressionRecord {
+    let path = input.path;
+    let mut names = vec![primary.final_format.clone()];
+    names.extend(formats.iter().map(|f| f.to_string()));
+    crate::tasks::set_outputs(app, path, &names);
+    crate::tasks::finish_output(app, path, &primary.final_format, true);
+
+    let mut grouped = Vec::new();
+    for &target in formats {
+        let result = extra_output(app, vips, img, input, target, primary.id);
+        crate::tasks::finish_output(app, path, &target.to_string(), result.is_ok());
+        match result {
+            Ok(record) => grouped.push(complete(app, path, record).id),
+            Err(e) => warn!(
+                "[processor] Failed to write {} copy of {}: {}",
+                target,
+                path.display(),
+                e
+            ),
+        }
+    }
+
+    if !grouped.is_empty() {
+        grouped.push(primary.id);
+        if let Ok(mut log) = app.state::<Mutex<crate::log::CompressionLog>>().lock() {
+            log.set_group(&grouped, primary.id);
+        }
+        primary.group_id = Some(primary.id);
+    }
+    primary
+}
+
+fn extra_output(
+    app: &tauri::AppHandle,
+    vips: &Arc<Vips>,
+    img: &VipsImage<'_>,
+    input: &FanOutInput<'_>,
+    target: ImageFormat,
+    group_id: u64,
+) -> Result<CompressionRecord, String> {
+    let path = input.path;
+    let output = compressed_output_path(path, Some(target.extension()))
+        .ok_or_else(|| output_path_error(path, Some(target.extension())))?;
+    let (quality, flags) = app
+        .state::<Mutex<crate::config::ConfigManager>>()
+        .lock()
+        .map(|c| output_settings(&c.config, path, target))
+        .map_err(|e| e.to_string())?;
+
+    // A still format needs the first frame rather than the whole strip
+    let still;
+    let img = if vips.is_animated(img) && !target.supports_animation() {
+        still = vips
+            .load_frames(path, input.format, target)
+            .map_err(|e| e.to_string())?;
+        &still
+    } else {
+        img
+    };
+    let size = match vips.compress_loaded(img, path, &output, quality, &flags, target) {
+        Ok(size) if flags.keep_metadata || flags.keep_icc => {
+            crate::engine::record_success(target);
+            preserve_metadata(path, &output, size, &flags)
+        }
+        Ok(size) => {
+            crate::engine::record_success(target);
+            size
+        }
+        Err(e) => {
+            let _ = std::fs::remove_file(&output);
+            crate::engine::record_failure(target, &e.to_string());
+            return Err(e.to_string());
+        }
+    };
+
+    let mut record = CompressionRecord {
+        initial_path: path.display().to_string(),
+        final_path: output.display().to_string(),
+        initial_size: std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+        compressed_size: size,
+        initial_format: input.format.to_string(),
+        final_format: target.to_string(),
+        quality,
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        deterministic: flags.deterministic,
+        group_id: Some(group_id),
+        trimmed: input.trimmed,
+        settings: Some(EncodeSettings {
+            engine: EncodeEngine::Libvips,
+            format: target,
+            quality,
+            flags,
+        }),
+        ..Default::default()
+    };
+    if input.folder.encrypt_output {
+        let key = input.folder.encryption_key.as_ref().map(Secret::expose);
+        if let Err(e) = encrypt_output(&mut record, path, key) {
+            let _ = std::fs::remove_file(&output);
+            return Err(format!("Failed to encrypt {}: {e}", output.display()));
+        }
+    }
+    Ok(record)
+}
+
+struct AnalysisInput<'a> {
+    format: ImageFormat,
+    target: ImageFormat,
+    quality: u8,
+    flags: &'a CompressionFlags,
+    initial_size: u64,
+    timestamp: u64,
+}
+
+/// Encode into a scratch file to measure the achievable size, then discard it.
+fn analyze_only(
+    app: &tauri::AppHandle,
+    vips: &Arc<Vips>,
+    path: &Path,
+    input: AnalysisInput<'_>,
+) -> Result<CompressionRecord, String> {
+    let probe = analysis_probe(path, input.target.extension())?;
+    let result = vips.compress(path, &probe, input.quality, input.flags, Some(input.target));
+    let _ = std::fs::remove_file(&probe);
+
+    match result {
+        Ok(size) => {
+            info!(
+                "[analysis] {} could shrink {} → {} bytes",
+                path.display(),
+                input.initial_size,
+                size
+            );
+            Ok(complete(
+                app,
+                path,
+                CompressionRecord {
+                    initial_path: path.display().to_string(),
+                    final_path: String::new(),
+                    initial_size: input.initial_size,
+                    compressed_size: size.min(input.initial_size),
+                    initial_format: input.format.to_string(),
+                    final_format: input.target.to_string(),
+                    quality: input.quality,
+                    timestamp: input.timestamp,
+                    analysis_only: true,
+                    ..Default::default()
+                },
+            ))
+        }
+        Err(e) => Err(fail(
+            app,
+            path,
+            input.timestamp,
+            format!("Failed to analyze {}: {e}", path.display()),
+        )),
+    }
+}
+
+/// Scratch file an observe-only folder's file is encoded into to measure the
+/// achievable size.
+fn analysis_probe(path: &Path, ext: &str) -> Result<PathBuf, String> {
+    let stem = path
+        .file_stem()
+        .ok_or_else(|| "Invalid file name".to_string())?;
+    Ok(crate::cache::temp_path(&format!(
+        "analysis-{}.{}",
+        stem.to_string_lossy(),
+        ext
+    )))
+}
+
+/// Record the size `record` measured in `probe` for an observe-only folder,
+/// then discard the probe.
+fn complete_analysis(
+    app: &tauri::AppHandle,
+    path: &Path,
+    probe: &Path,
+    record: CompressionRecord,
+) -> CompressionRecord {
+    let _ = std::fs::remove_file(probe);
+    info!(
+        "[analysis] {} could shrink {} → {} bytes",
+        path.display(),
+        record.initial_size,
+        record.compressed_size
+    );
+    let compressed_size = record.compressed_size.min(record.initial_size);
+    complete(
+        app,
+        path,
+        CompressionRecord {
+            final_path: String::new(),
+            compressed_size,
+            analysis_only: true,
+            ..record
+        },
+    )
+}
+
+/// Encrypt `record`'s output when `folder` asks for it. On failure the
+/// plaintext output is removed and the file reported as failed.
+fn encrypt_for_folder(
+    app: &tauri::AppHandle,
+    path: &Path,
+    timestamp: u64,
+    folder: &crate::config::FolderSettings,
+    record: &mut CompressionRecord,
+) -> Result<(), String> {
+    if !folder.encrypt_output {
+        return Ok(());
+    }
+    let output = record.final_path.clone();
+    let key = folder.encryption_key.as_ref().map(Secret::expose);
+    encrypt_output(record, path, key).map_err(|e| {
+        let _ = std::fs::remove_file(&output);
+        fail(
+            app,
+            path,
+            timestamp,
+            format!("Failed to encrypt {}: {e}", output),
+        )
+    })
+}
+
+/// Replace the plaintext output with an encrypted copy and remember the original's hash.
+fn encrypt_output(
+    record: &mut CompressionRecord,
+    original: &Path,
+    key: Option<&str>,
+) -> Result<(), String> {
+    let key = key.ok_or("encryption is enabled but no key is set")?;
+    record.original_sha256 = Some(crate::crypto::sha256_file(original).map_err(|e| e.to_string())?);
+    let (encrypted, size) = crate::crypto::encrypt_file(Path::new(&record.final_path), key)?;
+    record.final_path = encrypted.display().to_string();
+    record.compressed_size = size;
+    record.encrypted = true;
+    // Every encryption uses a fresh nonce
+    record.deterministic = false;
+    Ok(())
+}
+
+fn record_checksums(record: &mut CompressionRecord, mode: crate::config::ChecksumMode) {
+    let original = crate::checksum::original_path(record).map(str::to_string);
+    if record.original_sha256.is_none() {
+        record.original_sha256 = original
+            .as_deref()
+            .and_then(|p| crate::crypto::sha256_file(Path::new(p)).ok());
+    }
+    record.output_sha256 = crate::crypto::sha256_file(Path::new(&record.final_path)).ok();
+
+    if mode == crate::config::ChecksumMode::Sidecar {
+        let files = [
+            (original.as_deref(), &record.original_sha256),
+            (Some(record.final_path.as_str()), &record.output_sha256),
+        ];
+        for (file, hash) in files {
+            if let (Some(file), Some(hash)) = (file, hash) {
+                if let Err(e) = crate::checksum::write_sidecar(Path::new(file), hash) {
+                    error!("[checksum] Failed to write sidecar for {}: {}", file, e);
+                }
+            }
+        }
+    }
+}
+
+/// When `path` has the same name and content as an earlier original, point a
+/// new record at that original's output (hard-linked or copied next to
+/// `path` when needed) instead of compressing again.
+fn reuse_previous_output(
+    app: &tauri::AppHandle,
+    path: &Path,
+    initial_size: u64,
+) -> Option<CompressionRecord> {
+    let enabled = app
+        .state::<Mutex<crate::config::ConfigManager>>()
+        .lock()
+        .map(|c| c.config.reuse_redownloads)
+        .unwrap_or(false);
+    if !enabled {
+        return None;
+    }
+
+    let name = path.file_name()?;
+    let candidates: Vec<CompressionRecord> = app
+        .state::<Mutex<crate::log::CompressionLog>>()
+        .lock()
+        .ok()?
+        .records
+        .iter()
+        .rev()
+        .filter(|r| {
+            !r.analysis_only
+                && !r.encrypted
+                && r.original_sha256.is_some()
+                && r.initial_size == initial_size
+                && Path::new(&r.initial_path).file_name() == Some(name)
+                && Path::new(&r.final_path).is_file()
+        })
+        .cloned()
+        .collect();
+    if candidates.is_empty() {
+        return None;
+    }
+    let hash = crate::crypto::sha256_file(path).ok()?;
+    let previous = candidates
+        .into_iter()
+        .find(|r| r.original_sha256.as_deref() == Some(hash.as_str()))?;
+
+    let previous_output = Path::new(&previous.final_path);
+    let output = compressed_output_path(path, previous_output.extension()?.to_str())?;
+    if output != previous_output {
+        let _ = std::fs::remove_file(&output);
+        if std::fs::hard_link(previous_output, &output).is_err() {
+            if let Err(e) = std::fs::copy(previous_output, &output) {
+                warn!(
+                    "[processor] Could not reuse {} for {}: {}",
+                    previous_output.display(),
+                    path.display(),
+                    e
+                );
+                return None;
+            }
+        }
+    }
+    info!(
+        "[processor] {} is a re-download of record {}, reusing {}",
+        path.display(),
+        previous.id,
+        previous_output.display()
+    );
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    Some(complete(
+        app,
+        path,
+        CompressionRecord {
+            initial_path: path.display().to_string(),
+            final_path: output.display().to_string(),
+            initial_size,
+            compressed_size: previous.compressed_size,
+            initial_format: previous.initial_format,
+            final_format: previous.final_format,
+            quality: previous.quality,
+            timestamp,
+            original_sha256: Some(hash),
+            reused_from: Some(previous.id),
+            settings: previous.settings,
+            ..Default::default()
+        },
+    ))
+}
+
+/// Encode `path` with exactly the settings recorded for an earlier output,
+/// whatever the config says now.
+pub fn repeat(
+    app: &tauri::AppHandle,
+    vips: Option<&Vips>,
+    path: &Path,
+    settings: &EncodeSettings,
+) -> Result<CompressionRecord, String> {
+    let format = ImageFormat::from_path(path).ok_or_else(|| "Unsupported format".to_string())?;
+    let initial_size = std::fs::metadata(path)
+        .map(|m| m.len())
+        .map_err(|e| e.to_string())?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let _ = app.emit(
+        "compression-started",
+        &CompressionStarted {
+            initial_path: path.display().to_string(),
+            timestamp,
+        },
+    );
+
+    let flags = &settings.flags;
+    let mut trimmed = None;
+    let encoded = match settings.engine {
+        EncodeEngine::Libvips => {
+            let vips = vips.ok_or("libvips not available")?;
+            let output = compressed_output_path(path, Some(settings.format.extension()))
+                .ok_or_else(|| {
+                    let ext = Some(settings.format.extension());
+                    started_output_path_error(app, path, timestamp, ext)
+                })?;
+            vips.load_frames(path, format, settings.format)
+                .and_then(|img| {
+                    let (img, trim) = trim_borders(vips, img, path, flags);
+                    trimmed = trim;
+                    vips.compress_loaded(
+                        &img,
+                        path,
+                        &output,
+                        settings.quality,
+                        flags,
+                        settings.format,
+                    )
+                })
+                .map(|size| (output, size, settings.format))
+        }
+        EncodeEngine::Fallback => {
+            crate::fallback::compress(path, settings.quality, Some(settings.format), flags)
+                .map(|out| (out.path, out.size, out.format))
+        }
+    };
+    let (output, mut size, final_format) = encoded.map_err(|e| {
+        fail(
+            app,
+            path,
+            timestamp,
+            format!("Failed to compress {}: {e}", path.display()),
+        )
+    })?;
+    if flags.keep_metadata || flags.keep_icc {
+        size = preserve_metadata(path, &output, size, flags);
+    }
+    info!(
+        "[processor] Repeated {} as {} at quality {} ({} → {} bytes)",
+        path.display(),
+        final_format,
+        settings.quality,
+        initial_size,
+        size
+    );
+
+    Ok(complete(
+        app,
+        path,
+        CompressionRecord {
+            initial_path: path.display().to_string(),
+            final_path: output.display().to_string(),
+            initial_size,
+            compressed_size: size,
+            initial_format: format.to_string(),
+            final_format: final_format.to_string(),
+            quality: settings.quality,
+            timestamp,
+            deterministic: flags.deterministic,
+            settings: Some(settings.clone()),
+            trimmed,
+            ..Default::default()
+        },
+    ))
+}
+
+/// Crop `img`'s margins when `flags` ask for it, keeping it whole when it
+/// has none or trimming fails.
+fn trim_borders<'a>(
+    vips: &'a Vips,
+    img: VipsImage<'a>,
+    path: &Path,
+    flags: &CompressionFlags,
+) -> (VipsImage<'a>, Option<TrimmedSize>) {
+    if !flags.trim || vips.is_animated(&img) {
+        return (img, None);
+    }
+    match vips.trim_borders(&img) {
+        Ok(Some(cropped)) => {
+            let (original_width, original_height) = vips.dimensions(&img);
+            let (width, height) = vips.dimensions(&cropped);
+            info!(
+                "[processor] Trimmed {} from {}x{} to {}x{}",
+                path.display(),
+                original_width,
+                original_height,
+                width,
+                height
+            );
+            (
+                cropped,
+                Some(TrimmedSize {
+                    original_width,
+                    original_height,
+                    width,
+                    height,
+                }),
+            )
+        }
+        Ok(None) => (img, None),
+        Err(e) => {
+            warn!("[processor] Could not trim {}: {}", path.display(), e);
+            (img, None)
+        }
+    }
+}
+
+/// Log a finished record, notify the frontend and show a system notification.
+fn complete(
+    app: &tauri::AppHandle,
+    path: &Path,
+    mut record: CompressionRecord,
+) -> CompressionRecord {
+    let (checksum_mode, reuse_redownloads) = app
+        .state::<Mutex<crate::config::ConfigManager>>()
+        .lock()
+        .map(|c| (c.config.checksum_mode, c.config.reuse_redownloads))
+        .unwrap_or_default();
+    crate::replace::apply(app, &mut record);
+    crate::archive::apply(app, &mut record);
+    // Hashed where the files ended up, so sidecars sit next to them
+    if checksum_mode != crate::config::ChecksumMode::Off && !record.analysis_only {
+        record_checksums(&mut record, checksum_mode);
+    }
+    // Re-downloads are recognised by the original's hash
+    if reuse_redownloads && record.original_sha256.is_none() && !record.analysis_only {
+        record.original_sha256 = crate::checksum::original_path(&record)
+            .and_then(|p| crate::crypto::sha256_file(Path::new(p)).ok());
+    }
+
+    // Log it
+    let log = app.state::<Mutex<crate::log::CompressionLog>>();
+    let record = match log.lock() {
+        Ok(mut log) => log.append(record),
+        Err(_) => record,
+    };
+    if !record.analysis_only {
+        crate::pairs::record(path, Path::new(&record.final_path));
+    }
+
+    // Notify frontend
+    let _ = app.emit("compression-complete", &record);
+    let kind = if record.analysis_only {
+        crate::activity::ActivityKind::Analyzed
+    } else {
+        crate::activity::ActivityKind::Compressed
+    };
+    crate::activity::push(
+        app,
+        kind,
+        path,
+        record.initial_size.saturating_sub(record.compressed_size),
+    );
+    crate::scripting::on_compressed(app, &record);
+
+    // A fanned-out file is announced once, by its first output
+    let secondary = record.group_id.is_some_and(|group| group != record.id);
+    if !record.analysis_only && !secondary {
+        let file_name = path.file_name().and_then(|s| s.to_str()).unwrap_or("image");
+        crate::notifier::notify(
+            app,
+            crate::notifier::Notification {
+                event: NotificationEvent::Compressed,
+                title: "Image Compressed".into(),
+                body: format!(
+                    "{} compressed to {} (saved {})",
+                    file_name,
+                    crate::locale::format_bytes(record.compressed_size),
+                    crate::locale::format_percent(
+                        record.initial_size.saturating_sub(record.compressed_size) as f64
+                            / record.initial_size.max(1) as f64
+                            * 100.0
+                    )
+                ),
+                data: serde_json::to_value(&record).unwrap_or_default(),
+            },
+        );
+    }
+
+    record
+}
+
+/// Raise `flags` so `format` keeps more than 8 bits per sample. False when
+/// the format can't hold them.
+fn keep_high_depth(flags: &mut CompressionFlags, format: ImageFormat) -> bool {
+    match format {
+        ImageFormat::Png => {
+            flags.png_bitdepth = 16;
+            flags.png_palette = false;
+            flags.png_quantize_after = false;
+            true
+        }
+        ImageFormat::Avif => {
+            flags.avif_bitdepth = flags.avif_bitdepth.max(10);
+            flags.avif_quantize = false;
+            true
+        }
+        ImageFormat::Heif => {
+            flags.heif_bitdepth = flags.heif_bitdepth.max(10);
+            flags.heif_quantize = false;
+            true
+        }
+        ImageFormat::Tiff => {
+            flags.tiff_bitdepth = 0;
+            flags.tiff_quantize = false;
+            true
+        }
+        ImageFormat::Jxl => true,
+        _ => false,
+    }
+}
+
+/// Emit a `compression-warning` event for a file that is still compressed.
+fn warn_output(app: &tauri::AppHandle, path: &Path, timestamp: u64, message: String) {
+    warn!("[processor] {}", message);
+    let _ = app.emit(
+        "compression-warning",
+        &CompressionWarning {
+            initial_path: path.display().to_string(),
+            timestamp,
+            message,
+        },
+    );
+}
+
+/// Why `path` has no output path: an existing file or an unusable input.
+fn output_path_error(path: &Path, target_ext: Option<&str>) -> String {
+    let Some(existing) = crate::compression::existing_output(path, target_ext) else {
+        return "Invalid output path".to_string();
+    };
+    let message = format!("{} already exists", existing.display());
+    match crate::compression::collision_policy() {
+        CollisionPolicy::Skip => skip(path, SkipReason::OutputExists, message),
+        _ => message,
+    }
+}
+
+/// `output_path_error` for a file whose `compression-started` already went
+/// out, so the frontend hears about the skip or failure.
+fn started_output_path_error(
+    app: &tauri::AppHandle,
+    path: &Path,
+    timestamp: u64,
+    target_ext: Option<&str>,
+) -> String {
+    match crate::compression::existing_output(path, target_ext) {
+        Some(existing) if crate::compression::collision_policy() == CollisionPolicy::Skip => {
+            let message = format!("{} already exists", existing.display());
+            skip_started(app, path, timestamp, SkipReason::OutputExists, message)
+        }
+        _ => fail(app, path, timestamp, output_path_error(path, target_ext)),
+    }
+}
+
+/// Note a file left alone on purpose and hand the message back for propagation.
+fn skip(path: &Path, reason: SkipReason, message: String) -> String {
+    crate::skips::record(path, reason, &message);
+    message
+}
+
+/// Like `skip`, for a file whose `compression-started` already went out:
+/// `compression-skipped` tells the frontend to drop its entry.
+fn skip_started(
+    app: &tauri::AppHandle,
+    path: &Path,
+    timestamp: u64,
+    reason: SkipReason,
+    message: String,
+) -> String {
+    let _ = app.emit(
+        "compression-skipped",
+        &CompressionSkipped {
+            initial_path: path.display().to_string(),
+            timestamp,
+            reason: reason.to_string(),
+        },
+    );
+    skip(path, reason, message)
+}
+
+/// Emit a `compression-failed` event and hand the message back for propagation.
+fn fail(app: &tauri::AppHandle, path: &Path, timestamp: u64, error: String) -> String {
+    let _ = app.emit(
+        "compression-failed",
+        &CompressionFailed {
+            initial_path: path.display().to_string(),
+            timestamp,
+            error: error.clone(),
+        },
+    );
+    crate::activity::push(app, crate::activity::ActivityKind::Failed, path, 0);
+    crate::session::record_failure();
+    crate::notifier::notify(
+        app,
+        crate::notifier::Notification {
+            event: NotificationEvent::Failed,
+            title: "Compression Failed".into(),
+            body: error.clone(),
+            data: serde_json::json!({ "path": path.display().to_string() }),
+        },
+    );
+    error
+}
+
+/// Quality, encoder flags and conversion target configured for `format`.
+pub fn resolve_format_settings(
+    config: &crate::config::AppConfig,
+    path: &Path,
+    format: ImageFormat,
+) -> (u8, CompressionFlags, Option<ImageFormat>) {
+    let rule = config.format_rule_for(path, &format.to_string());
+    let global = config.active_format_options();
+    let convert_to_str = rule
+        .and_then(|r| r.convert_to.clone())
+        .or_else(|| config.folder_settings_for(path).target_format)
+        .or_else(|| match format {
+            ImageFormat::Png => global.png.convert_to.clone(),
+            ImageFormat::Jpeg => global.jpeg.convert_to.clone(),
+            ImageFormat::WebP => global.webp.convert_to.clone(),
+            ImageFormat::Avif => global.avif.convert_to.clone(),
+            ImageFormat::Heif => global.heif.convert_to.clone(),
+            ImageFormat::Tiff => global.tiff.convert_to.clone(),
+            ImageFormat::Gif => global.gif.convert_to.clone(),
+            ImageFormat::Jxl => global.jxl.convert_to.clone(),
+            ImageFormat::Raw => global.raw.convert_to.clone(),
+            ImageFormat::Psd => global.psd.convert_to.clone(),
+        })
+        .or_else(|| config.target_format.clone());
+    let target = convert_to_str
+        .and_then(|s| ImageFormat::from_extension(&s))
+        .filter(ImageFormat::is_writable)
+        // Read-only formats are always converted
+        .or_else(|| match format {
+            ImageFormat::Psd if crate::compression::psd_has_alpha(path) => Some(ImageFormat::Png),
+            f if !f.is_writable() => Some(ImageFormat::Jpeg),
+            _ => None,
+        });
+    let effective = target.unwrap_or(format);
+    let opts = match rule {
+        Some(rule) if !rule.options.is_empty() => {
+            std::borrow::Cow::Owned(with_overrides(global, effective, &rule.options))
+        }
+        _ => std::borrow::Cow::Borrowed(global),
+    };
+    let encoded_as = match format {
+        ImageFormat::Raw | ImageFormat::Psd => format,
+        _ => effective,
+    };
+    let quality = rule
+        .and_then(|r| r.quality)
+        .or_else(|| config.quality_for(encoded_as))
+        .unwrap_or_else(|| format_quality(&opts, encoded_as));
+    let mut flags = CompressionFlags {
+        deterministic: config.deterministic,
+        ..CompressionFlags::from_format_options(&opts, effective)
+    };
+    let interlace = flags.jpeg_interlace;
+    apply_folder_flags(config, path, &mut flags);
+    // A format rule that sets interlacing itself is more specific
+    if rule.is_some_and(|r| r.options.contains_key("interlace")) {
+        flags.jpeg_interlace = interlace;
+    }
+    crate::turbo::apply(&mut flags);
+    (quality, flags, target)
+}
+
+/// Quality and encoder flags for writing `path` as `format` without any
+/// format rule.
+fn output_settings(
+    config: &crate::config::AppConfig,
+    path: &Path,
+    format: ImageFormat,
+) -> (u8, CompressionFlags) {
+    let opts = config.active_format_options();
+    let quality = config
+        .quality_for(format)
+        .unwrap_or_else(|| format_quality(opts, format));
+    let mut flags = CompressionFlags {
+        deterministic: config.deterministic,
+        ..CompressionFlags::from_format_options(opts, format)
+    };
+    apply_folder_flags(config, path, &mut flags);
+    crate::turbo::apply(&mut flags);
+    (quality, flags)
+}
+
+/// Encoder settings the folder containing `path` overrides for every format.
+fn apply_folder_flags(
+    config: &crate::config::AppConfig,
+    path: &Path,
+    flags: &mut CompressionFlags,
+) {
+    let folder = config.folder_settings_for(path);
+    if let Some(progressive) = folder.progressive_jpeg {
+        flags.jpeg_interlace = progressive;
+    }
+    flags.strip_gps = folder.strip_location.unwrap_or(config.strip_location);
+    flags.keep_metadata =
+        flags.strip_gps || folder.preserve_metadata.unwrap_or(config.preserve_metadata);
+    flags.keep_icc = config.keep_icc_profile;
+    flags.keep_cmyk = config.keep_cmyk;
+    flags.watermark = folder.watermark.then(|| config.watermark.clone());
+    flags.trim = folder
+        .trim_borders
+        .unwrap_or(config.trim_borders && crate::ocr::is_screenshot(path));
+}
+
+/// Bring over metadata the encoder dropped, or just the ICC profile, then
+/// drop location when asked. Returns the output's new size.
+fn preserve_metadata(path: &Path, output: &Path, size: u64, flags: &CompressionFlags) -> u64 {
+    let copied = if flags.keep_metadata {
+        crate::metadata::preserve(path, output)
+    } else {
+        crate::metadata::copy_icc(path, output)
+    };
+    let mut size = match copied {
+        Ok(size) => size,
+        Err(e) => {
+            warn!(
+                "[processor] Could not copy metadata to {}: {}",
+                output.display(),
+                e
+            );
+            size
+        }
+    };
+    if flags.strip_gps {
+        match crate::metadata::strip_location(output) {
+            Ok(stripped) => size = stripped,
+            Err(e) => warn!(
+                "[processor] Could not strip location from {}: {}",
+                output.display(),
+                e
+            ),
+        }
+    }
+    size
+}
+
+/// Quality configured in `opts` for writing `format`.
+fn format_quality(opts: &crate::config::FormatOptions, format: ImageFormat) -> u8 {
+    match format {
+        ImageFormat::Png => opts.png.quality,
+        ImageFormat::Jpeg => opts.jpeg.quality,
+        ImageFormat::WebP => opts.webp.quality,
+        ImageFormat::Avif => opts.avif.quality,
+        ImageFormat::Heif => opts.heif.quality,
+        ImageFormat::Tiff => opts.tiff.quality,
+        ImageFormat::Gif => opts.gif.quality,
+        ImageFormat::Jxl => opts.jxl.quality,
+        ImageFormat::Raw => opts.raw.quality,
+        ImageFormat::Psd => opts.psd.quality,
     }
 }
 
-fn format_bytes(bytes: u64) -> String {
-    let kb = bytes as f64 / 1024.0;
-    if kb < 1024.0 {
-        return format!("{:.1} KB", kb);
+/// Copy of `opts` with `overrides` merged into the section for `format`.
+/// Overrides that don't fit the section's schema are ignored.
+fn with_overrides(
+    opts: &crate::config::FormatOptions,
+    format: ImageFormat,
+    overrides: &serde_json::Map<String, serde_json::Value>,
+) -> crate::config::FormatOptions {
+    let Ok(mut value) = serde_json::to_value(opts) else {
+        return opts.clone();
+    };
+    if let Some(section) = value
+        .get_mut(format.to_string())
+        .and_then(|s| s.as_object_mut())
+    {
+        section.extend(overrides.clone());
     }
-    let mb = kb / 1024.0;
-    format!("{:.1} MB", mb)
+    serde_json::from_value(value).unwrap_or_else(|e| {
+        warn!("[processor] Ignoring folder options for {}: {}", format, e);
+        opts.clone()
+    })
 }
 
 fn wait_for_file_stability(path: &Path) -> Result<(), String> {
@@ -0,0 +1,157 @@
+use crate::compression::CompressionRecord;
+use log::{info, warn};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+const BACKUP_DIR: &str = "backups";
+/// Moving an output over its original looks like a new file to the
+/// watcher; events for it are ignored this long.
+const OWN_WRITE_WINDOW: Duration = Duration::from_secs(10);
+
+static OWN_WRITES: Mutex<Vec<(PathBuf, Instant)>> = Mutex::new(Vec::new());
+
+fn mark_own_write(path: &Path) {
+    if let Ok(mut writes) = OWN_WRITES.lock() {
+        writes.retain(|(_, at)| at.elapsed() < OWN_WRITE_WINDOW);
+        writes.push((path.to_path_buf(), Instant::now()));
+    }
+}
+
+/// Whether Hat itself just wrote `path` in place of an original.
+pub fn is_own_write(path: &Path) -> bool {
+    OWN_WRITES.lock().is_ok_and(|writes| {
+        writes
+            .iter()
+            .any(|(p, at)| p == path && at.elapsed() < OWN_WRITE_WINDOW)
+    })
+}
+
+fn backup_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join(BACKUP_DIR);
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+/// Rename, or copy and delete when `to` is on another volume.
+pub fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    std::fs::copy(from, to)?;
+    std::fs::remove_file(from)
+}
+
+/// In replace-in-place mode, move `record`'s output over its original once
+/// the original is copied to the backup folder. The record then points at
+/// the original's path, with the output's extension, and at the backup.
+/// Failures leave the output where it was.
+pub fn apply(app: &tauri::AppHandle, record: &mut CompressionRecord) {
+    let (enabled, extra_outputs) = app
+        .state::<Mutex<crate::config::ConfigManager>>()
+        .lock()
+        .map_or((false, false), |c| {
+            (
+                c.config.replace_in_place,
+                !c.config.active_format_options().extra_outputs.is_empty(),
+            )
+        });
+    // Reused, encrypted and paired outputs keep their own paths
+    if !enabled
+        || record.analysis_only
+        || record.encrypted
+        || record.group_id.is_some()
+        || record.reused_from.is_some()
+        || crate::pairs::is_destination(Path::new(&record.final_path))
+    {
+        return;
+    }
+    // Extra outputs are written after this one and still need the original.
+    // The settings refuse the combination, but a profile can still bring it in
+    if extra_outputs {
+        warn!(
+            "[replace] Keeping {} next to its original: extra output formats still need it",
+            record.final_path
+        );
+        return;
+    }
+    if let Err(e) = replace(app, record) {
+        warn!(
+            "[replace] Keeping {} next to its original: {}",
+            record.final_path, e
+        );
+    }
+}
+
+fn replace(app: &tauri::AppHandle, record: &mut CompressionRecord) -> Result<(), String> {
+    let original = PathBuf::from(&record.initial_path);
+    let output = PathBuf::from(&record.final_path);
+    // `Photo.PNG` replaced by a `.png` keeps its spelling: on APFS and NTFS
+    // the two names are the same file
+    let target = match output.extension() {
+        Some(ext) if !ext.eq_ignore_ascii_case(original.extension().unwrap_or_default()) => {
+            original.with_extension(ext)
+        }
+        _ => original.clone(),
+    };
+    if target != original && target.exists() {
+        return Err(format!("{} already exists", target.display()));
+    }
+
+    let dir = backup_dir(app)?;
+    let name = original
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or("Invalid file name")?;
+    let backup = (0..)
+        .map(|n| match n {
+            0 => dir.join(format!("{}-{}", record.timestamp, name)),
+            n => dir.join(format!("{}-{}-{}", record.timestamp, n, name)),
+        })
+        .find(|p| !p.exists())
+        .ok_or("No free backup name")?;
+    std::fs::copy(&original, &backup).map_err(|e| format!("backup failed: {e}"))?;
+
+    mark_own_write(&target);
+    move_file(&output, &target).map_err(|e| e.to_string())?;
+    if target != original {
+        std::fs::remove_file(&original).map_err(|e| e.to_string())?;
+    }
+    info!(
+        "[replace] Replaced {} with its output, backup at {}",
+        original.display(),
+        backup.display()
+    );
+    record.final_path = target.display().to_string();
+    record.backup_path = Some(backup.display().to_string());
+    Ok(())
+}
+
+/// Put the backed-up original of record `id` back and drop the record.
+pub fn undo(app: &tauri::AppHandle, id: u64) -> Result<(), String> {
+    let log = app.state::<Mutex<crate::log::CompressionLog>>();
+    let mut log = log.lock().map_err(|e| e.to_string())?;
+    let record = log.find(id).ok_or("No such record")?;
+    let backup = record
+        .backup_path
+        .clone()
+        .ok_or("This output did not replace its original")?;
+    let original = PathBuf::from(&record.initial_path);
+    let output = PathBuf::from(&record.final_path);
+
+    mark_own_write(&original);
+    move_file(Path::new(&backup), &original).map_err(|e| e.to_string())?;
+    if output != original {
+        if let Err(e) = std::fs::remove_file(&output) {
+            warn!("[replace] Could not remove {}: {}", output.display(), e);
+        }
+    }
+    info!("[replace] Restored {} from {}", original.display(), backup);
+    log.remove(&[id]);
+    Ok(())
+}
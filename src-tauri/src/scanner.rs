@@ -0,0 +1,128 @@
+use crate::compression::ImageFormat;
+use crate::config::FolderSettings;
+use log::info;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+use tauri::Manager;
+
+/// Scans in progress, so a long walk can be stopped from the UI.
+#[derive(Default)]
+pub struct Scans {
+    next_id: AtomicU64,
+    running: Mutex<HashMap<u64, Arc<AtomicBool>>>,
+}
+
+/// Cancellation flag of one scan, checked between files. Unregisters the
+/// scan when dropped.
+pub struct ScanHandle {
+    pub id: u64,
+    cancelled: Arc<AtomicBool>,
+    app: tauri::AppHandle,
+}
+
+impl ScanHandle {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for ScanHandle {
+    fn drop(&mut self) {
+        if let Ok(mut running) = self.app.state::<Scans>().running.lock() {
+            running.remove(&self.id);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanStarted {
+    pub scan_id: u64,
+    pub folder: String,
+}
+
+/// Emitted when a scan ends; counts cover only what ran before a cancel.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanSummary {
+    pub scan_id: u64,
+    pub folder: String,
+    pub total: usize,
+    pub processed: usize,
+    pub cancelled: bool,
+    pub energy: crate::energy::EnergyEstimate,
+}
+
+pub fn start_scan(app: &tauri::AppHandle) -> ScanHandle {
+    let scans = app.state::<Scans>();
+    let id = scans.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+    let cancelled = Arc::new(AtomicBool::new(false));
+    if let Ok(mut running) = scans.running.lock() {
+        running.insert(id, cancelled.clone());
+    }
+    ScanHandle {
+        id,
+        cancelled,
+        app: app.clone(),
+    }
+}
+
+/// Ask scan `id` to stop. False when no such scan is running.
+pub fn cancel_scan(app: &tauri::AppHandle, id: u64) -> bool {
+    let scans = app.state::<Scans>();
+    let Ok(running) = scans.running.lock() else {
+        return false;
+    };
+    match running.get(&id) {
+        Some(cancelled) => {
+            cancelled.store(true, Ordering::Relaxed);
+            info!("[scanner] Cancelling scan {id}");
+            true
+        }
+        None => false,
+    }
+}
+
+/// Creation time in seconds since the epoch, falling back to modification time
+/// on filesystems that don't record it.
+pub fn created_at(path: &Path) -> Option<u64> {
+    let meta = std::fs::metadata(path).ok()?;
+    let time = meta.created().or_else(|_| meta.modified()).ok()?;
+    Some(time.duration_since(UNIX_EPOCH).ok()?.as_secs())
+}
+
+pub fn passes_age_filter(settings: &FolderSettings, path: &Path) -> bool {
+    match settings.cutoff() {
+        Some(cutoff) => created_at(path).is_some_and(|t| t >= cutoff),
+        None => true,
+    }
+}
+
+/// Images already sitting in `folder` that the watcher would have processed.
+pub fn scan_folder(folder: &Path, settings: &FolderSettings, scan: &ScanHandle) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(folder) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .take_while(|_| !scan.is_cancelled())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .filter(|p| match ImageFormat::from_path(p) {
+            Some(ImageFormat::Raw) => settings.decode_raw,
+            Some(_) => true,
+            None => false,
+        })
+        .filter(|p| crate::watcher::skip_reason(p).is_none())
+        .filter(|p| passes_age_filter(settings, p))
+        .collect();
+    files.sort();
+    info!(
+        "[scanner] Found {} files to process in {}",
+        files.len(),
+        folder.display()
+    );
+    files
+}
@@ -0,0 +1,205 @@
+use crate::compression::CompressionRecord;
+use log::{info, warn};
+use rhai::{Dynamic, Engine, Scope, AST};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+/// Each hook call is aborted once it runs longer than this.
+const SCRIPT_TIME_LIMIT: Duration = Duration::from_secs(2);
+const MAX_OPERATIONS: u64 = 5_000_000;
+const SCRIPT_EXTENSION: &str = "rhai";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScriptInfo {
+    pub name: String,
+    pub enabled: bool,
+    pub error: Option<String>,
+}
+
+struct LoadedScript {
+    name: String,
+    ast: AST,
+}
+
+/// Compiled user scripts from `<config dir>/scripts`.
+pub struct Scripts {
+    pub dir: PathBuf,
+    loaded: Mutex<Vec<LoadedScript>>,
+    errors: Mutex<HashMap<String, String>>,
+}
+
+impl Scripts {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            loaded: Mutex::new(Vec::new()),
+            errors: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn script_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(&self.dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| p.extension().and_then(|e| e.to_str()) == Some(SCRIPT_EXTENSION))
+                    .filter_map(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    /// Recompile every enabled script, remembering compile errors for `list`.
+    pub fn reload(&self, enabled: &[String]) {
+        let _ = std::fs::create_dir_all(&self.dir);
+        let engine = Engine::new();
+        let mut loaded = Vec::new();
+        let mut errors = HashMap::new();
+
+        for name in self.script_names() {
+            if !enabled.contains(&name) {
+                continue;
+            }
+            match engine.compile_file(self.dir.join(&name)) {
+                Ok(ast) => loaded.push(LoadedScript { name, ast }),
+                Err(e) => {
+                    warn!("[scripts] Failed to compile {}: {}", name, e);
+                    errors.insert(name, e.to_string());
+                }
+            }
+        }
+
+        info!("[scripts] Loaded {} scripts", loaded.len());
+        if let Ok(mut l) = self.loaded.lock() {
+            *l = loaded;
+        }
+        if let Ok(mut e) = self.errors.lock() {
+            *e = errors;
+        }
+    }
+
+    /// Names of loaded scripts that define `hook`.
+    pub fn with_hook(&self, hook: &str) -> Vec<String> {
+        self.loaded
+            .lock()
+            .map(|loaded| {
+                loaded
+                    .iter()
+                    .filter(|s| s.ast.iter_functions().any(|f| f.name == hook))
+                    .map(|s| s.name.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn list(&self, enabled: &[String]) -> Vec<ScriptInfo> {
+        let errors = self.errors.lock().map(|e| e.clone()).unwrap_or_default();
+        self.script_names()
+            .into_iter()
+            .map(|name| ScriptInfo {
+                enabled: enabled.contains(&name),
+                error: errors.get(&name).cloned(),
+                name,
+            })
+            .collect()
+    }
+}
+
+/// Build a sandboxed engine exposing only the script API.
+fn engine(app: &tauri::AppHandle, script: &str) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    let started = Instant::now();
+    engine.on_progress(move |_| (started.elapsed() > SCRIPT_TIME_LIMIT).then_some(Dynamic::UNIT));
+
+    let name = script.to_string();
+    engine.on_print(move |msg| info!("[script:{}] {}", name, msg));
+
+    let handle = app.clone();
+    engine.register_fn("enqueue", move |path: &str| {
+        let vips = handle.state::<crate::watcher::VipsState>().get();
+        let (h, p) = (handle.clone(), PathBuf::from(path));
+        if !p.is_file() {
+            return false;
+        }
+        std::thread::spawn(move || {
+            let _ = crate::processor::process_any(
+                &h,
+                vips.as_ref(),
+                &p,
+                crate::processor::InputMode::Manual,
+            );
+        });
+        true
+    });
+
+    // Never overwrites: scripts must not be able to destroy files
+    engine.register_fn("move_file", |from: &str, to: &str| {
+        let (from, to) = (Path::new(from), Path::new(to));
+        from.is_file() && !to.exists() && std::fs::rename(from, to).is_ok()
+    });
+
+    let handle = app.clone();
+    engine.register_fn("notify", move |title: &str, body: &str| {
+        use tauri_plugin_notification::NotificationExt;
+        let _ = handle
+            .notification()
+            .builder()
+            .title(title)
+            .body(body)
+            .show();
+    });
+
+    engine
+}
+
+fn call_hook(
+    app: &tauri::AppHandle,
+    hook: &str,
+    args: impl Fn() -> Dynamic,
+) -> Vec<(String, Dynamic)> {
+    let scripts = app.state::<Scripts>();
+    let Ok(loaded) = scripts.loaded.lock() else {
+        return Vec::new();
+    };
+
+    let mut results = Vec::new();
+    for script in loaded.iter() {
+        if !script.ast.iter_functions().any(|f| f.name == hook) {
+            continue;
+        }
+        let engine = engine(app, &script.name);
+        match engine.call_fn::<Dynamic>(&mut Scope::new(), &script.ast, hook, (args(),)) {
+            Ok(result) => results.push((script.name.clone(), result)),
+            Err(e) => warn!("[scripts] {} in {} failed: {}", hook, script.name, e),
+        }
+    }
+    results
+}
+
+/// Run `on_file_detected(path)`; a script returning `false` skips the file.
+pub fn on_file_detected(app: &tauri::AppHandle, path: &Path) -> bool {
+    let path = path.display().to_string();
+    for (script, result) in call_hook(app, "on_file_detected", || path.clone().into()) {
+        if result.as_bool() == Ok(false) {
+            info!("[scripts] {} skipped {}", script, path);
+            return false;
+        }
+    }
+    true
+}
+
+/// Run `on_compressed(record)` with the record as an object map.
+pub fn on_compressed(app: &tauri::AppHandle, record: &CompressionRecord) {
+    let Ok(record) = rhai::serde::to_dynamic(record) else {
+        return;
+    };
+    call_hook(app, "on_compressed", || record.clone());
+}
@@ -0,0 +1,96 @@
+use crate::locale::format_bytes;
+use log::info;
+use serde::Serialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
+
+/// When the main window was hidden, in seconds since the epoch.
+static HIDDEN_SINCE: Mutex<Option<u64>> = Mutex::new(None);
+/// Failures while hidden; they never make it into the history.
+static FAILURES: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSummary {
+    pub since: u64,
+    pub compressed: usize,
+    pub bytes_saved: u64,
+    pub failures: usize,
+    pub message: String,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// The main window went to the background; start counting.
+pub fn hidden() {
+    if let Ok(mut since) = HIDDEN_SINCE.lock() {
+        if since.is_none() {
+            *since = Some(now());
+            FAILURES.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+pub fn record_failure() {
+    if HIDDEN_SINCE.lock().is_ok_and(|since| since.is_some()) {
+        FAILURES.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// The main window is back: emit `session-summary` with what happened
+/// since it was hidden, if anything did.
+pub fn shown(app: &tauri::AppHandle) {
+    let Some(since) = HIDDEN_SINCE.lock().ok().and_then(|mut since| since.take()) else {
+        return;
+    };
+    let failures = FAILURES.swap(0, Ordering::Relaxed);
+    let (compressed, bytes_saved) = app
+        .state::<Mutex<crate::log::CompressionLog>>()
+        .lock()
+        .map(|log| {
+            log.records
+                .iter()
+                .filter(|r| r.timestamp >= since && !r.analysis_only)
+                .fold((0, 0), |(count, saved), r| {
+                    (
+                        count + 1,
+                        saved + r.initial_size.saturating_sub(r.compressed_size),
+                    )
+                })
+        })
+        .unwrap_or_default();
+    if compressed == 0 && failures == 0 {
+        return;
+    }
+
+    let mut message = format!(
+        "While you were away: {} {} compressed, {} saved",
+        compressed,
+        if compressed == 1 { "file" } else { "files" },
+        format_bytes(bytes_saved)
+    );
+    if failures > 0 {
+        message.push_str(&format!(
+            ", {} {}",
+            failures,
+            if failures == 1 { "failure" } else { "failures" }
+        ));
+    }
+    info!("[session] {}", message);
+    let _ = app.emit(
+        "session-summary",
+        &SessionSummary {
+            since,
+            compressed,
+            bytes_saved,
+            failures,
+            message,
+        },
+    );
+}
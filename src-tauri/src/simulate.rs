@@ -0,0 +1,166 @@
+use crate::compression::{planned_output_path, CompressionFlags, ImageFormat};
+use crate::config::AppConfig;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+
+/// What would happen to a file, computed without touching it.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RuleSimulation {
+    pub path: String,
+    /// Set when the file would be ignored entirely.
+    pub skipped: Option<String>,
+    /// Human-readable list of the settings that matched, in evaluation order.
+    pub rules: Vec<String>,
+    pub engine: Option<String>,
+    pub source_format: Option<String>,
+    pub target_format: Option<String>,
+    pub quality: Option<u8>,
+    pub output_path: Option<String>,
+}
+
+/// The settings a file would be compressed with, merged from the global
+/// options, the active profile, its folder's settings and format rule.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedSettings {
+    pub path: String,
+    pub source_format: String,
+    pub target_format: String,
+    pub quality: u8,
+    pub flags: CompressionFlags,
+    pub output_path: Option<String>,
+    /// The layers that contributed, least specific first.
+    pub layers: Vec<String>,
+}
+
+pub fn resolve(config: &AppConfig, path: &Path) -> Result<ResolvedSettings, String> {
+    let format = ImageFormat::from_path(path).ok_or_else(|| "Unsupported format".to_string())?;
+    let (quality, flags, convert_to) =
+        crate::processor::resolve_format_settings(config, path, format);
+
+    let mut layers = vec!["global".to_string()];
+    if let Some(profile) = config
+        .active_profile
+        .as_ref()
+        .filter(|name| config.profiles.iter().any(|p| &p.name == *name))
+    {
+        layers.push(format!("profile {}", profile));
+    }
+    if let Some(dir) = path.parent().filter(|dir| {
+        config
+            .folder_settings
+            .iter()
+            .any(|(folder, _)| Path::new(folder) == *dir)
+    }) {
+        layers.push(format!("folder {}", dir.display()));
+    }
+    if config.format_rule_for(path, &format.to_string()).is_some() {
+        layers.push(format!("{} rule", format));
+    }
+
+    let mut output_path = planned_output_path(path, convert_to.map(|f| f.extension()))
+        .map(|p| p.display().to_string());
+    if config.folder_settings_for(path).encrypt_output {
+        output_path = output_path.map(|o| format!("{}.enc", o));
+    }
+    Ok(ResolvedSettings {
+        path: path.display().to_string(),
+        source_format: format.to_string(),
+        target_format: convert_to.unwrap_or(format).to_string(),
+        quality,
+        flags,
+        output_path,
+        layers,
+    })
+}
+
+pub fn simulate(config: &AppConfig, detect_hooks: &[String], path: &Path) -> RuleSimulation {
+    let mut sim = RuleSimulation {
+        path: path.display().to_string(),
+        ..Default::default()
+    };
+
+    let watched = config
+        .watched_folders
+        .iter()
+        .any(|f| path.parent() == Some(Path::new(f)));
+    if watched {
+        sim.rules.push("In a watched folder".into());
+        if let Some(reason) = crate::watcher::skip_reason(path) {
+            sim.skipped = Some(format!("Watcher ignores {}s", reason));
+            return sim;
+        }
+        if crate::watcher::PAUSED.load(Ordering::Relaxed) {
+            sim.rules
+                .push("Watching is paused: only manual compression applies".into());
+        }
+        for script in detect_hooks {
+            sim.rules
+                .push(format!("Script {} may skip it (on_file_detected)", script));
+        }
+        if !config.producer_rules.is_empty() {
+            sim.rules
+                .push("Producer rules apply to the app that writes it".into());
+        }
+        if config.sensitive_guard.enabled {
+            sim.rules
+                .push("Sensitive guard may hold it for approval".into());
+        }
+    }
+
+    let Some(format) = ImageFormat::from_path(path) else {
+        sim.skipped = Some("Unsupported format".into());
+        return sim;
+    };
+    sim.source_format = Some(format.to_string());
+
+    let folder = config.folder_settings_for(path);
+    if folder.game_asset_mode {
+        sim.rules.push("Folder: game asset mode".into());
+        sim.engine = Some("texture".into());
+        let ext = match config.texture.output {
+            crate::config::TextureOutput::Ktx2 if config.texture.encoder_path.is_some() => "ktx2",
+            crate::config::TextureOutput::Dds => "dds",
+            _ => "png",
+        };
+        sim.target_format = Some(ext.into());
+        sim.quality = Some(100);
+        sim.output_path = planned_output_path(path, Some(ext)).map(|p| p.display().to_string());
+        return sim;
+    }
+
+    if config.format_rule_for(path, &format.to_string()).is_some() {
+        sim.rules.push(format!("Folder: {} format rule", format));
+    }
+    let (quality, _, convert_to) = crate::processor::resolve_format_settings(config, path, format);
+    let target = convert_to.unwrap_or(format);
+    if let Some(target) = convert_to {
+        sim.rules
+            .push(format!("Format options: convert {} to {}", format, target));
+    }
+    sim.rules
+        .push(format!("Format options: {} quality {}", target, quality));
+    sim.target_format = Some(target.to_string());
+    sim.quality = Some(quality);
+
+    if folder.observe_only {
+        sim.rules
+            .push("Folder: observe only, nothing is written".into());
+        sim.engine = Some("analysis".into());
+        return sim;
+    }
+
+    sim.engine = Some("libvips".into());
+    let mut output = planned_output_path(path, convert_to.map(|f| f.extension()))
+        .map(|p| p.display().to_string());
+    if folder.encrypt_output {
+        sim.rules.push("Folder: encrypt output".into());
+        output = output.map(|o| format!("{}.enc", o));
+    }
+    if config.checksum_mode != crate::config::ChecksumMode::Off {
+        sim.rules
+            .push(format!("Checksums: {:?}", config.checksum_mode).to_lowercase());
+    }
+    sim.output_path = output;
+    sim
+}
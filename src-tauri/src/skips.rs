@@ -0,0 +1,100 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Oldest entries are dropped past this many.
+const MAX_ENTRIES: usize = 1000;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static SKIPPED: Mutex<VecDeque<SkippedFile>> = Mutex::new(VecDeque::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    TemporaryFile,
+    CompressedOutput,
+    UnsupportedFormat,
+    Paused,
+    ProducerApp,
+    OlderThanCutoff,
+    ScriptVeto,
+    RemovableVolume,
+    DuplicateDownload,
+    RecentlyOpened,
+    /// More than 8 bits per sample or HDR, with the policy set to skip.
+    HighDepth,
+    /// The folder or global settings leave this kind of file alone.
+    Disabled,
+    /// The output path is taken, with the collision policy set to skip.
+    OutputExists,
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::TemporaryFile => "temporary file",
+            Self::CompressedOutput => "compressed file",
+            Self::UnsupportedFormat => "unsupported format",
+            Self::Paused => "paused",
+            Self::ProducerApp => "filtered by app",
+            Self::OlderThanCutoff => "older than cutoff",
+            Self::ScriptVeto => "rejected by script",
+            Self::RemovableVolume => "removable volume",
+            Self::DuplicateDownload => "duplicate download",
+            Self::RecentlyOpened => "recently opened",
+            Self::HighDepth => "high bit depth or HDR",
+            Self::Disabled => "disabled",
+            Self::OutputExists => "output exists",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedFile {
+    pub path: String,
+    pub reason: SkipReason,
+    pub detail: String,
+    pub timestamp: u64,
+}
+
+/// Set from the config; nothing is kept while off.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+    if !enabled {
+        if let Ok(mut skipped) = SKIPPED.lock() {
+            skipped.clear();
+        }
+    }
+}
+
+pub fn record(path: &Path, reason: SkipReason, detail: &str) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    if let Ok(mut skipped) = SKIPPED.lock() {
+        if skipped.len() >= MAX_ENTRIES {
+            skipped.pop_front();
+        }
+        skipped.push_back(SkippedFile {
+            path: path.display().to_string(),
+            reason,
+            detail: detail.to_string(),
+            timestamp,
+        });
+    }
+}
+
+/// Up to `limit` skipped files, newest first.
+pub fn recent(limit: usize) -> Vec<SkippedFile> {
+    SKIPPED
+        .lock()
+        .map(|skipped| skipped.iter().rev().take(limit).cloned().collect())
+        .unwrap_or_default()
+}
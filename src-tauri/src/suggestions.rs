@@ -0,0 +1,73 @@
+use log::info;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+/// New folders and inserted cards are noticed within this interval.
+const POLL_INTERVAL: Duration = Duration::from_secs(20);
+
+/// A folder worth offering to watch.
+#[derive(Debug, Clone, Serialize)]
+pub struct FolderSuggestion {
+    pub path: String,
+    /// Where it came from, e.g. "Chrome (Profile 1)" or "Camera card (EOS_DIGITAL)".
+    pub source: String,
+}
+
+/// Standard folders that currently exist: the screenshot folder and the
+/// `DCIM` folder of every mounted camera card.
+fn present() -> Vec<FolderSuggestion> {
+    let mut found = Vec::new();
+    if let Some(dir) = crate::platform::screenshot_dir() {
+        found.push(FolderSuggestion {
+            path: dir.display().to_string(),
+            source: "Screenshots".to_string(),
+        });
+    }
+    for volume in crate::platform::mounted_volumes() {
+        let name = volume
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| volume.display().to_string());
+        found.push(FolderSuggestion {
+            path: volume.join("DCIM").display().to_string(),
+            source: format!("Camera card ({name})"),
+        });
+    }
+    found.retain(|s| Path::new(&s.path).is_dir());
+    found
+}
+
+/// Spawn the thread that emits `folder-suggestion` whenever a standard
+/// folder appears that isn't watched yet. Folders present at launch are not
+/// suggested; a card that is removed and reinserted is suggested again.
+pub fn start(app: &tauri::AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let mut seen: HashSet<String> = present().into_iter().map(|s| s.path).collect();
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let current = present();
+            let watched = app
+                .state::<Mutex<crate::config::ConfigManager>>()
+                .lock()
+                .map(|c| c.config.watched_folders.clone())
+                .unwrap_or_default();
+            for suggestion in &current {
+                let path = Path::new(&suggestion.path);
+                if seen.contains(&suggestion.path) || watched.iter().any(|w| Path::new(w) == path) {
+                    continue;
+                }
+                info!(
+                    "[suggestions] New folder {} ({})",
+                    suggestion.path, suggestion.source
+                );
+                let _ = app.emit("folder-suggestion", suggestion);
+            }
+            seen = current.into_iter().map(|s| s.path).collect();
+        }
+    });
+}
@@ -0,0 +1,380 @@
+use crate::compression::{CompressionError, Result};
+use crate::config::SvgConfig;
+use log::info;
+use std::borrow::Cow;
+use std::path::Path;
+
+/// Elements whose character data is rendered or parsed, so it is kept as is.
+const VERBATIM_ELEMENTS: &[&str] = &[
+    "text",
+    "tspan",
+    "textPath",
+    "style",
+    "script",
+    "title",
+    "desc",
+    "foreignObject",
+];
+
+/// Namespaces design tools write their own bookkeeping into.
+const EDITOR_NAMESPACES: &[&str] = &["inkscape", "sodipodi", "sketch", "serif"];
+
+pub fn is_svg(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("svg"))
+}
+
+/// Minify an SVG and write it to `output`, returning the new size.
+pub fn compress_svg(input: &Path, output: &Path, settings: &SvgConfig) -> Result<u64> {
+    let source = std::fs::read_to_string(input)?;
+    let minified = minify(&source, settings.precision).map_err(CompressionError::Svg)?;
+    std::fs::write(output, &minified)?;
+    info!(
+        "[svg] Minified {}: {} → {} bytes",
+        input.display(),
+        source.len(),
+        minified.len()
+    );
+    Ok(minified.len() as u64)
+}
+
+/// Drop comments, editor metadata and insignificant whitespace, and rewrite
+/// path data with coordinates rounded to `precision` decimals.
+pub fn minify(source: &str, precision: u8) -> std::result::Result<String, String> {
+    let mut out = String::with_capacity(source.len() / 2);
+    let mut rest = source.trim_start_matches('\u{feff}');
+    let mut open: Vec<&str> = Vec::new();
+    // Nesting depth inside a dropped element, 0 while emitting
+    let mut skipping = 0usize;
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("<!--") {
+            let end = after.find("-->").ok_or("Unterminated comment")?;
+            rest = &after[end + 3..];
+        } else if rest.starts_with("<![CDATA[") {
+            let end = rest.find("]]>").ok_or("Unterminated CDATA section")? + 3;
+            if skipping == 0 {
+                out.push_str(&rest[..end]);
+            }
+            rest = &rest[end..];
+        } else if rest.starts_with("<?") {
+            let end = rest
+                .find("?>")
+                .ok_or("Unterminated processing instruction")?
+                + 2;
+            // The XML declaration is optional for UTF-8; stylesheet links are not
+            if skipping == 0 && !rest.starts_with("<?xml ") {
+                out.push_str(&rest[..end]);
+            }
+            rest = &rest[end..];
+        } else if rest.starts_with("<!") {
+            let end = declaration_end(rest).ok_or("Unterminated declaration")?;
+            // An internal subset declares entities the document may still use
+            if rest[..end].contains('[') {
+                out.push_str(&rest[..end]);
+            }
+            rest = &rest[end..];
+        } else if let Some(after) = rest.strip_prefix("</") {
+            let end = after.find('>').ok_or("Unterminated closing tag")?;
+            if skipping > 0 {
+                skipping -= 1;
+            } else {
+                let name = after[..end].trim();
+                open.pop();
+                out.push_str("</");
+                out.push_str(name);
+                out.push('>');
+            }
+            rest = &after[end + 1..];
+        } else if rest.starts_with('<') {
+            let tag = parse_tag(rest)?;
+            rest = &rest[tag.len..];
+            if skipping > 0 {
+                if !tag.self_closing {
+                    skipping += 1;
+                }
+            } else if tag.name == "metadata" || is_editor_name(tag.name) {
+                if !tag.self_closing {
+                    skipping = 1;
+                }
+            } else {
+                write_tag(&mut out, &tag, precision);
+                if !tag.self_closing {
+                    open.push(tag.name);
+                }
+            }
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            let text = &rest[..end];
+            rest = &rest[end..];
+            if skipping > 0 {
+                continue;
+            }
+            if open.iter().any(|name| VERBATIM_ELEMENTS.contains(name)) {
+                out.push_str(text);
+            } else {
+                out.push_str(text.trim());
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+fn declaration_end(source: &str) -> Option<usize> {
+    let mut in_subset = false;
+    for (i, c) in source.char_indices() {
+        match c {
+            '[' => in_subset = true,
+            ']' => in_subset = false,
+            '>' if !in_subset => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// `inkscape:label`, `xmlns:sodipodi` and the like.
+fn is_editor_name(name: &str) -> bool {
+    let namespace = match name.strip_prefix("xmlns:") {
+        Some(namespace) => namespace,
+        None => match name.split_once(':') {
+            Some((namespace, _)) => namespace,
+            None => return false,
+        },
+    };
+    EDITOR_NAMESPACES.contains(&namespace)
+}
+
+struct Tag<'a> {
+    name: &'a str,
+    /// Name, quote character and raw value of each attribute.
+    attributes: Vec<(&'a str, char, &'a str)>,
+    self_closing: bool,
+    len: usize,
+}
+
+fn parse_tag(source: &str) -> std::result::Result<Tag<'_>, String> {
+    let bytes = source.as_bytes();
+    let name_end = source[1..]
+        .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+        .map(|n| n + 1)
+        .ok_or("Unterminated tag")?;
+    let name = &source[1..name_end];
+    if name.is_empty() {
+        return Err("Tag without a name".to_string());
+    }
+
+    let mut attributes = Vec::new();
+    let mut i = name_end;
+    loop {
+        while bytes.get(i).is_some_and(u8::is_ascii_whitespace) {
+            i += 1;
+        }
+        match bytes.get(i) {
+            None => return Err(format!("Unterminated <{name}> tag")),
+            Some(b'>') => {
+                return Ok(Tag {
+                    name,
+                    attributes,
+                    self_closing: false,
+                    len: i + 1,
+                })
+            }
+            Some(b'/') if bytes.get(i + 1) == Some(&b'>') => {
+                return Ok(Tag {
+                    name,
+                    attributes,
+                    self_closing: true,
+                    len: i + 2,
+                })
+            }
+            _ => {}
+        }
+
+        let eq = source[i..]
+            .find('=')
+            .map(|n| n + i)
+            .ok_or_else(|| format!("Malformed attribute in <{name}>"))?;
+        let attr_name = source[i..eq].trim();
+        if attr_name.is_empty() || attr_name.contains(|c: char| c.is_whitespace() || c == '>') {
+            return Err(format!("Malformed attribute in <{name}>"));
+        }
+        let mut start = eq + 1;
+        while bytes.get(start).is_some_and(u8::is_ascii_whitespace) {
+            start += 1;
+        }
+        let quote = match bytes.get(start) {
+            Some(&q @ (b'"' | b'\'')) => q as char,
+            _ => return Err(format!("Unquoted attribute {attr_name} in <{name}>")),
+        };
+        let end = source[start + 1..]
+            .find(quote)
+            .map(|n| n + start + 1)
+            .ok_or_else(|| format!("Unterminated attribute {attr_name} in <{name}>"))?;
+        attributes.push((attr_name, quote, &source[start + 1..end]));
+        i = end + 1;
+    }
+}
+
+fn write_tag(out: &mut String, tag: &Tag, precision: u8) {
+    out.push('<');
+    out.push_str(tag.name);
+    for &(name, quote, value) in &tag.attributes {
+        if is_editor_name(name) {
+            continue;
+        }
+        let value = match (tag.name, name) {
+            ("path", "d") => minify_path(value, precision).map_or(Cow::Borrowed(value), Cow::Owned),
+            ("polyline" | "polygon", "points") => {
+                minify_points(value, precision).map_or(Cow::Borrowed(value), Cow::Owned)
+            }
+            _ => Cow::Borrowed(value),
+        };
+        out.push(' ');
+        out.push_str(name);
+        out.push('=');
+        out.push(quote);
+        out.push_str(&value);
+        out.push(quote);
+    }
+    out.push_str(if tag.self_closing { "/>" } else { ">" });
+}
+
+/// Rewrite path data with the shortest separators. `None` when the data does
+/// not parse, in which case it is left alone.
+fn minify_path(data: &str, precision: u8) -> Option<String> {
+    let bytes = data.as_bytes();
+    let mut out = String::with_capacity(data.len());
+    let mut previous: Option<String> = None;
+    let mut command = 0u8;
+    let mut arg = 0usize;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b.is_ascii_whitespace() || b == b',' {
+            i += 1;
+            continue;
+        }
+        if b.is_ascii_alphabetic() {
+            if !b"MmLlHhVvCcSsQqTtAaZz".contains(&b) {
+                return None;
+            }
+            out.push(b as char);
+            previous = None;
+            command = b;
+            arg = 0;
+            i += 1;
+            continue;
+        }
+        if command == 0 || command.eq_ignore_ascii_case(&b'z') {
+            return None;
+        }
+
+        // Arc flags are single digits that may run straight into the next number
+        let (number, len) = if command.eq_ignore_ascii_case(&b'a') && matches!(arg % 7, 3 | 4) {
+            if b != b'0' && b != b'1' {
+                return None;
+            }
+            ((b as char).to_string(), 1)
+        } else {
+            let len = number_len(&bytes[i..])?;
+            let value: f64 = data[i..i + len].parse().ok()?;
+            (format_number(value, precision), len)
+        };
+        push_number(&mut out, &mut previous, number);
+        arg += 1;
+        i += len;
+    }
+
+    Some(out)
+}
+
+fn minify_points(data: &str, precision: u8) -> Option<String> {
+    let bytes = data.as_bytes();
+    let mut out = String::with_capacity(data.len());
+    let mut previous: Option<String> = None;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i].is_ascii_whitespace() || bytes[i] == b',' {
+            i += 1;
+            continue;
+        }
+        let len = number_len(&bytes[i..])?;
+        let value: f64 = data[i..i + len].parse().ok()?;
+        push_number(&mut out, &mut previous, format_number(value, precision));
+        i += len;
+    }
+
+    Some(out)
+}
+
+/// Append `number`, separated from the one before only when a sign or second
+/// decimal point would not already end it.
+fn push_number(out: &mut String, previous: &mut Option<String>, number: String) {
+    if let Some(previous) = previous.as_deref() {
+        let self_delimiting =
+            number.starts_with('-') || (number.starts_with('.') && previous.contains('.'));
+        if !self_delimiting {
+            out.push(' ');
+        }
+    }
+    out.push_str(&number);
+    *previous = Some(number);
+}
+
+/// Length of the number at the start of `bytes`, exponent included.
+fn number_len(bytes: &[u8]) -> Option<usize> {
+    let digits = |i: &mut usize| {
+        let start = *i;
+        while bytes.get(*i).is_some_and(u8::is_ascii_digit) {
+            *i += 1;
+        }
+        *i - start
+    };
+
+    let mut i = 0;
+    if matches!(bytes.first(), Some(b'+' | b'-')) {
+        i += 1;
+    }
+    let mut count = digits(&mut i);
+    if bytes.get(i) == Some(&b'.') {
+        i += 1;
+        count += digits(&mut i);
+    }
+    if count == 0 {
+        return None;
+    }
+    if matches!(bytes.get(i), Some(b'e' | b'E')) {
+        let mut j = i + 1;
+        if matches!(bytes.get(j), Some(b'+' | b'-')) {
+            j += 1;
+        }
+        if digits(&mut j) > 0 {
+            i = j;
+        }
+    }
+    Some(i)
+}
+
+/// `0.500` → `.5`, `-0.25` → `-.25`, `3.000` → `3`.
+fn format_number(value: f64, precision: u8) -> String {
+    let mut s = format!("{:.*}", precision as usize, value);
+    if s.contains('.') {
+        let len = s.trim_end_matches('0').trim_end_matches('.').len();
+        s.truncate(len);
+    }
+    if s == "-0" {
+        return "0".to_string();
+    }
+    if let Some(fraction) = s.strip_prefix("0.") {
+        return format!(".{fraction}");
+    }
+    if let Some(fraction) = s.strip_prefix("-0.") {
+        return format!("-.{fraction}");
+    }
+    s
+}
@@ -0,0 +1,368 @@
+use crate::processor::InputMode;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogResult};
+
+const DEFERRED_FILE: &str = "deferred_tasks.json";
+const FINISH_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Changes within one interval reach subscribers as a single diff.
+const SYNC_INTERVAL: Duration = Duration::from_millis(250);
+
+const FINISH_LABEL: &str = "Finish first";
+const DEFER_LABEL: &str = "Resume next launch";
+const CANCEL_LABEL: &str = "Quit now";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    #[default]
+    Pending,
+    Done,
+    Failed,
+    /// Some outputs were written and others failed.
+    Partial,
+}
+
+/// One output of a file that a preset fans out into several formats.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputTask {
+    pub format: String,
+    pub status: TaskStatus,
+}
+
+/// A file that has been handed to the processor but not finished yet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingTask {
+    /// Unique while the app runs; reassigned when deferred tasks resume.
+    #[serde(default)]
+    pub id: u64,
+    pub path: String,
+    pub mode: InputMode,
+    pub queued_at: u64,
+    /// Empty unless the file is written in more than one format.
+    #[serde(default)]
+    pub outputs: Vec<OutputTask>,
+    /// Combined status of `outputs`.
+    #[serde(default)]
+    pub status: TaskStatus,
+}
+
+#[derive(Default)]
+pub struct TaskQueue {
+    pending: Mutex<Vec<PendingTask>>,
+    next_id: AtomicU64,
+    subscribers: Mutex<Vec<Subscriber>>,
+    syncing: AtomicBool,
+}
+
+/// What a `subscribe_tasks` channel receives: one snapshot, then diffs.
+#[derive(Clone, Serialize)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum TaskSync {
+    Snapshot {
+        tasks: Vec<PendingTask>,
+    },
+    Diff {
+        added: Vec<PendingTask>,
+        updated: Vec<PendingTask>,
+        removed: Vec<u64>,
+    },
+}
+
+struct Subscriber {
+    channel: tauri::ipc::Channel<TaskSync>,
+    /// The tasks as this subscriber last saw them.
+    seen: HashMap<u64, PendingTask>,
+}
+
+/// Mark `paths` as waiting to be processed. Paths already queued are kept as is.
+pub fn enqueue(app: &tauri::AppHandle, paths: &[PathBuf], mode: InputMode) {
+    let queued_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let queue = app.state::<TaskQueue>();
+    if let Ok(mut pending) = queue.pending.lock() {
+        for path in paths {
+            let path = path.display().to_string();
+            if !pending.iter().any(|t| t.path == path) {
+                pending.push(PendingTask {
+                    id: queue.next_id.fetch_add(1, Ordering::Relaxed) + 1,
+                    path,
+                    mode,
+                    queued_at,
+                    outputs: Vec::new(),
+                    status: TaskStatus::Pending,
+                });
+            }
+        }
+    }
+}
+
+pub fn pending(app: &tauri::AppHandle) -> Vec<PendingTask> {
+    app.state::<TaskQueue>()
+        .pending
+        .lock()
+        .map(|p| p.clone())
+        .unwrap_or_default()
+}
+
+/// Drop a queued file that will not be processed after all.
+pub fn dequeue(app: &tauri::AppHandle, path: &Path) {
+    let path = path.display().to_string();
+    if let Ok(mut pending) = app.state::<TaskQueue>().pending.lock() {
+        pending.retain(|t| t.path != path);
+    }
+}
+
+/// Send the current tasks to `channel`, then what changed about them every
+/// `SYNC_INTERVAL` for as long as the channel stays open.
+pub fn subscribe(app: &tauri::AppHandle, channel: tauri::ipc::Channel<TaskSync>) {
+    let tasks = pending(app);
+    if channel
+        .send(TaskSync::Snapshot {
+            tasks: tasks.clone(),
+        })
+        .is_err()
+    {
+        return;
+    }
+    let queue = app.state::<TaskQueue>();
+    if let Ok(mut subscribers) = queue.subscribers.lock() {
+        subscribers.push(Subscriber {
+            channel,
+            seen: tasks.into_iter().map(|t| (t.id, t)).collect(),
+        });
+    }
+    if !queue.syncing.swap(true, Ordering::Relaxed) {
+        let app = app.clone();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(SYNC_INTERVAL);
+            sync_subscribers(&app);
+        });
+    }
+}
+
+fn sync_subscribers(app: &tauri::AppHandle) {
+    let queue = app.state::<TaskQueue>();
+    let Ok(mut subscribers) = queue.subscribers.lock() else {
+        return;
+    };
+    if subscribers.is_empty() {
+        return;
+    }
+    let current = pending(app);
+    // A closed webview makes sends fail; its subscription ends there
+    subscribers.retain_mut(|subscriber| {
+        let mut added = Vec::new();
+        let mut updated = Vec::new();
+        for task in &current {
+            match subscriber.seen.get(&task.id) {
+                None => added.push(task.clone()),
+                Some(seen) if seen != task => updated.push(task.clone()),
+                Some(_) => {}
+            }
+        }
+        let removed: Vec<u64> = subscriber
+            .seen
+            .keys()
+            .filter(|id| !current.iter().any(|t| t.id == **id))
+            .copied()
+            .collect();
+        if added.is_empty() && updated.is_empty() && removed.is_empty() {
+            return true;
+        }
+        subscriber.seen = current.iter().map(|t| (t.id, t.clone())).collect();
+        subscriber
+            .channel
+            .send(TaskSync::Diff {
+                added,
+                updated,
+                removed,
+            })
+            .is_ok()
+    });
+}
+
+/// Split a queued file into one sub-task per output format.
+pub fn set_outputs(app: &tauri::AppHandle, path: &Path, formats: &[String]) {
+    update(app, path, |task| {
+        task.outputs = formats
+            .iter()
+            .map(|format| OutputTask {
+                format: format.clone(),
+                status: TaskStatus::Pending,
+            })
+            .collect();
+        task.status = TaskStatus::Pending;
+    });
+}
+
+/// Mark one output of a fanned-out file as written or failed.
+pub fn finish_output(app: &tauri::AppHandle, path: &Path, format: &str, ok: bool) {
+    update(app, path, |task| {
+        if let Some(output) = task.outputs.iter_mut().find(|o| o.format == format) {
+            output.status = if ok {
+                TaskStatus::Done
+            } else {
+                TaskStatus::Failed
+            };
+        }
+        task.status = combined_status(&task.outputs);
+    });
+}
+
+fn update(app: &tauri::AppHandle, path: &Path, f: impl FnOnce(&mut PendingTask)) {
+    let path = path.display().to_string();
+    if let Ok(mut pending) = app.state::<TaskQueue>().pending.lock() {
+        if let Some(task) = pending.iter_mut().find(|t| t.path == path) {
+            f(task);
+        }
+    }
+}
+
+fn combined_status(outputs: &[OutputTask]) -> TaskStatus {
+    let count = |status| outputs.iter().filter(|o| o.status == status).count();
+    if count(TaskStatus::Pending) > 0 {
+        TaskStatus::Pending
+    } else if count(TaskStatus::Failed) == 0 {
+        TaskStatus::Done
+    } else if count(TaskStatus::Done) == 0 {
+        TaskStatus::Failed
+    } else {
+        TaskStatus::Partial
+    }
+}
+
+/// Removes its file from the queue when dropped, however processing ends.
+pub struct TaskGuard {
+    app: tauri::AppHandle,
+    path: PathBuf,
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        dequeue(&self.app, &self.path);
+    }
+}
+
+pub fn track(app: &tauri::AppHandle, path: &Path, mode: InputMode) -> TaskGuard {
+    enqueue(app, &[path.to_path_buf()], mode);
+    TaskGuard {
+        app: app.clone(),
+        path: path.to_path_buf(),
+    }
+}
+
+/// Quit, first asking whether to finish, defer or drop any unfinished files.
+pub fn request_quit(app: &tauri::AppHandle) {
+    let app = app.clone();
+    // Message dialogs block, which must not happen on the main thread
+    std::thread::spawn(move || {
+        let count = pending(&app).len();
+        if count == 0 {
+            app.exit(0);
+            return;
+        }
+
+        let message = if count == 1 {
+            "1 file is still being compressed.".to_string()
+        } else {
+            format!("{count} files are still being compressed.")
+        };
+        let choice = app
+            .dialog()
+            .message(message)
+            .title("Quit Hat?")
+            .buttons(MessageDialogButtons::YesNoCancelCustom(
+                FINISH_LABEL.to_string(),
+                DEFER_LABEL.to_string(),
+                CANCEL_LABEL.to_string(),
+            ))
+            .blocking_show_with_result();
+
+        match choice {
+            MessageDialogResult::Custom(label) if label == FINISH_LABEL => {
+                // Stop new arrivals so the queue can drain
+                crate::watcher::PAUSED.store(true, Ordering::Relaxed);
+                while !pending(&app).is_empty() {
+                    std::thread::sleep(FINISH_POLL_INTERVAL);
+                }
+                app.exit(0);
+            }
+            MessageDialogResult::Custom(label) if label == DEFER_LABEL => {
+                crate::watcher::PAUSED.store(true, Ordering::Relaxed);
+                if let Err(e) = save_deferred(&app, &pending(&app)) {
+                    warn!("[tasks] Failed to save deferred tasks: {e}");
+                }
+                app.exit(0);
+            }
+            MessageDialogResult::Custom(label) if label == CANCEL_LABEL => app.exit(0),
+            _ => info!("[tasks] Quit cancelled"),
+        }
+    });
+}
+
+fn deferred_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_config_dir()
+        .map(|dir| dir.join(DEFERRED_FILE))
+        .map_err(|e| e.to_string())
+}
+
+fn save_deferred(app: &tauri::AppHandle, tasks: &[PendingTask]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(tasks).map_err(|e| e.to_string())?;
+    std::fs::write(deferred_path(app)?, json).map_err(|e| e.to_string())?;
+    info!("[tasks] Deferred {} tasks to the next launch", tasks.len());
+    Ok(())
+}
+
+/// Re-enqueue tasks deferred at the last quit. Files that have since
+/// disappeared are dropped.
+pub fn resume_deferred(app: &tauri::AppHandle) {
+    let Ok(path) = deferred_path(app) else {
+        return;
+    };
+    let Ok(json) = std::fs::read_to_string(&path) else {
+        return;
+    };
+    let _ = std::fs::remove_file(&path);
+    let tasks: Vec<PendingTask> = match serde_json::from_str(&json) {
+        Ok(tasks) => tasks,
+        Err(e) => {
+            warn!("[tasks] Ignoring unreadable deferred tasks: {e}");
+            return;
+        }
+    };
+    let tasks: Vec<PendingTask> = tasks
+        .into_iter()
+        .filter(|t| Path::new(&t.path).is_file())
+        .collect();
+    if tasks.is_empty() {
+        return;
+    }
+    info!("[tasks] Resuming {} deferred tasks", tasks.len());
+
+    for task in &tasks {
+        enqueue(app, &[PathBuf::from(&task.path)], task.mode);
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        use rayon::prelude::*;
+
+        let vips = app.state::<crate::watcher::VipsState>().get();
+        tasks.par_iter().for_each(|task| {
+            if let Err(e) =
+                crate::processor::process_any(&app, vips.as_ref(), Path::new(&task.path), task.mode)
+            {
+                warn!("[tasks] Deferred {} failed: {}", task.path, e);
+            }
+        });
+    });
+}
@@ -0,0 +1,357 @@
+use crate::compression::{compressed_output_path, CompressionError, Result, Vips};
+use crate::config::{TextureConfig, TextureOutput};
+use log::{info, warn};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Output of the game-asset pipeline.
+pub struct TextureOutputFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub format: &'static str,
+}
+
+/// Rounds a dimension to the closest power of two (ties round up), capped at
+/// 2^31, the largest one a `u32` holds.
+pub fn nearest_power_of_two(n: u32) -> u32 {
+    const LARGEST: u32 = 1 << 31;
+    if n <= 1 {
+        return 1;
+    }
+    if n >= LARGEST {
+        return LARGEST;
+    }
+    let upper = n.next_power_of_two();
+    let lower = upper / 2;
+    if n - lower < upper - n {
+        lower
+    } else {
+        upper
+    }
+}
+
+/// Resize `input` to power-of-two dimensions and write it as a lossless PNG,
+/// as DDS, or as KTX2 when an external encoder is configured.
+pub fn compress_texture(
+    vips: &Vips,
+    input: &Path,
+    config: &TextureConfig,
+) -> Result<TextureOutputFile> {
+    let img = vips.load_image(input)?;
+    let (width, height) = vips.dimensions(&img);
+    let (target_w, target_h) = (nearest_power_of_two(width), nearest_power_of_two(height));
+
+    let resized;
+    let source = if (target_w, target_h) != (width, height) {
+        info!(
+            "[texture] Resizing {} from {}x{} to {}x{}",
+            input.display(),
+            width,
+            height,
+            target_w,
+            target_h
+        );
+        resized = vips.resize(
+            &img,
+            target_w as f64 / width as f64,
+            target_h as f64 / height as f64,
+        )?;
+        &resized
+    } else {
+        &img
+    };
+
+    if config.output == TextureOutput::Dds {
+        let dds_output = compressed_output_path(input, Some("dds"))
+            .ok_or_else(|| CompressionError::InvalidPath(input.display().to_string()))?;
+        let (width, height, rgba) = vips.extract_rgba(source)?;
+        let size = write_dds(&dds_output, width, height, rgba)?;
+        return Ok(TextureOutputFile {
+            path: dds_output,
+            size,
+            format: "dds",
+        });
+    }
+
+    let png_output = compressed_output_path(input, Some("png"))
+        .ok_or_else(|| CompressionError::InvalidPath(input.display().to_string()))?;
+    let png_size = vips.save_png_lossless(source, &png_output)?;
+
+    let encoder = match (config.output, config.encoder_path.as_deref()) {
+        (TextureOutput::Ktx2, Some(encoder)) => encoder,
+        (TextureOutput::Ktx2, None) => {
+            warn!("[texture] KTX2 output selected but no encoder configured, keeping PNG");
+            return Ok(TextureOutputFile {
+                path: png_output,
+                size: png_size,
+                format: "png",
+            });
+        }
+        (TextureOutput::Png | TextureOutput::Dds, _) => {
+            return Ok(TextureOutputFile {
+                path: png_output,
+                size: png_size,
+                format: "png",
+            });
+        }
+    };
+
+    let ktx_output = compressed_output_path(input, Some("ktx2"))
+        .ok_or_else(|| CompressionError::InvalidPath(input.display().to_string()))?;
+    let result = encode_ktx2(encoder, &png_output, &ktx_output);
+    let _ = std::fs::remove_file(&png_output);
+    let size = result?;
+
+    Ok(TextureOutputFile {
+        path: ktx_output,
+        size,
+        format: "ktx2",
+    })
+}
+
+/// Write `rgba` as an uncompressed DDS with every mip level down to 1×1.
+fn write_dds(output: &Path, width: u32, height: u32, rgba: Vec<u8>) -> Result<u64> {
+    const DDSD_CAPS: u32 = 0x1;
+    const DDSD_HEIGHT: u32 = 0x2;
+    const DDSD_WIDTH: u32 = 0x4;
+    const DDSD_PITCH: u32 = 0x8;
+    const DDSD_PIXELFORMAT: u32 = 0x1000;
+    const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+    const DDPF_ALPHAPIXELS: u32 = 0x1;
+    const DDPF_RGB: u32 = 0x40;
+    const DDSCAPS_COMPLEX: u32 = 0x8;
+    const DDSCAPS_TEXTURE: u32 = 0x1000;
+    const DDSCAPS_MIPMAP: u32 = 0x400000;
+
+    let mut levels = vec![(width, height, rgba)];
+    while let Some((w, h, pixels)) = levels.last().filter(|(w, h, _)| *w > 1 || *h > 1) {
+        let next = downsample(*w, *h, pixels);
+        levels.push(next);
+    }
+
+    let header = [
+        124,
+        DDSD_CAPS | DDSD_HEIGHT | DDSD_WIDTH | DDSD_PITCH | DDSD_PIXELFORMAT | DDSD_MIPMAPCOUNT,
+        height,
+        width,
+        width * 4,
+        0,
+        levels.len() as u32,
+    ];
+    let pixel_format = [
+        32,
+        DDPF_RGB | DDPF_ALPHAPIXELS,
+        0,
+        32,
+        0x00ff_0000,
+        0x0000_ff00,
+        0x0000_00ff,
+        0xff00_0000,
+    ];
+    let caps = [
+        DDSCAPS_TEXTURE | DDSCAPS_MIPMAP | DDSCAPS_COMPLEX,
+        0,
+        0,
+        0,
+        0,
+    ];
+
+    let mut bytes = b"DDS ".to_vec();
+    let words = header
+        .iter()
+        .chain(&[0; 11])
+        .chain(&pixel_format)
+        .chain(&caps);
+    for word in words {
+        bytes.extend_from_slice(&word.to_le_bytes());
+    }
+    // The masks above put blue in the lowest byte
+    for (_, _, pixels) in &levels {
+        for px in pixels.chunks_exact(4) {
+            bytes.extend_from_slice(&[px[2], px[1], px[0], px[3]]);
+        }
+    }
+    std::fs::write(output, &bytes)?;
+    info!(
+        "[texture] Wrote {} with {} mip levels",
+        output.display(),
+        levels.len()
+    );
+    Ok(bytes.len() as u64)
+}
+
+/// Halve an RGBA image, averaging each 2×2 block; a side of 1 stays 1.
+fn downsample(width: u32, height: u32, rgba: &[u8]) -> (u32, u32, Vec<u8>) {
+    let (w, h) = ((width / 2).max(1), (height / 2).max(1));
+    let at = |x: u32, y: u32| ((y.min(height - 1) * width + x.min(width - 1)) * 4) as usize;
+    let mut out = Vec::with_capacity((w * h * 4) as usize);
+    for y in 0..h {
+        for x in 0..w {
+            let block = [
+                at(2 * x, 2 * y),
+                at(2 * x + 1, 2 * y),
+                at(2 * x, 2 * y + 1),
+                at(2 * x + 1, 2 * y + 1),
+            ];
+            for channel in 0..4 {
+                let sum: u32 = block.iter().map(|&i| rgba[i + channel] as u32).sum();
+                out.push(((sum + 2) / 4) as u8);
+            }
+        }
+    }
+    (w, h, out)
+}
+
+fn encode_ktx2(encoder: &str, input: &Path, output: &Path) -> Result<u64> {
+    info!(
+        "[texture] Encoding {} → {} with {}",
+        input.display(),
+        output.display(),
+        encoder
+    );
+    let result = Command::new(encoder)
+        .args(["--t2", "--encode", "uastc", "--genmipmap"])
+        .arg(output)
+        .arg(input)
+        .output()
+        .map_err(|e| CompressionError::External(format!("failed to run {}: {}", encoder, e)))?;
+
+    if !result.status.success() {
+        return Err(CompressionError::External(format!(
+            "{} exited with {}: {}",
+            encoder,
+            result.status,
+            String::from_utf8_lossy(&result.stderr).trim()
+        )));
+    }
+    Ok(std::fs::metadata(output)?.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    /// `width`×`height` RGBA pixels whose channels all hold the pixel's index.
+    fn ramp(width: u32, height: u32) -> Vec<u8> {
+        (0..width * height).flat_map(|i| [i as u8; 4]).collect()
+    }
+
+    #[test]
+    fn nearest_power_of_two_rounds_ties_up() {
+        assert_eq!(nearest_power_of_two(3), 4);
+        assert_eq!(nearest_power_of_two(6), 8);
+        assert_eq!(nearest_power_of_two(5), 4);
+        assert_eq!(nearest_power_of_two(7), 8);
+        assert_eq!(nearest_power_of_two(1000), 1024);
+        assert_eq!(nearest_power_of_two(1024), 1024);
+    }
+
+    #[test]
+    fn nearest_power_of_two_is_at_least_one() {
+        assert_eq!(nearest_power_of_two(0), 1);
+        assert_eq!(nearest_power_of_two(1), 1);
+        assert_eq!(nearest_power_of_two(2), 2);
+    }
+
+    #[test]
+    fn nearest_power_of_two_stops_at_two_to_the_31() {
+        assert_eq!(nearest_power_of_two((1 << 31) - 1), 1 << 31);
+        assert_eq!(nearest_power_of_two(1 << 31), 1 << 31);
+        assert_eq!(nearest_power_of_two((1 << 31) + 1), 1 << 31);
+        assert_eq!(nearest_power_of_two(u32::MAX), 1 << 31);
+    }
+
+    #[test]
+    fn downsample_averages_two_by_two_blocks() {
+        // Channels 0, 1, 2 and 3 round to (6 + 2) / 4 = 2
+        let (w, h, out) = downsample(2, 2, &ramp(2, 2));
+        assert_eq!((w, h), (1, 1));
+        assert_eq!(out, vec![2; 4]);
+    }
+
+    #[test]
+    fn downsample_keeps_a_side_of_one() {
+        let (w, h, out) = downsample(1, 4, &ramp(1, 4));
+        assert_eq!((w, h), (1, 2));
+        // Each pixel is counted twice, so 0 + 1 rounds to 1 and 2 + 3 to 3
+        assert_eq!(out, vec![1, 1, 1, 1, 3, 3, 3, 3]);
+
+        let (w, h, out) = downsample(4, 1, &ramp(4, 1));
+        assert_eq!((w, h), (2, 1));
+        assert_eq!(out, vec![1, 1, 1, 1, 3, 3, 3, 3]);
+    }
+
+    #[test]
+    fn downsample_drops_the_last_row_and_column_of_odd_sides() {
+        // Pixels 0, 1, 3 and 4 of the 3×3 image
+        let (w, h, out) = downsample(3, 3, &ramp(3, 3));
+        assert_eq!((w, h), (1, 1));
+        assert_eq!(out, vec![2; 4]);
+    }
+
+    #[test]
+    fn write_dds_lays_out_the_header() {
+        let path = std::env::temp_dir().join(format!("hat-dds-{}.dds", std::process::id()));
+        let size = write_dds(&path, 4, 2, ramp(4, 2)).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // 4×2, 2×1 and 1×1 levels after the 4-byte magic and 124-byte header
+        assert_eq!(size, bytes.len() as u64);
+        assert_eq!(bytes.len(), 128 + (8 + 2 + 1) * 4);
+        assert_eq!(&bytes[..4], b"DDS ");
+        assert_eq!(word(&bytes, 4), 124);
+        assert_eq!(word(&bytes, 8), 0x2100f);
+        assert_eq!(word(&bytes, 12), 2);
+        assert_eq!(word(&bytes, 16), 4);
+        assert_eq!(word(&bytes, 20), 16);
+        assert_eq!(word(&bytes, 24), 0);
+        assert_eq!(word(&bytes, 28), 3);
+        assert!((32..76).step_by(4).all(|offset| word(&bytes, offset) == 0));
+        // Pixel format
+        assert_eq!(word(&bytes, 76), 32);
+        assert_eq!(word(&bytes, 80), 0x41);
+        assert_eq!(word(&bytes, 84), 0);
+        assert_eq!(word(&bytes, 88), 32);
+        assert_eq!(word(&bytes, 92), 0x00ff_0000);
+        assert_eq!(word(&bytes, 96), 0x0000_ff00);
+        assert_eq!(word(&bytes, 100), 0x0000_00ff);
+        assert_eq!(word(&bytes, 104), 0xff00_0000);
+        // Caps
+        assert_eq!(word(&bytes, 108), 0x401008);
+        assert!((112..128)
+            .step_by(4)
+            .all(|offset| word(&bytes, offset) == 0));
+    }
+
+    #[test]
+    fn write_dds_stores_pixels_as_bgra() {
+        let path = std::env::temp_dir().join(format!("hat-bgra-{}.dds", std::process::id()));
+        write_dds(&path, 1, 1, vec![1, 2, 3, 4]).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(word(&bytes, 28), 1);
+        assert_eq!(&bytes[128..], &[3, 2, 1, 4]);
+    }
+
+    #[test]
+    fn write_dds_mips_one_pixel_wide_images_down_to_one_by_one() {
+        let path = std::env::temp_dir().join(format!("hat-column-{}.dds", std::process::id()));
+        write_dds(&path, 1, 4, ramp(1, 4)).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!((word(&bytes, 12), word(&bytes, 16)), (4, 1));
+        assert_eq!(word(&bytes, 20), 4);
+        assert_eq!(word(&bytes, 28), 3);
+        let levels = [&bytes[128..144], &bytes[144..152], &bytes[152..]];
+        assert_eq!(levels[0], &ramp(1, 4)[..]);
+        assert_eq!(levels[1], &[1, 1, 1, 1, 3, 3, 3, 3]);
+        assert_eq!(levels[2], &[2, 2, 2, 2]);
+    }
+}
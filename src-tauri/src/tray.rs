@@ -4,6 +4,8 @@ use tauri::{
     Manager,
 };
 
+const TRAY_ID: &str = "main";
+
 pub fn setup_tray(
     app: &mut tauri::App,
     icon: tauri::image::Image<'static>,
@@ -12,7 +14,7 @@ pub fn setup_tray(
     let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
     let menu = Menu::with_items(app, &[&show_i, &quit_i])?;
 
-    TrayIconBuilder::new()
+    TrayIconBuilder::with_id(TRAY_ID)
         .icon(icon)
         .tooltip("Hat")
         .menu(&menu)
@@ -26,7 +28,7 @@ pub fn setup_tray(
                 }
             }
             "quit" => {
-                app.exit(0);
+                crate::tasks::request_quit(app);
             }
             _ => {}
         })
@@ -49,3 +51,14 @@ pub fn setup_tray(
 
     Ok(())
 }
+
+/// Show the latest activity under the app name in the tray tooltip.
+pub fn set_status(app: &tauri::AppHandle, message: &str, timestamp: u64) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(format!(
+            "Hat\n{}\n{}",
+            message,
+            crate::locale::format_date(timestamp)
+        )));
+    }
+}
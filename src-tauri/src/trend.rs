@@ -0,0 +1,49 @@
+use log::info;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Manager;
+
+/// Folders are measured this often; each day keeps its latest sample.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Spawn the thread that records the size of every watched folder, so the
+/// statistics can show how it grew over time.
+pub fn start(app: &tauri::AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        sample(&app);
+        std::thread::sleep(SAMPLE_INTERVAL);
+    });
+}
+
+fn sample(app: &tauri::AppHandle) {
+    let folders = app
+        .state::<Mutex<crate::config::ConfigManager>>()
+        .lock()
+        .map(|c| c.config.watched_folders.clone())
+        .unwrap_or_default();
+    for folder in &folders {
+        let folder = Path::new(folder);
+        let Some((bytes, files)) = measure(folder) else {
+            continue;
+        };
+        if let Ok(mut log) = app.state::<Mutex<crate::log::CompressionLog>>().lock() {
+            log.add_folder_sample(folder, bytes, files);
+        }
+    }
+    info!("[trend] Sampled {} watched folders", folders.len());
+}
+
+/// Total size and count of the files directly in `folder`, which is what
+/// the watcher sees of it.
+fn measure(folder: &Path) -> Option<(u64, u64)> {
+    let entries = std::fs::read_dir(folder).ok()?;
+    Some(
+        entries
+            .flatten()
+            .filter_map(|e| e.metadata().ok())
+            .filter(|m| m.is_file())
+            .fold((0, 0), |(bytes, files), m| (bytes + m.len(), files + 1)),
+    )
+}
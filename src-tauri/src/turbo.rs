@@ -0,0 +1,81 @@
+use crate::compression::CompressionFlags;
+use log::info;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::Emitter;
+
+/// Longest window `start` accepts, in minutes.
+pub const MAX_MINUTES: u32 = 240;
+
+/// When turbo ends, in seconds since the epoch; 0 while off.
+static UNTIL: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TurboStatus {
+    pub active: bool,
+    pub until: Option<u64>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+pub fn status() -> TurboStatus {
+    let until = UNTIL.load(Ordering::SeqCst);
+    let active = until > now();
+    TurboStatus {
+        active,
+        until: active.then_some(until),
+    }
+}
+
+/// Run at maximum threads and encoder effort for `minutes`, then go back to
+/// the configured settings on its own. A later call replaces the window.
+pub fn start(app: &tauri::AppHandle, minutes: u32) -> TurboStatus {
+    let until = now() + minutes as u64 * 60;
+    UNTIL.store(until, Ordering::SeqCst);
+    info!("[turbo] On for {} minutes", minutes);
+    let _ = app.emit("turbo-changed", &status());
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_secs(minutes as u64 * 60));
+        // A later start or stop owns the window now
+        if UNTIL
+            .compare_exchange(until, 0, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+        {
+            info!("[turbo] Window over, back to normal limits");
+            let _ = app.emit("turbo-changed", &status());
+        }
+    });
+    status()
+}
+
+pub fn stop(app: &tauri::AppHandle) -> TurboStatus {
+    if UNTIL.swap(0, Ordering::SeqCst) != 0 {
+        info!("[turbo] Stopped early, back to normal limits");
+        let _ = app.emit("turbo-changed", &status());
+    }
+    status()
+}
+
+/// Raise every encoder's effort to its maximum while turbo is on.
+pub fn apply(flags: &mut CompressionFlags) {
+    if !status().active {
+        return;
+    }
+    flags.turbo = true;
+    flags.png_oxipng_level = 6;
+    flags.jpeg_optimize_coding = true;
+    flags.jpeg_trellis_quant = true;
+    flags.webp_effort = 6;
+    flags.avif_effort = 9;
+    flags.heif_effort = 9;
+    flags.gif_effort = 10;
+    flags.jxl_effort = 9;
+}
@@ -0,0 +1,164 @@
+use crate::compression::{CompressionError, Result};
+use crate::config::VideoConfig;
+use log::info;
+use serde::Serialize;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tauri::{Emitter, Manager};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VideoProgress {
+    pub path: String,
+    pub timestamp: u64,
+    pub percent: u8,
+}
+
+pub fn is_video(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| matches!(e.to_lowercase().as_str(), "mp4" | "mkv" | "webm"))
+}
+
+/// The configured ffmpeg, else the copy bundled with the app, else PATH.
+pub fn ffmpeg_path(app: &tauri::AppHandle, settings: &VideoConfig) -> PathBuf {
+    if let Some(path) = &settings.ffmpeg_path {
+        return PathBuf::from(path);
+    }
+    let name = if cfg!(windows) {
+        "ffmpeg.exe"
+    } else {
+        "ffmpeg"
+    };
+    app.path()
+        .resource_dir()
+        .map(|dir| dir.join("ffmpeg").join(name))
+        .ok()
+        .filter(|p| p.exists())
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+/// Re-encode `input` into `output` with the same container, emitting
+/// `video-progress` events as ffmpeg reports them.
+pub fn compress_video(
+    app: &tauri::AppHandle,
+    ffmpeg: &Path,
+    input: &Path,
+    output: &Path,
+    settings: &VideoConfig,
+    timestamp: u64,
+) -> Result<u64> {
+    let crf = settings.crf.to_string();
+    let webm = input
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("webm"));
+
+    let mut cmd = Command::new(ffmpeg);
+    cmd.args(["-hide_banner", "-nostats", "-y", "-i"])
+        .arg(input)
+        .args(["-progress", "pipe:1"]);
+    if webm {
+        cmd.args([
+            "-c:v",
+            "libvpx-vp9",
+            "-b:v",
+            "0",
+            "-row-mt",
+            "1",
+            "-crf",
+            &crf,
+        ]);
+    } else {
+        cmd.args(["-c:v", "libx264", "-preset", &settings.preset, "-crf", &crf]);
+        if output
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("mp4"))
+        {
+            cmd.args(["-movflags", "+faststart"]);
+        }
+    }
+    cmd.args(["-c:a", "copy"])
+        .arg(output)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    info!(
+        "[video] Encoding {} → {} (crf {})",
+        input.display(),
+        output.display(),
+        crf
+    );
+    let mut child = cmd.spawn().map_err(|e| {
+        CompressionError::External(format!("failed to run {}: {}", ffmpeg.display(), e))
+    })?;
+
+    // The input duration is only printed on stderr, before encoding starts
+    let duration_us = Arc::new(AtomicU64::new(0));
+    let stderr = child.stderr.take().map(|stderr| {
+        let duration_us = duration_us.clone();
+        std::thread::spawn(move || {
+            let mut log = String::new();
+            let _ = BufReader::new(stderr).read_to_string(&mut log);
+            if let Some(us) = log.lines().find_map(parse_duration) {
+                duration_us.store(us, Ordering::Relaxed);
+            }
+            log
+        })
+    });
+
+    let mut last_percent = None;
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(|l| l.ok()) {
+            let Some(out_time) = line
+                .strip_prefix("out_time_us=")
+                .and_then(|v| v.parse::<u64>().ok())
+            else {
+                continue;
+            };
+            let total = duration_us.load(Ordering::Relaxed);
+            if total == 0 {
+                continue;
+            }
+            let percent = (out_time.saturating_mul(100) / total).min(100) as u8;
+            if last_percent != Some(percent) {
+                last_percent = Some(percent);
+                let _ = app.emit(
+                    "video-progress",
+                    &VideoProgress {
+                        path: input.display().to_string(),
+                        timestamp,
+                        percent,
+                    },
+                );
+            }
+        }
+    }
+
+    let status = child.wait()?;
+    let log = stderr
+        .and_then(|handle| handle.join().ok())
+        .unwrap_or_default();
+    if !status.success() {
+        let _ = std::fs::remove_file(output);
+        let tail: Vec<&str> = log.lines().rev().take(3).collect();
+        return Err(CompressionError::External(format!(
+            "ffmpeg exited with {}: {}",
+            status,
+            tail.into_iter().rev().collect::<Vec<_>>().join(" / ")
+        )));
+    }
+    Ok(std::fs::metadata(output)?.len())
+}
+
+/// Parse `  Duration: 00:01:02.34, start: ...` into microseconds.
+fn parse_duration(line: &str) -> Option<u64> {
+    let value = line.trim_start().strip_prefix("Duration: ")?;
+    let clock = value.split(',').next()?;
+    let mut parts = clock.split(':');
+    let hours: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    Some(((hours * 3600.0 + minutes * 60.0 + seconds) * 1_000_000.0) as u64)
+}
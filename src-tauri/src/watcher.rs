@@ -1,45 +1,210 @@
 use crate::compression::{ImageFormat, Vips};
-use crate::platform::get_lib_path;
+use crate::skips::SkipReason;
 use log::{error, info};
 use notify::{Event, EventKind, RecursiveMode, Watcher};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
-use std::time::Instant;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use tauri::{Emitter, Manager};
 
+/// While set, new files are still reported but not auto-compressed.
+pub static PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// While set, every watcher decision is emitted as a `watch-debug` event.
+pub static WATCH_DEBUG: AtomicBool = AtomicBool::new(false);
+
+/// When each folder last announced a new file, in seconds since the epoch.
+static LAST_EVENTS: Mutex<Option<HashMap<PathBuf, u64>>> = Mutex::new(None);
+
+/// A watched folder and how it is doing, for the folders list.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct WatchedFolderInfo {
+    pub path: String,
+    pub exists: bool,
+    pub writable: bool,
+    /// Files directly inside, which is all the watcher sees.
+    pub item_count: usize,
+    /// Since the app started.
+    pub last_event: Option<u64>,
+    pub pending_tasks: usize,
+    pub compressed: usize,
+    pub bytes_saved: u64,
+}
+
+fn record_event(path: &Path) {
+    let Some(folder) = path.parent() else {
+        return;
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    if let Ok(mut events) = LAST_EVENTS.lock() {
+        events
+            .get_or_insert_with(HashMap::new)
+            .insert(folder.to_path_buf(), now);
+    }
+}
+
+pub fn folder_info(app: &tauri::AppHandle, folder: &str) -> WatchedFolderInfo {
+    let path = Path::new(folder);
+    let meta = std::fs::metadata(path).ok().filter(|m| m.is_dir());
+    let item_count = std::fs::read_dir(path).map_or(0, |entries| {
+        entries
+            .flatten()
+            .filter(|e| e.file_type().is_ok_and(|t| t.is_file()))
+            .count()
+    });
+    let last_event = LAST_EVENTS
+        .lock()
+        .ok()
+        .and_then(|events| events.as_ref()?.get(path).copied());
+    let pending_tasks = crate::tasks::pending(app)
+        .iter()
+        .filter(|t| Path::new(&t.path).parent() == Some(path))
+        .count();
+    let (compressed, bytes_saved) = app
+        .state::<Mutex<crate::log::CompressionLog>>()
+        .lock()
+        .map(|log| log.folder_savings(path))
+        .unwrap_or_default();
+    WatchedFolderInfo {
+        path: folder.to_string(),
+        exists: meta.is_some(),
+        writable: meta.is_some_and(|m| !m.permissions().readonly()),
+        item_count,
+        last_event,
+        pending_tasks,
+        compressed,
+        bytes_saved,
+    }
+}
+
 #[derive(Clone, serde::Serialize)]
 struct NewFile {
     path: String,
 }
 
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum WatchAction {
+    /// The event is not one that announces a new file.
+    Ignored,
+    Skipped,
+    Compressing,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct WatchDecision {
+    event_kind: String,
+    path: String,
+    /// The check that decided, when one did.
+    rule: Option<String>,
+    action: WatchAction,
+}
+
+fn report(
+    app: &tauri::AppHandle,
+    event_kind: &str,
+    path: &Path,
+    rule: Option<&str>,
+    action: WatchAction,
+) {
+    if !WATCH_DEBUG.load(Ordering::Relaxed) {
+        return;
+    }
+    let _ = app.emit(
+        "watch-debug",
+        &WatchDecision {
+            event_kind: event_kind.to_string(),
+            path: path.display().to_string(),
+            rule: rule.map(str::to_string),
+            action,
+        },
+    );
+}
+
+/// The loaded libvips, if any. It can appear later when a reload succeeds.
 pub struct VipsState {
-    pub vips: Option<Arc<Vips>>,
+    vips: RwLock<Option<Arc<Vips>>>,
+}
+
+impl VipsState {
+    pub fn get(&self) -> Option<Arc<Vips>> {
+        self.vips.read().ok().and_then(|v| v.clone())
+    }
+
+    pub fn set(&self, vips: Arc<Vips>) {
+        if let Ok(mut current) = self.vips.write() {
+            *current = Some(vips);
+        }
+    }
 }
 
 pub struct WatcherHandle {
     pub watcher: Mutex<Option<notify::RecommendedWatcher>>,
 }
 
-pub fn init_watcher(app: &tauri::AppHandle) {
-    let lib_path = get_lib_path(app);
-    let vips = match unsafe { Vips::new(&lib_path) } {
-        Ok(v) => {
-            info!("[compression] libvips loaded from {}", lib_path.display());
-            Some(Arc::new(v))
+/// Why a new file in a watched folder is ignored, if it is.
+pub fn skip_reason(path: &Path) -> Option<SkipReason> {
+    // Skip temporary/incomplete download files
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        let ext_lower = ext.to_lowercase();
+        if ext_lower == "tmp" || ext_lower == "crdownload" || ext_lower == "part" {
+            return Some(SkipReason::TemporaryFile);
         }
+    }
+
+    // Skip files that are already compressed outputs
+    if crate::compression::output_naming().is_output(path)
+        || crate::pairs::is_destination(path)
+        || crate::output_dir::contains(path)
+        || crate::replace::is_own_write(path)
+        || crate::archive::contains(path)
+    {
+        return Some(SkipReason::CompressedOutput);
+    }
+    None
+}
+
+pub fn init_watcher(app: &tauri::AppHandle) {
+    let vips = match crate::engine::load(app) {
+        Ok(v) => Some(v),
         Err(e) => {
-            error!("[compression] Failed to load libvips, auto-compression disabled: {e}");
+            error!("[compression] Failed to load libvips, using the fallback encoder: {e}");
+            crate::engine::start_retry(app);
             None
         }
     };
 
-    app.manage(VipsState { vips: vips.clone() });
+    let loaded = vips.is_some();
+    app.manage(VipsState {
+        vips: RwLock::new(vips),
+    });
+    if loaded {
+        crate::engine::start_self_check(app, false);
+        crate::engine::start_warmup(app);
+    }
 
     let handle = app.clone();
     let recent_files: Arc<Mutex<HashMap<PathBuf, Instant>>> = Arc::new(Mutex::new(HashMap::new()));
     let watcher_res = notify::recommended_watcher(move |res: Result<Event, _>| {
         if let Ok(event) = res {
+            let kind = format!("{:?}", event.kind);
+            let removed = matches!(
+                event.kind,
+                EventKind::Remove(_)
+                    | EventKind::Modify(notify::event::ModifyKind::Name(
+                        notify::event::RenameMode::From
+                    ))
+            );
+            if removed {
+                for path in &event.paths {
+                    crate::pairs::source_removed(path);
+                }
+            }
             let dominated = matches!(
                 event.kind,
                 EventKind::Create(_)
@@ -47,25 +212,27 @@ pub fn init_watcher(app: &tauri::AppHandle) {
                         notify::event::RenameMode::To
                     ))
             );
+            if !dominated {
+                for path in &event.paths {
+                    report(&handle, &kind, path, None, WatchAction::Ignored);
+                }
+            }
             if dominated {
                 for path in &event.paths {
                     let file_path = Path::new(path);
+                    record_event(file_path);
 
-                    // Skip temporary/incomplete download files
-                    if let Some(ext) = file_path.extension().and_then(|e| e.to_str()) {
-                        let ext_lower = ext.to_lowercase();
-                        if ext_lower == "tmp" || ext_lower == "crdownload" || ext_lower == "part" {
-                            info!("[watcher] Skipping temporary file: {}", path.display());
-                            continue;
-                        }
-                    }
-
-                    // Skip files that are already compressed outputs
-                    if let Some(stem) = file_path.file_stem().and_then(|s| s.to_str()) {
-                        if stem.ends_with("_compressed") {
-                            info!("[watcher] Skipping compressed file: {}", path.display());
-                            continue;
-                        }
+                    if let Some(reason) = skip_reason(file_path) {
+                        info!("[watcher] Skipping {}: {}", reason, path.display());
+                        crate::skips::record(file_path, reason, &reason.to_string());
+                        report(
+                            &handle,
+                            &kind,
+                            file_path,
+                            Some(&reason.to_string()),
+                            WatchAction::Skipped,
+                        );
+                        continue;
                     }
 
                     // Deduplicate rapid events for the same file (e.g. Create + Rename)
@@ -77,6 +244,13 @@ pub fn init_watcher(app: &tauri::AppHandle) {
                         if let Some(prev) = map.get(&canon) {
                             if prev.elapsed().as_secs() < 3 {
                                 info!("[watcher] Skipping duplicate event for: {}", path.display());
+                                report(
+                                    &handle,
+                                    &kind,
+                                    file_path,
+                                    Some("duplicate event"),
+                                    WatchAction::Skipped,
+                                );
                                 continue;
                             }
                         }
@@ -94,25 +268,118 @@ pub fn init_watcher(app: &tauri::AppHandle) {
                     let payload = NewFile {
                         path: path.display().to_string(),
                     };
-                    match handle.emit("new-download", &payload) {
+                    match crate::events::emit(&handle, "file-detected", &payload) {
                         Ok(_) => {
                             info!("[watcher] Emitted event for: {}", path.display())
                         }
                         Err(e) => error!("[watcher] Failed to emit event: {e}"),
                     }
 
+                    if PAUSED.load(Ordering::Relaxed) {
+                        info!("[watcher] Paused, not compressing: {}", path.display());
+                        crate::skips::record(file_path, SkipReason::Paused, "Hat is paused");
+                        report(
+                            &handle,
+                            &kind,
+                            file_path,
+                            Some("paused"),
+                            WatchAction::Skipped,
+                        );
+                        continue;
+                    }
+
                     // Auto-compress if it's a supported image format
-                    if format.is_some() {
-                        if let Some(ref vips) = vips {
-                            let h = handle.clone();
-                            let v = vips.clone();
-                            let p = path.to_path_buf();
-                            std::thread::spawn(move || {
-                                if let Err(e) = crate::processor::process_file(&h, &v, &p) {
-                                    error!("[watcher] Error: {h:?}: {e}");
+                    if format.is_some()
+                        || crate::pdf::is_pdf(file_path)
+                        || crate::svg::is_svg(file_path)
+                        || crate::video::is_video(file_path)
+                    {
+                        let h = handle.clone();
+                        let p = path.to_path_buf();
+                        let kind = kind.clone();
+                        std::thread::spawn(move || {
+                            let (settings, producer_rules) = h
+                                .state::<Mutex<crate::config::ConfigManager>>()
+                                .lock()
+                                .map(|c| {
+                                    (
+                                        c.config.folder_settings_for(&p),
+                                        c.config.producer_rules.clone(),
+                                    )
+                                })
+                                .unwrap_or_default();
+                            // Look the writer up first, while it still has the file open
+                            if !producer_rules.is_empty() {
+                                let app = crate::platform::producing_app(&p);
+                                if !producer_rules.allows(app.as_deref()) {
+                                    info!(
+                                        "[watcher] Skipping file written by {:?}: {}",
+                                        app,
+                                        p.display()
+                                    );
+                                    let detail = format!(
+                                        "Written by {}",
+                                        app.as_deref().unwrap_or("an unknown app")
+                                    );
+                                    crate::skips::record(&p, SkipReason::ProducerApp, &detail);
+                                    report(
+                                        &h,
+                                        &kind,
+                                        &p,
+                                        Some("producer rules"),
+                                        WatchAction::Skipped,
+                                    );
+                                    return;
                                 }
-                            });
-                        }
+                            }
+                            if !crate::scanner::passes_age_filter(&settings, &p) {
+                                info!("[watcher] Skipping file older than cutoff: {}", p.display());
+                                crate::skips::record(
+                                    &p,
+                                    SkipReason::OlderThanCutoff,
+                                    "Created before the folder's age cutoff",
+                                );
+                                report(&h, &kind, &p, Some("age filter"), WatchAction::Skipped);
+                                return;
+                            }
+                            if !crate::scripting::on_file_detected(&h, &p) {
+                                crate::skips::record(
+                                    &p,
+                                    SkipReason::ScriptVeto,
+                                    "A script asked to skip it",
+                                );
+                                report(
+                                    &h,
+                                    &kind,
+                                    &p,
+                                    Some("on_file_detected script"),
+                                    WatchAction::Skipped,
+                                );
+                                return;
+                            }
+                            report(&h, &kind, &p, None, WatchAction::Compressing);
+                            let mode = crate::processor::InputMode::Watched;
+                            let v = h.state::<VipsState>().get();
+                            let meter = crate::energy::Meter::start();
+                            if let Err(e) = crate::processor::process_any(&h, v.as_ref(), &p, mode)
+                            {
+                                error!("[watcher] Error: {h:?}: {e}");
+                            }
+                            meter.finish(&h);
+                        });
+                    } else {
+                        crate::skips::record(
+                            file_path,
+                            SkipReason::UnsupportedFormat,
+                            "Not an image, PDF, SVG or video",
+                        );
+                        report(
+                            &handle,
+                            &kind,
+                            file_path,
+                            Some("unsupported format"),
+                            WatchAction::Skipped,
+                        );
                     }
                 }
             }
@@ -124,7 +391,13 @@ pub fn init_watcher(app: &tauri::AppHandle) {
             let folders = {
                 let config = app.state::<Mutex<crate::config::ConfigManager>>();
                 let config_manager = config.lock().unwrap();
-                config_manager.config.watched_folders.clone()
+                let mut folders = config_manager.config.watched_folders.clone();
+                for pair in &config_manager.config.folder_pairs {
+                    if !folders.contains(&pair.source) {
+                        folders.push(pair.source.clone());
+                    }
+                }
+                folders
             };
             (Some(w), folders)
         }
@@ -0,0 +1,90 @@
+use crate::compression::{CompressionError, Result, Vips, VipsImage};
+use crate::config::{WatermarkConfig, WatermarkPosition};
+use std::path::Path;
+
+/// Text is rendered this sharp, then scaled like an image overlay.
+const TEXT_DPI: u32 = 300;
+
+/// Composite `config`'s overlay onto `img`, sized relative to its width.
+pub fn apply<'a>(
+    vips: &'a Vips,
+    img: &VipsImage<'a>,
+    config: &WatermarkConfig,
+) -> Result<VipsImage<'a>> {
+    let (width, height) = vips.dimensions(img);
+    let mark = overlay(vips, config)?;
+    let mark = if matches!(vips.bands(&mark), 2 | 4) {
+        mark
+    } else {
+        vips.add_alpha(&mark)?
+    };
+
+    let (mark_width, _) = vips.dimensions(&mark);
+    let scale = width as f64 * config.scale.clamp(0.01, 1.0) as f64 / mark_width.max(1) as f64;
+    let mark = vips.resize(&mark, scale, scale)?;
+    let opacity = config.opacity.clamp(0.0, 1.0) as f64;
+    let mark = if opacity < 1.0 {
+        vips.multiply_alpha(&mark, opacity)?
+    } else {
+        mark
+    };
+
+    let margin = (width as f32 * config.margin.max(0.0)) as i32;
+    let (x, y) = position(
+        config.position,
+        (width as i32, height as i32),
+        vips.dimensions(&mark),
+        margin,
+    );
+    let out = vips.composite_over(img, &mark, x, y)?;
+    // Compositing adds alpha even to opaque images, which would only grow them
+    if matches!(vips.bands(img), 2 | 4) {
+        Ok(out)
+    } else {
+        vips.extract_bands(&out, 0, 3)
+    }
+}
+
+fn overlay<'a>(vips: &'a Vips, config: &WatermarkConfig) -> Result<VipsImage<'a>> {
+    if let Some(image) = config.image.as_deref().filter(|s| !s.is_empty()) {
+        return vips.load_image(Path::new(image));
+    }
+    if let Some(text) = config.text.as_deref().filter(|s| !s.is_empty()) {
+        let markup = format!(
+            "<span foreground=\"{}\">{}</span>",
+            escape(&config.text_color),
+            escape(text)
+        );
+        return vips.text_rgba(&markup, TEXT_DPI);
+    }
+    Err(CompressionError::Vips(
+        "the watermark has neither an image nor text".into(),
+    ))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Top-left corner of a `mark`-sized overlay on an `image`-sized base.
+fn position(
+    position: WatermarkPosition,
+    image: (i32, i32),
+    mark: (u32, u32),
+    margin: i32,
+) -> (i32, i32) {
+    let (mark_w, mark_h) = (mark.0 as i32, mark.1 as i32);
+    let right = image.0 - mark_w - margin;
+    let bottom = image.1 - mark_h - margin;
+    let (x, y) = match position {
+        WatermarkPosition::TopLeft => (margin, margin),
+        WatermarkPosition::TopRight => (right, margin),
+        WatermarkPosition::BottomLeft => (margin, bottom),
+        WatermarkPosition::BottomRight => (right, bottom),
+        WatermarkPosition::Center => ((image.0 - mark_w) / 2, (image.1 - mark_h) / 2),
+    };
+    (x.max(0), y.max(0))
+}
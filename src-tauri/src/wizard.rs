@@ -0,0 +1,493 @@
+use crate::compression::{EncodeEngine, EncodeSettings, ImageFormat};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+const STATE_FILE: &str = "library_wizard.json";
+/// Files per checkpoint: an interrupted run redoes at most this many.
+const CHUNK_SIZE: usize = 100;
+/// Output/input ratio assumed for formats the history has never seen.
+const DEFAULT_RATIO: f64 = 0.6;
+
+/// Where the "optimize my photo library" flow stands. Each step only moves
+/// forward from the one before it; `reset` goes back to the start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WizardStep {
+    Scanned,
+    Estimated,
+    Configured,
+    Running,
+    /// Stopped between chunks, by the user or by quitting mid-run.
+    Paused,
+    Done,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeletionPolicy {
+    #[default]
+    Keep,
+    /// Delete each original once a smaller output was written next to it.
+    DeleteWhenSmaller,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormatEstimate {
+    pub format: String,
+    pub count: usize,
+    pub bytes: u64,
+    pub estimated_bytes: u64,
+}
+
+/// Expected result of compressing the scanned library, from the ratios
+/// the history shows for each format.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryEstimate {
+    pub count: usize,
+    pub bytes: u64,
+    pub estimated_bytes: u64,
+    pub formats: Vec<FormatEstimate>,
+}
+
+/// Everything the wizard needs to pick up where it left off after a restart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct WizardState {
+    step: WizardStep,
+    root: String,
+    files: Vec<String>,
+    #[serde(default)]
+    estimate: Option<LibraryEstimate>,
+    #[serde(default)]
+    quality: Option<u8>,
+    #[serde(default)]
+    deletion: DeletionPolicy,
+    /// Index of the first file of the next chunk.
+    #[serde(default)]
+    checkpoint: usize,
+    #[serde(default)]
+    compressed: usize,
+    #[serde(default)]
+    failed: usize,
+    #[serde(default)]
+    bytes_saved: u64,
+}
+
+/// What the UI sees of the wizard, without the file list.
+#[derive(Debug, Clone, Serialize)]
+pub struct WizardStatus {
+    pub step: WizardStep,
+    pub root: String,
+    pub total: usize,
+    pub checkpoint: usize,
+    pub estimate: Option<LibraryEstimate>,
+    pub quality: Option<u8>,
+    pub deletion: DeletionPolicy,
+    pub compressed: usize,
+    pub failed: usize,
+    pub bytes_saved: u64,
+}
+
+#[derive(Default)]
+pub struct Wizard {
+    state: Mutex<Option<WizardState>>,
+    /// Set to stop a run after its current chunk.
+    pause_requested: AtomicBool,
+}
+
+impl WizardState {
+    fn status(&self) -> WizardStatus {
+        WizardStatus {
+            step: self.step,
+            root: self.root.clone(),
+            total: self.files.len(),
+            checkpoint: self.checkpoint,
+            estimate: self.estimate.clone(),
+            quality: self.quality,
+            deletion: self.deletion,
+            compressed: self.compressed,
+            failed: self.failed,
+            bytes_saved: self.bytes_saved,
+        }
+    }
+}
+
+fn state_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_config_dir()
+        .map(|dir| dir.join(STATE_FILE))
+        .map_err(|e| e.to_string())
+}
+
+fn save(app: &tauri::AppHandle, state: &WizardState) {
+    let saved = state_path(app).and_then(|path| {
+        let json = serde_json::to_string(state).map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| e.to_string())
+    });
+    if let Err(e) = saved {
+        warn!("[wizard] Failed to save the checkpoint: {}", e);
+    }
+}
+
+/// Restore the wizard from the last launch. A run that was cut short comes
+/// back paused at its last checkpoint.
+pub fn load(app: &tauri::AppHandle) {
+    let Ok(json) =
+        state_path(app).and_then(|p| std::fs::read_to_string(p).map_err(|e| e.to_string()))
+    else {
+        return;
+    };
+    let mut state: WizardState = match serde_json::from_str(&json) {
+        Ok(state) => state,
+        Err(e) => {
+            warn!("[wizard] Ignoring unreadable state: {}", e);
+            return;
+        }
+    };
+    if state.step == WizardStep::Running {
+        info!(
+            "[wizard] Run in {} interrupted at {}/{}, resumable",
+            state.root,
+            state.checkpoint,
+            state.files.len()
+        );
+        state.step = WizardStep::Paused;
+    }
+    if let Ok(mut current) = app.state::<Wizard>().state.lock() {
+        *current = Some(state);
+    }
+}
+
+pub fn status(app: &tauri::AppHandle) -> Option<WizardStatus> {
+    app.state::<Wizard>()
+        .state
+        .lock()
+        .ok()
+        .and_then(|s| s.as_ref().map(WizardState::status))
+}
+
+/// Apply `f` to the state when it is at one of `from`, saving the result.
+fn transition(
+    app: &tauri::AppHandle,
+    from: &[WizardStep],
+    f: impl FnOnce(&mut WizardState),
+) -> Result<WizardStatus, String> {
+    let wizard = app.state::<Wizard>();
+    let mut current = wizard.state.lock().map_err(|e| e.to_string())?;
+    let state = current
+        .as_mut()
+        .ok_or_else(|| "Scan a library first".to_string())?;
+    if !from.contains(&state.step) {
+        return Err(format!("The wizard can't do that while {:?}", state.step));
+    }
+    f(state);
+    save(app, state);
+    Ok(state.status())
+}
+
+/// Start over with every image under `root`, subfolders included. Emits
+/// `scan-started` so the walk can be stopped with `cancel_scan`.
+pub fn scan(app: &tauri::AppHandle, root: &Path) -> Result<WizardStatus, String> {
+    if !root.is_dir() {
+        return Err(format!("{} is not a folder", root.display()));
+    }
+    if status(app).is_some_and(|s| s.step == WizardStep::Running) {
+        return Err("Pause the running wizard first".into());
+    }
+    let scan = crate::scanner::start_scan(app);
+    let _ = app.emit(
+        "scan-started",
+        &crate::scanner::ScanStarted {
+            scan_id: scan.id,
+            folder: root.display().to_string(),
+        },
+    );
+    let mut files = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|e| e.path()) {
+            if scan.is_cancelled() {
+                return Err("Scan cancelled".into());
+            }
+            if path.is_dir() {
+                dirs.push(path);
+            } else if ImageFormat::from_path(&path).is_some_and(|f| f != ImageFormat::Raw)
+                && crate::watcher::skip_reason(&path).is_none()
+            {
+                files.push(path.display().to_string());
+            }
+        }
+    }
+    files.sort();
+    info!(
+        "[wizard] Found {} images under {}",
+        files.len(),
+        root.display()
+    );
+
+    let state = WizardState {
+        step: WizardStep::Scanned,
+        root: root.display().to_string(),
+        files,
+        estimate: None,
+        quality: None,
+        deletion: DeletionPolicy::Keep,
+        checkpoint: 0,
+        compressed: 0,
+        failed: 0,
+        bytes_saved: 0,
+    };
+    save(app, &state);
+    let status = state.status();
+    let wizard = app.state::<Wizard>();
+    *wizard.state.lock().map_err(|e| e.to_string())? = Some(state);
+    Ok(status)
+}
+
+/// Size up the scanned files and predict their compressed size.
+pub fn estimate(app: &tauri::AppHandle) -> Result<WizardStatus, String> {
+    let files = app
+        .state::<Wizard>()
+        .state
+        .lock()
+        .map_err(|e| e.to_string())?
+        .as_ref()
+        .map(|s| s.files.clone())
+        .unwrap_or_default();
+    let ratios = history_ratios(app);
+
+    let mut formats: HashMap<String, FormatEstimate> = HashMap::new();
+    for file in &files {
+        let Some(format) = ImageFormat::from_path(Path::new(file)) else {
+            continue;
+        };
+        let bytes = std::fs::metadata(file).map_or(0, |m| m.len());
+        let format = format.to_string();
+        let ratio = ratios.get(&format).copied().unwrap_or(DEFAULT_RATIO);
+        let entry = formats
+            .entry(format.clone())
+            .or_insert_with(|| FormatEstimate {
+                format,
+                count: 0,
+                bytes: 0,
+                estimated_bytes: 0,
+            });
+        entry.count += 1;
+        entry.bytes += bytes;
+        entry.estimated_bytes += (bytes as f64 * ratio) as u64;
+    }
+    let mut formats: Vec<FormatEstimate> = formats.into_values().collect();
+    formats.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+    let estimate = LibraryEstimate {
+        count: formats.iter().map(|f| f.count).sum(),
+        bytes: formats.iter().map(|f| f.bytes).sum(),
+        estimated_bytes: formats.iter().map(|f| f.estimated_bytes).sum(),
+        formats,
+    };
+    transition(
+        app,
+        &[WizardStep::Scanned, WizardStep::Estimated],
+        |state| {
+            state.estimate = Some(estimate);
+            state.step = WizardStep::Estimated;
+        },
+    )
+}
+
+/// Output/input size ratio per input format across the history.
+fn history_ratios(app: &tauri::AppHandle) -> HashMap<String, f64> {
+    let mut totals: HashMap<String, (u64, u64)> = HashMap::new();
+    if let Ok(log) = app.state::<Mutex<crate::log::CompressionLog>>().lock() {
+        for record in log.records.iter().filter(|r| !r.analysis_only) {
+            let entry = totals.entry(record.initial_format.clone()).or_default();
+            entry.0 += record.initial_size;
+            entry.1 += record.compressed_size;
+        }
+    }
+    totals
+        .into_iter()
+        .filter(|(_, (initial, _))| *initial > 0)
+        .map(|(format, (initial, compressed))| {
+            (format, (compressed as f64 / initial as f64).min(1.0))
+        })
+        .collect()
+}
+
+/// Settle the quality and what happens to originals. Deleting them asks
+/// for authentication when the config requires it.
+pub async fn configure(
+    app: &tauri::AppHandle,
+    quality: u8,
+    deletion: DeletionPolicy,
+) -> Result<WizardStatus, String> {
+    if deletion == DeletionPolicy::DeleteWhenSmaller {
+        crate::auth::confirm(app, "delete the originals of your library").await?;
+    }
+    transition(
+        app,
+        &[WizardStep::Estimated, WizardStep::Configured],
+        |state| {
+            state.quality = Some(quality.clamp(1, 100));
+            state.deletion = deletion;
+            state.step = WizardStep::Configured;
+        },
+    )
+}
+
+/// Work through the remaining files in chunks on a background thread,
+/// emitting `wizard-progress` and saving a checkpoint after each.
+pub fn start(app: &tauri::AppHandle) -> Result<WizardStatus, String> {
+    app.state::<Wizard>()
+        .pause_requested
+        .store(false, Ordering::Relaxed);
+    let status = transition(
+        app,
+        &[WizardStep::Configured, WizardStep::Paused],
+        |state| state.step = WizardStep::Running,
+    )?;
+    info!(
+        "[wizard] Running {} from {}/{}",
+        status.root, status.checkpoint, status.total
+    );
+    let app = app.clone();
+    std::thread::spawn(move || run(&app));
+    Ok(status)
+}
+
+/// Stop after the chunk in progress.
+pub fn pause(app: &tauri::AppHandle) -> Result<(), String> {
+    if !status(app).is_some_and(|s| s.step == WizardStep::Running) {
+        return Err("The wizard is not running".into());
+    }
+    app.state::<Wizard>()
+        .pause_requested
+        .store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Forget the wizard, unless it is running.
+pub fn reset(app: &tauri::AppHandle) -> Result<(), String> {
+    let wizard = app.state::<Wizard>();
+    let mut current = wizard.state.lock().map_err(|e| e.to_string())?;
+    if current
+        .as_ref()
+        .is_some_and(|s| s.step == WizardStep::Running)
+    {
+        return Err("Pause the running wizard first".into());
+    }
+    *current = None;
+    if let Ok(path) = state_path(app) {
+        let _ = std::fs::remove_file(path);
+    }
+    Ok(())
+}
+
+fn run(app: &tauri::AppHandle) {
+    use rayon::prelude::*;
+
+    let wizard = app.state::<Wizard>();
+    loop {
+        let Some((chunk, quality, deletion)) = wizard.state.lock().ok().and_then(|s| {
+            let s = s.as_ref()?;
+            let end = (s.checkpoint + CHUNK_SIZE).min(s.files.len());
+            Some((s.files[s.checkpoint..end].to_vec(), s.quality?, s.deletion))
+        }) else {
+            return;
+        };
+
+        let vips = app.state::<crate::watcher::VipsState>().get();
+        let results: Vec<Option<u64>> = chunk
+            .par_iter()
+            .map(|file| {
+                let path = Path::new(file);
+                if !path.is_file() {
+                    return None;
+                }
+                compress(app, vips.as_deref(), path, quality, deletion)
+                    .map_err(|e| warn!("[wizard] {}: {}", file, e))
+                    .ok()
+            })
+            .collect();
+
+        let status = transition(app, &[WizardStep::Running], |state| {
+            state.checkpoint += chunk.len();
+            for result in &results {
+                match result {
+                    Some(saved) => {
+                        state.compressed += 1;
+                        state.bytes_saved += saved;
+                    }
+                    None => state.failed += 1,
+                }
+            }
+            if state.checkpoint >= state.files.len() {
+                state.step = WizardStep::Done;
+            } else if wizard.pause_requested.load(Ordering::Relaxed) {
+                state.step = WizardStep::Paused;
+            }
+        });
+        let Ok(status) = status else {
+            return;
+        };
+        let _ = app.emit("wizard-progress", &status);
+        if status.step != WizardStep::Running {
+            info!(
+                "[wizard] {:?} at {}/{}: {} compressed, {} failed",
+                status.step, status.checkpoint, status.total, status.compressed, status.failed
+            );
+            return;
+        }
+    }
+}
+
+/// Compress one file at the wizard's quality, with the rest of its settings
+/// resolved as usual. Returns the bytes saved.
+fn compress(
+    app: &tauri::AppHandle,
+    vips: Option<&crate::compression::Vips>,
+    path: &Path,
+    quality: u8,
+    deletion: DeletionPolicy,
+) -> Result<u64, String> {
+    let format = ImageFormat::from_path(path).ok_or_else(|| "Unsupported format".to_string())?;
+    let (_, flags, convert_to) = app
+        .state::<Mutex<crate::config::ConfigManager>>()
+        .lock()
+        .map(|c| crate::processor::resolve_format_settings(&c.config, path, format))
+        .map_err(|e| e.to_string())?;
+    let settings = EncodeSettings {
+        engine: if vips.is_some() {
+            EncodeEngine::Libvips
+        } else {
+            EncodeEngine::Fallback
+        },
+        format: convert_to.unwrap_or(format),
+        quality,
+        flags,
+    };
+    let record = crate::processor::repeat(app, vips, path, &settings)?;
+    let saved = record.initial_size.saturating_sub(record.compressed_size);
+
+    if deletion == DeletionPolicy::DeleteWhenSmaller
+        && saved > 0
+        && Path::new(&record.final_path) != path
+        && Path::new(&record.final_path).is_file()
+    {
+        std::fs::remove_file(path).map_err(|e| e.to_string())?;
+        info!("[wizard] Deleted original {}", path.display());
+        if let Ok(mut log) = app.state::<Mutex<crate::log::CompressionLog>>().lock() {
+            if let Some(logged) = log.records.iter_mut().find(|r| r.id == record.id) {
+                logged.original_deleted = true;
+            }
+            let _ = log.save();
+        }
+    }
+    Ok(saved)
+}
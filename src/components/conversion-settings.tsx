@@ -1,7 +1,7 @@
 import { AddCircleLinear, TrashBinTrashLinear, Tuning2Linear } from "@solar-icons/react-perf";
 import { invoke } from "@tauri-apps/api/core";
 import { useCallback, useEffect, useRef, useState } from "react";
-import { FORMAT_LABELS, type FormatKey } from "@/components/format-select";
+import { FORMAT_LABELS, type FormatKey, isWritableFormat } from "@/components/format-select";
 import { Badge } from "@/components/ui/badge";
 import { Button } from "@/components/ui/button";
 import {
@@ -119,8 +119,10 @@ export function ConversionSettings() {
 		const available = FORMAT_LABELS.map((f) => f.key).filter((k) => !usedSources.has(k));
 		if (available.length < 2) return;
 
-		const target = available[0];
-		const source = available.find((k) => k !== target) ?? available[1];
+		const target = available.find(isWritableFormat);
+		if (!target) return;
+		const source = available.find((k) => k !== target);
+		if (!source) return;
 
 		updateRules((prev) => [
 			...prev,
@@ -254,8 +256,9 @@ export function ConversionSettings() {
 									// as well as the sources explicitly specified for *this* rule.
 									const availableTargets = FORMAT_LABELS.filter(
 										(f) =>
-											(!usedSources.has(f.key) && !rule.sources.includes(f.key)) ||
-											f.key === rule.target
+											isWritableFormat(f.key) &&
+											((!usedSources.has(f.key) && !rule.sources.includes(f.key)) ||
+												f.key === rule.target)
 									);
 
 									return (
@@ -0,0 +1,37 @@
+import { FormatQualitySlider } from "@/components/format-quality-slider";
+import type { GifConfig } from "@/lib/types";
+
+interface GifPanelProps {
+	config: GifConfig;
+	onQualityChange: (value: number) => void;
+	onFieldChange: (field: string, value: unknown) => void;
+}
+
+export function GifPanel({ config, onQualityChange, onFieldChange }: GifPanelProps) {
+	return (
+		<div className="space-y-3 pr-8">
+			<FormatQualitySlider label="Quality" value={config.quality} onValueChange={onQualityChange} />
+			<FormatQualitySlider
+				label="Effort"
+				value={config.effort}
+				onValueChange={(val) => onFieldChange("effort", val)}
+				min={1}
+				max={10}
+			/>
+			<FormatQualitySlider
+				label="Max Colors"
+				value={config.colors}
+				onValueChange={(val) => onFieldChange("colors", val)}
+				min={2}
+				max={256}
+			/>
+			<FormatQualitySlider
+				label="Dithering"
+				value={config.dither}
+				onValueChange={(val) => onFieldChange("dither", val)}
+				min={0}
+				max={100}
+			/>
+		</div>
+	);
+}
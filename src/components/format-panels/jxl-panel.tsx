@@ -0,0 +1,36 @@
+import { FormatQualitySlider } from "@/components/format-quality-slider";
+import { SettingsSwitch } from "@/components/ui/settings-switch";
+import type { JxlConfig } from "@/lib/types";
+
+interface JxlPanelProps {
+	config: JxlConfig;
+	onQualityChange: (value: number) => void;
+	onFieldChange: (field: string, value: unknown) => void;
+}
+
+export function JxlPanel({ config, onQualityChange, onFieldChange }: JxlPanelProps) {
+	return (
+		<div className="space-y-3 pr-8">
+			<FormatQualitySlider label="Quality" value={config.quality} onValueChange={onQualityChange} />
+			<FormatQualitySlider
+				label="Effort"
+				value={config.effort}
+				onValueChange={(val) => onFieldChange("effort", val)}
+				min={1}
+				max={9}
+			/>
+			<SettingsSwitch
+				checked={config.lossless}
+				onCheckedChange={(val) => onFieldChange("lossless", val)}
+				title="Lossless"
+				description="Encode lossless JPEG XL."
+			/>
+			<SettingsSwitch
+				checked={config.lossless_jpeg}
+				onCheckedChange={(val) => onFieldChange("lossless_jpeg", val)}
+				title="Lossless JPEG Transcode"
+				description="Repack JPEGs without re-encoding. Requires cjxl; the original can be restored exactly."
+			/>
+		</div>
+	);
+}
@@ -20,7 +20,13 @@ export function PngPanel({ config, onQualityChange, onFieldChange }: PngPanelPro
 				title="Palette"
 				description="Reduce to indexed colors for smaller file sizes. Best for graphics and icons."
 			/>
-			{config.palette && (
+			<SettingsSwitch
+				checked={config.quantize_after}
+				onCheckedChange={(val) => onFieldChange("quantize_after", val)}
+				title="Quantize After"
+				description="Also try an indexed-color version of each PNG and keep it when smaller."
+			/>
+			{(config.palette || config.quantize_after) && (
 				<FormatQualitySlider
 					label="Max Colors"
 					value={config.colors}
@@ -29,6 +35,15 @@ export function PngPanel({ config, onQualityChange, onFieldChange }: PngPanelPro
 					max={256}
 				/>
 			)}
+			{(config.palette || config.quantize_after) && (
+				<FormatQualitySlider
+					label="Dithering"
+					value={config.dither}
+					onValueChange={(val) => onFieldChange("dither", val)}
+					min={0}
+					max={100}
+				/>
+			)}
 			<SettingsSwitch
 				checked={config.interlace}
 				onCheckedChange={(val) => onFieldChange("interlace", val)}
@@ -37,6 +52,7 @@ export function PngPanel({ config, onQualityChange, onFieldChange }: PngPanelPro
 			/>
 			<OptionSelect
 				label="Bit Depth"
+				description="Unset picks the smallest depth that keeps every color, e.g. 1-bit for black-and-white screenshots."
 				value={config.bitdepth > 0 ? String(config.bitdepth) : null}
 				onValueChange={(val) => onFieldChange("bitdepth", val ? Number(val) : 0)}
 				options={PNG_BITDEPTH_OPTIONS}
@@ -48,6 +64,19 @@ export function PngPanel({ config, onQualityChange, onFieldChange }: PngPanelPro
 				onValueChange={(val) => onFieldChange("filter", val)}
 				options={PNG_FILTER_OPTIONS}
 			/>
+			<FormatQualitySlider
+				label="oxipng Level"
+				value={config.oxipng_level}
+				onValueChange={(val) => onFieldChange("oxipng_level", val)}
+				min={0}
+				max={6}
+			/>
+			<SettingsSwitch
+				checked={config.zopfli}
+				onCheckedChange={(val) => onFieldChange("zopfli", val)}
+				title="Zopfli"
+				description="Squeeze PNGs written without libvips a little further. Much slower."
+			/>
 		</div>
 	);
 }
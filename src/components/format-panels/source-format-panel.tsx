@@ -0,0 +1,18 @@
+import type { ReactNode } from "react";
+import { FormatQualitySlider } from "@/components/format-quality-slider";
+
+interface SourceFormatPanelProps {
+	config: { quality: number };
+	onQualityChange: (value: number) => void;
+	children: ReactNode;
+}
+
+/** Settings for formats that are read but never written, like RAW and PSD */
+export function SourceFormatPanel({ config, onQualityChange, children }: SourceFormatPanelProps) {
+	return (
+		<div className="space-y-3 pr-8">
+			<FormatQualitySlider label="Quality" value={config.quality} onValueChange={onQualityChange} />
+			<p className="text-muted-foreground text-xs">{children}</p>
+		</div>
+	);
+}
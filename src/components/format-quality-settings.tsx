@@ -1,9 +1,12 @@
 import { invoke } from "@tauri-apps/api/core";
 import { useCallback, useEffect, useRef, useState } from "react";
 import { AvifPanel } from "@/components/format-panels/avif-panel";
+import { GifPanel } from "@/components/format-panels/gif-panel";
 import { HeifPanel } from "@/components/format-panels/heif-panel";
 import { JpegPanel } from "@/components/format-panels/jpeg-panel";
+import { JxlPanel } from "@/components/format-panels/jxl-panel";
 import { PngPanel } from "@/components/format-panels/png-panel";
+import { SourceFormatPanel } from "@/components/format-panels/source-format-panel";
 import { TiffPanel } from "@/components/format-panels/tiff-panel";
 import { WebpPanel } from "@/components/format-panels/webp-panel";
 import { FORMAT_LABELS, type FormatKey } from "@/components/format-select";
@@ -124,6 +127,50 @@ export function FormatQualitySettings() {
 					/>
 				</ScrollArea>
 			</TabsPanel>
+
+			<TabsPanel value="gif" className="overflow-hidden">
+				<ScrollArea scrollFade className="h-full">
+					<GifPanel
+						config={formatOptions.gif}
+						onQualityChange={(val) => handleQualityChange("gif", val)}
+						onFieldChange={(field, val) => updateField("gif", field, val)}
+					/>
+				</ScrollArea>
+			</TabsPanel>
+
+			<TabsPanel value="jxl" className="overflow-hidden">
+				<ScrollArea scrollFade className="h-full">
+					<JxlPanel
+						config={formatOptions.jxl}
+						onQualityChange={(val) => handleQualityChange("jxl", val)}
+						onFieldChange={(field, val) => updateField("jxl", field, val)}
+					/>
+				</ScrollArea>
+			</TabsPanel>
+
+			<TabsPanel value="raw" className="overflow-hidden">
+				<ScrollArea scrollFade className="h-full">
+					<SourceFormatPanel
+						config={formatOptions.raw}
+						onQualityChange={(val) => handleQualityChange("raw", val)}
+					>
+						RAW files are developed and saved in the format chosen under conversion rules, JPEG by
+						default. Enable RAW development per folder.
+					</SourceFormatPanel>
+				</ScrollArea>
+			</TabsPanel>
+
+			<TabsPanel value="psd" className="overflow-hidden">
+				<ScrollArea scrollFade className="h-full">
+					<SourceFormatPanel
+						config={formatOptions.psd}
+						onQualityChange={(val) => handleQualityChange("psd", val)}
+					>
+						Photoshop files are flattened and saved in the format chosen under conversion rules, or
+						as PNG when they have transparency and JPEG otherwise.
+					</SourceFormatPanel>
+				</ScrollArea>
+			</TabsPanel>
 		</Tabs>
 	);
 }
@@ -2,7 +2,17 @@ import type { VariantProps } from "class-variance-authority";
 import type { selectTriggerVariants } from "@/components/ui/select";
 import { Select, SelectItem, SelectPopup, SelectTrigger } from "@/components/ui/select";
 
-export type FormatKey = "jpeg" | "png" | "webp" | "avif" | "heif" | "tiff";
+export type FormatKey =
+	| "jpeg"
+	| "png"
+	| "webp"
+	| "avif"
+	| "heif"
+	| "tiff"
+	| "gif"
+	| "jxl"
+	| "raw"
+	| "psd";
 
 export const FORMAT_LABELS: { key: FormatKey; label: string }[] = [
 	{ key: "jpeg", label: "JPEG" },
@@ -11,8 +21,17 @@ export const FORMAT_LABELS: { key: FormatKey; label: string }[] = [
 	{ key: "avif", label: "AVIF" },
 	{ key: "heif", label: "HEIF" },
 	{ key: "tiff", label: "TIFF" },
+	{ key: "gif", label: "GIF" },
+	{ key: "jxl", label: "JPEG XL" },
+	{ key: "raw", label: "RAW" },
+	{ key: "psd", label: "PSD" },
 ];
 
+/** Camera RAW and Photoshop files can be read but never written */
+export function isWritableFormat(key: FormatKey) {
+	return key !== "raw" && key !== "psd";
+}
+
 interface FormatSelectProps {
 	value: string | null;
 	onValueChange: (value: string | null) => void;
@@ -45,7 +64,7 @@ export function FormatSelect({
 			</SelectTrigger>
 			<SelectPopup>
 				<SelectItem value="">{placeholder}</SelectItem>
-				{FORMAT_LABELS.filter((f) => f.key !== hideFormat).map((f) => (
+				{FORMAT_LABELS.filter((f) => f.key !== hideFormat && isWritableFormat(f.key)).map((f) => (
 					<SelectItem key={f.key} value={f.key}>
 						{f.label}
 					</SelectItem>
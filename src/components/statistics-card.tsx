@@ -1,13 +1,26 @@
+import { useEffect, useState } from "react";
 import { Card, CardHeader, CardPanel, CardTitle } from "@/components/ui/card";
+import { getHistoryTotals } from "@/lib/commands";
 import { formatBytes } from "@/lib/format";
-import type { CompressionRecord } from "@/lib/types";
+import type { CompressionRecord, EnergyEstimate } from "@/lib/types";
 
 interface StatisticsCardProps {
 	history: CompressionRecord[];
 }
 
 export function StatisticsCard({ history }: StatisticsCardProps) {
-	const completedHistory = history.filter((r) => r.status !== "processing");
+	const [energy, setEnergy] = useState<EnergyEstimate | null>(null);
+	useEffect(() => {
+		if (history.length === 0) return;
+		getHistoryTotals()
+			.then((totals) => setEnergy(totals.energy))
+			.catch(() => setEnergy(null));
+	}, [history]);
+
+	const completedHistory = history.filter((r) => r.status !== "processing" && !r.analysis_only);
+	const potentialSavings = history
+		.filter((r) => r.analysis_only)
+		.reduce((sum, r) => sum + Math.max(0, r.initial_size - r.compressed_size), 0);
 	const totalSaved = completedHistory.reduce(
 		(sum, r) => sum + Math.max(0, r.initial_size - r.compressed_size),
 		0
@@ -41,6 +54,20 @@ export function StatisticsCard({ history }: StatisticsCardProps) {
 						<p className="font-medium text-muted-foreground">Reduction</p>
 						<p className="font-semibold text-sm tabular-nums">{avgReduction}%</p>
 					</div>
+					{potentialSavings > 0 && (
+						<div>
+							<p className="font-medium text-muted-foreground">Potential Savings</p>
+							<p className="font-semibold text-sm tabular-nums">{formatBytes(potentialSavings)}</p>
+						</div>
+					)}
+					{energy && energy.cpu_seconds > 0 && (
+						<div>
+							<p className="font-medium text-muted-foreground">Energy Used (est.)</p>
+							<p className="font-semibold text-sm tabular-nums">
+								{energy.watt_hours.toFixed(2)} Wh · {Math.round(energy.cpu_seconds)} CPU s
+							</p>
+						</div>
+					)}
 				</div>
 			</CardPanel>
 		</Card>
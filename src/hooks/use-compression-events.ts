@@ -1,18 +1,26 @@
-import { invoke } from "@tauri-apps/api/core";
+import { Channel, invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { useCallback, useEffect, useState } from "react";
 import { toastManager } from "@/components/ui/toast";
 import { extractFileName, formatBytes } from "@/lib/format";
 import type {
+	BatchEvent,
 	CompressionFailed,
 	CompressionRecord,
 	CompressionRetry,
+	CompressionSkipped,
 	CompressionStarted,
+	CompressionWarning,
+	DuplicateDownload,
+	SelfCheckReport,
+	SessionSummary,
 } from "@/lib/types";
 
 export function useCompressionEvents() {
 	const [history, setHistory] = useState<CompressionRecord[]>([]);
 	const [recompressed, setRecompressed] = useState<Set<number>>(() => new Set());
+	// Latest event per file of the current manual batch, in the order files were dropped
+	const [batch, setBatch] = useState<Map<string, BatchEvent>>(() => new Map());
 
 	useEffect(() => {
 		invoke<CompressionRecord[]>("get_compression_history").then((records) => {
@@ -30,6 +38,7 @@ export function useCompressionEvents() {
 					return prev;
 
 				const newRecord: CompressionRecord = {
+					id: 0,
 					initial_path,
 					final_path: "",
 					initial_size: 0,
@@ -67,6 +76,7 @@ export function useCompressionEvents() {
 				}
 				// If not found, add it as failed
 				const newRecord: CompressionRecord = {
+					id: 0,
 					initial_path: event.payload.initial_path,
 					final_path: "",
 					initial_size: 0,
@@ -82,10 +92,19 @@ export function useCompressionEvents() {
 			});
 		});
 
+		const unlistenSkipped = listen<CompressionSkipped>("compression-skipped", (event) => {
+			setHistory((prev) =>
+				prev.filter(
+					(r) => !(r.timestamp === event.payload.timestamp && r.status === "processing")
+				)
+			);
+		});
+
 		return () => {
 			unlistenStarted.then((fn) => fn());
 			unlistenComplete.then((fn) => fn());
 			unlistenFailed.then((fn) => fn());
+			unlistenSkipped.then((fn) => fn());
 		};
 	}, []);
 
@@ -104,6 +123,84 @@ export function useCompressionEvents() {
 		};
 	}, []);
 
+	useEffect(() => {
+		const unlisten = listen<CompressionWarning>("compression-warning", (event) => {
+			toastManager.add({
+				title: `Heads up: ${extractFileName(event.payload.initial_path)}`,
+				description: event.payload.message,
+				type: "warning",
+			});
+		});
+		return () => {
+			unlisten.then((fn) => fn());
+		};
+	}, []);
+
+	useEffect(() => {
+		const unlisten = listen("engine-available", () => {
+			toastManager.add({
+				title: "libvips loaded",
+				description: "Compression is back to full quality",
+				type: "success",
+			});
+		});
+		return () => {
+			unlisten.then((fn) => fn());
+		};
+	}, []);
+
+	useEffect(() => {
+		const unlisten = listen<SelfCheckReport>("engine-ready", (event) => {
+			const { failures } = event.payload;
+			if (failures.length === 0) return;
+			toastManager.add({
+				title: "Some formats failed the engine self-check",
+				description: failures.map((f) => `${f.format}: ${f.error}`).join("\n"),
+				type: "warning",
+			});
+		});
+		return () => {
+			unlisten.then((fn) => fn());
+		};
+	}, []);
+
+	useEffect(() => {
+		const unlisten = listen<SessionSummary>("session-summary", (event) => {
+			toastManager.add({
+				title: "Welcome back",
+				description: event.payload.message,
+				type: event.payload.failures > 0 ? "warning" : "success",
+			});
+		});
+		return () => {
+			unlisten.then((fn) => fn());
+		};
+	}, []);
+
+	useEffect(() => {
+		const unlisten = listen<DuplicateDownload>("duplicate-download", (event) => {
+			const { path, original_path } = event.payload;
+			toastManager.add({
+				title: `Duplicate download: ${extractFileName(path)}`,
+				description: `Same as ${extractFileName(original_path)}`,
+				actionProps: {
+					children: "Delete",
+					onClick: () =>
+						invoke("delete_duplicate_download", { path }).catch((e) =>
+							toastManager.add({
+								title: "Failed to delete duplicate",
+								description: String(e),
+								type: "error",
+							})
+						),
+				},
+			});
+		});
+		return () => {
+			unlisten.then((fn) => fn());
+		};
+	}, []);
+
 	const handleRecompress = useCallback(
 		async (initialPath: string, previousQuality: number, timestamp: number) => {
 			setRecompressed((prev) => new Set(prev).add(timestamp));
@@ -151,8 +248,13 @@ export function useCompressionEvents() {
 	);
 
 	const handleManualCompress = useCallback(async (paths: string[]) => {
+		setBatch(new Map());
+		const onEvent = new Channel<BatchEvent>();
+		onEvent.onmessage = (event) => {
+			setBatch((prev) => new Map(prev).set(event.data.path, event));
+		};
 		try {
-			await invoke("compress_files", { paths });
+			await invoke("compress_files", { paths, onEvent });
 		} catch (e) {
 			toastManager.add({
 				title: "Compression failed",
@@ -164,6 +266,7 @@ export function useCompressionEvents() {
 
 	return {
 		history,
+		batch,
 		recompressed,
 		handleRecompress,
 		handleConvert,
@@ -7,7 +7,7 @@ interface NewFile {
 
 export function useDownloadsWatcher(onNewFile: (path: string) => void) {
 	useEffect(() => {
-		const unlisten = listen<NewFile>("new-download", (event) => {
+		const unlisten = listen<NewFile>("file-detected", (event) => {
 			onNewFile(event.payload.path);
 		});
 
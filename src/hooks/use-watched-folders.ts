@@ -1,3 +1,4 @@
+import { listen } from "@tauri-apps/api/event";
 import { useCallback, useEffect, useState } from "react";
 import { toastManager } from "@/components/ui/toast";
 import {
@@ -6,14 +7,17 @@ import {
 	removeWatchedFolder,
 	resetConfig,
 } from "@/lib/commands";
+import type { FolderSuggestion, WatchedFolderInfo } from "@/lib/types";
 
 export function useWatchedFolders() {
 	const [watchedFolders, setWatchedFolders] = useState<string[]>([]);
+	const [folderInfo, setFolderInfo] = useState<WatchedFolderInfo[]>([]);
 
 	const refreshFolders = useCallback(async () => {
 		try {
 			const folders = await getWatchedFolders();
-			setWatchedFolders(folders);
+			setFolderInfo(folders);
+			setWatchedFolders(folders.map((f) => f.path));
 		} catch (err) {
 			console.error("Failed to load watched folders", err);
 		}
@@ -23,34 +27,57 @@ export function useWatchedFolders() {
 		refreshFolders();
 	}, [refreshFolders]);
 
-	const addFolder = useCallback(async (path: string) => {
-		if (!path) return;
-		try {
-			const folders = await addWatchedFolder(path);
-			setWatchedFolders(folders);
-		} catch (err) {
-			console.error("Failed to add folder", err);
-			toastManager.add({
-				title: "Failed to add folder",
-				description: String(err),
-				type: "error",
-			});
-		}
-	}, []);
+	const addFolder = useCallback(
+		async (path: string) => {
+			if (!path) return;
+			try {
+				await addWatchedFolder(path);
+				await refreshFolders();
+			} catch (err) {
+				console.error("Failed to add folder", err);
+				toastManager.add({
+					title: "Failed to add folder",
+					description: String(err),
+					type: "error",
+				});
+			}
+		},
+		[refreshFolders]
+	);
 
-	const removeFolder = useCallback(async (path: string) => {
-		try {
-			const folders = await removeWatchedFolder(path);
-			setWatchedFolders(folders);
-		} catch (err) {
-			console.error("Failed to remove folder", err);
+	useEffect(() => {
+		const unlisten = listen<FolderSuggestion>("folder-suggestion", (event) => {
+			const { path, source } = event.payload;
 			toastManager.add({
-				title: "Failed to remove folder",
-				description: String(err),
-				type: "error",
+				title: `Watch ${source}?`,
+				description: path,
+				actionProps: {
+					children: "Watch",
+					onClick: () => addFolder(path),
+				},
 			});
-		}
-	}, []);
+		});
+		return () => {
+			unlisten.then((fn) => fn());
+		};
+	}, [addFolder]);
+
+	const removeFolder = useCallback(
+		async (path: string) => {
+			try {
+				await removeWatchedFolder(path);
+				await refreshFolders();
+			} catch (err) {
+				console.error("Failed to remove folder", err);
+				toastManager.add({
+					title: "Failed to remove folder",
+					description: String(err),
+					type: "error",
+				});
+			}
+		},
+		[refreshFolders]
+	);
 
 	const resetFoldersConfig = useCallback(async () => {
 		try {
@@ -73,6 +100,7 @@ export function useWatchedFolders() {
 
 	return {
 		watchedFolders,
+		folderInfo,
 		addFolder,
 		removeFolder,
 		refreshFolders,
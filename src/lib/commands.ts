@@ -1,5 +1,69 @@
-import { invoke } from "@tauri-apps/api/core";
-import type { CompressionRecord, FormatOptions } from "@/lib/types";
+import { Channel, invoke } from "@tauri-apps/api/core";
+import type {
+	ActionInfo,
+	AnalysisSummary,
+	BatchEvent,
+	BulkSummary,
+	ChecksumMode,
+	CollisionPolicy,
+	CompressionProfile,
+	CompressionRecord,
+	ContextRule,
+	ContextStatus,
+	DailyRollup,
+	DeleteOriginalsEstimate,
+	DeletionPolicy,
+	DuplicateDownloadPolicy,
+	EngineStatus,
+	EventInfo,
+	ExperimentGrid,
+	ExperimentReport,
+	FolderPair,
+	FolderSample,
+	FolderSettings,
+	FolderSuggestion,
+	FormatOptions,
+	FormatRule,
+	HeldFile,
+	HighDepthPolicy,
+	HistoryRetention,
+	HistoryTotals,
+	LocaleConfig,
+	MigrationReport,
+	NotificationSinkConfig,
+	OcrConfig,
+	OrphanedOutput,
+	OriginalCopyReport,
+	OutputNaming,
+	PendingTask,
+	PdfConfig,
+	PdfExtraction,
+	PdfPageSource,
+	ProducerRules,
+	RecentFilesConfig,
+	RecompressOptions,
+	RemovableVolume,
+	ResolvedSettings,
+	RestorePlan,
+	RuleSimulation,
+	ScanSummary,
+	ScriptInfo,
+	SeenFormat,
+	SensitiveGuardConfig,
+	SkippedFile,
+	SmallestWinsConfig,
+	SvgConfig,
+	TaskSync,
+	TempConfig,
+	TextureConfig,
+	TurboStatus,
+	VerifyReport,
+	VideoConfig,
+	VolumePolicy,
+	WatchedFolderInfo,
+	WatermarkConfig,
+	WizardStatus,
+} from "@/lib/types";
 
 export function getCompressionHistory() {
 	return invoke<CompressionRecord[]>("get_compression_history");
@@ -9,18 +73,176 @@ export function clearCompressionHistory() {
 	return invoke<void>("clear_compression_history");
 }
 
-export function compressFiles(paths: string[]) {
-	return invoke<void>("compress_files", { paths });
+export function getHistoryRetention() {
+	return invoke<HistoryRetention>("get_history_retention");
+}
+
+export function setHistoryRetention(retention: HistoryRetention) {
+	return invoke<HistoryRetention>("set_history_retention", { retention });
+}
+
+export function getHistoryTotals() {
+	return invoke<HistoryTotals>("get_history_totals");
+}
+
+export function getHistoryRollup() {
+	return invoke<DailyRollup[]>("get_history_rollup");
+}
+
+export function getFolderTrend(path: string) {
+	return invoke<FolderSample[]>("get_folder_trend", { path });
+}
+
+export function searchImages(query: string) {
+	return invoke<CompressionRecord[]>("search_images", { query });
+}
+
+export function getAnalysisSummary(folder?: string) {
+	return invoke<AnalysisSummary>("get_analysis_summary", { folder: folder ?? null });
+}
+
+export function getHistoryAsOf(timestamp: number) {
+	return invoke<CompressionRecord[]>("get_history_as_of", { timestamp });
+}
+
+export function getRestorePlan(timestamp: number) {
+	return invoke<RestorePlan>("get_restore_plan", { timestamp });
+}
+
+export function estimateDeleteOriginals() {
+	return invoke<DeleteOriginalsEstimate>("estimate_delete_originals");
+}
+
+export function compressFolder(path: string) {
+	return invoke<ScanSummary>("compress_folder", { path });
+}
+
+export function cancelScan(scanId: number) {
+	return invoke<boolean>("cancel_scan", { scanId });
+}
+
+export function getWizardStatus() {
+	return invoke<WizardStatus | null>("get_wizard_status");
+}
+
+export function wizardScan(root: string) {
+	return invoke<WizardStatus>("wizard_scan", { root });
+}
+
+export function wizardEstimate() {
+	return invoke<WizardStatus>("wizard_estimate");
+}
+
+export function wizardConfigure(quality: number, deletion: DeletionPolicy) {
+	return invoke<WizardStatus>("wizard_configure", { quality, deletion });
+}
+
+export function wizardStart() {
+	return invoke<WizardStatus>("wizard_start");
+}
+
+export function wizardPause() {
+	return invoke<void>("wizard_pause");
+}
+
+export function wizardReset() {
+	return invoke<void>("wizard_reset");
+}
+
+export function verifyOutputs() {
+	return invoke<VerifyReport>("verify_outputs");
+}
+
+export function compressFiles(paths: string[], onProgress: (event: BatchEvent) => void) {
+	const onEvent = new Channel<BatchEvent>();
+	onEvent.onmessage = onProgress;
+	return invoke<void>("compress_files", { paths, onEvent });
 }
 
 export function recompress(path: string, previousQuality: number) {
 	return invoke<void>("recompress", { path, previousQuality });
 }
 
+export function deleteTasks(ids: number[]) {
+	return invoke<BulkSummary>("delete_tasks", { ids });
+}
+
+export function retryTasks(ids: number[]) {
+	return invoke<BulkSummary>("retry_tasks", { ids });
+}
+
+export function recompressTasks(ids: number[], options: RecompressOptions = {}) {
+	return invoke<BulkSummary>("recompress_tasks", { ids, options });
+}
+
+export function repeatCompression(recordId: number, path: string) {
+	return invoke<CompressionRecord>("repeat_compression", { recordId, path });
+}
+
 export function convertImage(path: string, targetFormat: string) {
 	return invoke<void>("convert_image", { path, targetFormat });
 }
 
+export function generateContactSheet(recordIds: number[], path: string) {
+	return invoke<number>("generate_contact_sheet", { recordIds, path });
+}
+
+export function generateFavicon(path: string, out?: string) {
+	return invoke<string>("generate_favicon", { path, out });
+}
+
+export function createPdfFromImages(sources: PdfPageSource[], out: string) {
+	return invoke<number>("create_pdf_from_images", { sources, out });
+}
+
+export function extractPdfImages(path: string, rebuild: boolean) {
+	return invoke<PdfExtraction>("extract_pdf_images", { path, rebuild });
+}
+
+export function imagesToPdf(paths: string[], out: string) {
+	return invoke<number>("images_to_pdf", { paths, out });
+}
+
+export function pdfToImages(path: string, outDir: string, dpi: number) {
+	return invoke<string[]>("pdf_to_images", { path, outDir, dpi });
+}
+
+export function getEngineStatus() {
+	return invoke<EngineStatus>("get_engine_status");
+}
+
+export function reloadEngine() {
+	return invoke<void>("reload_engine");
+}
+
+export function runSelfCheck() {
+	return invoke<void>("run_self_check");
+}
+
+export function getHeldFiles() {
+	return invoke<HeldFile[]>("get_held_files");
+}
+
+export function approveHeldFile(path: string) {
+	return invoke<CompressionRecord>("approve_held_file", { path });
+}
+
+export function dismissHeldFile(path: string) {
+	return invoke<boolean>("dismiss_held_file", { path });
+}
+
+export function generateEncryptionKey() {
+	return invoke<string>("generate_encryption_key");
+}
+
+export function decryptFile(path: string, key: string, output: string) {
+	return invoke<number>("decrypt_file", { path, key, output });
+}
+
+export function findDuplicateOriginals(paths: string[] = []) {
+	return invoke<OriginalCopyReport[]>("find_duplicate_originals", { paths });
+}
+
 export function checkFileExists(path: string) {
 	return invoke<boolean>("check_file_exists", { path });
 }
@@ -49,6 +271,30 @@ export function setShowSystemNotifications(value: boolean) {
 	return invoke<boolean>("set_show_system_notifications", { value });
 }
 
+export function getLegacyEventNames() {
+	return invoke<boolean>("get_legacy_event_names");
+}
+
+export function setLegacyEventNames(enabled: boolean) {
+	return invoke<boolean>("set_legacy_event_names", { enabled });
+}
+
+export function getEventSchema() {
+	return invoke<EventInfo[]>("get_event_schema");
+}
+
+export function getNotificationSinks() {
+	return invoke<NotificationSinkConfig[]>("get_notification_sinks");
+}
+
+export function setNotificationSinks(sinks: NotificationSinkConfig[]) {
+	return invoke<NotificationSinkConfig[]>("set_notification_sinks", { sinks });
+}
+
+export function testNotificationSink(sink: NotificationSinkConfig) {
+	return invoke<void>("test_notification_sink", { sink });
+}
+
 export function getFormatOptions() {
 	return invoke<FormatOptions>("get_format_options");
 }
@@ -57,8 +303,378 @@ export function setFormatOptions(options: FormatOptions) {
 	return invoke<FormatOptions>("set_format_options", { options });
 }
 
+export function getFolderSettings(folder: string) {
+	return invoke<FolderSettings>("get_folder_settings", { folder });
+}
+
+export function setFolderSettings(folder: string, settings: FolderSettings) {
+	return invoke<FolderSettings>("set_folder_settings", { folder, settings });
+}
+
+export function getTextureConfig() {
+	return invoke<TextureConfig>("get_texture_config");
+}
+
+export function setTextureConfig(texture: TextureConfig) {
+	return invoke<TextureConfig>("set_texture_config", { texture });
+}
+
+export function getOcrConfig() {
+	return invoke<OcrConfig>("get_ocr_config");
+}
+
+export function setOcrConfig(ocr: OcrConfig) {
+	return invoke<OcrConfig>("set_ocr_config", { ocr });
+}
+
+export function getSensitiveGuard() {
+	return invoke<SensitiveGuardConfig>("get_sensitive_guard");
+}
+
+export function setSensitiveGuard(guard: SensitiveGuardConfig) {
+	return invoke<SensitiveGuardConfig>("set_sensitive_guard", { guard });
+}
+
+export function getDuplicateScanLocations() {
+	return invoke<string[]>("get_duplicate_scan_locations");
+}
+
+export function setDuplicateScanLocations(locations: string[]) {
+	return invoke<string[]>("set_duplicate_scan_locations", { locations });
+}
+
+export function getProducerRules() {
+	return invoke<ProducerRules>("get_producer_rules");
+}
+
+export function setProducerRules(rules: ProducerRules) {
+	return invoke<ProducerRules>("set_producer_rules", { rules });
+}
+
+export function getChecksumMode() {
+	return invoke<ChecksumMode>("get_checksum_mode");
+}
+
+export function setChecksumMode(mode: ChecksumMode) {
+	return invoke<ChecksumMode>("set_checksum_mode", { mode });
+}
+
+export function getHighDepth() {
+	return invoke<HighDepthPolicy>("get_high_depth");
+}
+
+export function setHighDepth(policy: HighDepthPolicy) {
+	return invoke<HighDepthPolicy>("set_high_depth", { policy });
+}
+
+export function getDuplicateDownloads() {
+	return invoke<DuplicateDownloadPolicy>("get_duplicate_downloads");
+}
+
+export function setDuplicateDownloads(policy: DuplicateDownloadPolicy) {
+	return invoke<DuplicateDownloadPolicy>("set_duplicate_downloads", { policy });
+}
+
+export function getCollisionPolicy() {
+	return invoke<CollisionPolicy>("get_collision_policy");
+}
+
+export function setCollisionPolicy(policy: CollisionPolicy) {
+	return invoke<CollisionPolicy>("set_collision_policy", { policy });
+}
+
+export function getTempConfig() {
+	return invoke<TempConfig>("get_temp_config");
+}
+
+export function setTempConfig(temp: TempConfig) {
+	return invoke<TempConfig>("set_temp_config", { temp });
+}
+
+export function clearCache() {
+	return invoke<number>("clear_cache");
+}
+
+export function getRecentFiles() {
+	return invoke<RecentFilesConfig>("get_recent_files");
+}
+
+export function setRecentFiles(recentFiles: RecentFilesConfig) {
+	return invoke<RecentFilesConfig>("set_recent_files", { recentFiles });
+}
+
+export function deleteDuplicateDownload(path: string) {
+	return invoke<void>("delete_duplicate_download", { path });
+}
+
+export function runTurbo(minutes: number) {
+	return invoke<TurboStatus>("run_turbo", { minutes });
+}
+
+export function stopTurbo() {
+	return invoke<TurboStatus>("stop_turbo");
+}
+
+export function getTurboStatus() {
+	return invoke<TurboStatus>("get_turbo_status");
+}
+
+export function getWatchDebug() {
+	return invoke<boolean>("get_watch_debug");
+}
+
+export function setWatchDebug(enabled: boolean) {
+	return invoke<boolean>("set_watch_debug", { enabled });
+}
+
+export function getSkipLog() {
+	return invoke<boolean>("get_skip_log");
+}
+
+export function setSkipLog(enabled: boolean) {
+	return invoke<boolean>("set_skip_log", { enabled });
+}
+
+export function getWarmStart() {
+	return invoke<boolean>("get_warm_start");
+}
+
+export function setWarmStart(enabled: boolean) {
+	return invoke<boolean>("set_warm_start", { enabled });
+}
+
+export function getSkipped(limit?: number) {
+	return invoke<SkippedFile[]>("get_skipped", { limit });
+}
+
+export function getReuseRedownloads() {
+	return invoke<boolean>("get_reuse_redownloads");
+}
+
+export function setReuseRedownloads(enabled: boolean) {
+	return invoke<boolean>("set_reuse_redownloads", { enabled });
+}
+
+export function getPreserveMetadata() {
+	return invoke<boolean>("get_preserve_metadata");
+}
+
+export function setPreserveMetadata(enabled: boolean) {
+	return invoke<boolean>("set_preserve_metadata", { enabled });
+}
+
+export function getStripLocation() {
+	return invoke<boolean>("get_strip_location");
+}
+
+export function setStripLocation(enabled: boolean) {
+	return invoke<boolean>("set_strip_location", { enabled });
+}
+
+export function getKeepIccProfile() {
+	return invoke<boolean>("get_keep_icc_profile");
+}
+
+export function setKeepIccProfile(enabled: boolean) {
+	return invoke<boolean>("set_keep_icc_profile", { enabled });
+}
+
+export function getKeepCmyk() {
+	return invoke<boolean>("get_keep_cmyk");
+}
+
+export function setKeepCmyk(enabled: boolean) {
+	return invoke<boolean>("set_keep_cmyk", { enabled });
+}
+
+export function getTrimBorders() {
+	return invoke<boolean>("get_trim_borders");
+}
+
+export function setTrimBorders(enabled: boolean) {
+	return invoke<boolean>("set_trim_borders", { enabled });
+}
+
+export function getRequireAuthentication() {
+	return invoke<boolean>("get_require_authentication");
+}
+
+export function setRequireAuthentication(enabled: boolean) {
+	return invoke<boolean>("set_require_authentication", { enabled });
+}
+
+export function getReplaceInPlace() {
+	return invoke<boolean>("get_replace_in_place");
+}
+
+export function setReplaceInPlace(enabled: boolean) {
+	return invoke<boolean>("set_replace_in_place", { enabled });
+}
+
+export function undoReplace(recordId: number) {
+	return invoke<void>("undo_replace", { recordId });
+}
+
+export function getWatermark() {
+	return invoke<WatermarkConfig>("get_watermark");
+}
+
+export function setWatermark(watermark: WatermarkConfig) {
+	return invoke<WatermarkConfig>("set_watermark", { watermark });
+}
+
+export function getDeterministic() {
+	return invoke<boolean>("get_deterministic");
+}
+
+export function setDeterministic(enabled: boolean) {
+	return invoke<boolean>("set_deterministic", { enabled });
+}
+
+export function getProfiles() {
+	return invoke<CompressionProfile[]>("get_profiles");
+}
+
+export function setProfiles(profiles: CompressionProfile[]) {
+	return invoke<CompressionProfile[]>("set_profiles", { profiles });
+}
+
+export function addEcoProfile() {
+	return invoke<CompressionProfile[]>("add_eco_profile");
+}
+
+export function getContextRules() {
+	return invoke<ContextRule[]>("get_context_rules");
+}
+
+export function setContextRules(rules: ContextRule[]) {
+	return invoke<ContextRule[]>("set_context_rules", { rules });
+}
+
+export function getContextStatus() {
+	return invoke<ContextStatus>("get_context_status");
+}
+
+export function getPdfConfig() {
+	return invoke<PdfConfig>("get_pdf_config");
+}
+
+export function setPdfConfig(pdf: PdfConfig) {
+	return invoke<PdfConfig>("set_pdf_config", { pdf });
+}
+
+export function getFolderFormatOptions(folder: string) {
+	return invoke<Record<string, FormatRule>>("get_folder_format_options", { folder });
+}
+
+export function setFolderFormatOptions(folder: string, rules: Record<string, FormatRule>) {
+	return invoke<Record<string, FormatRule>>("set_folder_format_options", { folder, rules });
+}
+
+export function getSeenFormats(folder: string) {
+	return invoke<SeenFormat[]>("get_seen_formats", { folder });
+}
+
+export function getVideoConfig() {
+	return invoke<VideoConfig>("get_video_config");
+}
+
+export function setVideoConfig(video: VideoConfig) {
+	return invoke<VideoConfig>("set_video_config", { video });
+}
+
+export function findOrphanedOutputs() {
+	return invoke<OrphanedOutput[]>("find_orphaned_outputs");
+}
+
+export function deleteOrphanedOutputs(paths: string[]) {
+	return invoke<string[]>("delete_orphaned_outputs", { paths });
+}
+
+export function adoptOrphanedOutputs(paths: string[]) {
+	return invoke<CompressionRecord[]>("adopt_orphaned_outputs", { paths });
+}
+
+export function getPendingTasks() {
+	return invoke<PendingTask[]>("get_pending_tasks");
+}
+
+export function subscribeTasks(onSync: (event: TaskSync) => void) {
+	const onEvent = new Channel<TaskSync>();
+	onEvent.onmessage = onSync;
+	return invoke<void>("subscribe_tasks", { onEvent });
+}
+
+export function getLocaleConfig() {
+	return invoke<LocaleConfig>("get_locale_config");
+}
+
+export function setLocaleConfig(locale: LocaleConfig) {
+	return invoke<LocaleConfig>("set_locale_config", { locale });
+}
+
+export function getSmallestWins() {
+	return invoke<SmallestWinsConfig>("get_smallest_wins");
+}
+
+export function setSmallestWins(smallestWins: SmallestWinsConfig) {
+	return invoke<SmallestWinsConfig>("set_smallest_wins", { smallestWins });
+}
+
+export function getFormatQuality() {
+	return invoke<Record<string, number>>("get_format_quality");
+}
+
+export function setFormatQuality(formatQuality: Record<string, number>) {
+	return invoke<Record<string, number>>("set_format_quality", { formatQuality });
+}
+
+export function getTargetFormat() {
+	return invoke<string | null>("get_target_format");
+}
+
+export function setTargetFormat(format: string | null) {
+	return invoke<string | null>("set_target_format", { format });
+}
+
+export function getOutputNaming() {
+	return invoke<OutputNaming>("get_output_naming");
+}
+
+export function setOutputNaming(naming: OutputNaming) {
+	return invoke<OutputNaming>("set_output_naming", { naming });
+}
+
+export function getOutputDir() {
+	return invoke<string | null>("get_output_dir");
+}
+
+export function setOutputDir(dir: string | null) {
+	return invoke<string | null>("set_output_dir", { dir });
+}
+
+export function getArchiveDir() {
+	return invoke<string | null>("get_archive_dir");
+}
+
+export function setArchiveDir(dir: string | null) {
+	return invoke<string | null>("set_archive_dir", { dir });
+}
+
+export function migrateOutputs(from: OutputNaming, to: OutputNaming, dryRun: boolean) {
+	return invoke<MigrationReport>("migrate_outputs", { from, to, dryRun });
+}
+
+export function getSvgConfig() {
+	return invoke<SvgConfig>("get_svg_config");
+}
+
+export function setSvgConfig(svg: SvgConfig) {
+	return invoke<SvgConfig>("set_svg_config", { svg });
+}
+
 export function getWatchedFolders() {
-	return invoke<string[]>("get_watched_folders");
+	return invoke<WatchedFolderInfo[]>("get_watched_folders");
 }
 
 export function addWatchedFolder(path: string) {
@@ -69,6 +685,30 @@ export function removeWatchedFolder(path: string) {
 	return invoke<string[]>("remove_watched_folder", { path });
 }
 
+export function getFolderPairs() {
+	return invoke<FolderPair[]>("get_folder_pairs");
+}
+
+export function addFolderPair(pair: FolderPair) {
+	return invoke<FolderPair[]>("add_folder_pair", { pair });
+}
+
+export function removeFolderPair(source: string) {
+	return invoke<FolderPair[]>("remove_folder_pair", { source });
+}
+
+export function suggestWatchFolders() {
+	return invoke<FolderSuggestion[]>("suggest_watch_folders");
+}
+
+export function listRemovableVolumes() {
+	return invoke<RemovableVolume[]>("list_removable_volumes");
+}
+
+export function setVolumeEnabled(label: string, serial: string, enabled: boolean) {
+	return invoke<VolumePolicy[]>("set_volume_enabled", { label, serial, enabled });
+}
+
 export function searchDirectories(query: string) {
 	return invoke<string[]>("search_directories", { query });
 }
@@ -84,3 +724,39 @@ export function resetConfig() {
 export function openConfigDir() {
 	return invoke<void>("open_config_dir");
 }
+
+export function listScripts() {
+	return invoke<ScriptInfo[]>("list_scripts");
+}
+
+export function setScriptEnabled(name: string, enabled: boolean) {
+	return invoke<ScriptInfo[]>("set_script_enabled", { name, enabled });
+}
+
+export function reloadScripts() {
+	return invoke<ScriptInfo[]>("reload_scripts");
+}
+
+export function openScriptsDir() {
+	return invoke<void>("open_scripts_dir");
+}
+
+export function simulateRules(path: string) {
+	return invoke<RuleSimulation>("simulate_rules", { path });
+}
+
+export function resolveSettings(path: string) {
+	return invoke<ResolvedSettings>("resolve_settings", { path });
+}
+
+export function runExperiment(samples: string[], grid: ExperimentGrid) {
+	return invoke<ExperimentReport>("run_experiment", { samples, grid });
+}
+
+export function listActions() {
+	return invoke<ActionInfo[]>("list_actions");
+}
+
+export function invokeAction<T = unknown>(id: string, args?: Record<string, unknown>) {
+	return invoke<T>("invoke_action", { id, args });
+}
@@ -1,4 +1,5 @@
 export interface CompressionRecord {
+	id: number;
 	initial_path: string;
 	final_path: string;
 	initial_size: number;
@@ -8,6 +9,19 @@ export interface CompressionRecord {
 	quality: number;
 	timestamp: number;
 	original_deleted: boolean;
+	ocr_text?: string | null;
+	original_sha256?: string | null;
+	output_sha256?: string | null;
+	encrypted?: boolean;
+	analysis_only?: boolean;
+	deterministic?: boolean;
+	reused_from?: number | null;
+	group_id?: number | null;
+	candidates?: CandidateSize[];
+	settings?: EncodeSettings | null;
+	trimmed?: TrimmedSize | null;
+	backup_path?: string | null;
+	original_archived_path?: string | null;
 	status?: "processing" | "completed" | "failed";
 }
 
@@ -22,6 +36,32 @@ export interface CompressionFailed {
 	error: string;
 }
 
+export interface CompressionSkipped {
+	initial_path: string;
+	timestamp: number;
+	reason: string;
+}
+
+export interface EventInfo {
+	name: string;
+	legacy_name: string | null;
+	version: number;
+	payload: string;
+}
+
+export interface DeprecationWarning {
+	event: string;
+	replacement: string;
+}
+
+export interface SessionSummary {
+	since: number;
+	compressed: number;
+	bytes_saved: number;
+	failures: number;
+	message: string;
+}
+
 export interface CompressionRetry {
 	path: string;
 	attempt: number;
@@ -39,6 +79,10 @@ export interface PngConfig {
 	bitdepth: number;
 	filter: string | null;
 	colors: number;
+	dither: number;
+	quantize_after: boolean;
+	oxipng_level: number;
+	zopfli: boolean;
 }
 
 export interface JpegConfig {
@@ -51,6 +95,7 @@ export interface JpegConfig {
 	overshoot_deringing: boolean;
 	quantize: boolean;
 	colors: number;
+	lossless: boolean;
 }
 
 export interface WebpConfig {
@@ -98,6 +143,33 @@ export interface TiffConfig {
 	colors: number;
 }
 
+export interface GifConfig {
+	quality: number;
+	convert_to: string | null;
+	effort: number;
+	colors: number;
+	dither: number;
+}
+
+export interface JxlConfig {
+	quality: number;
+	convert_to: string | null;
+	effort: number;
+	lossless: boolean;
+	lossless_jpeg: boolean;
+	encoder_path: string | null;
+}
+
+export interface RawConfig {
+	quality: number;
+	convert_to: string | null;
+}
+
+export interface PsdConfig {
+	quality: number;
+	convert_to: string | null;
+}
+
 export interface FormatOptions {
 	png: PngConfig;
 	jpeg: JpegConfig;
@@ -105,4 +177,610 @@ export interface FormatOptions {
 	avif: AvifConfig;
 	heif: HeifConfig;
 	tiff: TiffConfig;
+	gif: GifConfig;
+	jxl: JxlConfig;
+	raw: RawConfig;
+	psd: PsdConfig;
+	extra_outputs: string[];
+}
+
+export type AgeFilter =
+	| { mode: "any" }
+	| { mode: "since_added" }
+	| { mode: "since"; cutoff: number };
+
+export interface FolderSettings {
+	game_asset_mode: boolean;
+	encrypt_output: boolean;
+	encryption_key?: string | null;
+	observe_only: boolean;
+	age_filter: AgeFilter;
+	compress_videos: boolean;
+	decode_raw: boolean;
+	target_format: string | null;
+	progressive_jpeg: boolean | null;
+	preserve_metadata: boolean | null;
+	strip_location: boolean | null;
+	watermark: boolean;
+	trim_borders: boolean | null;
+	added_at: number | null;
+}
+
+export type WatermarkPosition =
+	| "top_left"
+	| "top_right"
+	| "bottom_left"
+	| "bottom_right"
+	| "center";
+
+export interface WatermarkConfig {
+	image: string | null;
+	text: string | null;
+	text_color: string;
+	position: WatermarkPosition;
+	opacity: number;
+	scale: number;
+	margin: number;
+}
+
+export interface TextureConfig {
+	output: "png" | "ktx2" | "dds";
+	encoder_path: string | null;
+}
+
+export interface OcrConfig {
+	enabled: boolean;
+	screenshots_only: boolean;
+	tesseract_path: string | null;
+	language: string;
+}
+
+export interface SensitiveGuardConfig {
+	enabled: boolean;
+	classifier_command: string | null;
+}
+
+export interface HeldFile {
+	path: string;
+	reason: string;
+	timestamp: number;
+}
+
+export interface AnalysisSummary {
+	files: number;
+	original_bytes: number;
+	potential_savings: number;
+}
+
+export interface OriginalCopyReport {
+	path: string;
+	sha256: string | null;
+	copies: string[];
+	only_copy: boolean;
+}
+
+export type ChecksumMode = "off" | "history" | "sidecar";
+
+export type HighDepthPolicy = "keep" | "skip" | "warn";
+
+export interface CompressionWarning {
+	initial_path: string;
+	timestamp: number;
+	message: string;
+}
+
+export type DuplicateDownloadPolicy = "compress" | "ask" | "delete";
+
+export type CollisionPolicy = "version" | "overwrite" | "skip" | "error";
+
+export type RecentFilePolicy = "off" | "skip" | "defer";
+
+export interface TempConfig {
+	directory: string | null;
+	max_bytes: number;
+}
+
+export interface RecentFilesConfig {
+	policy: RecentFilePolicy;
+	window_minutes: number;
+}
+
+export interface DuplicateDownload {
+	path: string;
+	original_path: string;
+	record_id: number;
+}
+
+export interface VerifyProblem {
+	record_id: number;
+	path: string;
+	status: "mismatch" | "missing" | "unreadable";
+}
+
+export interface VerifyReport {
+	checked: number;
+	ok: number;
+	problems: VerifyProblem[];
+}
+
+export interface PlannedFile {
+	record_id: number;
+	path: string;
+	exists_now: boolean;
+}
+
+export interface RestorePlan {
+	as_of: number;
+	outputs: PlannedFile[];
+	deleted_originals: PlannedFile[];
+	created_after: PlannedFile[];
+}
+
+export interface FolderImpact {
+	folder: string;
+	count: number;
+	bytes: number;
+}
+
+export interface DeleteOriginalsEstimate {
+	count: number;
+	bytes: number;
+	folders: FolderImpact[];
+}
+
+export interface ActivityEntry {
+	kind: "compressed" | "analyzed" | "failed" | "held";
+	message: string;
+	count: number;
+	bytes_saved: number;
+	timestamp: number;
+}
+
+export interface ActionArg {
+	name: string;
+	kind: string;
+	required: boolean;
+}
+
+export interface ActionInfo {
+	id: string;
+	title: string;
+	description: string;
+	category: string;
+	args: ActionArg[];
+}
+
+export interface ScriptInfo {
+	name: string;
+	enabled: boolean;
+	error: string | null;
+}
+
+export interface RuleSimulation {
+	path: string;
+	skipped: string | null;
+	rules: string[];
+	engine: string | null;
+	source_format: string | null;
+	target_format: string | null;
+	quality: number | null;
+	output_path: string | null;
+}
+
+export interface ResolvedSettings {
+	path: string;
+	source_format: string;
+	target_format: string;
+	quality: number;
+	flags: Record<string, unknown>;
+	output_path: string | null;
+	layers: string[];
+}
+
+export interface ProducerRules {
+	allowed_apps: string[];
+	ignored_apps: string[];
+}
+
+export interface CompressionProfile {
+	name: string;
+	format_options: FormatOptions;
+}
+
+export interface ContextRule {
+	profile: string;
+	hostname: string | null;
+	ssid: string | null;
+	interface: string | null;
+}
+
+export interface NetworkContext {
+	hostname: string | null;
+	ssid: string | null;
+	interfaces: string[];
+}
+
+export interface ContextStatus {
+	context: NetworkContext;
+	active_profile: string | null;
+}
+
+export interface PdfConfig {
+	enabled: boolean;
+	quality: number;
+	max_dimension: number;
+}
+
+export interface FormatRule {
+	convert_to: string | null;
+	quality: number | null;
+	options: Record<string, unknown>;
+}
+
+export interface SeenFormat {
+	format: string;
+	count: number;
+	last_seen: number;
+}
+
+export interface VideoConfig {
+	ffmpeg_path: string | null;
+	crf: number;
+	preset: string;
+}
+
+export interface VideoProgress {
+	path: string;
+	timestamp: number;
+	percent: number;
+}
+
+export interface OutputNaming {
+	suffix: string;
+	directory: string | null;
+	date_subfolders: boolean;
+}
+
+export type MoveStatus = "planned" | "moved" | "missing" | "target_exists" | "failed";
+
+export interface PlannedMove {
+	record_id: number;
+	from: string;
+	to: string;
+	status: MoveStatus;
+	error: string | null;
+}
+
+export interface MigrationReport {
+	dry_run: boolean;
+	moves: PlannedMove[];
+}
+
+export interface SvgConfig {
+	enabled: boolean;
+	precision: number;
+}
+
+export type OrphanReason = "untracked" | "original_missing";
+
+export interface OrphanedOutput {
+	path: string;
+	size: number;
+	reason: OrphanReason;
+	original: string | null;
+}
+
+export type TaskStatus = "pending" | "done" | "failed" | "partial";
+
+export interface OutputTask {
+	format: string;
+	status: TaskStatus;
+}
+
+export interface PendingTask {
+	id: number;
+	path: string;
+	mode: "manual" | "watched";
+	queued_at: number;
+	outputs: OutputTask[];
+	status: TaskStatus;
+}
+
+export type TaskSync =
+	| { event: "snapshot"; data: { tasks: PendingTask[] } }
+	| { event: "diff"; data: { added: PendingTask[]; updated: PendingTask[]; removed: number[] } };
+
+export type BatchEvent =
+	| { event: "started"; data: { path: string } }
+	| { event: "finished"; data: { path: string; record: CompressionRecord } }
+	| { event: "failed"; data: { path: string; error: string } }
+	| { event: "done"; data: { energy: EnergyEstimate } };
+
+export interface ScanStarted {
+	scan_id: number;
+	folder: string;
+}
+
+export interface ScanSummary {
+	scan_id: number;
+	folder: string;
+	total: number;
+	processed: number;
+	cancelled: boolean;
+	energy: EnergyEstimate;
+}
+
+export type WizardStep = "scanned" | "estimated" | "configured" | "running" | "paused" | "done";
+
+export type DeletionPolicy = "keep" | "delete_when_smaller";
+
+export interface FormatEstimate {
+	format: string;
+	count: number;
+	bytes: number;
+	estimated_bytes: number;
+}
+
+export interface LibraryEstimate {
+	count: number;
+	bytes: number;
+	estimated_bytes: number;
+	formats: FormatEstimate[];
+}
+
+export interface WizardStatus {
+	step: WizardStep;
+	root: string;
+	total: number;
+	checkpoint: number;
+	estimate: LibraryEstimate | null;
+	quality: number | null;
+	deletion: DeletionPolicy;
+	compressed: number;
+	failed: number;
+	bytes_saved: number;
+}
+
+export interface WatchedFolderInfo {
+	path: string;
+	exists: boolean;
+	writable: boolean;
+	item_count: number;
+	last_event: number | null;
+	pending_tasks: number;
+	compressed: number;
+	bytes_saved: number;
+}
+
+export interface FolderSuggestion {
+	path: string;
+	source: string;
+}
+
+export interface VolumePolicy {
+	label: string;
+	serial: string;
+	enabled: boolean;
+}
+
+export interface RemovableVolume {
+	root: string;
+	label: string;
+	serial: string;
+	removable: boolean;
+	enabled: boolean;
+}
+
+export type SizeUnits = "binary" | "decimal";
+
+export interface LocaleConfig {
+	language: string | null;
+	units: SizeUnits;
+}
+
+export type NotificationEvent = "compressed" | "failed" | "daily_summary";
+
+export interface EmailSettings {
+	smtp_host: string;
+	smtp_port: number;
+	username: string;
+	password?: string | null;
+	password_set?: boolean;
+	from: string;
+	to: string;
+}
+
+export type SinkKind =
+	| { kind: "system" }
+	| { kind: "webhook"; url: string }
+	| { kind: "slack"; webhook_url: string }
+	| { kind: "discord"; webhook_url: string }
+	| ({ kind: "email" } & EmailSettings);
+
+export type NotificationSinkConfig = SinkKind & {
+	events: NotificationEvent[];
+};
+
+export interface CandidateSize {
+	format: string;
+	size: number;
+}
+
+export interface TrimmedSize {
+	original_width: number;
+	original_height: number;
+	width: number;
+	height: number;
+}
+
+export interface EncodeSettings {
+	engine: "libvips" | "fallback";
+	format: string;
+	quality: number;
+	flags: Record<string, unknown>;
+}
+
+export interface SmallestWinsConfig {
+	enabled: boolean;
+	candidates: string[];
+	conditional_conversion: boolean;
+}
+
+export type PdfPageSource = { id: number } | { path: string };
+
+export interface HistoryRetention {
+	max_records: number | null;
+	max_age_days: number | null;
+	max_file_bytes: number | null;
+}
+
+export interface DailyRollup {
+	day: number;
+	format: string;
+	count: number;
+	initial_bytes: number;
+	compressed_bytes: number;
+}
+
+export interface FolderSample {
+	folder: string;
+	day: number;
+	bytes: number;
+	files: number;
+	saved_bytes: number;
+}
+
+export interface HistoryTotals {
+	count: number;
+	initial_bytes: number;
+	compressed_bytes: number;
+	pruned: number;
+	energy: EnergyEstimate;
+}
+
+export interface FormatHealth {
+	format: string;
+	successes: number;
+	failures: number;
+	consecutive_failures: number;
+	demoted_at: number | null;
+	last_error: string | null;
+}
+
+export interface SelfCheckFailure {
+	format: string;
+	error: string;
+}
+
+export interface SelfCheckReport {
+	fingerprint: string;
+	checked_at: number;
+	passed: string[];
+	failures: SelfCheckFailure[];
+}
+
+export interface WarmupReport {
+	formats: string[];
+	duration_ms: number;
+}
+
+export interface EngineStatus {
+	libvips_loaded: boolean;
+	ready: boolean;
+	warmed_up: boolean;
+	self_check_running: boolean;
+	self_check: SelfCheckReport | null;
+	formats: FormatHealth[];
+}
+
+export interface PdfExtraction {
+	records: CompressionRecord[];
+	rebuilt: string | null;
+}
+
+export type SkipReason =
+	| "temporary_file"
+	| "compressed_output"
+	| "unsupported_format"
+	| "paused"
+	| "producer_app"
+	| "older_than_cutoff"
+	| "script_veto"
+	| "removable_volume"
+	| "duplicate_download"
+	| "recently_opened"
+	| "high_depth"
+	| "disabled"
+	| "output_exists";
+
+export interface SkippedFile {
+	path: string;
+	reason: SkipReason;
+	detail: string;
+	timestamp: number;
+}
+
+export interface WatchDecision {
+	event_kind: string;
+	path: string;
+	rule: string | null;
+	action: "ignored" | "skipped" | "compressing";
+}
+
+export interface RecompressOptions {
+	quality?: number | null;
+	format?: string | null;
+}
+
+export interface TaskFailure {
+	id: number;
+	error: string;
+}
+
+export interface BulkSummary {
+	operation: "delete" | "retry" | "recompress";
+	requested: number;
+	succeeded: number;
+	failed: TaskFailure[];
+	records: CompressionRecord[];
+	energy: EnergyEstimate;
+}
+
+export interface TurboStatus {
+	active: boolean;
+	until: number | null;
+}
+
+export interface EnergyEstimate {
+	cpu_seconds: number;
+	watt_hours: number;
+}
+
+export interface ExperimentGrid {
+	formats: string[];
+	qualities: number[];
+	efforts?: number[];
+}
+
+export interface ExperimentRow {
+	file: string;
+	format: string;
+	quality: number;
+	effort: number | null;
+	original_size: number;
+	size: number;
+	ssim: number;
+	encode_ms: number;
+}
+
+export interface ExperimentReport {
+	csv_path: string;
+	rows: ExperimentRow[];
+	errors: string[];
+}
+
+export interface FolderPair {
+	source: string;
+	destination: string;
+	delete_with_source: boolean;
 }